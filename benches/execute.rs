@@ -0,0 +1,87 @@
+//! Compiled scripts against the token-walking engine.
+//!
+//! Run with:
+//!
+//! ```text
+//! cargo bench --bench execute
+//! ```
+//!
+//! Signature checks run in stub mode, so the numbers measure dispatch,
+//! branching and stack traffic rather than secp256k1.
+
+use std::time::{Duration, Instant};
+
+use mini_bitcoin_script::bytecode::CompiledScript;
+use mini_bitcoin_script::engine::{execute_on_items, ExecuteOpts};
+use mini_bitcoin_script::hash::hash160;
+use mini_bitcoin_script::hex::encode_hex;
+use mini_bitcoin_script::token::Token;
+use mini_bitcoin_script::tokenizer::parse_asm;
+
+/// Runs `f` repeatedly for about a second and returns the time per run.
+fn bench(mut f: impl FnMut()) -> Duration {
+    f();
+    let start = Instant::now();
+    let mut runs = 0u32;
+    while start.elapsed() < Duration::from_secs(1) {
+        f();
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn compare(name: &str, tokens: &[Token], items: &[Vec<u8>]) {
+    let opts = ExecuteOpts::default();
+    let compiled = CompiledScript::compile(tokens);
+
+    let engine = bench(|| {
+        let mut stack = items.to_vec();
+        execute_on_items(tokens, &mut stack, &opts).unwrap();
+    });
+    let bytecode = bench(|| {
+        let mut stack = items.to_vec();
+        compiled.execute_on_items(&mut stack, &opts).unwrap();
+    });
+    let speedup = engine.as_secs_f64() / bytecode.as_secs_f64();
+    println!("{name:<28} engine {engine:>10.2?}  compiled {bytecode:>10.2?}  {speedup:>5.2}x");
+}
+
+fn main() {
+    // Any 33 bytes pass as a key in stub mode.
+    let pubkey = vec![0x02; 33];
+    let p2pkh = parse_asm(&format!(
+        "OP_DUP OP_HASH160 <{}> OP_EQUALVERIFY OP_CHECKSIG",
+        encode_hex(&hash160(&pubkey))
+    ))
+    .unwrap();
+    compare("p2pkh", &p2pkh, &[vec![0x30; 71], pubkey]);
+
+    // An HTLC: reveal the SHA-256 preimage (32 zero bytes), or wait.
+    let htlc = parse_asm(
+        "OP_IF OP_SHA256 <66687aadf862bd776c8fc18b8e9f8e20089714856ee233b3902a591d0d5f2925> \
+         OP_EQUALVERIFY <02aa> OP_ELSE <0090> OP_CHECKSEQUENCEVERIFY OP_DROP <02bb> OP_ENDIF \
+         OP_CHECKSIG",
+    )
+    .unwrap();
+    compare(
+        "htlc, preimage branch",
+        &htlc,
+        &[vec![0x30; 71], vec![0; 32], vec![1]],
+    );
+    compare("htlc, timeout branch", &htlc, &[vec![0x30; 71], vec![]]);
+
+    // Deeply nested branches that are mostly skipped.
+    let mut nested = String::new();
+    for _ in 0..20 {
+        nested.push_str("OP_0 OP_IF OP_1 OP_2 OP_3 OP_ADD OP_ADD OP_DROP OP_ELSE ");
+    }
+    nested.push_str("OP_1");
+    for _ in 0..20 {
+        nested.push_str(" OP_ENDIF");
+    }
+    compare("nested conditionals", &parse_asm(&nested).unwrap(), &[]);
+
+    // Straight-line arithmetic and constants.
+    let arithmetic = "OP_1 OP_2 OP_ADD OP_3 OP_NUMEQUALVERIFY ".repeat(50) + "OP_1";
+    compare("arithmetic", &parse_asm(&arithmetic).unwrap(), &[]);
+}
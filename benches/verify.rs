@@ -0,0 +1,108 @@
+//! Transaction verification throughput.
+//!
+//! Run with `cargo bench --features test-utils`, adding `rayon` to compare
+//! serial and parallel verification:
+//!
+//! ```text
+//! cargo bench --features test-utils,rayon --bench verify
+//! ```
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use mini_bitcoin_script::builder;
+use mini_bitcoin_script::flags::VerifyFlags;
+use mini_bitcoin_script::sign::sign_p2wpkh;
+use mini_bitcoin_script::signature::SIGHASH_ALL;
+use mini_bitcoin_script::test_utils::TestKey;
+use mini_bitcoin_script::transaction::{OutPoint, Transaction, TxIn, TxOut};
+use mini_bitcoin_script::verify::{verify_input, verify_tx};
+
+/// A transaction spending `inputs` P2WPKH outputs, with the outputs it
+/// spends.
+fn p2wpkh_spend(inputs: usize) -> (Transaction, HashMap<OutPoint, TxOut>) {
+    let key = TestKey::from_seed(b"bench");
+    let prevout = TxOut {
+        value: 10_000,
+        script_pubkey: builder::p2wpkh(&key.pubkey_hash()),
+    };
+    let mut tx = Transaction {
+        version: 2,
+        inputs: (0..inputs)
+            .map(|n| {
+                TxIn::new(OutPoint {
+                    txid: [0x42; 32],
+                    vout: n as u32,
+                })
+            })
+            .collect(),
+        outputs: vec![TxOut {
+            value: 9_000 * inputs as u64,
+            script_pubkey: builder::p2wpkh(&[0x11; 20]),
+        }],
+        lock_time: 0,
+    };
+    let mut utxos = HashMap::new();
+    for index in 0..inputs {
+        sign_p2wpkh(&mut tx, index, &key.secret_key, &prevout, SIGHASH_ALL).unwrap();
+        utxos.insert(tx.inputs[index].previous_output, prevout.clone());
+    }
+    (tx, utxos)
+}
+
+/// Runs `f` repeatedly for about a second and prints inputs per second.
+fn bench(name: &str, inputs: usize, mut f: impl FnMut()) {
+    f();
+    let start = Instant::now();
+    let mut runs = 0u32;
+    while start.elapsed() < Duration::from_secs(1) {
+        f();
+        runs += 1;
+    }
+    let per_run = start.elapsed() / runs;
+    let rate = inputs as f64 / per_run.as_secs_f64();
+    println!("{name:<40} {per_run:>12.2?}/tx {rate:>12.0} inputs/s");
+}
+
+fn main() {
+    let flags = VerifyFlags::DERSIG | VerifyFlags::LOW_S | VerifyFlags::NULLFAIL;
+
+    for inputs in [10, 100, 500] {
+        let (tx, utxos) = p2wpkh_spend(inputs);
+        let prevouts: Vec<TxOut> = tx
+            .inputs
+            .iter()
+            .map(|input| utxos[&input.previous_output].clone())
+            .collect();
+        println!("{inputs} P2WPKH inputs");
+
+        // Each call rehashes the transaction for its BIP143 midstate.
+        bench("verify_input per input", inputs, || {
+            for index in 0..inputs {
+                verify_input(&tx, index, &prevouts, flags).unwrap();
+            }
+        });
+
+        #[cfg(not(feature = "rayon"))]
+        bench("verify_tx", inputs, || {
+            assert!(verify_tx(&tx, &utxos, flags).unwrap().is_valid());
+        });
+
+        #[cfg(feature = "rayon")]
+        {
+            let serial = rayon::ThreadPoolBuilder::new()
+                .num_threads(1)
+                .build()
+                .unwrap();
+            bench("verify_tx, 1 thread", inputs, || {
+                serial.install(|| {
+                    assert!(verify_tx(&tx, &utxos, flags).unwrap().is_valid());
+                });
+            });
+            let name = format!("verify_tx, global pool ({})", rayon::current_num_threads());
+            bench(&name, inputs, || {
+                assert!(verify_tx(&tx, &utxos, flags).unwrap().is_valid());
+            });
+        }
+    }
+}
@@ -1,6 +1,9 @@
 //! Demonstrates the complete Pay-to-Public-Key-Hash (P2PKH) validation flow.
 //!
 //! Run with: `cargo run --example p2pkh`
+//!
+//! The main walkthrough uses a fake signature and stub OP_CHECKSIG. Run
+//! with `--features test-utils` to also validate a genuinely signed spend.
 
 use mini_bitcoin_script::hash;
 use mini_bitcoin_script::script::validate_p2pkh;
@@ -45,4 +48,21 @@ fn main() {
     // 6. Validate the P2PKH script pair (stub CHECKSIG mode).
     let result = validate_p2pkh(&script_sig, &script_pubkey).expect("execution succeeded");
     println!("P2PKH validation result: {result}");
+
+    #[cfg(feature = "test-utils")]
+    real_signature();
+}
+
+/// Validates a real signature from a deterministic test fixture with
+/// full ECDSA verification and strict encoding rules.
+#[cfg(feature = "test-utils")]
+fn real_signature() {
+    use mini_bitcoin_script::script::validate_p2pkh_with_opts;
+    use mini_bitcoin_script::test_utils::{fixture, ScriptType};
+
+    let fx = fixture(ScriptType::P2pkh, b"example");
+    let script_pubkey = &fx.prevout().script_pubkey;
+    let result = validate_p2pkh_with_opts(fx.script_sig(), script_pubkey, &fx.execute_opts())
+        .expect("execution succeeded");
+    println!("P2PKH validation with a real signature: {result}");
 }
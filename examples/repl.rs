@@ -0,0 +1,236 @@
+//! Interactive REPL for exploring Bitcoin Script one step at a time.
+//!
+//! Run with: `cargo run --example repl`
+//!
+//! Each input line is parsed as ASM (`OP_DUP`, `HASH160`, `<89abcdef>`) and
+//! executed via [`execute_on_state`], so the stack, the alt stack and open
+//! conditionals carry over between lines instead of re-running the whole
+//! script: an OP_IF on one line can be closed by an OP_ENDIF on a later
+//! one. The prompt shows a `+` for each open conditional whose branch
+//! runs and a `-` for each one being skipped.
+//!
+//! Commands:
+//!
+//! - `:undo`        — revert the last executed line
+//! - `:reset`       — clear the stacks, open conditionals and history
+//! - `:load <hex>`  — execute a hex-encoded script as one step
+//! - `:stack`       — print the stack
+//! - `:altstack`    — print the alt stack
+//! - `:flags`       — print the enabled verification flags
+//! - `:flag <NAME>` — toggle a verification flag (`DERSIG`, `LOW_S`, ...)
+//! - `:history`     — print the lines executed so far
+//! - `:help`        — print this list
+//! - `:quit`        — exit (Ctrl-D also works)
+
+use std::io::{self, BufRead, Write};
+
+use mini_bitcoin_script::engine::{execute_on_state, ExecState, ExecuteOpts};
+use mini_bitcoin_script::error::ScriptError;
+use mini_bitcoin_script::flags::VerifyFlags;
+use mini_bitcoin_script::token::Token;
+use mini_bitcoin_script::tokenizer::{parse_asm, parse_script_hex};
+
+const HELP: &str = "\
+  <asm>        execute opcodes / <hex> pushes, e.g. `OP_1 OP_DUP <abcd>`
+  :undo        revert the last executed line
+  :reset       clear the stacks, open conditionals and history
+  :load <hex>  execute a hex-encoded script as one step
+  :stack       print the stack
+  :altstack    print the alt stack
+  :flags       print the enabled verification flags
+  :flag <NAME> toggle a verification flag (DERSIG, LOW_S, STRICTENC, NULLFAIL)
+  :history     print the lines executed so far
+  :help        print this help
+  :quit        exit";
+
+/// One executed step: the tokens that ran and the state before them.
+struct Step {
+    tokens: Vec<Token>,
+    before: ExecState,
+}
+
+struct Session {
+    state: ExecState,
+    history: Vec<Step>,
+    opts: ExecuteOpts,
+}
+
+impl Session {
+    fn new() -> Self {
+        Self {
+            state: ExecState::default(),
+            history: Vec::new(),
+            opts: ExecuteOpts::default(),
+        }
+    }
+
+    /// Runs `tokens` on the current state. On failure the state is left
+    /// untouched so a typo never costs the user their work.
+    fn run(&mut self, tokens: Vec<Token>) -> Result<(), ScriptError> {
+        let before = self.state.clone();
+        if let Err(err) = execute_on_state(&tokens, &mut self.state, &self.opts) {
+            self.state = before;
+            return Err(err);
+        }
+        self.history.push(Step { tokens, before });
+        Ok(())
+    }
+
+    fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(step) => {
+                self.state = step.before;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state = ExecState::default();
+        self.history.clear();
+    }
+
+    fn print_stack(&self) {
+        print_items(&self.state.stack, "empty stack");
+    }
+
+    fn print_alt_stack(&self) {
+        print_items(&self.state.alt_stack, "empty alt stack");
+    }
+
+    /// The prompt: `>` at the top level, or one `+` or `-` per open
+    /// conditional, `-` where the branch is skipped.
+    fn prompt(&self) -> String {
+        let branches: String = self
+            .state
+            .exec
+            .iter()
+            .map(|&runs| if runs { '+' } else { '-' })
+            .collect();
+        format!("{branches}> ")
+    }
+
+    /// Flips `flag` and returns whether it is now enabled.
+    fn toggle_flag(&mut self, flag: VerifyFlags) -> bool {
+        if self.opts.flags.contains(flag) {
+            self.opts.flags.remove(flag);
+            false
+        } else {
+            self.opts.flags.insert(flag);
+            true
+        }
+    }
+
+    fn print_flags(&self) {
+        let names: Vec<&str> = self.opts.flags.names().collect();
+        if names.is_empty() {
+            println!("  (no flags)");
+        } else {
+            println!("  {}", names.join(","));
+        }
+    }
+
+    fn print_history(&self) {
+        if self.history.is_empty() {
+            println!("  (no history)");
+        }
+        for (i, step) in self.history.iter().enumerate() {
+            let asm: Vec<String> = step.tokens.iter().map(|t| t.to_string()).collect();
+            println!("  {i}: {}", asm.join(" "));
+        }
+    }
+}
+
+fn print_items(items: &[Vec<u8>], empty: &str) {
+    if items.is_empty() {
+        println!("  ({empty})");
+        return;
+    }
+    for (i, item) in items.iter().enumerate().rev() {
+        let marker = if i + 1 == items.len() { "top" } else { "   " };
+        println!("  {marker} [{i}] {}", Token::PushData(item.clone()));
+    }
+}
+
+fn main() {
+    let mut session = Session::new();
+    let stdin = io::stdin();
+
+    println!("mini-bitcoin-script REPL — type :help for commands");
+    prompt(&session);
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                eprintln!("read error: {err}");
+                break;
+            }
+        };
+        let line = line.trim();
+
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line, ""),
+        };
+
+        match command {
+            "" => {}
+            ":quit" | ":q" => break,
+            ":help" => println!("{HELP}"),
+            ":stack" => session.print_stack(),
+            ":altstack" => session.print_alt_stack(),
+            ":history" => session.print_history(),
+            ":flags" => session.print_flags(),
+            ":flag" => match VerifyFlags::from_name(arg) {
+                Some(flag) => {
+                    let state = if session.toggle_flag(flag) {
+                        "on"
+                    } else {
+                        "off"
+                    };
+                    println!("  {arg} {state}");
+                }
+                None => println!("  unknown flag {arg:?}; try DERSIG, LOW_S, STRICTENC, NULLFAIL"),
+            },
+            ":reset" => {
+                session.reset();
+                println!("  state cleared");
+            }
+            ":undo" => {
+                if session.undo() {
+                    session.print_stack();
+                } else {
+                    println!("  nothing to undo");
+                }
+            }
+            ":load" => match parse_script_hex(arg) {
+                Ok(tokens) => execute_and_report(&mut session, tokens),
+                Err(err) => println!("  error: {err}"),
+            },
+            _ if command.starts_with(':') => {
+                println!("  unknown command {command}; type :help");
+            }
+            _ => match parse_asm(line) {
+                Ok(tokens) => execute_and_report(&mut session, tokens),
+                Err(err) => println!("  error: {err}"),
+            },
+        }
+
+        prompt(&session);
+    }
+}
+
+fn execute_and_report(session: &mut Session, tokens: Vec<Token>) {
+    match session.run(tokens) {
+        Ok(()) => session.print_stack(),
+        Err(err) => println!("  error: {err} (stack unchanged)"),
+    }
+}
+
+fn prompt(session: &Session) {
+    print!("{}", session.prompt());
+    // A failed flush only affects the prompt, not the session.
+    let _ = io::stdout().flush();
+}
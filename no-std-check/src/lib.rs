@@ -0,0 +1,26 @@
+//! Build-only check that `mini-bitcoin-script` works in a `#![no_std]`
+//! crate with only `alloc` available.
+//!
+//! Build with:
+//!
+//! ```sh
+//! cargo build --manifest-path no-std-check/Cargo.toml --target thumbv7em-none-eabi
+//! ```
+
+#![no_std]
+
+use mini_bitcoin_script::engine::execute;
+use mini_bitcoin_script::error::ScriptError;
+use mini_bitcoin_script::hash::hash160;
+use mini_bitcoin_script::tokenizer::parse_script;
+
+/// Returns `Ok(true)` if `script` evaluates to true on an empty stack.
+pub fn run(script: &[u8]) -> Result<bool, ScriptError> {
+    let tokens = parse_script(script)?;
+    execute(&tokens)
+}
+
+/// Returns HASH160 of `data`.
+pub fn address_hash(data: &[u8]) -> [u8; 20] {
+    hash160(data)
+}
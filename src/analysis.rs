@@ -0,0 +1,360 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::engine::encode_num;
+use crate::explore::{explore, Domain, Path, Stop};
+use crate::opcode::Opcode;
+use crate::stack::is_true;
+use crate::token::Token;
+
+/// Upper bound on explored execution paths. Each OP_IF or OP_IFDUP on an
+/// unknown value doubles the count, so long chains of conditionals are cut off
+/// here and [`Analysis::truncated`] is set.
+pub const MAX_PATHS: usize = 1024;
+
+/// What the analyzer found, and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Finding {
+    /// Index of the offending token, or `tokens.len()` for findings about
+    /// the state at the end of the script.
+    pub index: usize,
+    /// What is wrong.
+    pub kind: FindingKind,
+    /// The branch decisions leading to the problem, as `(index of the
+    /// OP_IF/OP_NOTIF, whether its first branch ran)`, or for OP_IFDUP
+    /// whether the value was true and duplicated. Empty for findings that
+    /// do not depend on the path taken.
+    pub path: Vec<(usize, bool)>,
+}
+
+/// The kinds of problem the analyzer reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum FindingKind {
+    /// The operation pops more items than the stack can hold on this path,
+    /// given the initial depth passed to [`analyze_with_depth`].
+    StackUnderflow,
+    /// OP_ELSE/OP_ENDIF without an open OP_IF, or an OP_IF never closed.
+    UnbalancedConditional,
+    /// The token follows an OP_RETURN outside any conditional, so it can
+    /// never execute.
+    Unreachable,
+    /// The operation fails on every input reaching it along this path
+    /// (OP_RETURN, a VERIFY of a value known to be false, an empty alt
+    /// stack or an oversized number).
+    AlwaysFails,
+    /// This path always finishes with an empty stack or a false top item.
+    AlwaysFalse,
+}
+
+impl core::fmt::Display for Finding {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "token {}: ", self.index)?;
+        match self.kind {
+            FindingKind::StackUnderflow => write!(f, "stack underflow")?,
+            FindingKind::UnbalancedConditional => write!(f, "unbalanced conditional")?,
+            FindingKind::Unreachable => write!(f, "unreachable after OP_RETURN")?,
+            FindingKind::AlwaysFails => write!(f, "always fails")?,
+            FindingKind::AlwaysFalse => write!(f, "leaves false on the stack")?,
+        }
+        if !self.path.is_empty() {
+            write!(f, " (path:")?;
+            for (index, taken) in &self.path {
+                let branch = if *taken { "if" } else { "else" };
+                write!(f, " {index}:{branch}")?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of [`analyze`] or [`analyze_with_depth`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Analysis {
+    /// The fewest initial stack items any path that can succeed consumes.
+    /// If no path can succeed, the fewest consumed by any path.
+    pub min_stack_depth: usize,
+    /// Problems found, ordered by token index.
+    pub findings: Vec<Finding>,
+    /// Number of execution paths explored.
+    pub paths: usize,
+    /// Whether exploration stopped at [`MAX_PATHS`].
+    pub truncated: bool,
+}
+
+impl Analysis {
+    /// Returns `true` if nothing was found.
+    pub fn is_clean(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+/// Analyzes a script run on an initial stack of unknown contents and
+/// depth, as for a locking script whose unlocking data is not known yet.
+///
+/// Every opcode is evaluated abstractly: pushes and operations on known
+/// values are computed, anything derived from the initial stack or from
+/// OP_CHECKSIG is unknown, and OP_IF/OP_NOTIF/OP_IFDUP on unknown values
+/// explore both outcomes. Paths reaching an OP_CHECKMULTISIG whose counts
+/// come from the initial stack are dropped without a finding. Underflows
+/// cannot be reported in this mode, since any depth is possible;
+/// [`Analysis::min_stack_depth`] says how deep the stack must be instead.
+///
+/// ```rust
+/// use mini_bitcoin_script::analysis::analyze;
+/// use mini_bitcoin_script::tokenizer::parse_asm;
+///
+/// let tokens = parse_asm("OP_DUP OP_HASH160 <89abcdefabbaabbaabbaabbaabbaabbaabbaabba> \
+///                         OP_EQUALVERIFY OP_CHECKSIG").unwrap();
+/// let analysis = analyze(&tokens);
+/// assert_eq!(analysis.min_stack_depth, 2);
+/// assert!(analysis.is_clean());
+/// ```
+pub fn analyze(tokens: &[Token]) -> Analysis {
+    run(tokens, None)
+}
+
+/// Analyzes a script run on exactly `depth` initial stack items of unknown
+/// contents, e.g. 0 for a scriptSig. Also reports guaranteed underflows.
+pub fn analyze_with_depth(tokens: &[Token], depth: usize) -> Analysis {
+    run(tokens, Some(depth))
+}
+
+/// An abstract stack value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Known(Vec<u8>),
+    Unknown,
+    /// Unknown bytes that are known to be true.
+    True,
+}
+
+/// The analyzer's view of the initial stack: its exact depth, if known.
+#[derive(Clone)]
+struct Limit(Option<usize>);
+
+impl Domain for Limit {
+    type Value = Value;
+
+    fn constant(bytes: Vec<u8>) -> Value {
+        Value::Known(bytes)
+    }
+
+    fn known(value: &Value) -> Option<&[u8]> {
+        match value {
+            Value::Known(bytes) => Some(bytes),
+            Value::Unknown | Value::True => None,
+        }
+    }
+
+    fn truth(value: &Value) -> Option<bool> {
+        match value {
+            Value::Known(bytes) => Some(is_true(bytes)),
+            Value::True => Some(true),
+            Value::Unknown => None,
+        }
+    }
+
+    fn input(&mut self, index: usize) -> Result<Value, Stop> {
+        match self.0 {
+            Some(limit) if index >= limit => Err(Stop::Underflow),
+            _ => Ok(Value::Unknown),
+        }
+    }
+
+    fn depth(&self, offset: i64) -> Value {
+        match self.0 {
+            Some(limit) => Value::Known(encode_num(limit as i64 + offset)),
+            None => Value::Unknown,
+        }
+    }
+
+    fn assume(&mut self, _value: Value, truth: bool) -> Value {
+        if truth {
+            Value::True
+        } else {
+            Value::Known(vec![])
+        }
+    }
+
+    fn unary(_op: Opcode, _a: Value) -> Value {
+        Value::Unknown
+    }
+
+    fn binary(_op: Opcode, _a: Value, _b: Value) -> Value {
+        Value::Unknown
+    }
+
+    fn multisig(_sigs: Vec<Value>, _pubkeys: Vec<Value>) -> Value {
+        Value::Unknown
+    }
+}
+
+/// The top value at the end of `path`, accounting for untouched initial
+/// items below the stack we track.
+fn final_top(path: &Path<Limit>) -> Option<Value> {
+    match path.stack.last() {
+        Some(value) => Some(value.clone()),
+        None if path.domain.0 == Some(path.borrowed) => None,
+        None => Some(Value::Unknown),
+    }
+}
+
+fn run(tokens: &[Token], limit: Option<usize>) -> Analysis {
+    let mut findings = syntactic_findings(tokens);
+    let exploration = explore(tokens, Limit(limit));
+    let mut min_success: Option<usize> = None;
+    let mut min_any: Option<usize> = None;
+
+    for (path, outcome) in exploration.paths {
+        min_any = Some(min_any.map_or(path.borrowed, |m| m.min(path.borrowed)));
+
+        let finding = match outcome {
+            Ok(()) => match final_top(&path) {
+                Some(top) if Limit::truth(&top) != Some(false) => {
+                    min_success = Some(min_success.map_or(path.borrowed, |m| m.min(path.borrowed)));
+                    None
+                }
+                _ => Some((tokens.len(), FindingKind::AlwaysFalse)),
+            },
+            Err((index, Stop::Underflow)) => Some((index, FindingKind::StackUnderflow)),
+            Err((index, Stop::Fails | Stop::Disabled(_))) => {
+                Some((index, FindingKind::AlwaysFails))
+            }
+            Err((_, Stop::Malformed | Stop::Unmodeled)) => None,
+        };
+
+        if let Some((index, kind)) = finding {
+            if !findings.iter().any(|f| f.index == index && f.kind == kind) {
+                findings.push(Finding {
+                    index,
+                    kind,
+                    path: path.branches,
+                });
+            }
+        }
+    }
+
+    findings.sort_by_key(|f| f.index);
+    Analysis {
+        min_stack_depth: min_success.or(min_any).unwrap_or(0),
+        findings,
+        paths: exploration.started,
+        truncated: exploration.truncated,
+    }
+}
+
+/// Reports unbalanced conditionals and code after a top-level OP_RETURN.
+fn syntactic_findings(tokens: &[Token]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    let mut returned = false;
+
+    for (index, token) in tokens.iter().enumerate() {
+        if returned {
+            findings.push(Finding {
+                index,
+                kind: FindingKind::Unreachable,
+                path: Vec::new(),
+            });
+            break;
+        }
+        match token {
+            Token::Op(Opcode::OpIf | Opcode::OpNotIf) => open.push(index),
+            Token::Op(Opcode::OpElse | Opcode::OpEndIf) => {
+                let closes = *token == Token::Op(Opcode::OpEndIf);
+                let matched = if closes {
+                    open.pop().is_some()
+                } else {
+                    !open.is_empty()
+                };
+                if !matched {
+                    findings.push(Finding {
+                        index,
+                        kind: FindingKind::UnbalancedConditional,
+                        path: Vec::new(),
+                    });
+                }
+            }
+            Token::Op(Opcode::OpReturn) if open.is_empty() => returned = true,
+            _ => {}
+        }
+    }
+
+    for index in open {
+        findings.push(Finding {
+            index,
+            kind: FindingKind::UnbalancedConditional,
+            path: Vec::new(),
+        });
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::parse_asm;
+
+    fn asm(text: &str) -> Vec<Token> {
+        parse_asm(text).unwrap()
+    }
+
+    #[test]
+    fn empty_script() {
+        let analysis = analyze(&[]);
+        assert_eq!(analysis.min_stack_depth, 0);
+        assert!(analysis.is_clean());
+        assert_eq!(analysis.paths, 1);
+
+        // With nothing on the stack, an empty script leaves false.
+        let analysis = analyze_with_depth(&[], 0);
+        assert_eq!(analysis.findings[0].kind, FindingKind::AlwaysFalse);
+    }
+
+    #[test]
+    fn depth_mode_computes_depth() {
+        let analysis = analyze_with_depth(&asm("OP_DEPTH OP_2 OP_EQUAL"), 2);
+        assert!(analysis.is_clean());
+        let analysis = analyze_with_depth(&asm("OP_DEPTH OP_2 OP_EQUAL"), 1);
+        assert_eq!(analysis.findings[0].kind, FindingKind::AlwaysFalse);
+    }
+
+    #[test]
+    fn ifdup_forks_on_unknown_values() {
+        // Only the path where x is false reaches the failing OP_RETURN.
+        let analysis = analyze(&asm("OP_IFDUP OP_NOTIF OP_RETURN OP_ENDIF"));
+        assert_eq!(analysis.paths, 2);
+        assert_eq!(analysis.findings.len(), 1);
+        assert_eq!(analysis.findings[0].index, 2);
+        assert_eq!(analysis.findings[0].path, vec![(0, false)]);
+    }
+
+    #[test]
+    fn path_cap() {
+        let text = "OP_IF OP_ENDIF ".repeat(12) + "OP_1";
+        let analysis = analyze(&asm(&text));
+        assert!(analysis.truncated);
+        assert_eq!(analysis.paths, MAX_PATHS);
+    }
+
+    #[test]
+    fn finding_display() {
+        let finding = Finding {
+            index: 4,
+            kind: FindingKind::AlwaysFails,
+            path: vec![(1, true), (3, false)],
+        };
+        assert_eq!(
+            alloc::format!("{finding}"),
+            "token 4: always fails (path: 1:if 3:else)"
+        );
+    }
+}
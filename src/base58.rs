@@ -0,0 +1,83 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::hash;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Decodes base58 and checks and strips the 4-byte HASH256 checksum.
+pub(crate) fn decode_base58check(s: &str) -> Option<Vec<u8>> {
+    // Big-endian base-256 digits of the number, built one base58 digit at
+    // a time.
+    let mut bytes: Vec<u8> = Vec::new();
+    for ch in s.bytes() {
+        let mut carry = ALPHABET.iter().position(|&b| b == ch)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = s.bytes().take_while(|&b| b == b'1').count();
+    let mut decoded = vec![0; zeros];
+    decoded.extend(bytes);
+
+    if decoded.len() < 4 {
+        return None;
+    }
+    let (payload, checksum) = decoded.split_at(decoded.len() - 4);
+    if hash::hash256(payload)[..4] != *checksum {
+        return None;
+    }
+    Some(payload.to_vec())
+}
+
+/// Appends the HASH256 checksum to `payload` and encodes it in base58.
+pub(crate) fn encode_base58check(payload: &[u8]) -> String {
+    let mut data = payload.to_vec();
+    data.extend_from_slice(&hash::hash256(payload)[..4]);
+
+    // Little-endian base58 digits, built one byte at a time.
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &data {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let zeros = data.iter().take_while(|&&b| b == 0).count();
+    core::iter::repeat('1')
+        .take(zeros)
+        .chain(digits.iter().rev().map(|&d| ALPHABET[d as usize] as char))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        // The genesis block's coinbase address.
+        let address = "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa";
+        let payload = decode_base58check(address).unwrap();
+        assert_eq!(payload.len(), 21);
+        assert_eq!(encode_base58check(&payload), address);
+        // Each leading zero byte becomes a '1'.
+        let encoded = encode_base58check(&[0, 0, 1]);
+        assert!(encoded.starts_with("11") && !encoded.starts_with("111"));
+        assert_eq!(decode_base58check(&encoded).unwrap(), [0, 0, 1]);
+        assert!(decode_base58check(&encoded.replace('1', "2")).is_none());
+    }
+}
@@ -0,0 +1,365 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::base58::{decode_base58check, encode_base58check};
+use crate::hash;
+use crate::hex::{decode_hex_array, encode_hex};
+use crate::signature::{pubkey_encoding, PubkeyEncoding};
+
+/// Child indexes at or above this are hardened.
+pub const HARDENED: u32 = 1 << 31;
+
+/// Version bytes of a mainnet extended public key (`xpub`).
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
+/// Version bytes of a testnet, signet or regtest extended public key
+/// (`tpub`).
+const TPUB_VERSION: [u8; 4] = [0x04, 0x35, 0x87, 0xcf];
+
+/// Length of a serialized extended key.
+const EXTENDED_KEY_LEN: usize = 78;
+
+/// Why an extended key, key origin or derivation failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bip32Error {
+    /// Not valid base58, or the checksum does not match.
+    Base58,
+    /// The decoded payload is not 78 bytes.
+    InvalidLength,
+    /// Version bytes other than `xpub` or `tpub`. Private keys and
+    /// SLIP-132 variants such as `zpub` are not supported.
+    UnknownVersion,
+    /// The key data is not a compressed public key, or a depth 0 key has
+    /// a parent fingerprint or child number.
+    InvalidKey,
+    /// A hardened child index, which needs the private key.
+    HardenedDerivation,
+    /// The derived key is invalid. This happens for fewer than 1 in
+    /// 2^127 indexes; BIP32 says to move on to the next one.
+    InvalidChild,
+    /// Deriving a child of a depth 255 key.
+    MaxDepth,
+    /// A malformed fingerprint or derivation path step.
+    InvalidPath,
+}
+
+impl fmt::Display for Bip32Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bip32Error::Base58 => write!(f, "invalid base58check encoding"),
+            Bip32Error::InvalidLength => write!(f, "extended key is not 78 bytes"),
+            Bip32Error::UnknownVersion => write!(f, "unknown extended key version"),
+            Bip32Error::InvalidKey => write!(f, "invalid extended public key"),
+            Bip32Error::HardenedDerivation => {
+                write!(f, "hardened derivation needs the private key")
+            }
+            Bip32Error::InvalidChild => write!(f, "derived key is invalid"),
+            Bip32Error::MaxDepth => write!(f, "extended key depth exceeds 255"),
+            Bip32Error::InvalidPath => write!(f, "invalid derivation path"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Bip32Error {}
+
+/// The network an extended key is encoded for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    /// `xpub`
+    Mainnet,
+    /// `tpub`: testnet, signet and regtest.
+    Testnet,
+}
+
+/// A BIP32 extended public key.
+///
+/// Parse one with `str::parse`; `Display` writes it back in base58check.
+/// Deriving children needs the `secp256k1` feature.
+///
+/// ```rust
+/// use mini_bitcoin_script::bip32::Xpub;
+///
+/// let xpub: Xpub = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(xpub.depth, 0);
+/// assert_eq!(xpub.fingerprint(), [0x34, 0x42, 0x19, 0x3e]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Xpub {
+    /// The network the key is encoded for.
+    pub network: Network,
+    /// Derivation steps from the master key; 0 for the master key.
+    pub depth: u8,
+    /// First 4 bytes of the parent key's HASH160; zero for the master
+    /// key.
+    pub parent_fingerprint: [u8; 4],
+    /// The index this key was derived at; zero for the master key.
+    pub child_number: u32,
+    /// The chain code.
+    pub chain_code: [u8; 32],
+    /// The compressed public key.
+    pub public_key: [u8; 33],
+}
+
+impl Xpub {
+    /// HASH160 of the public key, which identifies the key.
+    pub fn identifier(&self) -> [u8; 20] {
+        hash::hash160(&self.public_key)
+    }
+
+    /// The first 4 bytes of [`Xpub::identifier`], as used in key origins
+    /// and by children to name their parent.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        let id = self.identifier();
+        [id[0], id[1], id[2], id[3]]
+    }
+
+    /// The 78-byte serialization, without base58check.
+    pub fn encode(&self) -> [u8; EXTENDED_KEY_LEN] {
+        let version = match self.network {
+            Network::Mainnet => XPUB_VERSION,
+            Network::Testnet => TPUB_VERSION,
+        };
+        let mut data = [0u8; EXTENDED_KEY_LEN];
+        data[..4].copy_from_slice(&version);
+        data[4] = self.depth;
+        data[5..9].copy_from_slice(&self.parent_fingerprint);
+        data[9..13].copy_from_slice(&self.child_number.to_be_bytes());
+        data[13..45].copy_from_slice(&self.chain_code);
+        data[45..].copy_from_slice(&self.public_key);
+        data
+    }
+
+    /// Parses the 78-byte serialization.
+    ///
+    /// The key is checked to be compressed, but not to be on the curve.
+    ///
+    /// # Errors
+    ///
+    /// [`Bip32Error::InvalidLength`], [`Bip32Error::UnknownVersion`] or
+    /// [`Bip32Error::InvalidKey`].
+    pub fn decode(data: &[u8]) -> Result<Xpub, Bip32Error> {
+        let data: &[u8; EXTENDED_KEY_LEN] =
+            data.try_into().map_err(|_| Bip32Error::InvalidLength)?;
+        let network = match data[..4] {
+            [0x04, 0x88, 0xb2, 0x1e] => Network::Mainnet,
+            [0x04, 0x35, 0x87, 0xcf] => Network::Testnet,
+            _ => return Err(Bip32Error::UnknownVersion),
+        };
+        let xpub = Xpub {
+            network,
+            depth: data[4],
+            parent_fingerprint: data[5..9].try_into().expect("4 bytes"),
+            child_number: u32::from_be_bytes(data[9..13].try_into().expect("4 bytes")),
+            chain_code: data[13..45].try_into().expect("32 bytes"),
+            public_key: data[45..].try_into().expect("33 bytes"),
+        };
+        let orphan =
+            xpub.depth == 0 && (xpub.parent_fingerprint != [0; 4] || xpub.child_number != 0);
+        if orphan || pubkey_encoding(&xpub.public_key) != Some(PubkeyEncoding::Compressed) {
+            return Err(Bip32Error::InvalidKey);
+        }
+        Ok(xpub)
+    }
+
+    /// Derives the unhardened child at `index` (BIP32 `CKDpub`).
+    ///
+    /// # Errors
+    ///
+    /// [`Bip32Error::HardenedDerivation`] for an index of 2^31 or more,
+    /// [`Bip32Error::MaxDepth`] below a depth 255 key,
+    /// [`Bip32Error::InvalidKey`] if the key is not on the curve, and
+    /// [`Bip32Error::InvalidChild`] for the rare index with no valid
+    /// child.
+    #[cfg(feature = "secp256k1")]
+    pub fn derive_child(&self, index: u32) -> Result<Xpub, Bip32Error> {
+        use secp256k1::{PublicKey, Scalar, Secp256k1};
+
+        if index >= HARDENED {
+            return Err(Bip32Error::HardenedDerivation);
+        }
+        let depth = self.depth.checked_add(1).ok_or(Bip32Error::MaxDepth)?;
+
+        let mut data = [0u8; 37];
+        data[..33].copy_from_slice(&self.public_key);
+        data[33..].copy_from_slice(&index.to_be_bytes());
+        let i = hash::hmac_sha512(&self.chain_code, &data);
+        let (il, ir) = i.split_at(32);
+
+        let tweak = Scalar::from_be_bytes(il.try_into().expect("32 bytes"))
+            .map_err(|_| Bip32Error::InvalidChild)?;
+        let parent = PublicKey::from_slice(&self.public_key).map_err(|_| Bip32Error::InvalidKey)?;
+        let child = parent
+            .add_exp_tweak(&Secp256k1::verification_only(), &tweak)
+            .map_err(|_| Bip32Error::InvalidChild)?;
+
+        Ok(Xpub {
+            network: self.network,
+            depth,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code: ir.try_into().expect("32 bytes"),
+            public_key: child.serialize(),
+        })
+    }
+
+    /// Derives each index of `path` in turn.
+    ///
+    /// # Errors
+    ///
+    /// As [`Xpub::derive_child`].
+    #[cfg(feature = "secp256k1")]
+    pub fn derive_path(&self, path: &[u32]) -> Result<Xpub, Bip32Error> {
+        path.iter()
+            .try_fold(*self, |xpub, &index| xpub.derive_child(index))
+    }
+}
+
+impl fmt::Display for Xpub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_base58check(&self.encode()))
+    }
+}
+
+impl FromStr for Xpub {
+    type Err = Bip32Error;
+
+    fn from_str(s: &str) -> Result<Self, Bip32Error> {
+        Xpub::decode(&decode_base58check(s).ok_or(Bip32Error::Base58)?)
+    }
+}
+
+/// Where a key comes from: the fingerprint of the master key and the
+/// derivation path from it, as in a descriptor's `[d34db33f/84'/0'/0']`.
+///
+/// Parses hardened steps marked with `'` or `h`; displays them with `'`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyOrigin {
+    /// First 4 bytes of the master key's HASH160.
+    pub fingerprint: [u8; 4],
+    /// Child indexes from the master key; hardened ones include
+    /// [`HARDENED`].
+    pub path: Vec<u32>,
+}
+
+impl fmt::Display for KeyOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_hex(&self.fingerprint))?;
+        write_path(f, &self.path)
+    }
+}
+
+impl FromStr for KeyOrigin {
+    type Err = Bip32Error;
+
+    fn from_str(s: &str) -> Result<Self, Bip32Error> {
+        let mut parts = s.split('/');
+        let fingerprint = parts.next().unwrap_or("");
+        if fingerprint.len() != 8 {
+            return Err(Bip32Error::InvalidPath);
+        }
+        let fingerprint = decode_hex_array(fingerprint).map_err(|_| Bip32Error::InvalidPath)?;
+        let path = parts
+            .map(parse_child)
+            .collect::<Option<_>>()
+            .ok_or(Bip32Error::InvalidPath)?;
+        Ok(KeyOrigin { fingerprint, path })
+    }
+}
+
+/// Writes each step as `/index`, with `'` after hardened ones.
+pub(crate) fn write_path(f: &mut impl fmt::Write, path: &[u32]) -> fmt::Result {
+    for &index in path {
+        if index >= HARDENED {
+            write!(f, "/{}'", index - HARDENED)?;
+        } else {
+            write!(f, "/{index}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses one path step: a decimal index below 2^31, optionally
+/// followed by `'` or `h` to harden it.
+pub(crate) fn parse_child(step: &str) -> Option<u32> {
+    let index = step.trim_end_matches(['\'', 'h']);
+    let hardened = match step.len() - index.len() {
+        0 => false,
+        1 => true,
+        _ => return None,
+    };
+    if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let index: u32 = index.parse().ok().filter(|&n| n < HARDENED)?;
+    Some(if hardened { index + HARDENED } else { index })
+}
+
+/// Formats a path as `m/0'/1`.
+pub fn path_to_string(path: &[u32]) -> String {
+    let mut s = String::from("m");
+    write_path(&mut s, path).expect("writing to a String");
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    const MASTER: &str = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+
+    #[test]
+    fn roundtrip() {
+        let xpub: Xpub = MASTER.parse().unwrap();
+        assert_eq!(xpub.network, Network::Mainnet);
+        assert_eq!(xpub.to_string(), MASTER);
+        assert_eq!(Xpub::decode(&xpub.encode()), Ok(xpub));
+    }
+
+    #[test]
+    fn invalid_keys() {
+        let xpub: Xpub = MASTER.parse().unwrap();
+        let mut data = xpub.encode();
+        data[45] = 0x04;
+        assert_eq!(Xpub::decode(&data), Err(Bip32Error::InvalidKey));
+
+        let mut data = xpub.encode();
+        data[9..13].copy_from_slice(&1u32.to_be_bytes());
+        assert_eq!(Xpub::decode(&data), Err(Bip32Error::InvalidKey));
+
+        let mut data = xpub.encode();
+        data[3] = 0x1f;
+        assert_eq!(Xpub::decode(&data), Err(Bip32Error::UnknownVersion));
+        assert_eq!(Xpub::decode(&data[1..]), Err(Bip32Error::InvalidLength));
+        assert_eq!("xpub".parse::<Xpub>(), Err(Bip32Error::Base58));
+    }
+
+    #[test]
+    fn child_steps() {
+        assert_eq!(parse_child("0"), Some(0));
+        assert_eq!(parse_child("44'"), Some(44 + HARDENED));
+        assert_eq!(parse_child("1h"), Some(1 + HARDENED));
+        assert_eq!(parse_child("2147483648"), None);
+        assert_eq!(parse_child("1''"), None);
+        assert_eq!(parse_child(""), None);
+        assert_eq!(parse_child("+1"), None);
+        assert_eq!(path_to_string(&[HARDENED, 1, HARDENED + 2]), "m/0'/1/2'");
+    }
+
+    #[test]
+    fn key_origins() {
+        let origin: KeyOrigin = "d34db33f/44'/0h/1".parse().unwrap();
+        assert_eq!(origin.fingerprint, [0xd3, 0x4d, 0xb3, 0x3f]);
+        assert_eq!(origin.path, vec![44 + HARDENED, HARDENED, 1]);
+        assert_eq!(origin.to_string(), "d34db33f/44'/0'/1");
+        assert!("d34db33".parse::<KeyOrigin>().is_err());
+        assert!("d34db33f/".parse::<KeyOrigin>().is_err());
+        assert!("d34db33g".parse::<KeyOrigin>().is_err());
+    }
+}
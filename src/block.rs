@@ -0,0 +1,510 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use crate::flags::VerifyFlags;
+use crate::hash;
+use crate::transaction::{write_compact_size, DecodeError, OutPoint, Reader, Transaction, TxOut};
+use crate::verify::{verify_tx_with_opts, TxVerifyError, UtxoProvider, VerifyError, VerifyOpts};
+
+/// The scriptPubKey prefix of a BIP141 witness commitment output:
+/// OP_RETURN, a 36-byte push, and the commitment header `aa21a9ed`.
+const WITNESS_COMMITMENT_HEADER: [u8; 6] = [0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+
+/// An 80-byte block header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockHeader {
+    /// Block version, including BIP9 signalling bits.
+    pub version: i32,
+    /// Hash of the previous block's header, in internal byte order.
+    pub prev_blockhash: [u8; 32],
+    /// Merkle root of the block's txids, in internal byte order.
+    pub merkle_root: [u8; 32],
+    /// Block timestamp, in Unix seconds.
+    pub time: u32,
+    /// Compact encoding of the proof-of-work target.
+    pub bits: u32,
+    /// Proof-of-work nonce.
+    pub nonce: u32,
+}
+
+impl BlockHeader {
+    /// The size of a serialized header, in bytes.
+    pub const SIZE: usize = 80;
+
+    /// Serializes the header in consensus format.
+    pub fn serialize(&self) -> [u8; Self::SIZE] {
+        let mut out = [0u8; Self::SIZE];
+        out[..4].copy_from_slice(&self.version.to_le_bytes());
+        out[4..36].copy_from_slice(&self.prev_blockhash);
+        out[36..68].copy_from_slice(&self.merkle_root);
+        out[68..72].copy_from_slice(&self.time.to_le_bytes());
+        out[72..76].copy_from_slice(&self.bits.to_le_bytes());
+        out[76..].copy_from_slice(&self.nonce.to_le_bytes());
+        out
+    }
+
+    /// Parses a header from exactly [`BlockHeader::SIZE`] bytes.
+    ///
+    /// # Errors
+    ///
+    /// [`DecodeError::UnexpectedEnd`] or [`DecodeError::TrailingData`] if
+    /// `bytes` is not 80 bytes long.
+    pub fn deserialize(bytes: &[u8]) -> Result<BlockHeader, DecodeError> {
+        let mut reader = Reader::new(bytes);
+        let header = BlockHeader::decode(&mut reader)?;
+        reader.finish()?;
+        Ok(header)
+    }
+
+    fn decode(reader: &mut Reader<'_>) -> Result<BlockHeader, DecodeError> {
+        Ok(BlockHeader {
+            version: reader.read_u32()? as i32,
+            prev_blockhash: reader.read_array()?,
+            merkle_root: reader.read_array()?,
+            time: reader.read_u32()?,
+            bits: reader.read_u32()?,
+            nonce: reader.read_u32()?,
+        })
+    }
+
+    /// Returns the block hash: HASH256 of the header, in internal byte
+    /// order.
+    pub fn block_hash(&self) -> [u8; 32] {
+        hash::hash256(&self.serialize())
+    }
+}
+
+/// A block: its header and transactions, coinbase first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    /// The block header.
+    pub header: BlockHeader,
+    /// The transactions, in block order.
+    pub txdata: Vec<Transaction>,
+}
+
+impl Block {
+    /// Serializes the block in consensus format, with witnesses.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = self.header.serialize().to_vec();
+        write_compact_size(&mut out, self.txdata.len() as u64);
+        for tx in &self.txdata {
+            out.extend_from_slice(&tx.serialize());
+        }
+        out
+    }
+
+    /// Parses a block in consensus format, as stored in Bitcoin Core's
+    /// `blk*.dat` files or returned by `getblock <hash> 0`. The whole of
+    /// `bytes` must be used.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`DecodeError`] for truncated or malformed data, for a
+    /// malformed transaction, and for bytes left over after the block.
+    pub fn deserialize(bytes: &[u8]) -> Result<Block, DecodeError> {
+        let mut reader = Reader::new(bytes);
+        let header = BlockHeader::decode(&mut reader)?;
+        let tx_count = reader.read_compact_size()?;
+        // The smallest transaction, with one input and no outputs, is
+        // 51 bytes.
+        let tx_count = reader.count(tx_count, 51)?;
+        let mut txdata = Vec::with_capacity(tx_count);
+        for _ in 0..tx_count {
+            txdata.push(Transaction::decode(&mut reader)?);
+        }
+        reader.finish()?;
+        Ok(Block { header, txdata })
+    }
+
+    /// Returns the block hash.
+    pub fn block_hash(&self) -> [u8; 32] {
+        self.header.block_hash()
+    }
+
+    /// Returns the Merkle root of the transactions' txids, or `None` if
+    /// the block has no transactions.
+    pub fn compute_merkle_root(&self) -> Option<[u8; 32]> {
+        let txids: Vec<[u8; 32]> = self.txdata.iter().map(Transaction::txid).collect();
+        merkle_root(&txids)
+    }
+
+    /// Returns the BIP141 witness root: the Merkle root of the
+    /// transactions' wtxids, with the coinbase's taken as all zeros.
+    /// `None` if the block has no transactions.
+    pub fn compute_witness_root(&self) -> Option<[u8; 32]> {
+        let wtxids: Vec<[u8; 32]> = self
+            .txdata
+            .iter()
+            .enumerate()
+            .map(|(index, tx)| if index == 0 { [0; 32] } else { tx.wtxid() })
+            .collect();
+        merkle_root(&wtxids)
+    }
+
+    /// Returns the witness commitment in the coinbase: the 32 bytes after
+    /// the header of the last output matching the BIP141 pattern.
+    pub fn witness_commitment(&self) -> Option<[u8; 32]> {
+        self.txdata
+            .first()?
+            .outputs
+            .iter()
+            .rev()
+            .find_map(|output| {
+                let script = &output.script_pubkey;
+                if script.len() >= 38 && script[..6] == WITNESS_COMMITMENT_HEADER {
+                    let mut commitment = [0; 32];
+                    commitment.copy_from_slice(&script[6..38]);
+                    Some(commitment)
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Checks the block's structure: a single coinbase in first place,
+    /// the header's Merkle root, and the BIP141 witness commitment.
+    ///
+    /// # Errors
+    ///
+    /// The first [`BlockError`] found, in the order listed on its
+    /// variants.
+    pub fn check(&self) -> Result<(), BlockError> {
+        let coinbase = self.txdata.first().ok_or(BlockError::NoTransactions)?;
+        if !coinbase.is_coinbase() {
+            return Err(BlockError::NoCoinbase);
+        }
+        if let Some(index) = self
+            .txdata
+            .iter()
+            .skip(1)
+            .position(Transaction::is_coinbase)
+        {
+            return Err(BlockError::ExtraCoinbase { index: index + 1 });
+        }
+
+        let txids: Vec<[u8; 32]> = self.txdata.iter().map(Transaction::txid).collect();
+        if is_mutated(&txids) {
+            return Err(BlockError::MutatedMerkle);
+        }
+        if merkle_root(&txids) != Some(self.header.merkle_root) {
+            return Err(BlockError::MerkleRoot);
+        }
+
+        match self.witness_commitment() {
+            Some(commitment) => {
+                let nonce = match coinbase.inputs[0].witness.as_slice() {
+                    [nonce] if nonce.len() == 32 => nonce,
+                    _ => return Err(BlockError::WitnessNonce),
+                };
+                let mut data = Vec::with_capacity(64);
+                data.extend_from_slice(&self.compute_witness_root().unwrap_or_default());
+                data.extend_from_slice(nonce);
+                if hash::hash256(&data) != commitment {
+                    return Err(BlockError::WitnessCommitment);
+                }
+            }
+            None => {
+                if let Some(index) = self.txdata.iter().position(Transaction::has_witness) {
+                    return Err(BlockError::UnexpectedWitness { index });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Computes a Merkle root as Bitcoin does: hashing pairs with HASH256 and
+/// pairing the last hash with itself on levels of odd length. Returns
+/// `None` for no hashes.
+///
+/// ```rust
+/// use mini_bitcoin_script::block::merkle_root;
+///
+/// // A single transaction's txid is the root.
+/// assert_eq!(merkle_root(&[[7; 32]]), Some([7; 32]));
+/// assert_eq!(merkle_root(&[]), None);
+/// ```
+pub fn merkle_root(hashes: &[[u8; 32]]) -> Option<[u8; 32]> {
+    let mut level = hashes.to_vec();
+    while level.len() > 1 {
+        level = parent_level(&level);
+    }
+    level.first().copied()
+}
+
+/// Hashes each pair of `level`, pairing an odd last hash with itself.
+fn parent_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| {
+            let mut data = [0u8; 64];
+            data[..32].copy_from_slice(&pair[0]);
+            data[32..].copy_from_slice(pair.last().expect("chunks are not empty"));
+            hash::hash256(&data)
+        })
+        .collect()
+}
+
+/// Whether some level of the tree has two equal hashes in a pair, so that
+/// a different list of transactions has the same root (CVE-2012-2459).
+fn is_mutated(hashes: &[[u8; 32]]) -> bool {
+    let mut level = hashes.to_vec();
+    while level.len() > 1 {
+        if level
+            .chunks(2)
+            .any(|pair| pair.len() == 2 && pair[0] == pair[1])
+        {
+            return true;
+        }
+        level = parent_level(&level);
+    }
+    false
+}
+
+/// Why a block's structure is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum BlockError {
+    /// The block has no transactions.
+    NoTransactions,
+
+    /// The first transaction is not a coinbase.
+    NoCoinbase,
+
+    /// A transaction other than the first is a coinbase.
+    ExtraCoinbase {
+        /// Index of the transaction in the block.
+        index: usize,
+    },
+
+    /// Duplicate transactions make the Merkle tree ambiguous
+    /// (CVE-2012-2459).
+    MutatedMerkle,
+
+    /// The transactions do not hash to the header's Merkle root.
+    MerkleRoot,
+
+    /// The coinbase has a witness commitment, but its witness is not a
+    /// single 32-byte nonce.
+    WitnessNonce,
+
+    /// The witness commitment does not match the transactions' wtxids.
+    WitnessCommitment,
+
+    /// A transaction has witness data, but the coinbase has no witness
+    /// commitment.
+    UnexpectedWitness {
+        /// Index of the transaction in the block.
+        index: usize,
+    },
+}
+
+impl core::fmt::Display for BlockError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BlockError::NoTransactions => write!(f, "block has no transactions"),
+            BlockError::NoCoinbase => write!(f, "first transaction is not a coinbase"),
+            BlockError::ExtraCoinbase { index } => {
+                write!(f, "transaction {index} is a second coinbase")
+            }
+            BlockError::MutatedMerkle => write!(f, "duplicate transactions in merkle tree"),
+            BlockError::MerkleRoot => write!(f, "merkle root does not match header"),
+            BlockError::WitnessNonce => write!(f, "coinbase witness is not a 32-byte nonce"),
+            BlockError::WitnessCommitment => {
+                write!(f, "witness commitment does not match transactions")
+            }
+            BlockError::UnexpectedWitness { index } => write!(
+                f,
+                "transaction {index} has witness data but the block has no witness commitment"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BlockError {}
+
+/// Why a transaction in a block failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TxFailure {
+    /// The transaction as a whole was rejected before its scripts ran,
+    /// e.g. for spending an unknown output.
+    Tx(TxVerifyError),
+
+    /// These inputs failed script verification.
+    Inputs(Vec<(usize, VerifyError)>),
+}
+
+/// The result of [`validate_block`].
+///
+/// With the `serde` feature, txids serialize as hex in display order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BlockValidation {
+    /// Total fees of the transactions that passed the amount checks.
+    pub fees: u64,
+    /// Failed transactions: their index in the block, txid and failure.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_failures"))]
+    pub failures: Vec<(usize, [u8; 32], TxFailure)>,
+}
+
+impl BlockValidation {
+    /// Returns `true` if every transaction passed.
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_failures<S: serde::Serializer>(
+    failures: &[(usize, [u8; 32], TxFailure)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use crate::hex::ReversedHex;
+    serializer.collect_seq(
+        failures
+            .iter()
+            .map(|(index, txid, failure)| (index, ReversedHex(txid), failure)),
+    )
+}
+
+/// Checks `block` with [`Block::check`], then verifies the scripts of
+/// every non-coinbase transaction with [`verify_tx`](crate::verify::verify_tx).
+///
+/// Outputs are looked up in `utxos` (typically a snapshot of the UTXO set
+/// before the block) or among the outputs of earlier non-coinbase
+/// transactions in the block, and each can be spent once. A failing
+/// transaction's outputs stay spendable, so that failures are reported
+/// independently rather than cascading.
+///
+/// # Errors
+///
+/// A [`BlockError`] if the block's structure is invalid; no script runs
+/// then. Failing transactions are listed in
+/// [`BlockValidation::failures`] instead.
+pub fn validate_block<P: UtxoProvider + ?Sized>(
+    block: &Block,
+    utxos: &P,
+    flags: VerifyFlags,
+) -> Result<BlockValidation, BlockError> {
+    validate_block_with_opts(block, utxos, &flags.into())
+}
+
+/// [`validate_block`] with [`VerifyOpts`], e.g. to reuse the signature
+/// cache that checked the block's transactions for the mempool, or to
+/// replay a historic block under the rules of its height.
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use mini_bitcoin_script::block::{validate_block_with_opts, Block};
+/// use mini_bitcoin_script::snapshot::UtxoSnapshot;
+/// use mini_bitcoin_script::verify::VerifyOpts;
+///
+/// let utxos = UtxoSnapshot::deserialize(&std::fs::read("utxos-169.bin")?)?;
+/// let block = Block::deserialize(&std::fs::read("block-170.bin")?)?;
+/// let result = validate_block_with_opts(&block, &utxos, &VerifyOpts::mainnet(170))?;
+/// assert!(result.is_valid());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// As for [`validate_block`].
+pub fn validate_block_with_opts<P: UtxoProvider + ?Sized>(
+    block: &Block,
+    utxos: &P,
+    opts: &VerifyOpts,
+) -> Result<BlockValidation, BlockError> {
+    block.check()?;
+
+    let mut view = BlockUtxos {
+        utxos,
+        created: BTreeMap::new(),
+        spent: BTreeSet::new(),
+    };
+    let mut result = BlockValidation::default();
+    for (index, tx) in block.txdata.iter().enumerate().skip(1) {
+        let txid = tx.txid();
+        match verify_tx_with_opts(tx, &view, opts) {
+            Ok(verification) => {
+                result.fees += verification.fee;
+                let failed: Vec<(usize, VerifyError)> = verification
+                    .inputs
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(input, result)| result.err().map(|err| (input, err)))
+                    .collect();
+                if !failed.is_empty() {
+                    result
+                        .failures
+                        .push((index, txid, TxFailure::Inputs(failed)));
+                }
+            }
+            Err(err) => result.failures.push((index, txid, TxFailure::Tx(err))),
+        }
+
+        for input in &tx.inputs {
+            view.spent.insert(input.previous_output);
+        }
+        for (vout, output) in tx.outputs.iter().enumerate() {
+            let outpoint = OutPoint {
+                txid,
+                vout: vout as u32,
+            };
+            view.created.insert(outpoint, output.clone());
+        }
+    }
+    Ok(result)
+}
+
+/// The UTXO set as seen partway through a block.
+struct BlockUtxos<'a, P: ?Sized> {
+    utxos: &'a P,
+    created: BTreeMap<OutPoint, TxOut>,
+    spent: BTreeSet<OutPoint>,
+}
+
+impl<P: UtxoProvider + ?Sized> UtxoProvider for BlockUtxos<'_, P> {
+    fn utxo(&self, outpoint: &OutPoint) -> Option<TxOut> {
+        if self.spent.contains(outpoint) {
+            return None;
+        }
+        match self.created.get(outpoint) {
+            Some(output) => Some(output.clone()),
+            None => self.utxos.utxo(outpoint),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merkle_root_pairs_last_hash_with_itself() {
+        let (a, b, c) = ([1; 32], [2; 32], [3; 32]);
+        let pair = |x: [u8; 32], y: [u8; 32]| {
+            let mut data = x.to_vec();
+            data.extend_from_slice(&y);
+            hash::hash256(&data)
+        };
+        assert_eq!(merkle_root(&[a, b]), Some(pair(a, b)));
+        let expected = pair(pair(a, b), pair(c, c));
+        assert_eq!(merkle_root(&[a, b, c]), Some(expected));
+        // [a, b, c, c] has the same root, which is why it is rejected.
+        assert_eq!(merkle_root(&[a, b, c, c]), Some(expected));
+        assert!(is_mutated(&[a, b, c, c]));
+        assert!(!is_mutated(&[a, b, c]));
+    }
+}
@@ -0,0 +1,236 @@
+use alloc::vec::Vec;
+
+use crate::engine::encode_num;
+use crate::error::ScriptError;
+use crate::opcode::Opcode;
+use crate::script::ScriptBuf;
+
+/// Incrementally builds raw script bytes.
+///
+/// Pushes always use the smallest push-data encoding for their length, so
+/// the output satisfies the MINIMALDATA rule for data pushes.
+///
+/// ```rust
+/// use mini_bitcoin_script::builder::ScriptBuilder;
+/// use mini_bitcoin_script::opcode::Opcode;
+///
+/// let script = ScriptBuilder::new()
+///     .push_int(2)
+///     .push_slice(&[0xab; 3])
+///     .push_opcode(Opcode::OpEqual)
+///     .into_bytes();
+/// assert_eq!(script, [0x52, 0x03, 0xab, 0xab, 0xab, 0x87]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptBuilder {
+    bytes: Vec<u8>,
+}
+
+impl ScriptBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an opcode.
+    pub fn push_opcode(mut self, opcode: Opcode) -> Self {
+        self.bytes.push(opcode.to_byte());
+        self
+    }
+
+    /// Appends a data push using the shortest length encoding
+    /// (direct, OP_PUSHDATA1, OP_PUSHDATA2 or OP_PUSHDATA4).
+    ///
+    /// An empty slice is pushed as OP_0.
+    pub fn push_slice(mut self, data: &[u8]) -> Self {
+        let len = data.len();
+        match len {
+            0..=0x4b => self.bytes.push(len as u8),
+            0x4c..=0xff => {
+                self.bytes.push(0x4c);
+                self.bytes.push(len as u8);
+            }
+            0x100..=0xffff => {
+                self.bytes.push(0x4d);
+                self.bytes.extend_from_slice(&(len as u16).to_le_bytes());
+            }
+            _ => {
+                self.bytes.push(0x4e);
+                self.bytes.extend_from_slice(&(len as u32).to_le_bytes());
+            }
+        }
+        self.bytes.extend_from_slice(data);
+        self
+    }
+
+    /// Appends a number: OP_0, OP_1NEGATE or OP_1–OP_16 where possible,
+    /// otherwise a minimally encoded script-number push.
+    pub fn push_int(mut self, n: i64) -> Self {
+        match n {
+            0 => self.push_opcode(Opcode::Op0),
+            -1 => self.push_opcode(Opcode::Op1Negate),
+            1..=16 => {
+                self.bytes.push(0x50 + n as u8);
+                self
+            }
+            _ => self.push_slice(&encode_num(n)),
+        }
+    }
+
+    /// Returns the script bytes built so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Consumes the builder and returns the script bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Consumes the builder and returns the script as a [`ScriptBuf`].
+    pub fn into_script(self) -> ScriptBuf {
+        ScriptBuf::from_bytes(self.bytes)
+    }
+}
+
+/// Pay-to-Public-Key-Hash:
+/// `OP_DUP OP_HASH160 <pubkey_hash> OP_EQUALVERIFY OP_CHECKSIG`.
+pub fn p2pkh(pubkey_hash: &[u8; 20]) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_opcode(Opcode::OpDup)
+        .push_opcode(Opcode::OpHash160)
+        .push_slice(pubkey_hash)
+        .push_opcode(Opcode::OpEqualVerify)
+        .push_opcode(Opcode::OpCheckSig)
+        .into_bytes()
+}
+
+/// Pay-to-Script-Hash (BIP16): `OP_HASH160 <script_hash> OP_EQUAL`.
+pub fn p2sh(script_hash: &[u8; 20]) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_opcode(Opcode::OpHash160)
+        .push_slice(script_hash)
+        .push_opcode(Opcode::OpEqual)
+        .into_bytes()
+}
+
+/// Pay-to-Witness-Public-Key-Hash (BIP141): `OP_0 <pubkey_hash>`.
+pub fn p2wpkh(pubkey_hash: &[u8; 20]) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_opcode(Opcode::Op0)
+        .push_slice(pubkey_hash)
+        .into_bytes()
+}
+
+/// Pay-to-Witness-Script-Hash (BIP141): `OP_0 <sha256(witness_script)>`.
+pub fn p2wsh(script_hash: &[u8; 32]) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_opcode(Opcode::Op0)
+        .push_slice(script_hash)
+        .into_bytes()
+}
+
+/// Pay-to-Taproot (BIP341): `OP_1 <output_key>`, where `output_key` is the
+/// tweaked x-only public key.
+pub fn p2tr(output_key: &[u8; 32]) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_opcode(Opcode::Op1)
+        .push_slice(output_key)
+        .into_bytes()
+}
+
+/// Bare m-of-n multisig: `OP_m <pubkey>... OP_n OP_CHECKMULTISIG`.
+///
+/// Public keys appear in the order given; signatures must later be
+/// supplied in the same order.
+///
+/// # Errors
+///
+/// Returns [`ScriptError::PubkeyCount`] if there are no keys or more than
+/// 20, and [`ScriptError::SigCount`] if `m` is zero or exceeds the number
+/// of keys.
+pub fn multisig(m: usize, pubkeys: &[&[u8]]) -> Result<Vec<u8>, ScriptError> {
+    if pubkeys.is_empty() || pubkeys.len() > 20 {
+        return Err(ScriptError::PubkeyCount);
+    }
+    if m == 0 || m > pubkeys.len() {
+        return Err(ScriptError::SigCount);
+    }
+
+    let mut builder = ScriptBuilder::new().push_int(m as i64);
+    for pubkey in pubkeys {
+        builder = builder.push_slice(pubkey);
+    }
+    Ok(builder
+        .push_int(pubkeys.len() as i64)
+        .push_opcode(Opcode::OpCheckMultiSig)
+        .into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use hex_literal::hex;
+
+    #[test]
+    fn push_slice_encodings() {
+        let lengths = [(0, 1), (75, 1), (76, 2), (255, 2), (256, 3), (65_536, 5)];
+        for (len, prefix) in lengths {
+            let bytes = ScriptBuilder::new().push_slice(&vec![0; len]).into_bytes();
+            assert_eq!(bytes.len(), len + prefix, "length {len}");
+        }
+        assert_eq!(ScriptBuilder::new().push_slice(&[]).into_bytes(), [0x00]);
+        assert_eq!(
+            ScriptBuilder::new().push_slice(&[0; 76]).as_bytes()[..2],
+            [0x4c, 76]
+        );
+    }
+
+    #[test]
+    fn push_int_encodings() {
+        let encode = |n| ScriptBuilder::new().push_int(n).into_bytes();
+        assert_eq!(encode(0), [0x00]);
+        assert_eq!(encode(-1), [0x4f]);
+        assert_eq!(encode(1), [0x51]);
+        assert_eq!(encode(16), [0x60]);
+        assert_eq!(encode(17), [0x01, 0x11]);
+        assert_eq!(encode(-2), [0x01, 0x82]);
+        assert_eq!(encode(1000), [0x02, 0xe8, 0x03]);
+    }
+
+    #[test]
+    fn templates() {
+        assert_eq!(
+            p2pkh(&[0xaa; 20]),
+            [&hex!("76a914")[..], &[0xaa; 20], &hex!("88ac")].concat()
+        );
+        assert_eq!(
+            p2sh(&[0xbb; 20]),
+            [&hex!("a914")[..], &[0xbb; 20], &hex!("87")].concat()
+        );
+        assert_eq!(
+            p2wpkh(&[0xcc; 20]),
+            [&hex!("0014")[..], &[0xcc; 20]].concat()
+        );
+        assert_eq!(
+            p2wsh(&[0xdd; 32]),
+            [&hex!("0020")[..], &[0xdd; 32]].concat()
+        );
+        assert_eq!(p2tr(&[0xee; 32]), [&hex!("5120")[..], &[0xee; 32]].concat());
+    }
+
+    #[test]
+    fn multisig_template() {
+        let a = [0x02; 33];
+        let b = [0x03; 33];
+        let script = multisig(1, &[&a, &b]).unwrap();
+        assert_eq!(script.len(), 1 + 2 * 34 + 2);
+        assert_eq!(script[0], 0x51);
+        assert_eq!(&script[script.len() - 2..], &[0x52, 0xae]);
+
+        assert_eq!(multisig(0, &[&a]), Err(ScriptError::SigCount));
+        assert_eq!(multisig(2, &[&a]), Err(ScriptError::SigCount));
+        assert_eq!(multisig(1, &[]), Err(ScriptError::PubkeyCount));
+    }
+}
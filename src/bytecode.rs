@@ -0,0 +1,386 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::engine::{execute_opcode, ExecuteOpts, SigVersion};
+use crate::error::ScriptError;
+use crate::opcode::Opcode;
+use crate::stack::{is_true, Stack};
+use crate::token::Token;
+
+/// One step of a [`CompiledScript`].
+///
+/// Push data lives in the script's shared buffer and is referenced by
+/// offset and length; jump targets are instruction indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    /// Push `data[start..start + len]`.
+    Push { start: u32, len: u32 },
+    /// A push followed by OP_EQUAL: compares the top item with the data
+    /// without copying it onto the stack.
+    PushEqual { start: u32, len: u32 },
+    /// A push followed by OP_EQUALVERIFY.
+    PushEqualVerify { start: u32, len: u32 },
+    /// OP_IF: pops the condition and jumps to `target` if it is false.
+    If { target: u32 },
+    /// OP_NOTIF: pops the condition and jumps to `target` if it is true.
+    NotIf { target: u32 },
+    /// OP_ELSE reached from an executing branch: skips to `target`, just
+    /// past the next OP_ELSE or OP_ENDIF at the same depth.
+    Else { target: u32 },
+    /// An OP_ELSE or OP_ENDIF with no open conditional.
+    Unbalanced,
+    /// Any other opcode, run by the engine.
+    Op(Opcode),
+}
+
+/// A script compiled for repeated execution.
+///
+/// [`execute`](crate::engine::execute) walks the tokens on every run,
+/// re-matching each one, tracking which conditional branches execute and
+/// cloning push data. Compiling once moves that work out of the loop:
+///
+/// - push data, including OP_0 to OP_16, is copied into one shared
+///   buffer, with identical pushes stored once;
+/// - OP_IF, OP_NOTIF and OP_ELSE become jumps to pre-resolved targets,
+///   so skipped branches cost nothing, and OP_ENDIF and OP_NOP compile
+///   to no instructions at all;
+/// - a push followed by OP_EQUAL or OP_EQUALVERIFY, as in P2PKH and P2SH
+///   templates, becomes one instruction comparing against the buffer.
+///
+/// Every other opcode runs the engine's own implementation, so a compiled
+/// script returns the same result and error, and leaves the same stack,
+/// as executing its tokens with the same [`ExecuteOpts`]. Unbalanced
+/// conditionals still fail only when execution reaches them.
+///
+/// ```rust
+/// use mini_bitcoin_script::bytecode::CompiledScript;
+/// use mini_bitcoin_script::engine::{execute_on_items, ExecuteOpts};
+/// use mini_bitcoin_script::tokenizer::parse_script_hex;
+///
+/// // OP_IF OP_2 OP_ELSE OP_3 OP_ENDIF OP_3 OP_EQUAL
+/// let tokens = parse_script_hex("63526753685387").unwrap();
+/// let compiled = CompiledScript::compile(&tokens);
+/// let opts = ExecuteOpts::default();
+///
+/// for condition in [vec![], vec![1]] {
+///     let mut items = vec![condition.clone()];
+///     compiled.execute_on_items(&mut items, &opts).unwrap();
+///
+///     let mut expected = vec![condition];
+///     execute_on_items(&tokens, &mut expected, &opts).unwrap();
+///     assert_eq!(items, expected);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledScript {
+    instructions: Vec<Instruction>,
+    data: Vec<u8>,
+    /// Whether an OP_IF or OP_NOTIF is never closed, which fails the
+    /// script if execution reaches the end.
+    unclosed: bool,
+    /// Whether the script contains a disabled opcode, which is OP_SUCCESSx
+    /// in tapscript.
+    disabled: bool,
+}
+
+impl CompiledScript {
+    /// Compiles a token sequence.
+    ///
+    /// Compilation never fails: errors are reported when the compiled
+    /// script runs, at the point the engine would report them.
+    ///
+    /// # Panics
+    ///
+    /// If the script's push data exceeds 4 GiB.
+    pub fn compile(tokens: &[Token]) -> CompiledScript {
+        let mut compiler = Compiler::default();
+        for token in tokens {
+            compiler.token(token);
+        }
+        compiler.finish()
+    }
+
+    /// The number of instructions, after jumps are resolved and
+    /// instructions fused.
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    /// Returns `true` if the script compiled to no instructions.
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    /// Runs the script on a fresh stack.
+    ///
+    /// The result matches
+    /// [`execute_with_opts`](crate::engine::execute_with_opts) on the
+    /// original tokens.
+    pub fn execute(&self, opts: &ExecuteOpts) -> Result<bool, ScriptError> {
+        if self.is_op_success(opts) {
+            return Ok(true);
+        }
+        let mut stack = Stack::new();
+        self.execute_on_stack(&mut stack, opts)?;
+        if stack.is_empty() {
+            return Ok(false);
+        }
+        Ok(is_true(&stack.pop()?))
+    }
+
+    /// Runs the script on a caller-owned stack, ordered bottom to top.
+    ///
+    /// The result and the final `items` match
+    /// [`execute_on_items`](crate::engine::execute_on_items) on the
+    /// original tokens, including after an error.
+    pub fn execute_on_items(
+        &self,
+        items: &mut Vec<Vec<u8>>,
+        opts: &ExecuteOpts,
+    ) -> Result<(), ScriptError> {
+        let mut stack = Stack::from_items(core::mem::take(items));
+        let result = self.execute_on_stack(&mut stack, opts);
+        *items = stack.into_items();
+        result
+    }
+
+    pub(crate) fn execute_on_stack(
+        &self,
+        stack: &mut Stack,
+        opts: &ExecuteOpts,
+    ) -> Result<(), ScriptError> {
+        if self.is_op_success(opts) {
+            return Ok(());
+        }
+        let mut alt_stack: Vec<Vec<u8>> = Vec::new();
+        let mut pc = 0;
+
+        while let Some(&instruction) = self.instructions.get(pc) {
+            pc += 1;
+            match instruction {
+                Instruction::Push { start, len } => stack.push(self.slice(start, len).to_vec()),
+                Instruction::PushEqual { start, len } => {
+                    let a = stack.pop()?;
+                    stack.push_bool(a == self.slice(start, len));
+                }
+                Instruction::PushEqualVerify { start, len } => {
+                    if stack.pop()? != self.slice(start, len) {
+                        return Err(ScriptError::EqualVerifyFailed);
+                    }
+                }
+                Instruction::If { target } => {
+                    if !is_true(&stack.pop()?) {
+                        pc = target as usize;
+                    }
+                }
+                Instruction::NotIf { target } => {
+                    if is_true(&stack.pop()?) {
+                        pc = target as usize;
+                    }
+                }
+                Instruction::Else { target } => pc = target as usize,
+                Instruction::Unbalanced => return Err(ScriptError::UnbalancedConditional),
+                Instruction::Op(op) => execute_opcode(op, stack, &mut alt_stack, opts)?,
+            }
+        }
+
+        if self.unclosed {
+            return Err(ScriptError::UnbalancedConditional);
+        }
+        Ok(())
+    }
+
+    fn is_op_success(&self, opts: &ExecuteOpts) -> bool {
+        self.disabled && opts.sig_version == SigVersion::Tapscript
+    }
+
+    fn slice(&self, start: u32, len: u32) -> &[u8] {
+        &self.data[start as usize..][..len as usize]
+    }
+}
+
+/// State while compiling a script.
+#[derive(Default)]
+struct Compiler {
+    instructions: Vec<Instruction>,
+    data: Vec<u8>,
+    /// Offset of each distinct push in `data`.
+    pushes: BTreeMap<Vec<u8>, u32>,
+    /// The OP_IF, OP_NOTIF or OP_ELSE instruction of each open
+    /// conditional, whose target is not yet known, innermost last.
+    open: Vec<usize>,
+    /// The instruction index most recently made a jump target.
+    last_target: Option<usize>,
+    /// Set once a disabled opcode is compiled. The engine fails there
+    /// whichever branch it is in, so every open jump lands on it and the
+    /// rest of the script is dropped.
+    disabled: bool,
+}
+
+impl Compiler {
+    fn token(&mut self, token: &Token) {
+        if self.disabled {
+            return;
+        }
+        match token {
+            Token::PushData(data) => self.push(data),
+            Token::Op(op) => match op {
+                Opcode::Op0 => self.push(&[]),
+                Opcode::Op1Negate => self.push(&[0x81]),
+                Opcode::OpIf => self.open(Instruction::If { target: 0 }),
+                Opcode::OpNotIf => self.open(Instruction::NotIf { target: 0 }),
+                Opcode::OpElse => match self.open.pop() {
+                    Some(branch) => {
+                        let at = self.instructions.len();
+                        self.resolve(branch, at + 1);
+                        self.open(Instruction::Else { target: 0 });
+                    }
+                    None => self.instructions.push(Instruction::Unbalanced),
+                },
+                Opcode::OpEndIf => match self.open.pop() {
+                    Some(branch) => self.resolve(branch, self.instructions.len()),
+                    None => self.instructions.push(Instruction::Unbalanced),
+                },
+                op if op.is_disabled() => {
+                    let at = self.instructions.len();
+                    while let Some(branch) = self.open.pop() {
+                        self.resolve(branch, at);
+                    }
+                    self.instructions.push(Instruction::Op(*op));
+                    self.disabled = true;
+                }
+                Opcode::OpNop => {}
+                Opcode::OpEqual => {
+                    self.fuse(*op, |start, len| Instruction::PushEqual { start, len })
+                }
+                Opcode::OpEqualVerify => self.fuse(*op, |start, len| {
+                    Instruction::PushEqualVerify { start, len }
+                }),
+                // OP_1 to OP_16 push 1 to 16.
+                op => match op.to_byte() {
+                    byte @ 0x51..=0x60 => self.push(&[byte - 0x50]),
+                    _ => self.instructions.push(Instruction::Op(*op)),
+                },
+            },
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        let len = to_u32(bytes.len());
+        let start = match self.pushes.get(bytes) {
+            Some(&start) => start,
+            None => {
+                let start = to_u32(self.data.len());
+                self.data.extend_from_slice(bytes);
+                self.pushes.insert(bytes.to_vec(), start);
+                start
+            }
+        };
+        self.instructions.push(Instruction::Push { start, len });
+    }
+
+    /// Replaces a preceding push with `fused`, unless a jump lands
+    /// between the two.
+    fn fuse(&mut self, op: Opcode, fused: impl FnOnce(u32, u32) -> Instruction) {
+        let at = self.instructions.len();
+        if self.last_target != Some(at) {
+            if let Some(Instruction::Push { start, len }) = self.instructions.last().copied() {
+                self.instructions[at - 1] = fused(start, len);
+                return;
+            }
+        }
+        self.instructions.push(Instruction::Op(op));
+    }
+
+    fn open(&mut self, instruction: Instruction) {
+        self.open.push(self.instructions.len());
+        self.instructions.push(instruction);
+    }
+
+    fn resolve(&mut self, branch: usize, target: usize) {
+        let resolved = to_u32(target);
+        match &mut self.instructions[branch] {
+            Instruction::If { target }
+            | Instruction::NotIf { target }
+            | Instruction::Else { target } => *target = resolved,
+            _ => unreachable!("open conditionals are jumps"),
+        }
+        self.last_target = Some(target);
+    }
+
+    fn finish(mut self) -> CompiledScript {
+        let unclosed = !self.open.is_empty();
+        // Unclosed branches skip to the end, where the script fails.
+        let end = self.instructions.len();
+        while let Some(branch) = self.open.pop() {
+            self.resolve(branch, end);
+        }
+        CompiledScript {
+            instructions: self.instructions,
+            data: self.data,
+            unclosed,
+            disabled: self.disabled,
+        }
+    }
+}
+
+fn to_u32(n: usize) -> u32 {
+    u32::try_from(n).expect("compiled scripts are limited to 4 GiB")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::parse_asm;
+
+    fn compile(asm: &str) -> CompiledScript {
+        CompiledScript::compile(&parse_asm(asm).unwrap())
+    }
+
+    #[test]
+    fn resolves_jumps_and_shares_pushes() {
+        let compiled = compile("OP_IF <1234> OP_ELSE OP_1 <1234> OP_ENDIF OP_NOP OP_EQUAL");
+        assert_eq!(
+            compiled.instructions,
+            [
+                Instruction::If { target: 3 },
+                Instruction::Push { start: 0, len: 2 },
+                Instruction::Else { target: 5 },
+                Instruction::Push { start: 2, len: 1 },
+                Instruction::Push { start: 0, len: 2 },
+                // Both branches reach OP_EQUAL, so it is not fused.
+                Instruction::Op(Opcode::OpEqual),
+            ]
+        );
+        assert_eq!(compiled.data, [0x12, 0x34, 0x01]);
+        assert!(!compiled.unclosed);
+    }
+
+    #[test]
+    fn fuses_push_and_compare() {
+        let compiled = compile("OP_HASH160 <89ab> OP_EQUAL");
+        assert_eq!(
+            compiled.instructions,
+            [
+                Instruction::Op(Opcode::OpHash160),
+                Instruction::PushEqual { start: 0, len: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn unbalanced_conditionals() {
+        let compiled = compile("OP_ENDIF OP_IF OP_ELSE OP_ELSE");
+        assert_eq!(
+            compiled.instructions,
+            [
+                Instruction::Unbalanced,
+                Instruction::If { target: 3 },
+                Instruction::Else { target: 4 },
+                // Unclosed: skips to the end, which fails.
+                Instruction::Else { target: 4 },
+            ]
+        );
+        assert!(compiled.unclosed);
+    }
+}
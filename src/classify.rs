@@ -0,0 +1,310 @@
+use alloc::vec::Vec;
+
+use crate::signature::pubkey_encoding;
+
+const OP_0: u8 = 0x00;
+const OP_PUSHDATA4: u8 = 0x4e;
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
+const OP_RETURN: u8 = 0x6a;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKMULTISIG: u8 = 0xae;
+
+/// The standard output templates, as recognized by Bitcoin Core's
+/// `Solver`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputType {
+    /// Matches no template.
+    NonStandard,
+    /// Pay-to-Public-Key: `<pubkey> OP_CHECKSIG`.
+    P2pk,
+    /// Pay-to-Public-Key-Hash.
+    P2pkh,
+    /// Pay-to-Script-Hash (BIP16).
+    P2sh,
+    /// Bare multisig: `OP_m <pubkey>... OP_n OP_CHECKMULTISIG`.
+    Multisig {
+        /// Signatures required (m).
+        required: u8,
+        /// Public keys listed (n).
+        keys: u8,
+    },
+    /// `OP_RETURN` followed only by pushes; provably unspendable.
+    NullData,
+    /// Version 0, 20-byte witness program (BIP141).
+    P2wpkh,
+    /// Version 0, 32-byte witness program (BIP141).
+    P2wsh,
+    /// Version 1, 32-byte witness program (BIP341).
+    P2tr,
+    /// A witness program of a version or length with no defined meaning
+    /// yet, reserved for future soft forks.
+    WitnessUnknown {
+        /// The witness version, 1 to 16 (0 is never unknown).
+        version: u8,
+    },
+}
+
+impl OutputType {
+    /// Returns `true` for the native and future segwit output types.
+    pub fn is_witness(&self) -> bool {
+        matches!(
+            self,
+            OutputType::P2wpkh
+                | OutputType::P2wsh
+                | OutputType::P2tr
+                | OutputType::WitnessUnknown { .. }
+        )
+    }
+}
+
+/// Classifies a scriptPubKey.
+///
+/// ```rust
+/// use mini_bitcoin_script::builder;
+/// use mini_bitcoin_script::classify::{classify, OutputType};
+///
+/// assert_eq!(classify(&builder::p2pkh(&[0; 20])), OutputType::P2pkh);
+/// assert_eq!(classify(&[0x6a, 0x01, 0xff]), OutputType::NullData);
+/// assert_eq!(classify(&[0x51]), OutputType::NonStandard);
+/// ```
+pub fn classify(script_pubkey: &[u8]) -> OutputType {
+    let s = script_pubkey;
+
+    if is_p2sh(s) {
+        return OutputType::P2sh;
+    }
+    if let Some((version, program)) = witness_program(s) {
+        return match (version, program.len()) {
+            (0, 20) => OutputType::P2wpkh,
+            (0, 32) => OutputType::P2wsh,
+            (0, _) => OutputType::NonStandard,
+            (1, 32) => OutputType::P2tr,
+            (version, _) => OutputType::WitnessUnknown { version },
+        };
+    }
+    if s.first() == Some(&OP_RETURN) && is_push_only(&s[1..]) {
+        return OutputType::NullData;
+    }
+    if s.len() == 25 && s[..3] == [0x76, 0xa9, 0x14] && s[23..] == [0x88, OP_CHECKSIG] {
+        return OutputType::P2pkh;
+    }
+    if let [len @ (33 | 65), ref key @ .., OP_CHECKSIG] = *s {
+        if key.len() == len as usize && pubkey_encoding(key).is_some() {
+            return OutputType::P2pk;
+        }
+    }
+    if let Some((required, keys)) = match_multisig(s) {
+        return OutputType::Multisig { required, keys };
+    }
+    OutputType::NonStandard
+}
+
+/// Returns `true` for `OP_HASH160 <20 bytes> OP_EQUAL`.
+pub fn is_p2sh(script_pubkey: &[u8]) -> bool {
+    let s = script_pubkey;
+    s.len() == 23 && s[0] == 0xa9 && s[1] == 0x14 && s[22] == 0x87
+}
+
+/// Splits a witness program into its version and program bytes: a version
+/// opcode (OP_0 or OP_1–OP_16) followed by a single 2–40 byte push, with
+/// nothing else.
+pub fn witness_program(script_pubkey: &[u8]) -> Option<(u8, &[u8])> {
+    let s = script_pubkey;
+    if s.len() < 4 || s.len() > 42 {
+        return None;
+    }
+    let version = match s[0] {
+        OP_0 => 0,
+        op @ OP_1..=OP_16 => op - OP_1 + 1,
+        _ => return None,
+    };
+    if s[1] as usize + 2 != s.len() {
+        return None;
+    }
+    Some((version, &s[2..]))
+}
+
+/// Returns `true` if the script contains only push operations (including
+/// OP_0, OP_1NEGATE and OP_1–OP_16) and parses to the end.
+pub fn is_push_only(script: &[u8]) -> bool {
+    let mut pos = 0;
+    while pos < script.len() {
+        match read_instruction(script, pos) {
+            Some((op, _, next)) if op <= OP_16 => pos = next,
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Reads the instruction at `pos`, returning its opcode byte, its push data
+/// (if any) and the position of the next instruction. `None` if a push
+/// runs past the end of the script.
+pub(crate) fn read_instruction(script: &[u8], pos: usize) -> Option<(u8, Option<&[u8]>, usize)> {
+    let op = *script.get(pos)?;
+    let mut pos = pos + 1;
+    let len = match op {
+        0x01..=0x4b => op as usize,
+        0x4c..=OP_PUSHDATA4 => {
+            let width = match op {
+                0x4c => 1,
+                0x4d => 2,
+                _ => 4,
+            };
+            let bytes = script.get(pos..pos + width)?;
+            pos += width;
+            bytes
+                .iter()
+                .rev()
+                .fold(0usize, |acc, &b| (acc << 8) | b as usize)
+        }
+        _ => return Some((op, None, pos)),
+    };
+    let data = script.get(pos..pos.checked_add(len)?)?;
+    Some((op, Some(data), pos + len))
+}
+
+/// The data of the last push in a push-only script, e.g. the redeem
+/// script of a P2SH scriptSig. `None` if the script is not push-only or
+/// its last push carries no data (OP_0, OP_1NEGATE, OP_1–OP_16).
+pub(crate) fn last_push(script: &[u8]) -> Option<&[u8]> {
+    if !is_push_only(script) {
+        return None;
+    }
+    let mut last = None;
+    let mut pos = 0;
+    while let Some((_, data, next)) = read_instruction(script, pos) {
+        last = data;
+        pos = next;
+    }
+    last
+}
+
+/// The signatures required and the public keys of a bare multisig
+/// script, as matched by [`classify`].
+pub(crate) fn multisig_keys(script: &[u8]) -> Option<(usize, Vec<&[u8]>)> {
+    let (required, n) = match_multisig(script)?;
+    let mut keys = Vec::with_capacity(n as usize);
+    let mut pos = 1;
+    for _ in 0..n {
+        let (_, data, next) = read_instruction(script, pos)?;
+        keys.push(data?);
+        pos = next;
+    }
+    Some((required as usize, keys))
+}
+
+/// Matches `OP_m <pubkey>... OP_n OP_CHECKMULTISIG` with 1 <= m <= n <= 16.
+fn match_multisig(script: &[u8]) -> Option<(u8, u8)> {
+    let (&last, body) = script.split_last()?;
+    if last != OP_CHECKMULTISIG {
+        return None;
+    }
+    let (&first, body) = body.split_first()?;
+    let (&count, mut keys) = body.split_last()?;
+    let small_int = |op: u8| (OP_1..=OP_16).contains(&op).then(|| op - OP_1 + 1);
+    let required = small_int(first)?;
+    let n = small_int(count)?;
+
+    let mut found = 0u8;
+    while !keys.is_empty() {
+        let (_, data, next) = read_instruction(keys, 0)?;
+        pubkey_encoding(data?)?;
+        found += 1;
+        keys = &keys[next..];
+    }
+    (found == n && required <= n).then_some((required, n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder;
+    use alloc::vec;
+
+    #[test]
+    fn templates() {
+        assert_eq!(classify(&builder::p2pkh(&[1; 20])), OutputType::P2pkh);
+        assert_eq!(classify(&builder::p2sh(&[1; 20])), OutputType::P2sh);
+        assert_eq!(classify(&builder::p2wpkh(&[1; 20])), OutputType::P2wpkh);
+        assert_eq!(classify(&builder::p2wsh(&[1; 32])), OutputType::P2wsh);
+        assert_eq!(classify(&builder::p2tr(&[1; 32])), OutputType::P2tr);
+
+        let key = [0x02; 33];
+        let p2pk = [&[33][..], &key, &[OP_CHECKSIG]].concat();
+        assert_eq!(classify(&p2pk), OutputType::P2pk);
+
+        let multisig = builder::multisig(2, &[&key, &key, &key]).unwrap();
+        assert_eq!(
+            classify(&multisig),
+            OutputType::Multisig {
+                required: 2,
+                keys: 3
+            }
+        );
+    }
+
+    #[test]
+    fn witness_programs() {
+        assert_eq!(
+            classify(&[0x52, 0x02, 0xaa, 0xbb]),
+            OutputType::WitnessUnknown { version: 2 }
+        );
+        // v0 programs must be 20 or 32 bytes.
+        assert_eq!(classify(&[0x00, 0x02, 0xaa, 0xbb]), OutputType::NonStandard);
+        // Too short to be a program.
+        assert_eq!(classify(&[0x51, 0x01, 0xaa]), OutputType::NonStandard);
+        assert_eq!(
+            witness_program(&builder::p2tr(&[7; 32])),
+            Some((1, &[7; 32][..]))
+        );
+    }
+
+    #[test]
+    fn null_data() {
+        assert_eq!(classify(&[OP_RETURN]), OutputType::NullData);
+        assert_eq!(
+            classify(&[OP_RETURN, 0x4c, 0x01, 0xff]),
+            OutputType::NullData
+        );
+        // A non-push after OP_RETURN.
+        assert_eq!(classify(&[OP_RETURN, OP_CHECKSIG]), OutputType::NonStandard);
+        // A truncated push.
+        assert_eq!(classify(&[OP_RETURN, 0x05, 0xff]), OutputType::NonStandard);
+    }
+
+    #[test]
+    fn malformed_multisig() {
+        let key = [0x02; 33];
+        let mut script = builder::multisig(1, &[&key, &key]).unwrap();
+        // Claims 3 keys.
+        let len = script.len();
+        script[len - 2] = 0x53;
+        assert_eq!(classify(&script), OutputType::NonStandard);
+
+        // Not a public key.
+        let script = [vec![OP_1, 0x01, 0xaa, OP_1], vec![OP_CHECKMULTISIG]].concat();
+        assert_eq!(classify(&script), OutputType::NonStandard);
+    }
+
+    #[test]
+    fn push_only() {
+        assert!(is_push_only(&[]));
+        assert!(is_push_only(&[0x00, 0x4f, 0x51, 0x60, 0x01, 0xaa]));
+        assert!(!is_push_only(&[0x61]));
+        assert!(!is_push_only(&[0x02, 0xaa]));
+    }
+
+    #[test]
+    fn last_push_of_script() {
+        assert_eq!(
+            last_push(&[0x00, 0x02, 0xaa, 0xbb]),
+            Some(&[0xaa, 0xbb][..])
+        );
+        // A trailing OP_0 leaves no data; Core's empty redeem script
+        // counts 0 sigops either way.
+        assert_eq!(last_push(&[0x01, 0xaa, 0x00]), None);
+        assert_eq!(last_push(&[]), None);
+    }
+}
@@ -0,0 +1,1080 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
+
+use crate::base58::decode_base58check;
+use crate::bip32::{parse_child, write_path, KeyOrigin, Xpub, HARDENED};
+use crate::builder::{self, ScriptBuilder};
+use crate::classify::{classify, witness_program, OutputType};
+use crate::hash;
+use crate::hex::{decode_hex, encode_hex, HexError};
+use crate::opcode::Opcode;
+use crate::script::ScriptBuf;
+use crate::signature::{pubkey_encoding, PubkeyEncoding};
+use crate::sigops::MAX_PUBKEYS_PER_MULTISIG;
+use crate::transaction::write_compact_size;
+use crate::weight::{estimate_satisfaction, EstimateError, SatisfactionSize};
+
+/// Characters allowed in a descriptor, in the order the checksum assigns
+/// them values (BIP380).
+const INPUT_CHARSET: &[u8] =
+    b"0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+
+/// The bech32 alphabet, used for descriptor checksums and addresses.
+const CHECKSUM_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Largest script that can be pushed as a P2SH redeem script.
+const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+
+/// Keys allowed in a bare (top level) `multi()`, as in Bitcoin Core.
+const MAX_BARE_MULTISIG_KEYS: usize = 3;
+
+/// Deepest script tree a control block can prove (BIP341).
+const TAPROOT_CONTROL_MAX_NODE_COUNT: usize = 128;
+
+/// The BIP342 tapscript leaf version.
+const TAPSCRIPT_LEAF_VERSION: u8 = 0xc0;
+
+/// Why a descriptor could not be parsed or turned into a script.
+///
+/// Positions are byte offsets into the descriptor string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DescriptorError {
+    /// A character outside the descriptor character set.
+    InvalidCharacter {
+        /// Byte offset of the character.
+        index: usize,
+    },
+    /// The `#` checksum is malformed or does not match.
+    InvalidChecksum,
+    /// Unexpected input, such as a missing parenthesis or trailing text.
+    Syntax {
+        /// Byte offset where parsing stopped.
+        index: usize,
+    },
+    /// A script function name that is not supported.
+    UnknownFunction {
+        /// Byte offset of the name.
+        index: usize,
+    },
+    /// A function used where it is not allowed, e.g. `sh()` nested in
+    /// `wsh()`, or an uncompressed key in a segwit script.
+    InvalidContext {
+        /// Byte offset of the function or key.
+        index: usize,
+    },
+    /// A key expression that is not a valid public key or extended
+    /// public key, whose origin is malformed, or that has a hardened
+    /// step after an extended key.
+    InvalidKey {
+        /// Byte offset of the key expression.
+        index: usize,
+    },
+    /// A `multi()`/`sortedmulti()` with a threshold of zero or above the
+    /// key count, more than 20 keys, more than 3 keys at the top level, or
+    /// a redeem script over 520 bytes inside `sh()`.
+    InvalidMultisig,
+    /// An `addr()` argument that is not a valid base58 or bech32 address.
+    InvalidAddress,
+    /// The hex argument of `raw()` is malformed.
+    InvalidHex(HexError),
+    /// A `tr()` script tree deeper than 128 levels.
+    TreeTooDeep,
+    /// Deriving a `tr()` output key needs the `secp256k1` feature, and a
+    /// valid internal key.
+    TaprootTweak,
+    /// A script was requested from a descriptor with a `/*` key; pick an
+    /// index with [`Descriptor::at_derivation_index`] first.
+    Ranged,
+    /// Deriving a key from an extended key needs the `secp256k1`
+    /// feature, an unhardened index, and fails for the rare index with no
+    /// valid child.
+    Derivation,
+}
+
+impl fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DescriptorError::InvalidCharacter { index } => {
+                write!(f, "invalid descriptor character at {index}")
+            }
+            DescriptorError::InvalidChecksum => write!(f, "invalid descriptor checksum"),
+            DescriptorError::Syntax { index } => write!(f, "syntax error at {index}"),
+            DescriptorError::UnknownFunction { index } => {
+                write!(f, "unknown script function at {index}")
+            }
+            DescriptorError::InvalidContext { index } => {
+                write!(f, "expression at {index} not allowed in this context")
+            }
+            DescriptorError::InvalidKey { index } => write!(f, "invalid key at {index}"),
+            DescriptorError::InvalidMultisig => write!(f, "invalid multisig threshold or keys"),
+            DescriptorError::InvalidAddress => write!(f, "invalid address"),
+            DescriptorError::InvalidHex(err) => write!(f, "invalid raw script: {err}"),
+            DescriptorError::TreeTooDeep => write!(f, "taproot script tree too deep"),
+            DescriptorError::TaprootTweak => write!(f, "cannot derive taproot output key"),
+            DescriptorError::Ranged => write!(f, "ranged descriptor needs a derivation index"),
+            DescriptorError::Derivation => write!(f, "cannot derive key from extended key"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DescriptorError {}
+
+/// A key in a descriptor, with its optional origin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorKey {
+    /// Where the key comes from, e.g. `[d34db33f/84'/0'/0']`.
+    pub origin: Option<KeyOrigin>,
+    /// The key.
+    pub key: DescriptorPublicKey,
+}
+
+/// A fixed public key, or one derived from an extended public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DescriptorPublicKey {
+    /// A fixed key: 33 or 65 bytes, or 32 (x-only) inside `tr()`.
+    Single(Vec<u8>),
+    /// `xpub.../1/2`, or `xpub.../1/*` when `wildcard` is set: unhardened
+    /// derivation steps from an extended key, then the derivation index.
+    Extended {
+        /// The extended key.
+        xpub: Xpub,
+        /// Steps derived before the wildcard, all unhardened.
+        path: Vec<u32>,
+        /// Whether the path ends in `/*`.
+        wildcard: bool,
+    },
+}
+
+impl DescriptorKey {
+    /// Whether the key ends in a `/*` wildcard.
+    pub fn is_ranged(&self) -> bool {
+        matches!(
+            self.key,
+            DescriptorPublicKey::Extended { wildcard: true, .. }
+        )
+    }
+
+    /// The key at derivation `index`: extended keys are derived down to a
+    /// fixed key, with their steps (and `index`, for a wildcard) appended
+    /// to the origin. Without an origin, the extended key's own
+    /// fingerprint starts it. Fixed keys are returned unchanged.
+    ///
+    /// # Errors
+    ///
+    /// [`DescriptorError::Derivation`] if the key cannot be derived.
+    pub fn at_derivation_index(&self, index: u32) -> Result<DescriptorKey, DescriptorError> {
+        let (xpub, path, wildcard) = match &self.key {
+            DescriptorPublicKey::Single(_) => return Ok(self.clone()),
+            DescriptorPublicKey::Extended {
+                xpub,
+                path,
+                wildcard,
+            } => (xpub, path, *wildcard),
+        };
+        let mut steps = path.clone();
+        if wildcard {
+            steps.push(index);
+        }
+        let key = derive(xpub, &steps)?;
+
+        let mut origin = self.origin.clone().unwrap_or_else(|| KeyOrigin {
+            fingerprint: xpub.fingerprint(),
+            path: Vec::new(),
+        });
+        origin.path.extend(steps);
+        Ok(DescriptorKey {
+            origin: Some(origin),
+            key: DescriptorPublicKey::Single(key.to_vec()),
+        })
+    }
+
+    /// The public key, deriving an extended key without a wildcard.
+    fn bytes(&self) -> Result<Vec<u8>, DescriptorError> {
+        match &self.key {
+            DescriptorPublicKey::Single(key) => Ok(key.clone()),
+            DescriptorPublicKey::Extended { wildcard: true, .. } => Err(DescriptorError::Ranged),
+            DescriptorPublicKey::Extended { xpub, path, .. } => Ok(derive(xpub, path)?.to_vec()),
+        }
+    }
+
+    /// The key as a taproot x-only key: 32-byte keys as-is, compressed
+    /// keys without their parity byte.
+    fn x_only(&self) -> Result<Vec<u8>, DescriptorError> {
+        let mut key = self.bytes()?;
+        if key.len() == 33 {
+            key.remove(0);
+        }
+        Ok(key)
+    }
+
+    /// Length of the public key, known without deriving it.
+    fn len(&self) -> usize {
+        match &self.key {
+            DescriptorPublicKey::Single(key) => key.len(),
+            DescriptorPublicKey::Extended { .. } => 33,
+        }
+    }
+}
+
+impl fmt::Display for DescriptorKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(origin) = &self.origin {
+            write!(f, "[{origin}]")?;
+        }
+        match &self.key {
+            DescriptorPublicKey::Single(key) => f.write_str(&encode_hex(key)),
+            DescriptorPublicKey::Extended {
+                xpub,
+                path,
+                wildcard,
+            } => {
+                write!(f, "{xpub}")?;
+                write_path(f, path)?;
+                if *wildcard {
+                    f.write_str("/*")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A taproot script tree: `pk()` leaves combined by `{left,right}`
+/// branches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TapTree {
+    /// A leaf script; always [`Descriptor::Pk`]. A compressed key is
+    /// kept as written but enters the leaf script x-only, as BIP342
+    /// requires.
+    Leaf(Box<Descriptor>),
+    /// A branch with two subtrees.
+    Branch(Box<TapTree>, Box<TapTree>),
+}
+
+impl TapTree {
+    /// The BIP341 merkle root of the tree.
+    pub fn merkle_root(&self) -> Result<[u8; 32], DescriptorError> {
+        let hash = match self {
+            TapTree::Leaf(script) => {
+                let script = script.leaf_script()?;
+                let mut data = vec![TAPSCRIPT_LEAF_VERSION];
+                write_compact_size(&mut data, script.len() as u64);
+                data.extend_from_slice(&script);
+                hash::tagged_hash("TapLeaf", &data)
+            }
+            TapTree::Branch(left, right) => {
+                let (a, b) = (left.merkle_root()?, right.merkle_root()?);
+                let (a, b) = if a <= b { (a, b) } else { (b, a) };
+                hash::tagged_hash("TapBranch", &[a, b].concat())
+            }
+        };
+        Ok(hash)
+    }
+
+    fn at_derivation_index(&self, index: u32) -> Result<TapTree, DescriptorError> {
+        Ok(match self {
+            TapTree::Leaf(script) => TapTree::Leaf(Box::new(script.at_derivation_index(index)?)),
+            TapTree::Branch(left, right) => TapTree::Branch(
+                Box::new(left.at_derivation_index(index)?),
+                Box::new(right.at_derivation_index(index)?),
+            ),
+        })
+    }
+
+    fn is_ranged(&self) -> bool {
+        match self {
+            TapTree::Leaf(script) => script.is_ranged(),
+            TapTree::Branch(left, right) => left.is_ranged() || right.is_ranged(),
+        }
+    }
+}
+
+impl fmt::Display for TapTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TapTree::Leaf(script) => script.write_body(f),
+            TapTree::Branch(left, right) => write!(f, "{{{left},{right}}}"),
+        }
+    }
+}
+
+/// An output descriptor (BIP380–386) over fixed or extended public keys.
+///
+/// Parse one with `str::parse`; a trailing `#checksum` is verified if
+/// present. `Display` writes the descriptor with its checksum.
+///
+/// Extended keys (`xpub`/`tpub`) may be followed by unhardened steps and
+/// a final `/*`. Scripts of such a ranged descriptor come from
+/// [`Descriptor::at_derivation_index`] or [`Descriptor::script_pubkeys`];
+/// deriving needs the `secp256k1` feature.
+///
+/// ```rust
+/// use mini_bitcoin_script::descriptor::Descriptor;
+///
+/// let desc: Descriptor = "sh(wpkh(03fff97bd5755eeea420453a14355235d382f6472f8568a18b2f057a1460297556))"
+///     .parse()
+///     .unwrap();
+/// let script_pubkey = desc.script_pubkey().unwrap();
+/// assert!(script_pubkey.is_p2sh());
+/// assert!(desc.to_string().ends_with("#qkrrc7je"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Descriptor {
+    /// `pk(KEY)`: `<key> OP_CHECKSIG`.
+    Pk(DescriptorKey),
+    /// `pkh(KEY)`: pay to the key's HASH160.
+    Pkh(DescriptorKey),
+    /// `wpkh(KEY)`: P2WPKH; the key must be compressed.
+    Wpkh(DescriptorKey),
+    /// `sh(SCRIPT)`: P2SH of the inner script.
+    Sh(Box<Descriptor>),
+    /// `wsh(SCRIPT)`: P2WSH of the inner script.
+    Wsh(Box<Descriptor>),
+    /// `multi(k,KEY,...)` or, when `sorted`, `sortedmulti(k,KEY,...)`,
+    /// whose keys are sorted when the script is built.
+    Multi {
+        /// Signatures required.
+        threshold: usize,
+        /// Keys in the order given.
+        keys: Vec<DescriptorKey>,
+        /// Whether this is `sortedmulti()`.
+        sorted: bool,
+    },
+    /// `tr(KEY)` or `tr(KEY,TREE)`: P2TR.
+    Tr {
+        /// The internal key.
+        internal_key: DescriptorKey,
+        /// The script tree, if any.
+        tree: Option<TapTree>,
+    },
+    /// `addr(ADDR)`: the scriptPubKey a base58 or bech32 address encodes.
+    Addr {
+        /// The address as written.
+        address: String,
+        /// Its decoded scriptPubKey.
+        script_pubkey: Vec<u8>,
+    },
+    /// `raw(HEX)`: a literal scriptPubKey.
+    Raw(Vec<u8>),
+}
+
+impl Descriptor {
+    /// The scriptPubKey this descriptor describes.
+    ///
+    /// # Errors
+    ///
+    /// [`DescriptorError::TaprootTweak`] for `tr()` without the
+    /// `secp256k1` feature, [`DescriptorError::Ranged`] for a ranged
+    /// descriptor, [`DescriptorError::Derivation`] for extended keys
+    /// without the `secp256k1` feature, and
+    /// [`DescriptorError::InvalidMultisig`] for a hand-built `Multi` that
+    /// violates the limits checked when parsing.
+    pub fn script_pubkey(&self) -> Result<ScriptBuf, DescriptorError> {
+        let bytes = match self {
+            Descriptor::Sh(inner) => builder::p2sh(&hash::hash160(&inner.script()?)),
+            Descriptor::Wsh(inner) => builder::p2wsh(&hash::sha256(&inner.script()?)),
+            Descriptor::Wpkh(key) => builder::p2wpkh(&hash::hash160(&key.bytes()?)),
+            Descriptor::Tr { internal_key, tree } => {
+                let merkle_root = match tree {
+                    Some(tree) => Some(tree.merkle_root()?),
+                    None => None,
+                };
+                builder::p2tr(&taproot_output_key(&internal_key.x_only()?, merkle_root)?)
+            }
+            Descriptor::Addr { script_pubkey, .. } => script_pubkey.clone(),
+            Descriptor::Raw(script) => script.clone(),
+            _ => self.script()?,
+        };
+        Ok(ScriptBuf::from_bytes(bytes))
+    }
+
+    /// Whether any key ends in a `/*` wildcard, so that scripts need a
+    /// derivation index.
+    pub fn is_ranged(&self) -> bool {
+        match self {
+            Descriptor::Pk(key) | Descriptor::Pkh(key) | Descriptor::Wpkh(key) => key.is_ranged(),
+            Descriptor::Sh(inner) | Descriptor::Wsh(inner) => inner.is_ranged(),
+            Descriptor::Multi { keys, .. } => keys.iter().any(DescriptorKey::is_ranged),
+            Descriptor::Tr { internal_key, tree } => {
+                internal_key.is_ranged() || tree.as_ref().map_or(false, TapTree::is_ranged)
+            }
+            Descriptor::Addr { .. } | Descriptor::Raw(_) => false,
+        }
+    }
+
+    /// The descriptor with every extended key derived at `index`; see
+    /// [`DescriptorKey::at_derivation_index`]. The result has only fixed
+    /// keys, so its scripts can be computed.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "secp256k1")] {
+    /// use mini_bitcoin_script::descriptor::Descriptor;
+    ///
+    /// let desc: Descriptor = "wpkh(xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8/0/*)"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert!(desc.is_ranged());
+    /// let first = desc.at_derivation_index(0).unwrap();
+    /// assert!(first.to_string().starts_with("wpkh([3442193e/0/0]"));
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [`DescriptorError::Derivation`] if a key cannot be derived.
+    pub fn at_derivation_index(&self, index: u32) -> Result<Descriptor, DescriptorError> {
+        let key = |key: &DescriptorKey| key.at_derivation_index(index);
+        let descriptor = match self {
+            Descriptor::Pk(k) => Descriptor::Pk(key(k)?),
+            Descriptor::Pkh(k) => Descriptor::Pkh(key(k)?),
+            Descriptor::Wpkh(k) => Descriptor::Wpkh(key(k)?),
+            Descriptor::Sh(inner) => Descriptor::Sh(Box::new(inner.at_derivation_index(index)?)),
+            Descriptor::Wsh(inner) => Descriptor::Wsh(Box::new(inner.at_derivation_index(index)?)),
+            Descriptor::Multi {
+                threshold,
+                keys,
+                sorted,
+            } => Descriptor::Multi {
+                threshold: *threshold,
+                keys: keys.iter().map(key).collect::<Result<_, _>>()?,
+                sorted: *sorted,
+            },
+            Descriptor::Tr { internal_key, tree } => Descriptor::Tr {
+                internal_key: key(internal_key)?,
+                tree: match tree {
+                    Some(tree) => Some(tree.at_derivation_index(index)?),
+                    None => None,
+                },
+            },
+            Descriptor::Addr { .. } | Descriptor::Raw(_) => self.clone(),
+        };
+        Ok(descriptor)
+    }
+
+    /// The scriptPubKeys at each derivation index in `range`, e.g. to scan
+    /// for a wallet's addresses. A descriptor that is not ranged gives
+    /// the same script at every index.
+    ///
+    /// # Errors
+    ///
+    /// As [`Descriptor::at_derivation_index`] and
+    /// [`Descriptor::script_pubkey`].
+    pub fn script_pubkeys(&self, range: Range<u32>) -> Result<Vec<ScriptBuf>, DescriptorError> {
+        range
+            .map(|index| self.at_derivation_index(index)?.script_pubkey())
+            .collect()
+    }
+
+    /// The redeem script a spend must reveal: the inner script of `sh()`.
+    pub fn redeem_script(&self) -> Result<Option<ScriptBuf>, DescriptorError> {
+        match self {
+            Descriptor::Sh(inner) => inner.script_pubkey().map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// The witness script a spend must reveal: the inner script of
+    /// `wsh()`, top level or inside `sh()`.
+    pub fn witness_script(&self) -> Result<Option<ScriptBuf>, DescriptorError> {
+        match self {
+            Descriptor::Wsh(inner) => inner.script().map(|s| Some(ScriptBuf::from_bytes(s))),
+            Descriptor::Sh(inner) => inner.witness_script(),
+            _ => Ok(None),
+        }
+    }
+
+    /// The template of the scriptPubKey, as the classifier sees it.
+    pub fn output_type(&self) -> Result<OutputType, DescriptorError> {
+        match self {
+            // Known without computing the tweak.
+            Descriptor::Tr { .. } => Ok(OutputType::P2tr),
+            _ => Ok(classify(self.script_pubkey()?.as_bytes())),
+        }
+    }
+
+    /// Worst-case and typical scriptSig and witness sizes for spending
+    /// the output; see [`estimate_satisfaction`]. `tr()` is estimated as a
+    /// key path spend.
+    pub fn max_satisfaction_size(&self) -> Result<SatisfactionSize, EstimateError> {
+        let output_type = self.output_type().unwrap_or(OutputType::NonStandard);
+        let redeem = self.redeem_script().ok().flatten();
+        let witness = self.witness_script().ok().flatten();
+        estimate_satisfaction(
+            output_type,
+            redeem.as_ref().map(|s| s.as_bytes()),
+            witness.as_ref().map(|s| s.as_bytes()),
+        )
+    }
+
+    /// The script an inner descriptor contributes to its parent: the
+    /// redeem or witness script for `sh()`/`wsh()`, the leaf script in a
+    /// tree. Top-level-only descriptors return their scriptPubKey.
+    fn script(&self) -> Result<Vec<u8>, DescriptorError> {
+        let bytes = match self {
+            Descriptor::Pk(key) => ScriptBuilder::new()
+                .push_slice(&key.bytes()?)
+                .push_opcode(Opcode::OpCheckSig)
+                .into_bytes(),
+            Descriptor::Pkh(key) => builder::p2pkh(&hash::hash160(&key.bytes()?)),
+            Descriptor::Multi {
+                threshold,
+                keys,
+                sorted,
+            } => {
+                let keys = keys
+                    .iter()
+                    .map(DescriptorKey::bytes)
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut keys: Vec<&[u8]> = keys.iter().map(Vec::as_slice).collect();
+                if *sorted {
+                    keys.sort_unstable();
+                }
+                builder::multisig(*threshold, &keys)
+                    .map_err(|_| DescriptorError::InvalidMultisig)?
+            }
+            _ => self.script_pubkey()?.into_bytes(),
+        };
+        Ok(bytes)
+    }
+
+    /// The script of a taproot leaf. Keys are pushed x-only: a 33-byte
+    /// push would be an unknown key type, satisfied by any signature.
+    fn leaf_script(&self) -> Result<Vec<u8>, DescriptorError> {
+        match self {
+            Descriptor::Pk(key) => Ok(ScriptBuilder::new()
+                .push_slice(&key.x_only()?)
+                .push_opcode(Opcode::OpCheckSig)
+                .into_bytes()),
+            _ => self.script(),
+        }
+    }
+
+    fn write_body<W: fmt::Write>(&self, f: &mut W) -> fmt::Result {
+        match self {
+            Descriptor::Pk(key) => write!(f, "pk({key})"),
+            Descriptor::Pkh(key) => write!(f, "pkh({key})"),
+            Descriptor::Wpkh(key) => write!(f, "wpkh({key})"),
+            Descriptor::Sh(inner) => {
+                f.write_str("sh(")?;
+                inner.write_body(f)?;
+                f.write_str(")")
+            }
+            Descriptor::Wsh(inner) => {
+                f.write_str("wsh(")?;
+                inner.write_body(f)?;
+                f.write_str(")")
+            }
+            Descriptor::Multi {
+                threshold,
+                keys,
+                sorted,
+            } => {
+                let name = if *sorted { "sortedmulti" } else { "multi" };
+                write!(f, "{name}({threshold}")?;
+                for key in keys {
+                    write!(f, ",{key}")?;
+                }
+                f.write_str(")")
+            }
+            Descriptor::Tr { internal_key, tree } => match tree {
+                Some(tree) => write!(f, "tr({internal_key},{tree})"),
+                None => write!(f, "tr({internal_key})"),
+            },
+            Descriptor::Addr { address, .. } => write!(f, "addr({address})"),
+            Descriptor::Raw(script) => write!(f, "raw({})", encode_hex(script)),
+        }
+    }
+}
+
+/// Writes the descriptor followed by `#` and its checksum.
+impl fmt::Display for Descriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut body = String::new();
+        self.write_body(&mut body)?;
+        let checksum = descriptor_checksum(&body).map_err(|_| fmt::Error)?;
+        write!(f, "{body}#{checksum}")
+    }
+}
+
+impl FromStr for Descriptor {
+    type Err = DescriptorError;
+
+    fn from_str(s: &str) -> Result<Self, DescriptorError> {
+        let body = match s.split_once('#') {
+            Some((body, checksum)) => {
+                if descriptor_checksum(body)? != checksum {
+                    return Err(DescriptorError::InvalidChecksum);
+                }
+                body
+            }
+            None => {
+                descriptor_checksum(s)?;
+                s
+            }
+        };
+
+        let mut parser = Parser { s: body, pos: 0 };
+        let descriptor = parser.descriptor(Context::Top)?;
+        if parser.pos != body.len() {
+            return Err(DescriptorError::Syntax { index: parser.pos });
+        }
+        Ok(descriptor)
+    }
+}
+
+/// Computes the 8-character BIP380 checksum of a descriptor (without its
+/// `#`).
+///
+/// ```rust
+/// use mini_bitcoin_script::descriptor::descriptor_checksum;
+///
+/// assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+/// ```
+pub fn descriptor_checksum(descriptor: &str) -> Result<String, DescriptorError> {
+    let mut c = 1u64;
+    let mut class = 0u64;
+    let mut class_count = 0;
+    for (index, ch) in descriptor.bytes().enumerate() {
+        let value = INPUT_CHARSET
+            .iter()
+            .position(|&b| b == ch)
+            .ok_or(DescriptorError::InvalidCharacter { index })? as u64;
+        c = polymod(c, value & 31);
+        class = class * 3 + (value >> 5);
+        class_count += 1;
+        if class_count == 3 {
+            c = polymod(c, class);
+            class = 0;
+            class_count = 0;
+        }
+    }
+    if class_count > 0 {
+        c = polymod(c, class);
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+    Ok((0..8)
+        .map(|j| CHECKSUM_CHARSET[((c >> (5 * (7 - j))) & 31) as usize] as char)
+        .collect())
+}
+
+fn polymod(c: u64, value: u64) -> u64 {
+    const GENERATOR: [u64; 5] = [
+        0xf5dee51989,
+        0xa9fdca3312,
+        0x1bab10e32d,
+        0x3706b1677a,
+        0x644d626ffd,
+    ];
+    let top = c >> 35;
+    let mut c = ((c & 0x7_ffff_ffff) << 5) ^ value;
+    for (i, g) in GENERATOR.iter().enumerate() {
+        if (top >> i) & 1 == 1 {
+            c ^= g;
+        }
+    }
+    c
+}
+
+/// Where an expression appears; decides which functions and keys are
+/// allowed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    Top,
+    Sh,
+    Wsh,
+    Tap,
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn descriptor(&mut self, ctx: Context) -> Result<Descriptor, DescriptorError> {
+        let start = self.pos;
+        let name = self.take_while(|b| b.is_ascii_lowercase());
+        self.expect(b'(')?;
+        let allowed = |ok: bool| {
+            if ok {
+                Ok(())
+            } else {
+                Err(DescriptorError::InvalidContext { index: start })
+            }
+        };
+
+        let descriptor = match name {
+            "pk" => Descriptor::Pk(self.key(ctx)?),
+            "pkh" => {
+                allowed(ctx != Context::Tap)?;
+                Descriptor::Pkh(self.key(ctx)?)
+            }
+            "wpkh" => {
+                allowed(matches!(ctx, Context::Top | Context::Sh))?;
+                // Parsed as a segwit key: compressed only.
+                Descriptor::Wpkh(self.key(Context::Wsh)?)
+            }
+            "sh" => {
+                allowed(ctx == Context::Top)?;
+                Descriptor::Sh(Box::new(self.descriptor(Context::Sh)?))
+            }
+            "wsh" => {
+                allowed(matches!(ctx, Context::Top | Context::Sh))?;
+                Descriptor::Wsh(Box::new(self.descriptor(Context::Wsh)?))
+            }
+            "multi" | "sortedmulti" => {
+                allowed(ctx != Context::Tap)?;
+                self.multi(ctx, name == "sortedmulti")?
+            }
+            "tr" => {
+                allowed(ctx == Context::Top)?;
+                let internal_key = self.key(Context::Tap)?;
+                let tree = if self.peek() == Some(b',') {
+                    self.pos += 1;
+                    Some(self.tree(0)?)
+                } else {
+                    None
+                };
+                Descriptor::Tr { internal_key, tree }
+            }
+            "addr" => {
+                allowed(ctx == Context::Top)?;
+                let address = self.take_while(|b| b != b')');
+                let script_pubkey =
+                    decode_address(address).ok_or(DescriptorError::InvalidAddress)?;
+                Descriptor::Addr {
+                    address: address.into(),
+                    script_pubkey,
+                }
+            }
+            "raw" => {
+                allowed(ctx == Context::Top)?;
+                let hex = self.take_while(|b| b != b')');
+                Descriptor::Raw(decode_hex(hex).map_err(DescriptorError::InvalidHex)?)
+            }
+            _ => return Err(DescriptorError::UnknownFunction { index: start }),
+        };
+        self.expect(b')')?;
+        Ok(descriptor)
+    }
+
+    fn multi(&mut self, ctx: Context, sorted: bool) -> Result<Descriptor, DescriptorError> {
+        let start = self.pos;
+        let threshold = self
+            .take_while(|b| b.is_ascii_digit())
+            .parse()
+            .map_err(|_| DescriptorError::Syntax { index: start })?;
+        let mut keys = Vec::new();
+        while self.peek() == Some(b',') {
+            self.pos += 1;
+            keys.push(self.key(ctx)?);
+        }
+
+        let key_count = keys.len();
+        if threshold == 0 || threshold > key_count || key_count > MAX_PUBKEYS_PER_MULTISIG {
+            return Err(DescriptorError::InvalidMultisig);
+        }
+        // The script size, computed without deriving extended keys:
+        // OP_m, the key pushes, OP_n and OP_CHECKMULTISIG, where m and n
+        // above 16 take a two-byte push.
+        let int_len = |n: usize| if n <= 16 { 1 } else { 2 };
+        let script_len = int_len(threshold)
+            + keys.iter().map(|key| 1 + key.len()).sum::<usize>()
+            + int_len(key_count)
+            + 1;
+        if (ctx == Context::Top && key_count > MAX_BARE_MULTISIG_KEYS)
+            || (ctx == Context::Sh && script_len > MAX_SCRIPT_ELEMENT_SIZE)
+        {
+            return Err(DescriptorError::InvalidMultisig);
+        }
+        Ok(Descriptor::Multi {
+            threshold,
+            keys,
+            sorted,
+        })
+    }
+
+    fn tree(&mut self, depth: usize) -> Result<TapTree, DescriptorError> {
+        if depth > TAPROOT_CONTROL_MAX_NODE_COUNT {
+            return Err(DescriptorError::TreeTooDeep);
+        }
+        if self.peek() != Some(b'{') {
+            return Ok(TapTree::Leaf(Box::new(self.descriptor(Context::Tap)?)));
+        }
+        self.pos += 1;
+        let left = self.tree(depth + 1)?;
+        self.expect(b',')?;
+        let right = self.tree(depth + 1)?;
+        self.expect(b'}')?;
+        Ok(TapTree::Branch(Box::new(left), Box::new(right)))
+    }
+
+    /// Parses `[origin]hex` or `[origin]xpub/1/2/*`. In segwit scripts
+    /// keys must be compressed; in taproot they are x-only or compressed.
+    fn key(&mut self, ctx: Context) -> Result<DescriptorKey, DescriptorError> {
+        let start = self.pos;
+        let invalid = DescriptorError::InvalidKey { index: start };
+
+        let origin = if self.peek() == Some(b'[') {
+            self.pos += 1;
+            let origin = self.take_while(|b| b != b']');
+            self.expect(b']')?;
+            Some(origin.parse().map_err(|_| invalid)?)
+        } else {
+            None
+        };
+
+        let text = self.take_while(|b| b.is_ascii_alphanumeric());
+        if text.starts_with("xpub") || text.starts_with("tpub") {
+            let xpub = text.parse().map_err(|_| invalid)?;
+            let key = self.extended_key(xpub).ok_or(invalid)?;
+            return Ok(DescriptorKey { origin, key });
+        }
+
+        let key = decode_hex(text).map_err(|_| invalid)?;
+        let valid = match (ctx, pubkey_encoding(&key)) {
+            (Context::Tap, encoding) => {
+                key.len() == 32 || encoding == Some(PubkeyEncoding::Compressed)
+            }
+            (_, Some(PubkeyEncoding::Compressed)) => true,
+            (Context::Wsh, Some(PubkeyEncoding::Uncompressed)) => {
+                return Err(DescriptorError::InvalidContext { index: start })
+            }
+            (_, Some(PubkeyEncoding::Uncompressed)) => true,
+            _ => false,
+        };
+        if !valid {
+            return Err(invalid);
+        }
+        Ok(DescriptorKey {
+            origin,
+            key: DescriptorPublicKey::Single(key),
+        })
+    }
+
+    /// Parses the `/1/2/*` after an extended key. Hardened steps would
+    /// need the private key, so they are rejected.
+    fn extended_key(&mut self, xpub: Xpub) -> Option<DescriptorPublicKey> {
+        let mut path = Vec::new();
+        let mut wildcard = false;
+        while self.peek() == Some(b'/') {
+            self.pos += 1;
+            if self.peek() == Some(b'*') {
+                self.pos += 1;
+                wildcard = true;
+                break;
+            }
+            let step = self.take_while(|b| b.is_ascii_digit() || b == b'\'' || b == b'h');
+            path.push(parse_child(step).filter(|&index| index < HARDENED)?);
+        }
+        if matches!(self.peek(), Some(b'\'' | b'h' | b'/')) {
+            return None;
+        }
+        Some(DescriptorPublicKey::Extended {
+            xpub,
+            path,
+            wildcard,
+        })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), DescriptorError> {
+        if self.peek() != Some(byte) {
+            return Err(DescriptorError::Syntax { index: self.pos });
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn take_while(&mut self, pred: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().map_or(false, &pred) {
+            self.pos += 1;
+        }
+        &self.s[start..self.pos]
+    }
+}
+
+#[cfg(feature = "secp256k1")]
+fn derive(xpub: &Xpub, path: &[u32]) -> Result<[u8; 33], DescriptorError> {
+    xpub.derive_path(path)
+        .map(|child| child.public_key)
+        .map_err(|_| DescriptorError::Derivation)
+}
+
+#[cfg(not(feature = "secp256k1"))]
+fn derive(_xpub: &Xpub, _path: &[u32]) -> Result<[u8; 33], DescriptorError> {
+    Err(DescriptorError::Derivation)
+}
+
+#[cfg(feature = "secp256k1")]
+fn taproot_output_key(
+    internal_key: &[u8],
+    merkle_root: Option<[u8; 32]>,
+) -> Result<[u8; 32], DescriptorError> {
+    use secp256k1::XOnlyPublicKey;
+
+    let internal_key =
+        XOnlyPublicKey::from_slice(internal_key).map_err(|_| DescriptorError::TaprootTweak)?;
+    Ok(crate::sign::taproot_script_output_key(&internal_key, merkle_root.as_ref()).serialize())
+}
+
+#[cfg(not(feature = "secp256k1"))]
+fn taproot_output_key(
+    _internal_key: &[u8],
+    _merkle_root: Option<[u8; 32]>,
+) -> Result<[u8; 32], DescriptorError> {
+    Err(DescriptorError::TaprootTweak)
+}
+
+/// Decodes a mainnet, testnet or regtest address into its scriptPubKey.
+fn decode_address(address: &str) -> Option<Vec<u8>> {
+    if let Some(script) = decode_segwit_address(address) {
+        return Some(script);
+    }
+    let payload = decode_base58check(address)?;
+    let (&version, hash) = payload.split_first()?;
+    let hash: &[u8; 20] = hash.try_into().ok()?;
+    match version {
+        0x00 | 0x6f => Some(builder::p2pkh(hash)),
+        0x05 | 0xc4 => Some(builder::p2sh(hash)),
+        _ => None,
+    }
+}
+
+/// Decodes a BIP173 (v0) or BIP350 (v1+) segwit address.
+fn decode_segwit_address(s: &str) -> Option<Vec<u8>> {
+    const BECH32_CONST: u32 = 1;
+    const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+    if s.bytes().any(|b| b.is_ascii_lowercase()) && s.bytes().any(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    let s = s.to_ascii_lowercase();
+    let (hrp, data) = s.rsplit_once('1')?;
+    if !matches!(hrp, "bc" | "tb" | "bcrt") || data.len() < 7 || s.len() > 90 {
+        return None;
+    }
+    let values = data
+        .bytes()
+        .map(|ch| {
+            CHECKSUM_CHARSET
+                .iter()
+                .position(|&b| b == ch)
+                .map(|v| v as u8)
+        })
+        .collect::<Option<Vec<u8>>>()?;
+
+    let mut check: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    check.push(0);
+    check.extend(hrp.bytes().map(|b| b & 31));
+    check.extend(&values);
+    let constant = bech32_polymod(&check);
+
+    let (&version, rest) = values[..values.len() - 6].split_first()?;
+    let expected = if version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    if constant != expected || version > 16 {
+        return None;
+    }
+
+    // Regroup 5-bit values into bytes; leftover bits must be zero padding.
+    let mut program = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for &v in rest {
+        acc = (acc << 5) | v as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            program.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || acc & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+
+    let version_op = match version {
+        0 => 0x00,
+        v => 0x50 + v,
+    };
+    let mut script = vec![version_op, program.len() as u8];
+    script.extend(program);
+    match witness_program(&script) {
+        Some((0, program)) if program.len() != 20 && program.len() != 32 => None,
+        Some(_) => Some(script),
+        None => None,
+    }
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk = 1u32;
+    for &v in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1ff_ffff) << 5) ^ v as u32;
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_vector() {
+        assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+        assert_eq!(
+            descriptor_checksum("raw(é)"),
+            Err(DescriptorError::InvalidCharacter { index: 4 })
+        );
+    }
+
+    #[test]
+    fn origins() {
+        let valid = |origin: &str| origin.parse::<KeyOrigin>().is_ok();
+        assert!(valid("d34db33f"));
+        assert!(valid("d34db33f/44'/0h/1"));
+        assert!(!valid("d34db33"));
+        assert!(!valid("d34db33f/"));
+        assert!(!valid("d34db33f/1''"));
+        assert!(!valid("d34db33f/2147483648"));
+    }
+
+    #[test]
+    fn base58_and_bech32() {
+        // The genesis block's coinbase address.
+        let script = decode_address("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa").unwrap();
+        assert_eq!(
+            encode_hex(&script),
+            "76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac"
+        );
+        assert!(decode_address("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb").is_none());
+        assert!(decode_address("bc1qw508d6qejxtdg4c3ztdgskqyhm9xxz8").is_none());
+    }
+}
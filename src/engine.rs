@@ -1,20 +1,123 @@
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
 use crate::error::ScriptError;
+use crate::flags::VerifyFlags;
 use crate::hash;
 use crate::opcode::Opcode;
+#[cfg(feature = "std")]
+use crate::sigcache::SignatureCache;
+use crate::signature;
+use crate::sigops::MAX_PUBKEYS_PER_MULTISIG;
 use crate::stack::{is_true, Stack};
 use crate::token::Token;
 
+/// The kind of script being executed, which decides how signature
+/// opcodes behave (BIP143, BIP342).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SigVersion {
+    /// A legacy scriptSig, scriptPubKey or P2SH redeem script.
+    #[default]
+    Base,
+    /// A P2WPKH or P2WSH script.
+    WitnessV0,
+    /// A BIP342 tapscript leaf: OP_CHECKSIG checks Schnorr signatures,
+    /// OP_CHECKSIGADD is available and OP_CHECKMULTISIG is not.
+    Tapscript,
+}
+
+/// Computes the digest a signature commits to from the signature itself,
+/// so each signature in a script is checked against its own sighash type.
+///
+/// The function returns `None` for a sighash type it does not accept;
+/// the signature then fails.
+#[derive(Clone)]
+pub struct SighashFn(Arc<DigestFn>);
+
+type DigestFn = dyn Fn(&[u8]) -> Option<[u8; 32]> + Send + Sync;
+
+impl SighashFn {
+    /// Wraps `f`, which is given each signature, sighash type byte
+    /// included, and returns the digest it must sign.
+    pub fn new(f: impl Fn(&[u8]) -> Option<[u8; 32]> + Send + Sync + 'static) -> Self {
+        SighashFn(Arc::new(f))
+    }
+
+    /// The digest `sig` must sign, or `None` if its sighash type is not
+    /// accepted.
+    pub fn sighash(&self, sig: &[u8]) -> Option<[u8; 32]> {
+        (self.0)(sig)
+    }
+}
+
+impl fmt::Debug for SighashFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SighashFn(..)")
+    }
+}
+
 /// Options for script execution.
 ///
-/// Controls optional behavior such as real OP_CHECKSIG verification.
+/// Controls optional behavior such as real OP_CHECKSIG verification and
+/// which additional verification rules are enforced.
 #[derive(Debug, Clone, Default)]
 pub struct ExecuteOpts {
     /// The sighash digest for OP_CHECKSIG verification.
     ///
-    /// When `None`, OP_CHECKSIG always pushes true (stub mode).
-    /// When `Some` and the `secp256k1` feature is enabled,
-    /// real ECDSA signature verification is performed.
+    /// When `None`, and [`sighash_fn`](Self::sighash_fn) is too, signature
+    /// checks accept any non-empty signature (stub mode). Otherwise, with
+    /// the `secp256k1` feature enabled, real ECDSA verification is
+    /// performed, or BIP340 Schnorr verification in tapscript.
     pub sighash: Option<[u8; 32]>,
+
+    /// Computes the digest per signature, from its sighash type.
+    ///
+    /// Takes precedence over [`sighash`](Self::sighash), which only suits
+    /// scripts whose signatures all share one sighash type.
+    pub sighash_fn: Option<SighashFn>,
+
+    /// The kind of script being executed. OP_CHECKSIGADD fails outside
+    /// tapscript.
+    pub sig_version: SigVersion,
+
+    /// Additional verification rules, e.g. strict signature encoding.
+    ///
+    /// Encoding rules apply in stub mode too: they only inspect bytes.
+    pub flags: VerifyFlags,
+
+    /// The spending transaction's locktime.
+    ///
+    /// When `None`, OP_CHECKLOCKTIMEVERIFY is a no-op, as OP_NOP2 was
+    /// before BIP65.
+    pub lock_time: Option<u32>,
+
+    /// The spending input's sequence number.
+    ///
+    /// When `None`, OP_CHECKSEQUENCEVERIFY is a no-op, as OP_NOP3 was
+    /// before BIP112. OP_CHECKLOCKTIMEVERIFY also uses it to reject final
+    /// inputs, whose locktime is not enforced.
+    pub sequence: Option<u32>,
+
+    /// The spending transaction's version.
+    ///
+    /// When `Some`, OP_CHECKSEQUENCEVERIFY fails for versions below 2,
+    /// which do not have relative locktimes (BIP68). When `None`, the
+    /// version is not checked.
+    pub tx_version: Option<i32>,
+
+    /// Treat OP_CHECKSEQUENCEVERIFY as OP_NOP3 even when
+    /// [`sequence`](Self::sequence) is set, as before BIP112 activated.
+    /// OP_CHECKLOCKTIMEVERIFY still sees the sequence.
+    pub nop3: bool,
+
+    /// A cache of signatures already verified.
+    ///
+    /// With the `secp256k1` feature, ECDSA and Schnorr checks look here
+    /// first and record the signatures they accept. Ignored in stub mode.
+    #[cfg(feature = "std")]
+    pub sig_cache: Option<Arc<SignatureCache>>,
 }
 
 /// Executes a sequence of tokens on a fresh stack.
@@ -23,7 +126,7 @@ pub struct ExecuteOpts {
 /// Returns `Ok(false)` if the stack is empty or the top element is falsy.
 /// Returns `Err(ScriptError)` if any operation fails during execution.
 ///
-/// OP_CHECKSIG uses stub mode (always succeeds). For real signature
+/// OP_CHECKSIG uses stub mode (any non-empty signature succeeds). For real signature
 /// verification, use [`execute_with_opts`] with a sighash and the
 /// `secp256k1` feature enabled.
 pub fn execute(tokens: &[Token]) -> Result<bool, ScriptError> {
@@ -35,6 +138,9 @@ pub fn execute(tokens: &[Token]) -> Result<bool, ScriptError> {
 /// See [`execute`] for return value semantics. The `opts` parameter
 /// controls OP_CHECKSIG behavior via [`ExecuteOpts::sighash`].
 pub fn execute_with_opts(tokens: &[Token], opts: &ExecuteOpts) -> Result<bool, ScriptError> {
+    if has_op_success(tokens, opts) {
+        return Ok(true);
+    }
     let mut stack = Stack::new();
     execute_on_stack(tokens, &mut stack, opts)?;
 
@@ -45,6 +151,91 @@ pub fn execute_with_opts(tokens: &[Token], opts: &ExecuteOpts) -> Result<bool, S
     Ok(is_true(&top))
 }
 
+/// The state a script leaves behind: stacks and open conditionals.
+///
+/// [`execute_on_state`] updates it in place, so a script can be fed in
+/// pieces, with OP_IF on one piece and OP_ENDIF on a later one, as the
+/// `repl` example does.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecState {
+    /// The main stack, bottom to top.
+    pub stack: Vec<Vec<u8>>,
+    /// The alt stack, bottom to top.
+    pub alt_stack: Vec<Vec<u8>>,
+    /// One entry per open OP_IF or OP_NOTIF, innermost last: whether its
+    /// current branch runs.
+    pub exec: Vec<bool>,
+}
+
+impl ExecState {
+    /// Returns `true` if every open conditional is in a running branch,
+    /// so the next opcode will execute.
+    pub fn is_executing(&self) -> bool {
+        is_executing(&self.exec)
+    }
+}
+
+/// Executes tokens on a caller-owned stack of byte vectors.
+///
+/// `items` is ordered bottom to top, matching the engine's internal stack.
+/// It is updated in place, so a caller can feed a script in pieces and
+/// inspect the stack between them. Unlike [`execute`], no final
+/// truthiness check is made.
+///
+/// Conditional blocks must be balanced within each call, and the alt stack
+/// starts empty on each call; [`execute_on_state`] keeps both. A tapscript
+/// containing an OP_SUCCESSx opcode succeeds without running, leaving
+/// `items` as they were. On error, `items` holds whatever state execution
+/// had reached; callers that need atomic steps should keep their own copy.
+pub fn execute_on_items(
+    tokens: &[Token],
+    items: &mut Vec<Vec<u8>>,
+    opts: &ExecuteOpts,
+) -> Result<(), ScriptError> {
+    let mut stack = Stack::from_items(core::mem::take(items));
+    let result = execute_on_stack(tokens, &mut stack, opts);
+    *items = stack.into_items();
+    result
+}
+
+/// Executes tokens on an [`ExecState`], continuing from where the last
+/// call left off.
+///
+/// Unlike [`execute_on_items`], conditionals may stay open between calls
+/// and the alt stack is kept; nothing checks that they are closed at the
+/// end. On error, `state` holds whatever execution had reached.
+///
+/// ```rust
+/// use mini_bitcoin_script::engine::{execute_on_state, ExecState, ExecuteOpts};
+/// use mini_bitcoin_script::tokenizer::parse_asm;
+///
+/// let opts = ExecuteOpts::default();
+/// let mut state = ExecState::default();
+/// execute_on_state(&parse_asm("OP_0 OP_IF").unwrap(), &mut state, &opts).unwrap();
+/// assert!(!state.is_executing());
+/// execute_on_state(&parse_asm("OP_2 OP_ELSE OP_3 OP_ENDIF").unwrap(), &mut state, &opts).unwrap();
+/// assert_eq!(state.stack, vec![vec![3]]);
+/// ```
+pub fn execute_on_state(
+    tokens: &[Token],
+    state: &mut ExecState,
+    opts: &ExecuteOpts,
+) -> Result<(), ScriptError> {
+    if has_op_success(tokens, opts) {
+        return Ok(());
+    }
+    let mut stack = Stack::from_items(core::mem::take(&mut state.stack));
+    let result = run_tokens(
+        tokens,
+        &mut stack,
+        &mut state.alt_stack,
+        &mut state.exec,
+        opts,
+    );
+    state.stack = stack.into_items();
+    result
+}
+
 /// Executes tokens on an existing stack.
 ///
 /// Used internally by `script.rs` for two-phase P2PKH execution where
@@ -54,10 +245,31 @@ pub(crate) fn execute_on_stack(
     stack: &mut Stack,
     opts: &ExecuteOpts,
 ) -> Result<(), ScriptError> {
+    if has_op_success(tokens, opts) {
+        return Ok(());
+    }
     let mut exec_stack: Vec<bool> = Vec::new();
+    let mut alt_stack: Vec<Vec<u8>> = Vec::new();
+    run_tokens(tokens, stack, &mut alt_stack, &mut exec_stack, opts)?;
 
+    if !exec_stack.is_empty() {
+        return Err(ScriptError::UnbalancedConditional);
+    }
+
+    Ok(())
+}
+
+/// The token loop shared by [`execute_on_stack`] and [`execute_on_state`],
+/// leaving conditionals open for the caller to check.
+fn run_tokens(
+    tokens: &[Token],
+    stack: &mut Stack,
+    alt_stack: &mut Vec<Vec<u8>>,
+    exec_stack: &mut Vec<bool>,
+    opts: &ExecuteOpts,
+) -> Result<(), ScriptError> {
     for token in tokens {
-        let executing = is_executing(&exec_stack);
+        let executing = is_executing(exec_stack);
 
         match token {
             // ── Conditional flow control (always processed) ──────────
@@ -89,6 +301,11 @@ pub(crate) fn execute_on_stack(
                 }
             }
 
+            // ── Disabled opcodes fail even when not executing ────────
+            Token::Op(op) if op.is_disabled() => {
+                return Err(ScriptError::DisabledOpcode(op.to_byte()));
+            }
+
             // ── Skip everything else when not executing ──────────────
             _ if !executing => continue,
 
@@ -97,167 +314,331 @@ pub(crate) fn execute_on_stack(
                 stack.push(data.clone());
             }
 
-            // ── Constants ────────────────────────────────────────────
-            Token::Op(Opcode::Op0) => stack.push(vec![]),
-            Token::Op(Opcode::Op1Negate) => stack.push(vec![0x81]),
-            Token::Op(Opcode::Op1) => stack.push(vec![1]),
-            Token::Op(Opcode::Op2) => stack.push(vec![2]),
-            Token::Op(Opcode::Op3) => stack.push(vec![3]),
-            Token::Op(Opcode::Op4) => stack.push(vec![4]),
-            Token::Op(Opcode::Op5) => stack.push(vec![5]),
-            Token::Op(Opcode::Op6) => stack.push(vec![6]),
-            Token::Op(Opcode::Op7) => stack.push(vec![7]),
-            Token::Op(Opcode::Op8) => stack.push(vec![8]),
-            Token::Op(Opcode::Op9) => stack.push(vec![9]),
-            Token::Op(Opcode::Op10) => stack.push(vec![10]),
-            Token::Op(Opcode::Op11) => stack.push(vec![11]),
-            Token::Op(Opcode::Op12) => stack.push(vec![12]),
-            Token::Op(Opcode::Op13) => stack.push(vec![13]),
-            Token::Op(Opcode::Op14) => stack.push(vec![14]),
-            Token::Op(Opcode::Op15) => stack.push(vec![15]),
-            Token::Op(Opcode::Op16) => stack.push(vec![16]),
-
-            // ── Flow control ─────────────────────────────────────────
-            Token::Op(Opcode::OpNop) => {}
-            Token::Op(Opcode::OpVerify) => {
-                let val = stack.pop()?;
-                if !is_true(&val) {
-                    return Err(ScriptError::VerifyFailed);
-                }
-            }
-            Token::Op(Opcode::OpReturn) => {
-                return Err(ScriptError::OpReturnEncountered);
-            }
+            Token::Op(op) => execute_opcode(*op, stack, alt_stack, opts)?,
+        }
+    }
+    Ok(())
+}
 
-            // ── Stack manipulation ───────────────────────────────────
-            Token::Op(Opcode::OpDup) => {
-                let top = stack.peek()?.to_vec();
-                stack.push(top);
-            }
-            Token::Op(Opcode::OpDrop) => {
-                stack.pop()?;
-            }
-            Token::Op(Opcode::Op2Dup) => {
-                let b = stack.pop()?;
-                let a = stack.pop()?;
-                stack.push(a.clone());
-                stack.push(b.clone());
-                stack.push(a);
-                stack.push(b);
-            }
-            Token::Op(Opcode::Op2Drop) => {
-                stack.pop()?;
-                stack.pop()?;
-            }
-            Token::Op(Opcode::OpNip) => {
-                if stack.len() < 2 {
-                    return Err(ScriptError::StackUnderflow);
-                }
-                stack.remove(stack.len() - 2)?;
-            }
-            Token::Op(Opcode::OpOver) => {
-                if stack.len() < 2 {
-                    return Err(ScriptError::StackUnderflow);
-                }
-                let second = stack.pop()?;
-                let first = stack.peek()?.to_vec();
-                stack.push(second);
-                stack.push(first);
-            }
-            Token::Op(Opcode::OpSwap) => {
-                let b = stack.pop()?;
-                let a = stack.pop()?;
-                stack.push(b);
-                stack.push(a);
+/// Executes one opcode other than OP_IF, OP_NOTIF, OP_ELSE and OP_ENDIF,
+/// in an executing branch.
+///
+/// Shared by [`execute_on_stack`] and the [`bytecode`](crate::bytecode)
+/// interpreter, which both handle conditionals themselves.
+pub(crate) fn execute_opcode(
+    op: Opcode,
+    stack: &mut Stack,
+    alt_stack: &mut Vec<Vec<u8>>,
+    opts: &ExecuteOpts,
+) -> Result<(), ScriptError> {
+    match op {
+        Opcode::OpIf | Opcode::OpNotIf | Opcode::OpElse | Opcode::OpEndIf => {
+            unreachable!("conditionals are handled by the caller")
+        }
+
+        // ── Constants ────────────────────────────────────────────
+        Opcode::Op0 => stack.push(vec![]),
+        Opcode::Op1Negate => stack.push(vec![0x81]),
+        Opcode::Op1 => stack.push(vec![1]),
+        Opcode::Op2 => stack.push(vec![2]),
+        Opcode::Op3 => stack.push(vec![3]),
+        Opcode::Op4 => stack.push(vec![4]),
+        Opcode::Op5 => stack.push(vec![5]),
+        Opcode::Op6 => stack.push(vec![6]),
+        Opcode::Op7 => stack.push(vec![7]),
+        Opcode::Op8 => stack.push(vec![8]),
+        Opcode::Op9 => stack.push(vec![9]),
+        Opcode::Op10 => stack.push(vec![10]),
+        Opcode::Op11 => stack.push(vec![11]),
+        Opcode::Op12 => stack.push(vec![12]),
+        Opcode::Op13 => stack.push(vec![13]),
+        Opcode::Op14 => stack.push(vec![14]),
+        Opcode::Op15 => stack.push(vec![15]),
+        Opcode::Op16 => stack.push(vec![16]),
+
+        // ── Flow control ─────────────────────────────────────────
+        Opcode::OpNop => {}
+        Opcode::OpVerify => {
+            let val = stack.pop()?;
+            if !is_true(&val) {
+                return Err(ScriptError::VerifyFailed);
             }
-            Token::Op(Opcode::OpTuck) => {
-                let b = stack.pop()?;
-                let a = stack.pop()?;
-                stack.push(b.clone());
-                stack.push(a);
-                stack.push(b);
+        }
+        Opcode::OpReturn => {
+            return Err(ScriptError::OpReturnEncountered);
+        }
+
+        // ── Stack manipulation ───────────────────────────────────
+        Opcode::OpDup => {
+            let top = stack.peek()?.to_vec();
+            stack.push(top);
+        }
+        Opcode::OpDrop => {
+            stack.pop()?;
+        }
+        Opcode::Op2Dup => {
+            let b = stack.pop()?;
+            let a = stack.pop()?;
+            stack.push(a.clone());
+            stack.push(b.clone());
+            stack.push(a);
+            stack.push(b);
+        }
+        Opcode::Op2Drop => {
+            stack.pop()?;
+            stack.pop()?;
+        }
+        Opcode::OpNip => {
+            if stack.len() < 2 {
+                return Err(ScriptError::StackUnderflow);
             }
-            Token::Op(Opcode::OpDepth) => {
-                let depth = stack.len();
-                stack.push(encode_num(depth as i64));
+            stack.remove(stack.len() - 2)?;
+        }
+        Opcode::OpOver => {
+            if stack.len() < 2 {
+                return Err(ScriptError::StackUnderflow);
             }
-            Token::Op(Opcode::OpSize) => {
-                let top = stack.peek()?;
-                let size = top.len();
-                stack.push(encode_num(size as i64));
+            let second = stack.pop()?;
+            let first = stack.peek()?.to_vec();
+            stack.push(second);
+            stack.push(first);
+        }
+        Opcode::OpSwap => {
+            let b = stack.pop()?;
+            let a = stack.pop()?;
+            stack.push(b);
+            stack.push(a);
+        }
+        Opcode::OpTuck => {
+            let b = stack.pop()?;
+            let a = stack.pop()?;
+            stack.push(b.clone());
+            stack.push(a);
+            stack.push(b);
+        }
+        Opcode::OpToAltStack => {
+            alt_stack.push(stack.pop()?);
+        }
+        Opcode::OpFromAltStack => {
+            let item = alt_stack
+                .pop()
+                .ok_or(ScriptError::InvalidAltStackOperation)?;
+            stack.push(item);
+        }
+        Opcode::OpIfDup => {
+            let top = stack.peek()?.to_vec();
+            if is_true(&top) {
+                stack.push(top);
             }
+        }
+        Opcode::OpDepth => {
+            let depth = stack.len();
+            stack.push(encode_num(depth as i64));
+        }
+        Opcode::OpSize => {
+            let top = stack.peek()?;
+            let size = top.len();
+            stack.push(encode_num(size as i64));
+        }
 
-            // ── Comparison ───────────────────────────────────────────
-            Token::Op(Opcode::OpEqual) => {
-                let b = stack.pop()?;
-                let a = stack.pop()?;
-                stack.push_bool(a == b);
-            }
-            Token::Op(Opcode::OpEqualVerify) => {
-                let b = stack.pop()?;
-                let a = stack.pop()?;
-                if a != b {
-                    return Err(ScriptError::VerifyFailed);
-                }
+        // ── Comparison ───────────────────────────────────────────
+        Opcode::OpEqual => {
+            let b = stack.pop()?;
+            let a = stack.pop()?;
+            stack.push_bool(a == b);
+        }
+        Opcode::OpEqualVerify => {
+            let b = stack.pop()?;
+            let a = stack.pop()?;
+            if a != b {
+                return Err(ScriptError::EqualVerifyFailed);
             }
+        }
 
-            // ── Logic ────────────────────────────────────────────────
-            Token::Op(Opcode::OpNot) => {
-                let val = stack.pop()?;
-                // OP_NOT: 0 -> 1, 1 -> 0, anything else -> 0
-                if val.is_empty() || val == [0x00] {
-                    stack.push(vec![0x01]);
-                } else {
-                    stack.push(vec![]);
-                }
+        // ── Logic ────────────────────────────────────────────────
+        Opcode::OpNot => {
+            let val = stack.pop()?;
+            // OP_NOT: 0 -> 1, 1 -> 0, anything else -> 0
+            if val.is_empty() || val == [0x00] {
+                stack.push(vec![0x01]);
+            } else {
+                stack.push(vec![]);
             }
+        }
 
-            // ── Crypto ───────────────────────────────────────────────
-            Token::Op(Opcode::OpRipemd160) => {
-                let data = stack.pop()?;
-                stack.push(hash::ripemd160(&data).to_vec());
-            }
-            Token::Op(Opcode::OpSha256) => {
-                let data = stack.pop()?;
-                stack.push(hash::sha256(&data).to_vec());
-            }
-            Token::Op(Opcode::OpHash160) => {
-                let data = stack.pop()?;
-                stack.push(hash::hash160(&data).to_vec());
+        // ── Arithmetic ───────────────────────────────────────────
+        Opcode::Op1Add | Opcode::Op1Sub | Opcode::OpNegate | Opcode::OpAbs => {
+            let a = decode_num(&stack.pop()?, 4)?;
+            stack.push(encode_num(match op {
+                Opcode::Op1Add => a + 1,
+                Opcode::Op1Sub => a - 1,
+                Opcode::OpNegate => -a,
+                _ => a.abs(),
+            }));
+        }
+        Opcode::Op0NotEqual => {
+            let a = decode_num(&stack.pop()?, 4)?;
+            stack.push_bool(a != 0);
+        }
+        Opcode::OpAdd => {
+            let (a, b) = pop_num_pair(stack)?;
+            stack.push(encode_num(a + b));
+        }
+        Opcode::OpSub => {
+            let (a, b) = pop_num_pair(stack)?;
+            stack.push(encode_num(a - b));
+        }
+        Opcode::OpBoolAnd => {
+            let (a, b) = pop_num_pair(stack)?;
+            stack.push_bool(a != 0 && b != 0);
+        }
+        Opcode::OpBoolOr => {
+            let (a, b) = pop_num_pair(stack)?;
+            stack.push_bool(a != 0 || b != 0);
+        }
+        Opcode::OpNumEqual => {
+            let (a, b) = pop_num_pair(stack)?;
+            stack.push_bool(a == b);
+        }
+        Opcode::OpNumEqualVerify => {
+            let (a, b) = pop_num_pair(stack)?;
+            if a != b {
+                return Err(ScriptError::NumEqualVerifyFailed);
             }
-            Token::Op(Opcode::OpHash256) => {
-                let data = stack.pop()?;
-                stack.push(hash::hash256(&data).to_vec());
+        }
+        Opcode::OpNumNotEqual
+        | Opcode::OpLessThan
+        | Opcode::OpGreaterThan
+        | Opcode::OpLessThanOrEqual
+        | Opcode::OpGreaterThanOrEqual => {
+            let (a, b) = pop_num_pair(stack)?;
+            stack.push_bool(match op {
+                Opcode::OpNumNotEqual => a != b,
+                Opcode::OpLessThan => a < b,
+                Opcode::OpGreaterThan => a > b,
+                Opcode::OpLessThanOrEqual => a <= b,
+                _ => a >= b,
+            });
+        }
+        Opcode::OpMin => {
+            let (a, b) = pop_num_pair(stack)?;
+            stack.push(encode_num(a.min(b)));
+        }
+        Opcode::OpMax => {
+            let (a, b) = pop_num_pair(stack)?;
+            stack.push(encode_num(a.max(b)));
+        }
+        Opcode::OpWithin => {
+            let (min, max) = pop_num_pair(stack)?;
+            let x = decode_num(&stack.pop()?, 4)?;
+            stack.push_bool(min <= x && x < max);
+        }
+
+        // ── Crypto ───────────────────────────────────────────────
+        Opcode::OpRipemd160 => {
+            let data = stack.pop()?;
+            stack.push(hash::ripemd160(&data).to_vec());
+        }
+        Opcode::OpSha256 => {
+            let data = stack.pop()?;
+            stack.push(hash::sha256(&data).to_vec());
+        }
+        Opcode::OpHash160 => {
+            let data = stack.pop()?;
+            stack.push(hash::hash160(&data).to_vec());
+        }
+        Opcode::OpHash256 => {
+            let data = stack.pop()?;
+            stack.push(hash::hash256(&data).to_vec());
+        }
+        Opcode::OpCheckSig => {
+            checksig(stack, opts)?;
+        }
+        Opcode::OpCheckSigVerify => {
+            checksig(stack, opts)?;
+            let val = stack.pop()?;
+            if !is_true(&val) {
+                return Err(ScriptError::CheckSigVerifyFailed);
             }
-            Token::Op(Opcode::OpCheckSig) => {
-                checksig(stack, opts)?;
+        }
+        Opcode::OpCheckMultiSig | Opcode::OpCheckMultiSigVerify
+            if opts.sig_version == SigVersion::Tapscript =>
+        {
+            return Err(ScriptError::TapscriptCheckMultiSig);
+        }
+        Opcode::OpCheckMultiSig => {
+            checkmultisig(stack, opts)?;
+        }
+        Opcode::OpCheckMultiSigVerify => {
+            checkmultisig(stack, opts)?;
+            let val = stack.pop()?;
+            if !is_true(&val) {
+                return Err(ScriptError::CheckMultiSigVerifyFailed);
             }
-            Token::Op(Opcode::OpCheckSigVerify) => {
-                checksig(stack, opts)?;
-                let val = stack.pop()?;
-                if !is_true(&val) {
-                    return Err(ScriptError::VerifyFailed);
-                }
+        }
+        Opcode::OpCheckSigAdd => {
+            if opts.sig_version != SigVersion::Tapscript {
+                return Err(ScriptError::UnsupportedOpcode(op.to_byte()));
             }
+            checksigadd(stack, opts)?;
         }
-    }
 
-    if !exec_stack.is_empty() {
-        return Err(ScriptError::UnbalancedConditional);
-    }
+        // ── Disabled ─────────────────────────────────────────────
+        Opcode::OpCat
+        | Opcode::OpSubStr
+        | Opcode::OpLeft
+        | Opcode::OpRight
+        | Opcode::OpInvert
+        | Opcode::OpAnd
+        | Opcode::OpOr
+        | Opcode::OpXor
+        | Opcode::Op2Mul
+        | Opcode::Op2Div
+        | Opcode::OpMul
+        | Opcode::OpDiv
+        | Opcode::OpMod
+        | Opcode::OpLShift
+        | Opcode::OpRShift => {
+            return Err(ScriptError::DisabledOpcode(op.to_byte()));
+        }
 
+        // ── Locktime ─────────────────────────────────────────────
+        Opcode::OpCheckLockTimeVerify => {
+            if let Some(lock_time) = opts.lock_time {
+                let required = decode_num(stack.peek()?, 5)?;
+                check_lock_time(required, lock_time, opts.sequence)?;
+            }
+        }
+        Opcode::OpCheckSequenceVerify => match opts.sequence {
+            Some(sequence) if !opts.nop3 => {
+                let required = decode_num(stack.peek()?, 5)?;
+                check_sequence(required, sequence, opts.tx_version)?;
+            }
+            _ => {}
+        },
+    }
     Ok(())
 }
 
 // ── Helpers ──────────────────────────────────────────────────────────────
 
+/// Returns `true` for a tapscript containing an OP_SUCCESSx opcode, which
+/// succeeds unconditionally (BIP342). Only the disabled opcodes among them
+/// tokenize; the others are rejected by the tokenizer.
+fn has_op_success(tokens: &[Token], opts: &ExecuteOpts) -> bool {
+    opts.sig_version == SigVersion::Tapscript
+        && tokens
+            .iter()
+            .any(|token| matches!(token, Token::Op(op) if op.is_disabled()))
+}
+
 /// Returns `true` if the execution stack indicates we are in an executing branch.
 fn is_executing(exec_stack: &[bool]) -> bool {
     exec_stack.iter().all(|&v| v)
 }
 
 /// Encodes a non-negative integer as a minimal Bitcoin Script number.
-fn encode_num(n: i64) -> Vec<u8> {
+pub(crate) fn encode_num(n: i64) -> Vec<u8> {
     if n == 0 {
         return vec![];
     }
@@ -283,54 +664,336 @@ fn encode_num(n: i64) -> Vec<u8> {
     result
 }
 
+/// Decodes a Bitcoin Script number of at most `max_len` bytes.
+///
+/// Numbers are little-endian with the sign in the top bit of the last
+/// byte. Non-minimal encodings are accepted.
+pub(crate) fn decode_num(bytes: &[u8], max_len: usize) -> Result<i64, ScriptError> {
+    if bytes.len() > max_len {
+        return Err(ScriptError::NumberOverflow);
+    }
+    let last = match bytes.last() {
+        Some(&last) => last,
+        None => return Ok(0),
+    };
+    let mut n = 0i64;
+    for (i, &byte) in bytes.iter().enumerate() {
+        n |= i64::from(byte) << (8 * i);
+    }
+    if last & 0x80 != 0 {
+        let sign_bit = 0x80i64 << (8 * (bytes.len() - 1));
+        return Ok(-(n & !sign_bit));
+    }
+    Ok(n)
+}
+
+/// Pops two numeric operands, returning them in push order.
+fn pop_num_pair(stack: &mut Stack) -> Result<(i64, i64), ScriptError> {
+    let b = stack.pop()?;
+    let a = stack.pop()?;
+    Ok((decode_num(&a, 4)?, decode_num(&b, 4)?))
+}
+
 /// OP_CHECKSIG implementation.
 ///
-/// Default: stub mode (always pushes true).
+/// Default: stub mode (any non-empty signature is valid).
 /// With `secp256k1` feature + sighash: real ECDSA verification.
+///
+/// Signature and public key encodings are checked first according to
+/// `opts.flags`; under NULLFAIL a failed check with a non-empty signature
+/// is an error rather than a pushed false. Tapscript follows BIP342
+/// instead; see [`tapscript_signature_valid`].
 fn checksig(stack: &mut Stack, opts: &ExecuteOpts) -> Result<(), ScriptError> {
     let pubkey = stack.pop()?;
     let sig = stack.pop()?;
 
+    if opts.sig_version == SigVersion::Tapscript {
+        let result = tapscript_signature_valid(&sig, &pubkey, opts)?;
+        stack.push_bool(result);
+        return Ok(());
+    }
+
+    signature::check_signature_encoding(&sig, opts.flags)?;
+    signature::check_pubkey_encoding(&pubkey, opts.flags)?;
+
+    let result = signature_valid(&sig, &pubkey, opts);
+    if !result && !sig.is_empty() && opts.flags.contains(VerifyFlags::NULLFAIL) {
+        return Err(ScriptError::NullFail);
+    }
+    stack.push_bool(result);
+    Ok(())
+}
+
+/// OP_CHECKMULTISIG implementation.
+///
+/// Pops the key count, the keys, the signature count, the signatures and
+/// the extra item consumed by the original implementation's off-by-one.
+/// Signatures must appear in the same order as their keys: each one is
+/// tried against the remaining keys in turn, and the check fails as soon
+/// as too few keys remain for the signatures left.
+fn checkmultisig(stack: &mut Stack, opts: &ExecuteOpts) -> Result<(), ScriptError> {
+    let key_count = decode_num(&stack.pop()?, 4)?;
+    if !(0..=MAX_PUBKEYS_PER_MULTISIG as i64).contains(&key_count) {
+        return Err(ScriptError::PubkeyCount);
+    }
+    let mut pubkeys = Vec::with_capacity(key_count as usize);
+    for _ in 0..key_count {
+        pubkeys.push(stack.pop()?);
+    }
+    let sig_count = decode_num(&stack.pop()?, 4)?;
+    if !(0..=key_count).contains(&sig_count) {
+        return Err(ScriptError::SigCount);
+    }
+    let mut sigs = Vec::with_capacity(sig_count as usize);
+    for _ in 0..sig_count {
+        sigs.push(stack.pop()?);
+    }
+    stack.pop()?;
+
+    // Both lists were popped last item first.
+    pubkeys.reverse();
+    sigs.reverse();
+
+    let mut success = true;
+    let (mut sig_index, mut key_index) = (0, 0);
+    while success && sig_index < sigs.len() {
+        let sig = &sigs[sig_index];
+        let pubkey = &pubkeys[key_index];
+        signature::check_signature_encoding(sig, opts.flags)?;
+        signature::check_pubkey_encoding(pubkey, opts.flags)?;
+        if signature_valid(sig, pubkey, opts) {
+            sig_index += 1;
+        }
+        key_index += 1;
+        if sigs.len() - sig_index > pubkeys.len() - key_index {
+            success = false;
+        }
+    }
+
+    if !success
+        && opts.flags.contains(VerifyFlags::NULLFAIL)
+        && sigs.iter().any(|sig| !sig.is_empty())
+    {
+        return Err(ScriptError::NullFail);
+    }
+    stack.push_bool(success);
+    Ok(())
+}
+
+/// OP_CHECKSIGADD implementation (BIP342), for tapscript only.
+///
+/// Pops a public key, a number and a signature, and pushes the number plus
+/// one if the signature is valid.
+fn checksigadd(stack: &mut Stack, opts: &ExecuteOpts) -> Result<(), ScriptError> {
+    let pubkey = stack.pop()?;
+    let n = decode_num(&stack.pop()?, 4)?;
+    let sig = stack.pop()?;
+
+    let valid = tapscript_signature_valid(&sig, &pubkey, opts)?;
+    stack.push(encode_num(n + i64::from(valid)));
+    Ok(())
+}
+
+/// A tapscript signature check (BIP342). An empty public key fails the
+/// script. An empty signature is a failed check; any other signature must
+/// be valid or the script fails. Public keys that are neither empty nor
+/// 32 bytes are an unknown key type and are accepted without verification.
+fn tapscript_signature_valid(
+    sig: &[u8],
+    pubkey: &[u8],
+    opts: &ExecuteOpts,
+) -> Result<bool, ScriptError> {
+    if pubkey.is_empty() {
+        return Err(ScriptError::TapscriptEmptyPubkey);
+    }
+    if sig.is_empty() {
+        return Ok(false);
+    }
+
     #[cfg(feature = "secp256k1")]
     {
+        let sighash = match &opts.sighash_fn {
+            _ if pubkey.len() != 32 => None,
+            Some(sighash_fn) => Some(
+                sighash_fn
+                    .sighash(sig)
+                    .ok_or(ScriptError::SchnorrSigHashType)?,
+            ),
+            None => opts.sighash,
+        };
+        if let Some(sighash) = sighash {
+            if !check_cached(sig, pubkey, &sighash, opts, verify_schnorr) {
+                return Err(ScriptError::SchnorrSig);
+            }
+        }
+    }
+
+    let _ = opts;
+    Ok(true)
+}
+
+/// Checks one signature against one public key: real ECDSA with the
+/// `secp256k1` feature and a sighash, otherwise stub mode.
+///
+/// Without a flag requiring strict DER, signatures are parsed as leniently
+/// as consensus did before BIP66.
+fn signature_valid(sig: &[u8], pubkey: &[u8], opts: &ExecuteOpts) -> bool {
+    #[cfg(feature = "secp256k1")]
+    {
+        let strict = VerifyFlags::DERSIG | VerifyFlags::LOW_S | VerifyFlags::STRICTENC;
+        let verify = if opts.flags.intersects(strict) {
+            verify_ecdsa
+        } else {
+            verify_ecdsa_lax
+        };
+        if let Some(sighash_fn) = &opts.sighash_fn {
+            return sighash_fn.sighash(sig).map_or(false, |sighash| {
+                check_cached(sig, pubkey, &sighash, opts, verify)
+            });
+        }
         if let Some(sighash) = opts.sighash {
-            let result = verify_ecdsa(&sig, &pubkey, &sighash);
-            stack.push_bool(result);
-            return Ok(());
+            return check_cached(sig, pubkey, &sighash, opts, verify);
         }
     }
 
     // Stub mode: suppress unused warning when feature is off
-    let _ = (&pubkey, &sig, &opts);
-    stack.push(vec![0x01]);
+    let _ = (pubkey, opts);
+    !sig.is_empty()
+}
+
+/// Locktimes below this are block heights; at or above it, Unix times.
+const LOCKTIME_THRESHOLD: i64 = 500_000_000;
+
+/// Sequence bit that disables relative locktime (BIP68).
+const SEQUENCE_DISABLE_FLAG: i64 = 1 << 31;
+
+/// Sequence bit selecting 512-second units instead of blocks (BIP68).
+const SEQUENCE_TYPE_FLAG: i64 = 1 << 22;
+
+/// Sequence bits holding the relative locktime value (BIP68).
+const SEQUENCE_MASK: i64 = 0xffff;
+
+/// OP_CHECKLOCKTIMEVERIFY check of `required` against the transaction.
+pub(crate) fn check_lock_time(
+    required: i64,
+    lock_time: u32,
+    sequence: Option<u32>,
+) -> Result<(), ScriptError> {
+    if required < 0 {
+        return Err(ScriptError::NegativeLocktime);
+    }
+    let lock_time = i64::from(lock_time);
+    let same_kind = (required < LOCKTIME_THRESHOLD) == (lock_time < LOCKTIME_THRESHOLD);
+    if !same_kind || required > lock_time {
+        return Err(ScriptError::UnsatisfiedLocktime);
+    }
+    // A final input opts out of the transaction's locktime.
+    if sequence == Some(u32::MAX) {
+        return Err(ScriptError::UnsatisfiedLocktime);
+    }
+    Ok(())
+}
+
+/// OP_CHECKSEQUENCEVERIFY check of `required` against the input, and the
+/// transaction's version if known.
+pub(crate) fn check_sequence(
+    required: i64,
+    sequence: u32,
+    tx_version: Option<i32>,
+) -> Result<(), ScriptError> {
+    if required < 0 {
+        return Err(ScriptError::NegativeLocktime);
+    }
+    if required & SEQUENCE_DISABLE_FLAG != 0 {
+        return Ok(());
+    }
+    if tx_version.map_or(false, |version| version < 2) {
+        return Err(ScriptError::UnsatisfiedLocktime);
+    }
+    let sequence = i64::from(sequence);
+    if sequence & SEQUENCE_DISABLE_FLAG != 0 {
+        return Err(ScriptError::UnsatisfiedLocktime);
+    }
+    let mask = SEQUENCE_TYPE_FLAG | SEQUENCE_MASK;
+    let (required, sequence) = (required & mask, sequence & mask);
+    let same_kind = (required < SEQUENCE_TYPE_FLAG) == (sequence < SEQUENCE_TYPE_FLAG);
+    if !same_kind || required > sequence {
+        return Err(ScriptError::UnsatisfiedLocktime);
+    }
     Ok(())
 }
 
-/// Real ECDSA signature verification using secp256k1.
+/// Runs `verify` on the signature, unless [`ExecuteOpts::sig_cache`]
+/// already holds it.
+#[cfg(feature = "secp256k1")]
+fn check_cached(
+    sig: &[u8],
+    pubkey: &[u8],
+    sighash: &[u8; 32],
+    opts: &ExecuteOpts,
+    verify: fn(&[u8], &[u8], &[u8; 32]) -> bool,
+) -> bool {
+    #[cfg(feature = "std")]
+    {
+        if let Some(cache) = &opts.sig_cache {
+            return cache.verify_with(sighash, pubkey, sig, || verify(sig, pubkey, sighash));
+        }
+    }
+    let _ = opts;
+    verify(sig, pubkey, sighash)
+}
+
+/// Real ECDSA signature verification using secp256k1, with a strict DER
+/// signature.
 #[cfg(feature = "secp256k1")]
 fn verify_ecdsa(sig_bytes: &[u8], pubkey_bytes: &[u8], sighash: &[u8; 32]) -> bool {
-    use secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1};
+    ecdsa(
+        sig_bytes,
+        pubkey_bytes,
+        sighash,
+        secp256k1::ecdsa::Signature::from_der,
+    )
+}
+
+/// [`verify_ecdsa`] accepting the BER-like encodings OpenSSL allowed
+/// before BIP66.
+#[cfg(feature = "secp256k1")]
+fn verify_ecdsa_lax(sig_bytes: &[u8], pubkey_bytes: &[u8], sighash: &[u8; 32]) -> bool {
+    ecdsa(
+        sig_bytes,
+        pubkey_bytes,
+        sighash,
+        secp256k1::ecdsa::Signature::from_der_lax,
+    )
+}
+
+#[cfg(feature = "secp256k1")]
+fn ecdsa(
+    sig_bytes: &[u8],
+    pubkey_bytes: &[u8],
+    sighash: &[u8; 32],
+    parse: fn(&[u8]) -> Result<secp256k1::ecdsa::Signature, secp256k1::Error>,
+) -> bool {
+    use secp256k1::{Message, PublicKey, Secp256k1};
 
     // Signature must have at least 1 byte (the hash type byte)
     if sig_bytes.is_empty() {
         return false;
     }
 
-    // Last byte is the hash type. We only support SIGHASH_ALL (0x01).
-    let hash_type = sig_bytes[sig_bytes.len() - 1];
-    if hash_type != 0x01 {
-        // Unsupported hash type — fall back to false
-        return false;
-    }
-
+    // Last byte is the hash type. The caller's sighash already commits to
+    // it, so it only needs stripping here.
     let der_sig = &sig_bytes[..sig_bytes.len() - 1];
 
     let secp = Secp256k1::verification_only();
 
-    let signature = match Signature::from_der(der_sig) {
+    let mut signature = match parse(der_sig) {
         Ok(s) => s,
         Err(_) => return false,
     };
+    // libsecp256k1 only accepts low-S signatures; high S is a policy rule
+    // (VerifyFlags::LOW_S), not a consensus one.
+    signature.normalize_s();
 
     let public_key = match PublicKey::from_slice(pubkey_bytes) {
         Ok(k) => k,
@@ -342,6 +1005,32 @@ fn verify_ecdsa(sig_bytes: &[u8], pubkey_bytes: &[u8], sighash: &[u8; 32]) -> bo
     secp.verify_ecdsa(&message, &signature, &public_key).is_ok()
 }
 
+/// BIP340 Schnorr verification of a 64-byte signature, or 65 bytes with
+/// a sighash type byte, against an x-only public key.
+#[cfg(feature = "secp256k1")]
+pub(crate) fn verify_schnorr(sig_bytes: &[u8], pubkey_bytes: &[u8], sighash: &[u8; 32]) -> bool {
+    use secp256k1::{schnorr::Signature, Message, Secp256k1, XOnlyPublicKey};
+
+    let sig_bytes = match sig_bytes.len() {
+        64 => sig_bytes,
+        65 => &sig_bytes[..64],
+        _ => return false,
+    };
+    let signature = match Signature::from_slice(sig_bytes) {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    let public_key = match XOnlyPublicKey::from_slice(pubkey_bytes) {
+        Ok(k) => k,
+        Err(_) => return false,
+    };
+
+    let secp = Secp256k1::verification_only();
+    let message = Message::from_digest(*sighash);
+    secp.verify_schnorr(&signature, &message, &public_key)
+        .is_ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -540,6 +1229,47 @@ mod tests {
         assert!(matches!(err, ScriptError::UnbalancedConditional));
     }
 
+    #[test]
+    fn disabled_opcode_fails_in_unexecuted_branch() {
+        // OP_0 OP_IF OP_CAT OP_ENDIF OP_1
+        let tokens = [
+            op(Opcode::Op0),
+            op(Opcode::OpIf),
+            op(Opcode::OpCat),
+            op(Opcode::OpEndIf),
+            op(Opcode::Op1),
+        ];
+        assert_eq!(execute(&tokens), Err(ScriptError::DisabledOpcode(0x7e)));
+
+        // An earlier failure is reported first.
+        let tokens = [op(Opcode::OpReturn), op(Opcode::OpMul)];
+        assert_eq!(execute(&tokens), Err(ScriptError::OpReturnEncountered));
+    }
+
+    #[test]
+    fn disabled_opcodes_are_op_success_in_tapscript() {
+        let opts = ExecuteOpts {
+            sig_version: SigVersion::Tapscript,
+            ..ExecuteOpts::default()
+        };
+        // OP_RETURN OP_CAT: OP_SUCCESSx wins wherever it is.
+        let tokens = [op(Opcode::OpReturn), op(Opcode::OpCat)];
+        assert_eq!(execute_with_opts(&tokens, &opts), Ok(true));
+
+        let mut items = vec![vec![]];
+        execute_on_items(&tokens, &mut items, &opts).unwrap();
+        assert_eq!(items, vec![vec![]]);
+
+        let opts = ExecuteOpts {
+            sig_version: SigVersion::WitnessV0,
+            ..ExecuteOpts::default()
+        };
+        assert_eq!(
+            execute_with_opts(&tokens, &opts),
+            Err(ScriptError::OpReturnEncountered)
+        );
+    }
+
     // ── Stack manipulation ───────────────────────────────────────────
 
     #[test]
@@ -683,7 +1413,7 @@ mod tests {
     fn op_equalverify_fail() {
         let tokens = [push(&[0xaa]), push(&[0xbb]), op(Opcode::OpEqualVerify)];
         let err = execute(&tokens).unwrap_err();
-        assert!(matches!(err, ScriptError::VerifyFailed));
+        assert!(matches!(err, ScriptError::EqualVerifyFailed));
     }
 
     // ── Logic ────────────────────────────────────────────────────────
@@ -706,6 +1436,153 @@ mod tests {
         assert!(!execute(&tokens).unwrap());
     }
 
+    // ── Alt stack ────────────────────────────────────────────────────
+
+    #[test]
+    fn altstack_roundtrip() {
+        let tokens = [
+            op(Opcode::Op2),
+            op(Opcode::OpToAltStack),
+            op(Opcode::Op1),
+            op(Opcode::OpFromAltStack),
+        ];
+        let mut stack = Stack::new();
+        execute_on_stack(&tokens, &mut stack, &ExecuteOpts::default()).unwrap();
+        assert_eq!(stack.into_items(), vec![vec![1], vec![2]]);
+
+        let err = execute(&[op(Opcode::OpFromAltStack)]).unwrap_err();
+        assert!(matches!(err, ScriptError::InvalidAltStackOperation));
+    }
+
+    #[test]
+    fn op_ifdup() {
+        let mut stack = Stack::new();
+        let tokens = [op(Opcode::Op0), op(Opcode::OpIfDup), op(Opcode::Op3)];
+        execute_on_stack(&tokens, &mut stack, &ExecuteOpts::default()).unwrap();
+        assert_eq!(stack.into_items(), vec![vec![], vec![3]]);
+
+        let mut stack = Stack::new();
+        let tokens = [op(Opcode::Op3), op(Opcode::OpIfDup)];
+        execute_on_stack(&tokens, &mut stack, &ExecuteOpts::default()).unwrap();
+        assert_eq!(stack.into_items(), vec![vec![3], vec![3]]);
+    }
+
+    // ── Arithmetic ───────────────────────────────────────────────────
+
+    #[test]
+    fn arithmetic() {
+        let mut stack = Stack::new();
+        let tokens = [op(Opcode::Op1Negate), op(Opcode::Op3), op(Opcode::OpAdd)];
+        execute_on_stack(&tokens, &mut stack, &ExecuteOpts::default()).unwrap();
+        assert_eq!(stack.pop().unwrap(), vec![2]);
+
+        assert!(!execute(&[op(Opcode::Op1), op(Opcode::Op0), op(Opcode::OpBoolAnd)]).unwrap());
+        assert!(execute(&[op(Opcode::Op1), op(Opcode::Op0), op(Opcode::OpBoolOr)]).unwrap());
+        assert!(execute(&[push(&[2, 0]), op(Opcode::Op2), op(Opcode::OpNumEqual)]).unwrap());
+        assert!(!execute(&[op(Opcode::Op0), op(Opcode::Op0NotEqual)]).unwrap());
+
+        let err = execute(&[
+            op(Opcode::Op1),
+            op(Opcode::Op2),
+            op(Opcode::OpNumEqualVerify),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, ScriptError::NumEqualVerifyFailed));
+
+        let err = execute(&[push(&[1; 5]), op(Opcode::Op1), op(Opcode::OpAdd)]).unwrap_err();
+        assert!(matches!(err, ScriptError::NumberOverflow));
+    }
+
+    #[test]
+    fn unary_arithmetic() {
+        let run = |a: Opcode, op_: Opcode| {
+            let mut stack = Stack::new();
+            execute_on_stack(&[op(a), op(op_)], &mut stack, &ExecuteOpts::default()).unwrap();
+            stack.pop().unwrap()
+        };
+        assert_eq!(run(Opcode::Op1Negate, Opcode::Op1Add), Vec::<u8>::new());
+        assert_eq!(run(Opcode::Op0, Opcode::Op1Sub), vec![0x81]);
+        assert_eq!(run(Opcode::Op5, Opcode::OpNegate), vec![0x85]);
+        assert_eq!(run(Opcode::Op1Negate, Opcode::OpAbs), vec![1]);
+
+        // The result may exceed four bytes but cannot be reused.
+        let mut stack = Stack::new();
+        let tokens = [push(&[0xff, 0xff, 0xff, 0x7f]), op(Opcode::Op1Add)];
+        execute_on_stack(&tokens, &mut stack, &ExecuteOpts::default()).unwrap();
+        assert_eq!(stack.pop().unwrap(), vec![0, 0, 0, 0x80, 0]);
+        let tokens = [push(&[0, 0, 0, 0x80, 0]), op(Opcode::Op1Sub)];
+        assert_eq!(execute(&tokens), Err(ScriptError::NumberOverflow));
+    }
+
+    #[test]
+    fn comparisons() {
+        let check = |a: Opcode, b: Opcode, op_: Opcode| execute(&[op(a), op(b), op(op_)]).unwrap();
+        assert!(check(Opcode::Op1, Opcode::Op2, Opcode::OpLessThan));
+        assert!(!check(Opcode::Op2, Opcode::Op2, Opcode::OpLessThan));
+        assert!(check(Opcode::Op2, Opcode::Op2, Opcode::OpLessThanOrEqual));
+        assert!(check(Opcode::Op3, Opcode::Op1Negate, Opcode::OpGreaterThan));
+        assert!(!check(
+            Opcode::Op1Negate,
+            Opcode::Op0,
+            Opcode::OpGreaterThanOrEqual
+        ));
+        assert!(check(Opcode::Op1, Opcode::Op2, Opcode::OpNumNotEqual));
+        assert!(!check(Opcode::Op3, Opcode::Op3, Opcode::OpNumNotEqual));
+        assert!(check(Opcode::Op3, Opcode::Op2, Opcode::OpSub));
+        assert!(!check(Opcode::Op2, Opcode::Op2, Opcode::OpSub));
+
+        let mut stack = Stack::new();
+        let tokens = [
+            op(Opcode::Op4),
+            op(Opcode::Op1Negate),
+            op(Opcode::OpMin),
+            op(Opcode::Op4),
+            op(Opcode::Op9),
+            op(Opcode::OpMax),
+        ];
+        execute_on_stack(&tokens, &mut stack, &ExecuteOpts::default()).unwrap();
+        assert_eq!(stack.into_items(), vec![vec![0x81], vec![9]]);
+    }
+
+    #[test]
+    fn within_is_half_open() {
+        let within = |x: Opcode| {
+            execute(&[
+                op(x),
+                op(Opcode::Op2),
+                op(Opcode::Op5),
+                op(Opcode::OpWithin),
+            ])
+            .unwrap()
+        };
+        assert!(!within(Opcode::Op1));
+        assert!(within(Opcode::Op2));
+        assert!(within(Opcode::Op4));
+        assert!(!within(Opcode::Op5));
+    }
+
+    #[test]
+    fn decode_num_roundtrip() {
+        for n in [
+            0,
+            1,
+            -1,
+            127,
+            128,
+            -128,
+            255,
+            256,
+            -32768,
+            0x7fff_ffff,
+            -0x7fff_ffff,
+        ] {
+            assert_eq!(decode_num(&encode_num(n), 4).unwrap(), n, "n = {n}");
+        }
+        // Non-minimal encodings and negative zero are accepted.
+        assert_eq!(decode_num(&[0x01, 0x00], 4).unwrap(), 1);
+        assert_eq!(decode_num(&[0x80], 4).unwrap(), 0);
+    }
+
     // ── Crypto ───────────────────────────────────────────────────────
 
     #[test]
@@ -731,11 +1608,17 @@ mod tests {
     // ── OP_CHECKSIG stub ─────────────────────────────────────────────
 
     #[test]
-    fn checksig_stub_always_true() {
+    fn checksig_stub_accepts_nonempty_signature() {
         let tokens = [push(&[0x00]), push(&[0x00]), op(Opcode::OpCheckSig)];
         assert!(execute(&tokens).unwrap());
     }
 
+    #[test]
+    fn checksig_stub_rejects_empty_signature() {
+        let tokens = [push(&[]), push(&[0x02; 33]), op(Opcode::OpCheckSig)];
+        assert!(!execute(&tokens).unwrap());
+    }
+
     #[test]
     fn checksigverify_stub() {
         let tokens = [
@@ -747,6 +1630,208 @@ mod tests {
         assert!(execute(&tokens).unwrap());
     }
 
+    #[test]
+    fn checksig_enforces_encoding_flags() {
+        let opts = ExecuteOpts {
+            flags: VerifyFlags::DERSIG,
+            ..ExecuteOpts::default()
+        };
+        let tokens = [
+            push(&[0x30, 0x00]),
+            push(&[0x02; 33]),
+            op(Opcode::OpCheckSig),
+        ];
+        let mut stack = Stack::new();
+        let err = execute_on_stack(&tokens, &mut stack, &opts).unwrap_err();
+        assert!(matches!(err, ScriptError::SigDer));
+
+        // An empty signature is always well-formed.
+        let tokens = [push(&[]), push(&[0x02; 33]), op(Opcode::OpCheckSig)];
+        let mut stack = Stack::new();
+        execute_on_stack(&tokens, &mut stack, &opts).unwrap();
+    }
+
+    #[test]
+    fn checksig_strictenc_rejects_hybrid_pubkey() {
+        let opts = ExecuteOpts {
+            flags: VerifyFlags::STRICTENC,
+            ..ExecuteOpts::default()
+        };
+        let mut hybrid = [0x04; 65];
+        hybrid[0] = 0x06;
+        let tokens = [push(&[]), push(&hybrid), op(Opcode::OpCheckSig)];
+        let mut stack = Stack::new();
+        let err = execute_on_stack(&tokens, &mut stack, &opts).unwrap_err();
+        assert!(matches!(err, ScriptError::PubkeyType));
+    }
+
+    // ── Multisig and OP_CHECKSIGADD stubs ────────────────────────────
+
+    #[test]
+    fn checkmultisig_stub() {
+        // 1-of-2, signature for the second key.
+        let tokens = [
+            op(Opcode::Op0),
+            push(&[0x30]),
+            op(Opcode::Op1),
+            push(&[0x02; 33]),
+            push(&[0x03; 33]),
+            op(Opcode::Op2),
+            op(Opcode::OpCheckMultiSig),
+        ];
+        assert!(execute(&tokens).unwrap());
+
+        // Empty signatures never verify.
+        let mut tokens = tokens;
+        tokens[1] = push(&[]);
+        assert!(!execute(&tokens).unwrap());
+    }
+
+    #[test]
+    fn checkmultisig_counts() {
+        let tokens = [op(Opcode::Op0), push(&[21]), op(Opcode::OpCheckMultiSig)];
+        let err = execute(&tokens).unwrap_err();
+        assert!(matches!(err, ScriptError::PubkeyCount));
+
+        let tokens = [
+            op(Opcode::Op0),
+            op(Opcode::Op2),
+            push(&[0x02; 33]),
+            op(Opcode::Op1),
+            op(Opcode::OpCheckMultiSig),
+        ];
+        let err = execute(&tokens).unwrap_err();
+        assert!(matches!(err, ScriptError::SigCount));
+
+        // 0-of-0 succeeds and leaves nothing behind.
+        let tokens = [
+            op(Opcode::Op0),
+            op(Opcode::Op0),
+            op(Opcode::Op0),
+            op(Opcode::OpCheckMultiSigVerify),
+            op(Opcode::OpDepth),
+        ];
+        assert!(!execute(&tokens).unwrap());
+    }
+
+    #[test]
+    fn checksigadd_stub() {
+        let tokens = [
+            push(&[0x01; 64]),
+            op(Opcode::Op0),
+            push(&[0x02; 32]),
+            op(Opcode::OpCheckSigAdd),
+            push(&[]),
+            op(Opcode::OpSwap),
+            push(&[0x03; 32]),
+            op(Opcode::OpCheckSigAdd),
+        ];
+        let mut stack = Stack::new();
+        assert_eq!(
+            execute_on_stack(&tokens, &mut stack, &ExecuteOpts::default()),
+            Err(ScriptError::UnsupportedOpcode(0xba))
+        );
+
+        let opts = ExecuteOpts {
+            sig_version: SigVersion::Tapscript,
+            ..ExecuteOpts::default()
+        };
+        let mut stack = Stack::new();
+        execute_on_stack(&tokens, &mut stack, &opts).unwrap();
+        assert_eq!(stack.into_items(), vec![vec![1]]);
+
+        // OP_CHECKMULTISIG is gone from tapscript.
+        let tokens = [
+            op(Opcode::Op0),
+            op(Opcode::Op0),
+            op(Opcode::OpCheckMultiSig),
+        ];
+        assert_eq!(
+            execute_on_stack(&tokens, &mut Stack::new(), &opts),
+            Err(ScriptError::TapscriptCheckMultiSig)
+        );
+
+        // An empty public key fails even with an empty signature.
+        let tokens = [push(&[]), push(&[]), op(Opcode::OpCheckSig)];
+        assert_eq!(
+            execute_on_stack(&tokens, &mut Stack::new(), &opts),
+            Err(ScriptError::TapscriptEmptyPubkey)
+        );
+    }
+
+    // ── Timelocks ────────────────────────────────────────────────────
+
+    #[test]
+    fn timelocks_are_nops_without_transaction_data() {
+        let tokens = [push(&[0x81]), op(Opcode::OpCheckLockTimeVerify)];
+        assert!(execute(&tokens).unwrap());
+    }
+
+    #[test]
+    fn checklocktimeverify() {
+        let opts = |lock_time, sequence| ExecuteOpts {
+            lock_time: Some(lock_time),
+            sequence: Some(sequence),
+            ..ExecuteOpts::default()
+        };
+        let tokens = [push(&[100]), op(Opcode::OpCheckLockTimeVerify)];
+        let run = |opts: &ExecuteOpts| execute_with_opts(&tokens, opts);
+        assert!(run(&opts(100, 0)).unwrap());
+        assert_eq!(run(&opts(99, 0)), Err(ScriptError::UnsatisfiedLocktime));
+        assert_eq!(
+            run(&opts(500_000_000, 0)),
+            Err(ScriptError::UnsatisfiedLocktime)
+        );
+        assert_eq!(
+            run(&opts(100, u32::MAX)),
+            Err(ScriptError::UnsatisfiedLocktime)
+        );
+
+        let tokens = [push(&[0x81]), op(Opcode::OpCheckLockTimeVerify)];
+        let err = execute_with_opts(&tokens, &opts(100, 0)).unwrap_err();
+        assert!(matches!(err, ScriptError::NegativeLocktime));
+    }
+
+    #[test]
+    fn checksequenceverify() {
+        let opts = |sequence| ExecuteOpts {
+            sequence: Some(sequence),
+            ..ExecuteOpts::default()
+        };
+        let tokens = [push(&[10]), op(Opcode::OpCheckSequenceVerify)];
+        let run = |sequence| execute_with_opts(&tokens, &opts(sequence));
+        assert!(run(10).unwrap());
+        assert!(run(0xffff_0000 | 10).is_err());
+        assert_eq!(run(9), Err(ScriptError::UnsatisfiedLocktime));
+        assert_eq!(run(1 << 22 | 10), Err(ScriptError::UnsatisfiedLocktime));
+
+        // The disable flag in the operand skips the check.
+        let tokens = [push(&[0, 0, 0, 0x80, 0]), op(Opcode::OpCheckSequenceVerify)];
+        assert!(execute_with_opts(&tokens, &opts(0)).unwrap());
+    }
+
+    #[test]
+    fn checksequenceverify_needs_version_2() {
+        let tokens = [push(&[10]), op(Opcode::OpCheckSequenceVerify)];
+        let opts = |tx_version| ExecuteOpts {
+            sequence: Some(10),
+            tx_version,
+            ..ExecuteOpts::default()
+        };
+        assert!(execute_with_opts(&tokens, &opts(Some(2))).unwrap());
+        assert_eq!(
+            execute_with_opts(&tokens, &opts(Some(1))),
+            Err(ScriptError::UnsatisfiedLocktime)
+        );
+
+        // As OP_NOP3 the opcode ignores the version and sequence.
+        let opts = ExecuteOpts {
+            nop3: true,
+            ..opts(Some(1))
+        };
+        assert!(execute_with_opts(&tokens, &opts).unwrap());
+    }
+
     // ── encode_num ───────────────────────────────────────────────────
 
     #[test]
@@ -770,6 +1855,64 @@ mod tests {
         assert_eq!(encode_num(-128), vec![0x80, 0x80]);
     }
 
+    // ── execute_on_items ─────────────────────────────────────────────
+
+    #[test]
+    fn execute_on_items_is_incremental() {
+        let mut items = Vec::new();
+        let opts = ExecuteOpts::default();
+        execute_on_items(&[op(Opcode::Op2)], &mut items, &opts).unwrap();
+        execute_on_items(&[op(Opcode::OpDup)], &mut items, &opts).unwrap();
+        assert_eq!(items, vec![vec![2], vec![2]]);
+    }
+
+    #[test]
+    fn execute_on_state_keeps_conditionals_and_alt_stack() {
+        let opts = ExecuteOpts::default();
+        let mut state = ExecState::default();
+        execute_on_state(
+            &[
+                op(Opcode::Op7),
+                op(Opcode::OpToAltStack),
+                op(Opcode::Op1),
+                op(Opcode::OpIf),
+            ],
+            &mut state,
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(state.alt_stack, vec![vec![7]]);
+        assert_eq!(state.exec, vec![true]);
+
+        execute_on_state(
+            &[op(Opcode::OpFromAltStack), op(Opcode::OpEndIf)],
+            &mut state,
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(
+            state,
+            ExecState {
+                stack: vec![vec![7]],
+                ..ExecState::default()
+            }
+        );
+
+        assert_eq!(
+            execute_on_state(&[op(Opcode::OpEndIf)], &mut state, &opts),
+            Err(ScriptError::UnbalancedConditional)
+        );
+    }
+
+    #[test]
+    fn execute_on_items_keeps_stack_on_error() {
+        let mut items = vec![vec![0xaa]];
+        let tokens = [op(Opcode::OpDup), op(Opcode::OpReturn)];
+        let err = execute_on_items(&tokens, &mut items, &ExecuteOpts::default()).unwrap_err();
+        assert!(matches!(err, ScriptError::OpReturnEncountered));
+        assert_eq!(items, vec![vec![0xaa], vec![0xaa]]);
+    }
+
     // ── Stack underflow ──────────────────────────────────────────────
 
     #[test]
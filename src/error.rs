@@ -1,5 +1,19 @@
 /// All error conditions that can arise during script parsing or execution.
+///
+/// Variants mirror the conditions Bitcoin Core's interpreter distinguishes,
+/// and [`ScriptError::code`] returns the matching Core name (`EVAL_FALSE`,
+/// `SIG_DER`, ...) so results can be compared with Core's output directly.
+/// Several variants describe rules this engine does not enforce yet; they
+/// exist so every Core outcome has a counterpart.
+///
+/// With the `serde` feature, errors serialize as `{"error": <variant>}`
+/// plus a `"payload"` field for variants that carry data.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "error", content = "payload")
+)]
 pub enum ScriptError {
     /// Stack had fewer elements than the operation required.
     StackUnderflow,
@@ -13,7 +27,7 @@ pub enum ScriptError {
     /// An opcode byte is valid in Bitcoin but not implemented by this engine.
     UnsupportedOpcode(u8),
 
-    /// OP_VERIFY, OP_EQUALVERIFY, or OP_CHECKSIGVERIFY consumed a false value.
+    /// OP_VERIFY consumed a false value.
     VerifyFailed,
 
     /// Execution completed but the stack is empty or the top element is false.
@@ -27,10 +41,240 @@ pub enum ScriptError {
 
     /// A hex string could not be decoded (odd length or invalid character).
     InvalidHex,
+
+    /// An ASM string contained an unknown opcode name or malformed push.
+    InvalidAsm,
+
+    // ── Verify variants ──────────────────────────────────────────────
+    /// OP_EQUALVERIFY found its two operands unequal.
+    EqualVerifyFailed,
+
+    /// OP_CHECKSIGVERIFY found an invalid signature.
+    CheckSigVerifyFailed,
+
+    /// OP_CHECKMULTISIGVERIFY found an invalid signature set.
+    CheckMultiSigVerifyFailed,
+
+    /// OP_NUMEQUALVERIFY found its two operands unequal.
+    NumEqualVerifyFailed,
+
+    // ── Resource limits ──────────────────────────────────────────────
+    /// Script exceeds the maximum script size (10,000 bytes).
+    ScriptSize,
+
+    /// A pushed element exceeds the maximum element size (520 bytes).
+    PushSize,
+
+    /// Script exceeds the maximum number of non-push opcodes (201).
+    OpCount,
+
+    /// Combined stack and alt-stack exceed the maximum size (1,000).
+    StackSize,
+
+    /// OP_CHECKMULTISIG signature count is negative or exceeds the key count.
+    SigCount,
+
+    /// OP_CHECKMULTISIG public key count is negative or exceeds 20.
+    PubkeyCount,
+
+    // ── Opcode and operand errors ────────────────────────────────────
+    /// A disabled opcode (OP_CAT, OP_MUL, ...) appears in the script.
+    DisabledOpcode(u8),
+
+    /// OP_FROMALTSTACK was executed with an empty alt-stack.
+    InvalidAltStackOperation,
+
+    /// A locktime operand is negative.
+    NegativeLocktime,
+
+    /// The transaction's locktime or sequence does not satisfy the script.
+    UnsatisfiedLocktime,
+
+    /// A numeric operand is longer than the opcode accepts (4 bytes, or 5
+    /// for the timelock opcodes).
+    NumberOverflow,
+
+    // ── Signature and public key encoding ────────────────────────────
+    /// The signature's sighash type byte is not a defined type.
+    SigHashType,
+
+    /// The signature is not strict DER (BIP66).
+    SigDer,
+
+    /// A push did not use the smallest possible encoding.
+    MinimalData,
+
+    /// The scriptSig contains a non-push operation.
+    SigPushOnly,
+
+    /// The signature's S value is above half the curve order.
+    SigHighS,
+
+    /// OP_CHECKMULTISIG's dummy element is not empty (BIP147).
+    SigNullDummy,
+
+    /// The public key is neither compressed nor uncompressed.
+    PubkeyType,
+
+    /// More than one element is left on the stack after execution.
+    CleanStack,
+
+    /// The OP_IF / OP_NOTIF argument is not exactly empty or `0x01`.
+    MinimalIf,
+
+    /// A failing signature check was given a non-empty signature (BIP146).
+    NullFail,
+
+    // ── Soft-fork upgrade discouragement ─────────────────────────────
+    /// A reserved OP_NOPx was executed.
+    DiscourageUpgradableNops,
+
+    /// A witness program with an unknown version was spent.
+    DiscourageUpgradableWitnessProgram,
+
+    /// A taproot leaf with an unknown version was spent.
+    DiscourageUpgradableTaprootVersion,
+
+    /// An OP_SUCCESSx opcode was encountered in tapscript.
+    DiscourageOpSuccess,
+
+    /// A tapscript public key with an unknown type was used.
+    DiscourageUpgradablePubkeyType,
+
+    // ── Segregated witness ───────────────────────────────────────────
+    /// A v0 witness program has the wrong length.
+    WitnessProgramWrongLength,
+
+    /// A witness program was spent with an empty witness.
+    WitnessProgramWitnessEmpty,
+
+    /// The witness does not match the witness program.
+    WitnessProgramMismatch,
+
+    /// A native witness spend has a non-empty scriptSig.
+    WitnessMalleated,
+
+    /// A P2SH-wrapped witness spend has extra scriptSig data.
+    WitnessMalleatedP2sh,
+
+    /// A witness was provided for a non-witness input.
+    WitnessUnexpected,
+
+    /// A public key in a witness script is not compressed.
+    WitnessPubkeyType,
+
+    // ── Taproot ──────────────────────────────────────────────────────
+    /// A Schnorr signature has an invalid size.
+    SchnorrSigSize,
+
+    /// A Schnorr signature has an invalid sighash type.
+    SchnorrSigHashType,
+
+    /// A Schnorr signature failed verification.
+    SchnorrSig,
+
+    /// A taproot control block has an invalid size.
+    TaprootWrongControlSize,
+
+    /// Tapscript exceeded its signature-operation budget.
+    TapscriptValidationWeight,
+
+    /// OP_CHECKMULTISIG(VERIFY) was used in tapscript.
+    TapscriptCheckMultiSig,
+
+    /// The tapscript OP_IF / OP_NOTIF argument is not minimal.
+    TapscriptMinimalIf,
+
+    /// A tapscript signature check was given an empty public key.
+    TapscriptEmptyPubkey,
+
+    // ── Legacy script quirks ─────────────────────────────────────────
+    /// OP_CODESEPARATOR was used in a non-segwit script under policy.
+    OpCodeSeparator,
+
+    /// A signature was found in the scriptCode (FindAndDelete) under policy.
+    SigFindAndDelete,
+}
+
+impl ScriptError {
+    /// Returns the stable code for this error.
+    ///
+    /// For conditions Bitcoin Core distinguishes, this is Core's name as
+    /// used in its `script_tests.json` vectors (the `SCRIPT_ERR_` suffix),
+    /// e.g. `"EVAL_FALSE"` or `"SIG_DER"`. Truncated or malformed pushes
+    /// report `"BAD_OPCODE"` as Core does, and oversized numbers report
+    /// `"UNKNOWN_ERROR"`, which is what Core's number overflow exception
+    /// becomes. Errors that only arise from this crate's text inputs use
+    /// their own codes (`"INVALID_HEX"`, `"INVALID_ASM"`).
+    pub fn code(&self) -> &'static str {
+        match self {
+            ScriptError::StackUnderflow => "INVALID_STACK_OPERATION",
+            ScriptError::UnexpectedEndOfScript => "BAD_OPCODE",
+            ScriptError::InvalidPushData => "BAD_OPCODE",
+            ScriptError::UnsupportedOpcode(_) => "BAD_OPCODE",
+            ScriptError::VerifyFailed => "VERIFY",
+            ScriptError::ScriptFailed => "EVAL_FALSE",
+            ScriptError::OpReturnEncountered => "OP_RETURN",
+            ScriptError::UnbalancedConditional => "UNBALANCED_CONDITIONAL",
+            ScriptError::InvalidHex => "INVALID_HEX",
+            ScriptError::InvalidAsm => "INVALID_ASM",
+            ScriptError::EqualVerifyFailed => "EQUALVERIFY",
+            ScriptError::CheckSigVerifyFailed => "CHECKSIGVERIFY",
+            ScriptError::CheckMultiSigVerifyFailed => "CHECKMULTISIGVERIFY",
+            ScriptError::NumEqualVerifyFailed => "NUMEQUALVERIFY",
+            ScriptError::ScriptSize => "SCRIPT_SIZE",
+            ScriptError::PushSize => "PUSH_SIZE",
+            ScriptError::OpCount => "OP_COUNT",
+            ScriptError::StackSize => "STACK_SIZE",
+            ScriptError::SigCount => "SIG_COUNT",
+            ScriptError::PubkeyCount => "PUBKEY_COUNT",
+            ScriptError::DisabledOpcode(_) => "DISABLED_OPCODE",
+            ScriptError::InvalidAltStackOperation => "INVALID_ALTSTACK_OPERATION",
+            ScriptError::NegativeLocktime => "NEGATIVE_LOCKTIME",
+            ScriptError::UnsatisfiedLocktime => "UNSATISFIED_LOCKTIME",
+            ScriptError::NumberOverflow => "UNKNOWN_ERROR",
+            ScriptError::SigHashType => "SIG_HASHTYPE",
+            ScriptError::SigDer => "SIG_DER",
+            ScriptError::MinimalData => "MINIMALDATA",
+            ScriptError::SigPushOnly => "SIG_PUSHONLY",
+            ScriptError::SigHighS => "SIG_HIGH_S",
+            ScriptError::SigNullDummy => "SIG_NULLDUMMY",
+            ScriptError::PubkeyType => "PUBKEYTYPE",
+            ScriptError::CleanStack => "CLEANSTACK",
+            ScriptError::MinimalIf => "MINIMALIF",
+            ScriptError::NullFail => "NULLFAIL",
+            ScriptError::DiscourageUpgradableNops => "DISCOURAGE_UPGRADABLE_NOPS",
+            ScriptError::DiscourageUpgradableWitnessProgram => {
+                "DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM"
+            }
+            ScriptError::DiscourageUpgradableTaprootVersion => {
+                "DISCOURAGE_UPGRADABLE_TAPROOT_VERSION"
+            }
+            ScriptError::DiscourageOpSuccess => "DISCOURAGE_OP_SUCCESS",
+            ScriptError::DiscourageUpgradablePubkeyType => "DISCOURAGE_UPGRADABLE_PUBKEYTYPE",
+            ScriptError::WitnessProgramWrongLength => "WITNESS_PROGRAM_WRONG_LENGTH",
+            ScriptError::WitnessProgramWitnessEmpty => "WITNESS_PROGRAM_WITNESS_EMPTY",
+            ScriptError::WitnessProgramMismatch => "WITNESS_PROGRAM_MISMATCH",
+            ScriptError::WitnessMalleated => "WITNESS_MALLEATED",
+            ScriptError::WitnessMalleatedP2sh => "WITNESS_MALLEATED_P2SH",
+            ScriptError::WitnessUnexpected => "WITNESS_UNEXPECTED",
+            ScriptError::WitnessPubkeyType => "WITNESS_PUBKEYTYPE",
+            ScriptError::SchnorrSigSize => "SCHNORR_SIG_SIZE",
+            ScriptError::SchnorrSigHashType => "SCHNORR_SIG_HASHTYPE",
+            ScriptError::SchnorrSig => "SCHNORR_SIG",
+            ScriptError::TaprootWrongControlSize => "TAPROOT_WRONG_CONTROL_SIZE",
+            ScriptError::TapscriptValidationWeight => "TAPSCRIPT_VALIDATION_WEIGHT",
+            ScriptError::TapscriptCheckMultiSig => "TAPSCRIPT_CHECKMULTISIG",
+            ScriptError::TapscriptMinimalIf => "TAPSCRIPT_MINIMALIF",
+            ScriptError::TapscriptEmptyPubkey => "TAPSCRIPT_EMPTY_PUBKEY",
+            ScriptError::OpCodeSeparator => "OP_CODESEPARATOR",
+            ScriptError::SigFindAndDelete => "SIG_FINDANDDELETE",
+        }
+    }
 }
 
-impl std::fmt::Display for ScriptError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             ScriptError::StackUnderflow => {
                 write!(f, "stack underflow: not enough elements on the stack")
@@ -59,8 +303,184 @@ impl std::fmt::Display for ScriptError {
             ScriptError::InvalidHex => {
                 write!(f, "invalid hex string")
             }
+            ScriptError::InvalidAsm => {
+                write!(f, "invalid script ASM")
+            }
+            ScriptError::EqualVerifyFailed => {
+                write!(f, "OP_EQUALVERIFY failed: operands are not equal")
+            }
+            ScriptError::CheckSigVerifyFailed => {
+                write!(f, "OP_CHECKSIGVERIFY failed: invalid signature")
+            }
+            ScriptError::CheckMultiSigVerifyFailed => {
+                write!(f, "OP_CHECKMULTISIGVERIFY failed: invalid signatures")
+            }
+            ScriptError::NumEqualVerifyFailed => {
+                write!(f, "OP_NUMEQUALVERIFY failed: numbers are not equal")
+            }
+            ScriptError::ScriptSize => {
+                write!(f, "script is larger than the maximum script size")
+            }
+            ScriptError::PushSize => {
+                write!(f, "pushed element is larger than the maximum element size")
+            }
+            ScriptError::OpCount => {
+                write!(f, "script exceeds the maximum opcode count")
+            }
+            ScriptError::StackSize => {
+                write!(f, "stack exceeds the maximum stack size")
+            }
+            ScriptError::SigCount => {
+                write!(f, "signature count out of range")
+            }
+            ScriptError::PubkeyCount => {
+                write!(f, "public key count out of range")
+            }
+            ScriptError::DisabledOpcode(b) => {
+                write!(f, "disabled opcode: 0x{b:02x}")
+            }
+            ScriptError::InvalidAltStackOperation => {
+                write!(f, "alt-stack underflow: alt-stack is empty")
+            }
+            ScriptError::NegativeLocktime => {
+                write!(f, "negative locktime")
+            }
+            ScriptError::UnsatisfiedLocktime => {
+                write!(f, "locktime requirement not satisfied")
+            }
+            ScriptError::NumberOverflow => {
+                write!(f, "numeric operand is too long")
+            }
+            ScriptError::SigHashType => {
+                write!(f, "signature hash type is undefined")
+            }
+            ScriptError::SigDer => {
+                write!(f, "signature is not strict DER")
+            }
+            ScriptError::MinimalData => {
+                write!(f, "data push is not minimally encoded")
+            }
+            ScriptError::SigPushOnly => {
+                write!(f, "scriptSig contains non-push operations")
+            }
+            ScriptError::SigHighS => {
+                write!(f, "signature S value is not low")
+            }
+            ScriptError::SigNullDummy => {
+                write!(f, "OP_CHECKMULTISIG dummy element is not empty")
+            }
+            ScriptError::PubkeyType => {
+                write!(f, "public key is neither compressed nor uncompressed")
+            }
+            ScriptError::CleanStack => {
+                write!(f, "stack is not clean after execution")
+            }
+            ScriptError::MinimalIf => {
+                write!(f, "OP_IF/OP_NOTIF argument is not minimal")
+            }
+            ScriptError::NullFail => {
+                write!(f, "failed signature check with a non-empty signature")
+            }
+            ScriptError::DiscourageUpgradableNops => {
+                write!(f, "reserved OP_NOP opcode used")
+            }
+            ScriptError::DiscourageUpgradableWitnessProgram => {
+                write!(f, "unknown witness program version")
+            }
+            ScriptError::DiscourageUpgradableTaprootVersion => {
+                write!(f, "unknown taproot leaf version")
+            }
+            ScriptError::DiscourageOpSuccess => {
+                write!(f, "OP_SUCCESSx opcode used")
+            }
+            ScriptError::DiscourageUpgradablePubkeyType => {
+                write!(f, "unknown tapscript public key type")
+            }
+            ScriptError::WitnessProgramWrongLength => {
+                write!(f, "witness program has the wrong length")
+            }
+            ScriptError::WitnessProgramWitnessEmpty => {
+                write!(f, "witness program spent with an empty witness")
+            }
+            ScriptError::WitnessProgramMismatch => {
+                write!(f, "witness does not match the witness program")
+            }
+            ScriptError::WitnessMalleated => {
+                write!(f, "witness spend has a non-empty scriptSig")
+            }
+            ScriptError::WitnessMalleatedP2sh => {
+                write!(f, "P2SH-wrapped witness spend has extra scriptSig data")
+            }
+            ScriptError::WitnessUnexpected => {
+                write!(f, "witness provided for a non-witness input")
+            }
+            ScriptError::WitnessPubkeyType => {
+                write!(f, "witness public key is not compressed")
+            }
+            ScriptError::SchnorrSigSize => {
+                write!(f, "Schnorr signature has an invalid size")
+            }
+            ScriptError::SchnorrSigHashType => {
+                write!(f, "Schnorr signature has an invalid hash type")
+            }
+            ScriptError::SchnorrSig => {
+                write!(f, "Schnorr signature verification failed")
+            }
+            ScriptError::TaprootWrongControlSize => {
+                write!(f, "taproot control block has an invalid size")
+            }
+            ScriptError::TapscriptValidationWeight => {
+                write!(f, "tapscript exceeded its validation weight budget")
+            }
+            ScriptError::TapscriptCheckMultiSig => {
+                write!(f, "OP_CHECKMULTISIG is not available in tapscript")
+            }
+            ScriptError::TapscriptMinimalIf => {
+                write!(f, "tapscript OP_IF/OP_NOTIF argument is not minimal")
+            }
+            ScriptError::TapscriptEmptyPubkey => {
+                write!(f, "empty public key in tapscript")
+            }
+            ScriptError::OpCodeSeparator => {
+                write!(f, "OP_CODESEPARATOR used in a non-segwit script")
+            }
+            ScriptError::SigFindAndDelete => {
+                write!(f, "signature found in scriptCode")
+            }
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for ScriptError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_match_core_names() {
+        assert_eq!(ScriptError::ScriptFailed.code(), "EVAL_FALSE");
+        assert_eq!(ScriptError::OpReturnEncountered.code(), "OP_RETURN");
+        assert_eq!(
+            ScriptError::StackUnderflow.code(),
+            "INVALID_STACK_OPERATION"
+        );
+        assert_eq!(ScriptError::EqualVerifyFailed.code(), "EQUALVERIFY");
+        assert_eq!(ScriptError::SigHighS.code(), "SIG_HIGH_S");
+        assert_eq!(ScriptError::NullFail.code(), "NULLFAIL");
+        assert_eq!(ScriptError::CleanStack.code(), "CLEANSTACK");
+        assert_eq!(
+            ScriptError::TapscriptEmptyPubkey.code(),
+            "TAPSCRIPT_EMPTY_PUBKEY"
+        );
+    }
+
+    #[test]
+    fn malformed_scripts_report_bad_opcode() {
+        assert_eq!(ScriptError::UnexpectedEndOfScript.code(), "BAD_OPCODE");
+        assert_eq!(ScriptError::InvalidPushData.code(), "BAD_OPCODE");
+        assert_eq!(ScriptError::UnsupportedOpcode(0x50).code(), "BAD_OPCODE");
+        assert_eq!(ScriptError::DisabledOpcode(0x7e).code(), "DISABLED_OPCODE");
+    }
+}
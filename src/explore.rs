@@ -0,0 +1,574 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::analysis::MAX_PATHS;
+use crate::engine::{decode_num, encode_num};
+use crate::hash;
+use crate::opcode::Opcode;
+use crate::sigops::MAX_PUBKEYS_PER_MULTISIG;
+use crate::stack::is_true;
+use crate::token::Token;
+
+/// What an abstract interpreter knows about stack values, and what it
+/// records along a path.
+///
+/// [`explore`] handles flow control, stack shuffling, the alt stack and
+/// constant folding; a domain decides what the values it cannot fold
+/// become and what an unknown value read from the initial stack is.
+pub(crate) trait Domain: Clone {
+    /// An abstract stack value.
+    type Value: Clone;
+
+    /// A value with known bytes.
+    fn constant(bytes: Vec<u8>) -> Self::Value;
+
+    /// The bytes of `value`, if known.
+    fn known(value: &Self::Value) -> Option<&[u8]>;
+
+    /// The truthiness of `value`, if known.
+    fn truth(value: &Self::Value) -> Option<bool> {
+        Self::known(value).map(is_true)
+    }
+
+    /// The `index`th initial stack item the path reads, counting from the
+    /// top.
+    fn input(&mut self, index: usize) -> Result<Self::Value, Stop>;
+
+    /// The result of OP_DEPTH: the initial depth plus `offset`.
+    fn depth(&self, offset: i64) -> Self::Value;
+
+    /// Records that an unknown `value` has the given truthiness on this
+    /// path, and returns it refined accordingly.
+    fn assume(&mut self, value: Self::Value, truth: bool) -> Self::Value;
+
+    /// A one-operand opcode (OP_NOT, OP_0NOTEQUAL, OP_SIZE or a hash) on
+    /// a value that is not known.
+    fn unary(op: Opcode, a: Self::Value) -> Self::Value;
+
+    /// A two-operand opcode (OP_EQUAL, a numeric opcode or OP_CHECKSIG)
+    /// whose operands are not both known.
+    fn binary(op: Opcode, a: Self::Value, b: Self::Value) -> Self::Value;
+
+    /// OP_CHECKMULTISIG of signatures and public keys, in script order.
+    fn multisig(sigs: Vec<Self::Value>, pubkeys: Vec<Self::Value>) -> Self::Value;
+
+    /// Records a timelock opcode's operand.
+    fn timelock(&mut self, _op: Opcode, _value: &Self::Value) {}
+}
+
+/// Why a path stopped early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Stop {
+    /// The path pops more items than the initial stack holds.
+    Underflow,
+    /// The path fails whatever the initial stack holds.
+    Fails,
+    /// A disabled opcode, which fails even in an unexecuted branch.
+    Disabled(u8),
+    /// Unbalanced flow control.
+    Malformed,
+    /// The rest of the path depends on an unknown item count.
+    Unmodeled,
+}
+
+/// How a path ended: `Err((index, stop))` if it stopped at token `index`.
+pub(crate) type Outcome = Result<(), (usize, Stop)>;
+
+/// One execution path.
+#[derive(Clone)]
+pub(crate) struct Path<D: Domain> {
+    pc: usize,
+    pub(crate) stack: Vec<D::Value>,
+    alt: Vec<D::Value>,
+    exec: Vec<bool>,
+    /// Initial stack items consumed so far.
+    pub(crate) borrowed: usize,
+    /// Branch decisions, as `(index of the OP_IF/OP_NOTIF/OP_IFDUP,
+    /// whether its first branch ran)`.
+    pub(crate) branches: Vec<(usize, bool)>,
+    pub(crate) domain: D,
+}
+
+/// Every path through a script.
+pub(crate) struct Exploration<D: Domain> {
+    /// Each path with how it ended, in script order, first branches
+    /// first.
+    pub(crate) paths: Vec<(Path<D>, Outcome)>,
+    /// Number of paths started.
+    pub(crate) started: usize,
+    /// Whether exploration stopped at [`MAX_PATHS`].
+    pub(crate) truncated: bool,
+}
+
+/// Runs `tokens` on an initial stack of unknown contents, forking on
+/// every OP_IF, OP_NOTIF and OP_IFDUP whose operand is not known.
+pub(crate) fn explore<D: Domain>(tokens: &[Token], domain: D) -> Exploration<D> {
+    let mut worklist = vec![Path {
+        pc: 0,
+        stack: Vec::new(),
+        alt: Vec::new(),
+        exec: Vec::new(),
+        borrowed: 0,
+        branches: Vec::new(),
+        domain,
+    }];
+    let mut exploration = Exploration {
+        paths: Vec::new(),
+        started: 1,
+        truncated: false,
+    };
+
+    while let Some(mut path) = worklist.pop() {
+        let outcome = path.run(tokens, &mut worklist, &mut exploration);
+        exploration.paths.push((path, outcome));
+    }
+    exploration
+}
+
+impl<D: Domain> Path<D> {
+    pub(crate) fn pop(&mut self) -> Result<D::Value, Stop> {
+        if let Some(value) = self.stack.pop() {
+            return Ok(value);
+        }
+        self.borrowed += 1;
+        self.domain.input(self.borrowed - 1)
+    }
+
+    fn push(&mut self, value: D::Value) {
+        self.stack.push(value);
+    }
+
+    /// Forks off the other outcome of a branch on an unknown value, unless
+    /// [`MAX_PATHS`] is reached.
+    fn fork(
+        &self,
+        index: usize,
+        worklist: &mut Vec<Path<D>>,
+        exploration: &mut Exploration<D>,
+        other: impl FnOnce(&mut Path<D>),
+    ) {
+        if exploration.started < MAX_PATHS {
+            exploration.started += 1;
+            let mut path = self.clone();
+            other(&mut path);
+            path.branches.push((index, false));
+            worklist.push(path);
+        } else {
+            exploration.truncated = true;
+        }
+    }
+
+    /// Runs to the end of the script, queueing the other side of every
+    /// unknown branch.
+    fn run(
+        &mut self,
+        tokens: &[Token],
+        worklist: &mut Vec<Path<D>>,
+        exploration: &mut Exploration<D>,
+    ) -> Outcome {
+        while self.pc < tokens.len() {
+            let index = self.pc;
+            self.pc += 1;
+            let executing = self.exec.iter().all(|&b| b);
+
+            match &tokens[index] {
+                Token::Op(op @ (Opcode::OpIf | Opcode::OpNotIf)) => {
+                    if !executing {
+                        self.exec.push(false);
+                        continue;
+                    }
+                    let condition = self.pop().map_err(|stop| (index, stop))?;
+                    let invert = *op == Opcode::OpNotIf;
+                    match D::truth(&condition) {
+                        Some(truth) => self.exec.push(truth != invert),
+                        None => {
+                            self.fork(index, worklist, exploration, |other| {
+                                other.domain.assume(condition.clone(), invert);
+                                other.exec.push(false);
+                            });
+                            self.domain.assume(condition, !invert);
+                            self.exec.push(true);
+                            self.branches.push((index, true));
+                        }
+                    }
+                }
+                Token::Op(Opcode::OpElse) => match self.exec.last_mut() {
+                    Some(top) => *top = !*top,
+                    None => return Err((index, Stop::Malformed)),
+                },
+                Token::Op(Opcode::OpEndIf) => {
+                    if self.exec.pop().is_none() {
+                        return Err((index, Stop::Malformed));
+                    }
+                }
+                Token::Op(op) if op.is_disabled() => {
+                    return Err((index, Stop::Disabled(op.to_byte())));
+                }
+                _ if !executing => {}
+                Token::Op(Opcode::OpIfDup) => {
+                    let value = self.pop().map_err(|stop| (index, stop))?;
+                    match D::truth(&value) {
+                        Some(truth) => {
+                            if truth {
+                                self.push(value.clone());
+                            }
+                            self.push(value);
+                        }
+                        None => {
+                            self.fork(index, worklist, exploration, |other| {
+                                let value = other.domain.assume(value.clone(), false);
+                                other.push(value);
+                            });
+                            let value = self.domain.assume(value, true);
+                            self.push(value.clone());
+                            self.push(value);
+                            self.branches.push((index, true));
+                        }
+                    }
+                }
+                Token::PushData(data) => self.push(D::constant(data.clone())),
+                Token::Op(op) => self.apply(*op).map_err(|stop| (index, stop))?,
+            }
+        }
+
+        if self.exec.is_empty() {
+            Ok(())
+        } else {
+            Err((tokens.len(), Stop::Malformed))
+        }
+    }
+
+    /// Fails if `value` is known to be false, and otherwise assumes it is
+    /// true, as OP_VERIFY does.
+    fn verify(&mut self, value: D::Value) -> Result<(), Stop> {
+        match D::truth(&value) {
+            Some(true) => Ok(()),
+            Some(false) => Err(Stop::Fails),
+            None => {
+                self.domain.assume(value, true);
+                Ok(())
+            }
+        }
+    }
+
+    /// Pops a known count from 0 to `max`, failing if it is out of range
+    /// and stopping if it is not known.
+    fn count(&mut self, max: i64) -> Result<i64, Stop> {
+        let value = self.pop()?;
+        let count = num(D::known(&value))?.ok_or(Stop::Unmodeled)?;
+        if !(0..=max).contains(&count) {
+            return Err(Stop::Fails);
+        }
+        Ok(count)
+    }
+
+    /// Pops `count` items, returned in script order.
+    fn pop_list(&mut self, count: i64) -> Result<Vec<D::Value>, Stop> {
+        let mut items = (0..count)
+            .map(|_| self.pop())
+            .collect::<Result<Vec<_>, _>>()?;
+        items.reverse();
+        Ok(items)
+    }
+
+    /// Applies a non-flow-control opcode, mirroring the engine's semantics.
+    fn apply(&mut self, op: Opcode) -> Result<(), Stop> {
+        match op {
+            Opcode::Op0 => self.push(D::constant(vec![])),
+            Opcode::Op1Negate => self.push(D::constant(vec![0x81])),
+            Opcode::Op1
+            | Opcode::Op2
+            | Opcode::Op3
+            | Opcode::Op4
+            | Opcode::Op5
+            | Opcode::Op6
+            | Opcode::Op7
+            | Opcode::Op8
+            | Opcode::Op9
+            | Opcode::Op10
+            | Opcode::Op11
+            | Opcode::Op12
+            | Opcode::Op13
+            | Opcode::Op14
+            | Opcode::Op15
+            | Opcode::Op16 => self.push(D::constant(vec![op.to_byte() - 0x50])),
+
+            Opcode::OpNop => {}
+            Opcode::OpVerify => {
+                let value = self.pop()?;
+                self.verify(value)?;
+            }
+            Opcode::OpReturn => return Err(Stop::Fails),
+
+            Opcode::OpDup => {
+                let a = self.pop()?;
+                self.push(a.clone());
+                self.push(a);
+            }
+            Opcode::OpDrop => {
+                self.pop()?;
+            }
+            Opcode::Op2Dup => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.push(a.clone());
+                self.push(b.clone());
+                self.push(a);
+                self.push(b);
+            }
+            Opcode::Op2Drop => {
+                self.pop()?;
+                self.pop()?;
+            }
+            Opcode::OpNip => {
+                let b = self.pop()?;
+                self.pop()?;
+                self.push(b);
+            }
+            Opcode::OpOver => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.push(a.clone());
+                self.push(b);
+                self.push(a);
+            }
+            Opcode::OpSwap => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.push(b);
+                self.push(a);
+            }
+            Opcode::OpTuck => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.push(b.clone());
+                self.push(a);
+                self.push(b);
+            }
+            Opcode::OpToAltStack => {
+                let a = self.pop()?;
+                self.alt.push(a);
+            }
+            Opcode::OpFromAltStack => {
+                let a = self.alt.pop().ok_or(Stop::Fails)?;
+                self.push(a);
+            }
+            Opcode::OpDepth => {
+                let offset = self.stack.len() as i64 - self.borrowed as i64;
+                let depth = self.domain.depth(offset);
+                self.push(depth);
+            }
+            Opcode::OpSize => {
+                let a = self.pop()?;
+                let size = match D::known(&a) {
+                    Some(bytes) => D::constant(encode_num(bytes.len() as i64)),
+                    None => D::unary(op, a.clone()),
+                };
+                self.push(a);
+                self.push(size);
+            }
+
+            Opcode::OpEqual | Opcode::OpEqualVerify => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let equal = match (D::known(&a), D::known(&b)) {
+                    (Some(x), Some(y)) => boolean::<D>(x == y),
+                    _ => D::binary(Opcode::OpEqual, a, b),
+                };
+                if op == Opcode::OpEqual {
+                    self.push(equal);
+                } else {
+                    self.verify(equal)?;
+                }
+            }
+
+            Opcode::OpNot => {
+                let a = self.pop()?;
+                let value = match D::known(&a) {
+                    Some(bytes) => boolean::<D>(bytes.is_empty() || bytes == [0x00]),
+                    None => D::unary(op, a),
+                };
+                self.push(value);
+            }
+            Opcode::Op0NotEqual => {
+                let a = self.pop()?;
+                let value = match num(D::known(&a))? {
+                    Some(n) => boolean::<D>(n != 0),
+                    None => D::unary(op, a),
+                };
+                self.push(value);
+            }
+            Opcode::Op1Add | Opcode::Op1Sub => {
+                let a = self.pop()?;
+                let one = D::constant(encode_num(1));
+                let op = if op == Opcode::Op1Add {
+                    Opcode::OpAdd
+                } else {
+                    Opcode::OpSub
+                };
+                self.push(numeric::<D>(op, a, one)?);
+            }
+            Opcode::OpNegate | Opcode::OpAbs => {
+                let a = self.pop()?;
+                let value = match num(D::known(&a))? {
+                    Some(n) if op == Opcode::OpNegate => D::constant(encode_num(-n)),
+                    Some(n) => D::constant(encode_num(n.abs())),
+                    None => D::unary(op, a),
+                };
+                self.push(value);
+            }
+            Opcode::OpWithin => {
+                let max = self.pop()?;
+                let min = self.pop()?;
+                let x = self.pop()?;
+                let above = numeric::<D>(Opcode::OpGreaterThanOrEqual, x.clone(), min)?;
+                let below = numeric::<D>(Opcode::OpLessThan, x, max)?;
+                self.push(numeric::<D>(Opcode::OpBoolAnd, above, below)?);
+            }
+            Opcode::OpAdd
+            | Opcode::OpSub
+            | Opcode::OpBoolAnd
+            | Opcode::OpBoolOr
+            | Opcode::OpNumEqual
+            | Opcode::OpNumEqualVerify
+            | Opcode::OpNumNotEqual
+            | Opcode::OpLessThan
+            | Opcode::OpGreaterThan
+            | Opcode::OpLessThanOrEqual
+            | Opcode::OpGreaterThanOrEqual
+            | Opcode::OpMin
+            | Opcode::OpMax => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let value = if op == Opcode::OpNumEqualVerify {
+                    numeric::<D>(Opcode::OpNumEqual, a, b)?
+                } else {
+                    numeric::<D>(op, a, b)?
+                };
+                if op == Opcode::OpNumEqualVerify {
+                    self.verify(value)?;
+                } else {
+                    self.push(value);
+                }
+            }
+
+            Opcode::OpRipemd160 | Opcode::OpSha256 | Opcode::OpHash160 | Opcode::OpHash256 => {
+                let a = self.pop()?;
+                let value = match D::known(&a) {
+                    Some(bytes) => D::constant(match op {
+                        Opcode::OpRipemd160 => hash::ripemd160(bytes).to_vec(),
+                        Opcode::OpSha256 => hash::sha256(bytes).to_vec(),
+                        Opcode::OpHash160 => hash::hash160(bytes).to_vec(),
+                        _ => hash::hash256(bytes).to_vec(),
+                    }),
+                    None => D::unary(op, a),
+                };
+                self.push(value);
+            }
+
+            // Signature checks depend on the spending transaction.
+            Opcode::OpCheckSig | Opcode::OpCheckSigVerify => {
+                let pubkey = self.pop()?;
+                let sig = self.pop()?;
+                let check = D::binary(Opcode::OpCheckSig, sig, pubkey);
+                if op == Opcode::OpCheckSig {
+                    self.push(check);
+                } else {
+                    self.verify(check)?;
+                }
+            }
+            Opcode::OpCheckMultiSig | Opcode::OpCheckMultiSigVerify => {
+                let keys = self.count(MAX_PUBKEYS_PER_MULTISIG as i64)?;
+                let pubkeys = self.pop_list(keys)?;
+                let sigs = self.count(keys)?;
+                let sigs = self.pop_list(sigs)?;
+                // The extra item consumed by the off-by-one.
+                self.pop()?;
+                let check = D::multisig(sigs, pubkeys);
+                if op == Opcode::OpCheckMultiSig {
+                    self.push(check);
+                } else {
+                    self.verify(check)?;
+                }
+            }
+            Opcode::OpCheckSigAdd => {
+                let pubkey = self.pop()?;
+                let n = self.pop()?;
+                let sig = self.pop()?;
+                let check = D::binary(Opcode::OpCheckSig, sig, pubkey);
+                let sum = numeric::<D>(Opcode::OpAdd, n, check)?;
+                self.push(sum);
+            }
+
+            // Timelocks depend on the spending transaction; only a known
+            // operand can be checked here.
+            Opcode::OpCheckLockTimeVerify | Opcode::OpCheckSequenceVerify => {
+                let a = self.pop()?;
+                if let Some(bytes) = D::known(&a) {
+                    match decode_num(bytes, 5) {
+                        Ok(n) if n >= 0 => {}
+                        _ => return Err(Stop::Fails),
+                    }
+                }
+                self.domain.timelock(op, &a);
+                self.push(a);
+            }
+
+            // Handled by `run`.
+            Opcode::OpIf | Opcode::OpNotIf | Opcode::OpElse | Opcode::OpEndIf | Opcode::OpIfDup => {
+            }
+            Opcode::OpCat
+            | Opcode::OpSubStr
+            | Opcode::OpLeft
+            | Opcode::OpRight
+            | Opcode::OpInvert
+            | Opcode::OpAnd
+            | Opcode::OpOr
+            | Opcode::OpXor
+            | Opcode::Op2Mul
+            | Opcode::Op2Div
+            | Opcode::OpMul
+            | Opcode::OpDiv
+            | Opcode::OpMod
+            | Opcode::OpLShift
+            | Opcode::OpRShift => return Err(Stop::Disabled(op.to_byte())),
+        }
+        Ok(())
+    }
+}
+
+fn boolean<D: Domain>(b: bool) -> D::Value {
+    D::constant(if b { vec![0x01] } else { vec![] })
+}
+
+/// The numeric value of known bytes. A known operand longer than four
+/// bytes makes the opcode fail.
+fn num(bytes: Option<&[u8]>) -> Result<Option<i64>, Stop> {
+    match bytes {
+        Some(bytes) => decode_num(bytes, 4).map(Some).map_err(|_| Stop::Fails),
+        None => Ok(None),
+    }
+}
+
+/// Applies a two-operand numeric opcode (OP_ADD to OP_MAX, other than
+/// the VERIFY forms), folding known operands.
+fn numeric<D: Domain>(op: Opcode, a: D::Value, b: D::Value) -> Result<D::Value, Stop> {
+    let value = match (num(D::known(&a))?, num(D::known(&b))?) {
+        (Some(x), Some(y)) => match op {
+            Opcode::OpAdd => D::constant(encode_num(x + y)),
+            Opcode::OpSub => D::constant(encode_num(x - y)),
+            Opcode::OpMin => D::constant(encode_num(x.min(y))),
+            Opcode::OpMax => D::constant(encode_num(x.max(y))),
+            Opcode::OpBoolAnd => boolean::<D>(x != 0 && y != 0),
+            Opcode::OpBoolOr => boolean::<D>(x != 0 || y != 0),
+            Opcode::OpNumNotEqual => boolean::<D>(x != y),
+            Opcode::OpLessThan => boolean::<D>(x < y),
+            Opcode::OpGreaterThan => boolean::<D>(x > y),
+            Opcode::OpLessThanOrEqual => boolean::<D>(x <= y),
+            Opcode::OpGreaterThanOrEqual => boolean::<D>(x >= y),
+            _ => boolean::<D>(x == y),
+        },
+        _ => D::binary(op, a, b),
+    };
+    Ok(value)
+}
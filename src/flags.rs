@@ -0,0 +1,130 @@
+use core::ops::{BitOr, BitOrAssign};
+
+/// Script verification flags, matching Bitcoin Core's `SCRIPT_VERIFY_*`.
+///
+/// Flags switch on rules beyond the engine's default behavior. Only the
+/// flags defined as constants here are enforced; each is named after its
+/// Core counterpart so test vectors and tooling can map them one-to-one.
+///
+/// Combine flags with `|`:
+///
+/// ```rust
+/// use mini_bitcoin_script::flags::VerifyFlags;
+///
+/// let flags = VerifyFlags::DERSIG | VerifyFlags::LOW_S;
+/// assert!(flags.contains(VerifyFlags::LOW_S));
+/// assert!(!flags.contains(VerifyFlags::STRICTENC));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct VerifyFlags(u32);
+
+impl VerifyFlags {
+    /// No additional rules.
+    pub const NONE: VerifyFlags = VerifyFlags(0);
+
+    /// Signatures must be strict DER (BIP66).
+    pub const DERSIG: VerifyFlags = VerifyFlags(1 << 0);
+
+    /// Signatures must use a low S value (implies DER checks).
+    pub const LOW_S: VerifyFlags = VerifyFlags(1 << 1);
+
+    /// Signatures must be strict DER with a defined sighash type, and public
+    /// keys must be compressed or uncompressed (no hybrid keys).
+    pub const STRICTENC: VerifyFlags = VerifyFlags(1 << 2);
+
+    /// A failing OP_CHECKSIG must have been given an empty signature (BIP146).
+    pub const NULLFAIL: VerifyFlags = VerifyFlags(1 << 3);
+
+    /// All flags this engine enforces, with their Core names.
+    const NAMED: &'static [(&'static str, VerifyFlags)] = &[
+        ("DERSIG", VerifyFlags::DERSIG),
+        ("LOW_S", VerifyFlags::LOW_S),
+        ("STRICTENC", VerifyFlags::STRICTENC),
+        ("NULLFAIL", VerifyFlags::NULLFAIL),
+    ];
+
+    /// Returns `true` if every flag in `other` is set in `self`.
+    pub fn contains(self, other: VerifyFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if any flag in `other` is set in `self`.
+    pub fn intersects(self, other: VerifyFlags) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Sets the flags in `other`.
+    pub fn insert(&mut self, other: VerifyFlags) {
+        self.0 |= other.0;
+    }
+
+    /// Clears the flags in `other`.
+    pub fn remove(&mut self, other: VerifyFlags) {
+        self.0 &= !other.0;
+    }
+
+    /// Looks up a single flag by its Core name (e.g. `"LOW_S"`).
+    ///
+    /// Returns `None` for flags this engine does not enforce.
+    pub fn from_name(name: &str) -> Option<VerifyFlags> {
+        VerifyFlags::NAMED
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, flag)| *flag)
+    }
+
+    /// Iterates over the Core names of the flags set in `self`.
+    pub fn names(self) -> impl Iterator<Item = &'static str> {
+        VerifyFlags::NAMED
+            .iter()
+            .filter(move |(_, flag)| self.contains(*flag))
+            .map(|(name, _)| *name)
+    }
+}
+
+impl BitOr for VerifyFlags {
+    type Output = VerifyFlags;
+
+    fn bitor(self, rhs: VerifyFlags) -> VerifyFlags {
+        VerifyFlags(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for VerifyFlags {
+    fn bitor_assign(&mut self, rhs: VerifyFlags) {
+        self.0 |= rhs.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn default_is_none() {
+        assert_eq!(VerifyFlags::default(), VerifyFlags::NONE);
+        assert!(VerifyFlags::NONE.contains(VerifyFlags::NONE));
+        assert!(!VerifyFlags::NONE.intersects(VerifyFlags::DERSIG));
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut flags = VerifyFlags::NONE;
+        flags.insert(VerifyFlags::STRICTENC);
+        flags |= VerifyFlags::NULLFAIL;
+        assert!(flags.contains(VerifyFlags::STRICTENC | VerifyFlags::NULLFAIL));
+        flags.remove(VerifyFlags::STRICTENC);
+        assert!(!flags.contains(VerifyFlags::STRICTENC));
+        assert!(flags.contains(VerifyFlags::NULLFAIL));
+    }
+
+    #[test]
+    fn names_roundtrip() {
+        let flags = VerifyFlags::DERSIG | VerifyFlags::NULLFAIL;
+        let names: Vec<&str> = flags.names().collect();
+        assert_eq!(names, ["DERSIG", "NULLFAIL"]);
+        assert_eq!(VerifyFlags::from_name("LOW_S"), Some(VerifyFlags::LOW_S));
+        assert_eq!(VerifyFlags::from_name("P2SH"), None);
+    }
+}
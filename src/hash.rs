@@ -1,5 +1,5 @@
 use ripemd::Ripemd160;
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha256, Sha512};
 
 /// Computes the SHA-256 hash of the input data.
 ///
@@ -41,6 +41,40 @@ pub fn hash256(data: &[u8]) -> [u8; 32] {
     sha256(&sha256(data))
 }
 
+/// Computes a BIP340 tagged hash: `SHA256(SHA256(tag) || SHA256(tag) || data)`.
+///
+/// Used by Taproot (BIP341) and Schnorr signatures (BIP340) to separate
+/// hashes computed for different purposes.
+pub fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
+    let tag_hash = sha256(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// Computes HMAC-SHA512 (RFC 2104), the key derivation function of
+/// BIP32.
+pub fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    const BLOCK_SIZE: usize = 128;
+
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..64].copy_from_slice(&Sha512::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha512::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(data);
+    let mut outer = Sha512::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -99,4 +133,34 @@ mod tests {
         let expected = sha256(&sha256(data));
         assert_eq!(hash256(data), expected);
     }
+
+    #[test]
+    fn tagged_hash_matches_definition() {
+        let data = b"test composition";
+        let tag = sha256(b"TapSighash");
+        let expected = sha256(&[&tag[..], &tag, data].concat());
+        assert_eq!(tagged_hash("TapSighash", data), expected);
+    }
+
+    #[test]
+    fn hmac_sha512_rfc4231() {
+        // Test cases 1 and 6 (a key longer than the block size).
+        assert_eq!(
+            hmac_sha512(&[0x0b; 20], b"Hi There"),
+            hex!(
+                "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde"
+                "daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"
+            )
+        );
+        assert_eq!(
+            hmac_sha512(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            ),
+            hex!(
+                "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352"
+                "6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"
+            )
+        );
+    }
 }
@@ -1,28 +1,247 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
 use crate::error::ScriptError;
 
+const LOWER: &[u8; 16] = b"0123456789abcdef";
+const UPPER: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Why a hex string could not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexError {
+    /// The string has an odd number of hex digits.
+    OddLength {
+        /// Number of hex digits found.
+        len: usize,
+    },
+    /// A character is not a hex digit.
+    InvalidChar {
+        /// Byte offset of the character in the input string.
+        index: usize,
+        /// The offending byte; the first byte of its UTF-8 encoding for
+        /// non-ASCII characters.
+        byte: u8,
+    },
+    /// The string decodes to the wrong number of bytes for a fixed-size
+    /// array.
+    WrongLength {
+        /// Bytes the array holds.
+        expected: usize,
+        /// Bytes the string encodes.
+        found: usize,
+    },
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::OddLength { len } => write!(f, "odd number of hex digits: {len}"),
+            HexError::InvalidChar { index, byte } if byte.is_ascii_graphic() => {
+                write!(f, "invalid hex character '{}' at {index}", *byte as char)
+            }
+            HexError::InvalidChar { index, byte } => {
+                write!(f, "invalid hex byte 0x{byte:02x} at {index}")
+            }
+            HexError::WrongLength { expected, found } => {
+                write!(f, "expected {expected} bytes of hex, found {found}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HexError {}
+
+/// Every hex error is a [`ScriptError::InvalidHex`] to the script APIs.
+impl From<HexError> for ScriptError {
+    fn from(_: HexError) -> Self {
+        ScriptError::InvalidHex
+    }
+}
+
+/// Encodes bytes as lowercase hex.
+///
+/// ```rust
+/// use mini_bitcoin_script::hex::encode_hex;
+///
+/// assert_eq!(encode_hex(&[0xde, 0xad, 0xbe, 0xef]), "deadbeef");
+/// ```
+pub fn encode_hex(bytes: &[u8]) -> String {
+    encode_with(bytes, LOWER)
+}
+
+/// Encodes bytes as uppercase hex.
+pub fn encode_hex_upper(bytes: &[u8]) -> String {
+    encode_with(bytes, UPPER)
+}
+
+fn encode_with(bytes: &[u8], digits: &[u8; 16]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for &byte in bytes {
+        hex.push(digits[(byte >> 4) as usize] as char);
+        hex.push(digits[(byte & 0x0f) as usize] as char);
+    }
+    hex
+}
+
+/// Displays bytes as lowercase hex in reverse order, without allocating.
+///
+/// Txids and block hashes are shown this way by convention: the hash
+/// bytes are treated as a little-endian number and printed big-endian.
+///
+/// ```rust
+/// use mini_bitcoin_script::hex::ReversedHex;
+///
+/// let mut txid = [0u8; 32];
+/// txid[0] = 0x01;
+/// let shown = ReversedHex(&txid).to_string();
+/// assert!(shown.starts_with("0000"));
+/// assert!(shown.ends_with("01"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReversedHex<'a>(pub &'a [u8]);
+
+impl fmt::Display for ReversedHex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.iter().rev() {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ReversedHex<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_reversed_hex<S: serde::Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&ReversedHex(bytes))
+}
+
 /// Decode a hexadecimal string into a byte vector.
 ///
 /// Accepts both uppercase and lowercase hex digits. Does not accept
-/// a `0x` prefix — callers must strip it if present.
+/// a `0x` prefix or whitespace; see [`decode_hex_lenient`] for that.
 ///
 /// # Errors
 ///
-/// Returns [`ScriptError::InvalidHex`] if the string has an odd number
-/// of characters or contains non-hex characters.
-pub fn decode_hex(hex: &str) -> Result<Vec<u8>, ScriptError> {
-    if hex.len() % 2 != 0 {
-        return Err(ScriptError::InvalidHex);
+/// [`HexError::OddLength`] if the string has an odd number of characters,
+/// and [`HexError::InvalidChar`] with the position of the first non-hex
+/// character otherwise.
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, HexError> {
+    let bytes = hex.as_bytes();
+    if let Some(index) = bytes.iter().position(|b| !b.is_ascii_hexdigit()) {
+        return Err(HexError::InvalidChar {
+            index,
+            byte: bytes[index],
+        });
     }
+    if bytes.len() % 2 != 0 {
+        return Err(HexError::OddLength { len: bytes.len() });
+    }
+    Ok(bytes
+        .chunks(2)
+        .map(|pair| (digit(pair[0]) << 4) | digit(pair[1]))
+        .collect())
+}
 
-    let mut bytes = Vec::with_capacity(hex.len() / 2);
+/// Like [`decode_hex`], but skips an optional `0x`/`0X` prefix and any
+/// ASCII whitespace between digits, as found in pasted or wrapped hex.
+///
+/// Error positions refer to the original string.
+///
+/// ```rust
+/// use mini_bitcoin_script::hex::decode_hex_lenient;
+///
+/// assert_eq!(decode_hex_lenient(" 0xdead beef\n").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+/// ```
+pub fn decode_hex_lenient(hex: &str) -> Result<Vec<u8>, HexError> {
+    let trimmed = hex.trim_start();
+    let mut offset = hex.len() - trimmed.len();
+    if trimmed.starts_with("0x") || trimmed.starts_with("0X") {
+        offset += 2;
+    }
 
-    for i in (0..hex.len()).step_by(2) {
-        let pair = &hex[i..i + 2];
-        let byte = u8::from_str_radix(pair, 16).map_err(|_| ScriptError::InvalidHex)?;
-        bytes.push(byte);
+    let mut digits = Vec::with_capacity(hex.len() - offset);
+    for (index, &byte) in hex.as_bytes().iter().enumerate().skip(offset) {
+        if byte.is_ascii_hexdigit() {
+            digits.push(digit(byte));
+        } else if !byte.is_ascii_whitespace() {
+            return Err(HexError::InvalidChar { index, byte });
+        }
     }
+    if digits.len() % 2 != 0 {
+        return Err(HexError::OddLength { len: digits.len() });
+    }
+    Ok(digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect())
+}
 
-    Ok(bytes)
+/// Decodes exactly `N` bytes of hex into an array. A `const fn`, so fixed
+/// values can be written as hex in constants:
+///
+/// ```rust
+/// use mini_bitcoin_script::hex::decode_hex_array;
+///
+/// const MAGIC: [u8; 4] = match decode_hex_array("f9beb4d9") {
+///     Ok(bytes) => bytes,
+///     Err(_) => panic!("bad hex"),
+/// };
+/// assert_eq!(MAGIC, [0xf9, 0xbe, 0xb4, 0xd9]);
+/// ```
+///
+/// # Errors
+///
+/// As [`decode_hex`], plus [`HexError::WrongLength`] if the string does
+/// not encode exactly `N` bytes.
+pub const fn decode_hex_array<const N: usize>(hex: &str) -> Result<[u8; N], HexError> {
+    let bytes = hex.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_hexdigit() {
+            return Err(HexError::InvalidChar {
+                index: i,
+                byte: bytes[i],
+            });
+        }
+        i += 1;
+    }
+    if bytes.len() % 2 != 0 {
+        return Err(HexError::OddLength { len: bytes.len() });
+    }
+    if bytes.len() / 2 != N {
+        return Err(HexError::WrongLength {
+            expected: N,
+            found: bytes.len() / 2,
+        });
+    }
+
+    let mut out = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        out[i] = (digit(bytes[2 * i]) << 4) | digit(bytes[2 * i + 1]);
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// Value of an ASCII hex digit; callers check `is_ascii_hexdigit` first.
+const fn digit(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        _ => c - b'A' + 10,
+    }
 }
 
 #[cfg(test)]
@@ -56,13 +275,39 @@ mod tests {
 
     #[test]
     fn odd_length() {
-        assert_eq!(decode_hex("0"), Err(ScriptError::InvalidHex));
-        assert_eq!(decode_hex("abc"), Err(ScriptError::InvalidHex));
+        assert_eq!(decode_hex("0"), Err(HexError::OddLength { len: 1 }));
+        assert_eq!(decode_hex("abc"), Err(HexError::OddLength { len: 3 }));
     }
 
     #[test]
     fn invalid_characters() {
-        assert_eq!(decode_hex("gg"), Err(ScriptError::InvalidHex));
-        assert_eq!(decode_hex("0x00"), Err(ScriptError::InvalidHex));
+        assert_eq!(
+            decode_hex("gg"),
+            Err(HexError::InvalidChar {
+                index: 0,
+                byte: b'g'
+            })
+        );
+        assert_eq!(
+            decode_hex("0x00"),
+            Err(HexError::InvalidChar {
+                index: 1,
+                byte: b'x'
+            })
+        );
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode_hex(&encode_hex(&bytes)).unwrap(), bytes);
+        assert_eq!(decode_hex(&encode_hex_upper(&bytes)).unwrap(), bytes);
+        assert_eq!(encode_hex_upper(&[0xab, 0x01]), "AB01");
+    }
+
+    #[test]
+    fn hex_errors_become_script_errors() {
+        let err: ScriptError = HexError::OddLength { len: 1 }.into();
+        assert_eq!(err, ScriptError::InvalidHex);
     }
 }
@@ -29,25 +29,95 @@
 //! - **Tokenizer**: Parses raw script bytes into a sequence of [`token::Token`]s,
 //!   handling all four push-data encodings (direct, PUSHDATA1/2/4).
 //! - **Execution engine**: A stack-based virtual machine that executes
-//!   tokenized scripts with support for 27 opcodes including conditionals,
-//!   stack manipulation, comparison, hashing, and signature verification.
+//!   tokenized scripts with support for 54 opcodes including conditionals,
+//!   stack manipulation, arithmetic, hashing, multisig, timelocks and
+//!   signature verification.
+//! - **Bytecode**: [`bytecode::CompiledScript`] compiles tokens once into
+//!   an instruction array with resolved conditional jumps and shared push
+//!   data, for scripts executed many times; results match the engine.
 //! - **P2PKH validation**: Protocol-accurate two-phase execution model
 //!   (post-2010) for Pay-to-Public-Key-Hash scripts.
+//! - **Script types**: [`script::Script`] and [`script::ScriptBuf`], a
+//!   borrowed/owned pair with template checks, instruction iteration, ASM
+//!   and hex conversion, and P2SH/P2WSH script hashes.
 //! - **Hash functions**: SHA-256, RIPEMD-160, HASH160, and HASH256.
+//! - **Hex**: [`hex`] encodes and decodes hex, including lenient and
+//!   `const` fixed-size decoding and byte-reversed txid display.
+//! - **Transactions and sighashes**: consensus serialization, txids, and
+//!   legacy, BIP143 and BIP341 (key path) signature hashes.
+//! - **Signature cache** (`std` feature): [`sigcache::SignatureCache`]
+//!   remembers verified signatures, so re-validating a transaction skips
+//!   ECDSA and Schnorr checks it has already done. Set it in
+//!   [`engine::ExecuteOpts`], or in [`verify::VerifyOpts`] for
+//!   transactions, blocks and PSBT extraction.
+//! - **Signing** (`secp256k1` feature): complete scriptSigs and witnesses
+//!   for P2PKH, P2WPKH, P2SH multisig and P2TR key path spends. Each
+//!   `sign_*` function checks that the spent output pays to the given key
+//!   or script before signing.
+//! - **Output classification and size estimation**: [`classify::classify`]
+//!   recognizes the standard scriptPubKey templates, and
+//!   [`weight::estimate_satisfaction`] gives the worst-case and typical
+//!   scriptSig, witness and virtual size needed to spend them.
+//! - **Descriptors**: [`descriptor::Descriptor`] parses BIP380–386 output
+//!   descriptors (`pk`, `pkh`, `wpkh`, `sh`, `wsh`, `multi`,
+//!   `sortedmulti`, `tr`, `addr`, `raw`), checks their checksum and
+//!   derives scriptPubKeys, redeem scripts and witness scripts. Keys may
+//!   be fixed or `xpub.../0/*` ranges, expanded per derivation index for
+//!   address scanning. `tr()` output keys and xpub derivation need the
+//!   `secp256k1` feature.
+//! - **BIP32**: [`bip32::Xpub`] parses and encodes xpubs and tpubs,
+//!   derives unhardened children, and computes fingerprints;
+//!   [`bip32::KeyOrigin`] holds `[fingerprint/path]` key origins.
+//! - **Input verification**: [`verify::verify_input`] runs an input's
+//!   scriptSig, scriptPubKey, P2SH redeem script and segwit v0 or
+//!   Taproot key path witness against the outputs it spends;
+//!   [`verify::verify_tx`] checks a whole transaction, its inputs looked
+//!   up through a [`verify::UtxoProvider`], and its fee, sharing one
+//!   [`sighash::SegwitV0Midstate`] and [`sighash::TaprootMidstate`]
+//!   across inputs and (with the `rayon` feature) checking them in
+//!   parallel.
+//! - **Blocks**: [`block::Block`] decodes and encodes blocks, computes
+//!   Merkle roots and checks the header's root and the BIP141 witness
+//!   commitment; [`block::validate_block`] runs every non-coinbase
+//!   input, spending outputs created earlier in the block, and reports
+//!   failures per transaction. [`verify::VerifyOpts::mainnet`] applies
+//!   only the soft forks active at a historic block's height.
+//! - **UTXO snapshots**: [`snapshot::UtxoSnapshot`] holds the outputs
+//!   transactions spend, imported from CSV or (with `serde`) JSON dumps
+//!   and stored in a compact binary format, so historic spends can be
+//!   verified offline.
+//! - **PSBT**: [`psbt::Psbt`] decodes and encodes BIP174 (v0) and BIP370
+//!   (v2) PSBTs in binary and base64, finalizes P2PK, P2PKH, multisig,
+//!   P2WPKH, P2WSH, nested segwit and P2TR key path inputs, and extracts
+//!   the signed transaction after verifying every input.
+//! - **Sigop counting**: legacy, accurate, P2SH and witness counts in
+//!   [`sigops`], matching Bitcoin Core's block limit accounting.
+//! - **Standardness**: [`policy`] lists the reasons Bitcoin Core's default
+//!   relay policy would reject a transaction, such as dust outputs,
+//!   oversized OP_RETURN data or non-standard witnesses.
+//! - **Static analysis**: [`analysis::analyze`] explores every execution
+//!   path without running signature checks, reporting the initial stack
+//!   depth a script needs, guaranteed underflows, unbalanced conditionals,
+//!   unreachable code and paths that can never succeed.
+//! - **Symbolic execution**: [`symbolic::symbolic_execute`] treats the
+//!   initial stack as variables and lists, per branch combination, the
+//!   constraints a witness must satisfy.
+//! - **Miniscript**: [`miniscript::Miniscript`] parses and type-checks
+//!   Miniscript expressions for P2WSH and tapscript, compiles them to
+//!   tokens, lifts compiled scripts back, and builds satisfying witnesses
+//!   from available signatures, preimages and timelocks.
 //!
 //! # What is NOT implemented
 //!
-//! - Arithmetic opcodes (OP_ADD, OP_SUB, etc.)
-//! - Multi-signature opcodes (OP_CHECKMULTISIG)
-//! - Timelock opcodes (OP_CHECKLOCKTIMEVERIFY, OP_CHECKSEQUENCEVERIFY)
-//! - SegWit, Taproot, or any witness-based script types
-//! - Transaction serialization or sighash computation
+//! - Taproot script path sighashes and annexes
 //!
 //! # OP_CHECKSIG behavior
 //!
 //! By default, `OP_CHECKSIG` operates in **stub mode**: it pops two stack
-//! elements (pubkey and signature) and always pushes `true`. This allows
-//! testing script logic without real cryptographic keys.
+//! elements (pubkey and signature) and pushes `true` for any non-empty
+//! signature, `false` for an empty one. This allows testing script logic,
+//! including branches that expect a failed check, without real
+//! cryptographic keys.
 //!
 //! With the `secp256k1` Cargo feature enabled and a pre-computed sighash
 //! provided via [`engine::ExecuteOpts`], real ECDSA signature verification
@@ -74,15 +144,59 @@
 //!
 //! | Feature     | Description                                        |
 //! |-------------|----------------------------------------------------|
+//! | `std`       | On by default. Implements `std::error::Error` for  |
+//! |             | `ScriptError`. Disable for `no_std` + `alloc`.     |
 //! | `secp256k1` | Enables real ECDSA signature verification for      |
-//! |             | OP_CHECKSIG via the `secp256k1` crate.             |
+//! |             | OP_CHECKSIG via the `secp256k1` crate, and the     |
+//! |             | `sign` module. The crate is re-exported.           |
+//! | `test-utils`| Deterministic keys and signed funding/spending     |
+//! |             | transaction fixtures (implies `secp256k1`).        |
+//! | `serde`     | `Serialize`/`Deserialize` for `Token`, `Opcode`    |
+//! |             | and `ScriptError`; `Serialize` for symbolic        |
+//! |             | spending conditions, analysis findings and         |
+//! |             | transaction and block verification reports.        |
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod analysis;
+pub(crate) mod base58;
+pub(crate) mod base64;
+pub mod bip32;
+pub mod block;
+pub mod builder;
+pub mod bytecode;
+pub mod classify;
+pub mod descriptor;
 pub mod engine;
 pub mod error;
+pub(crate) mod explore;
+pub mod flags;
 pub mod hash;
 pub mod hex;
+pub mod miniscript;
 pub mod opcode;
+pub mod policy;
+pub mod psbt;
 pub mod script;
+#[cfg(feature = "std")]
+pub mod sigcache;
+pub mod sighash;
+#[cfg(feature = "secp256k1")]
+pub mod sign;
+pub mod signature;
+pub mod sigops;
+pub mod snapshot;
 pub(crate) mod stack;
+pub mod symbolic;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 pub mod token;
 pub mod tokenizer;
+pub mod transaction;
+pub mod verify;
+pub mod weight;
+
+#[cfg(feature = "secp256k1")]
+pub use secp256k1;
@@ -0,0 +1,1577 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::builder::ScriptBuilder;
+use crate::engine::{check_lock_time, check_sequence, decode_num, encode_num};
+use crate::hash;
+use crate::hex::{decode_hex, encode_hex};
+use crate::opcode::Opcode;
+use crate::script::{Script, ScriptBuf};
+use crate::signature::{pubkey_encoding, PubkeyEncoding};
+use crate::sigops::MAX_PUBKEYS_PER_MULTISIG;
+use crate::token::Token;
+
+/// Most keys a tapscript `multi_a()` may list, as in Bitcoin Core.
+const MAX_PUBKEYS_PER_MULTI_A: usize = 999;
+
+/// Timelocks must be in `1..2^31`; bit 31 would disable `older()`.
+const MAX_TIMELOCK: u32 = 1 << 31;
+
+/// Why a Miniscript expression or script could not be parsed or
+/// satisfied.
+///
+/// Positions are byte offsets into the expression string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MiniscriptError {
+    /// Unexpected input, such as a missing parenthesis or trailing text.
+    Syntax {
+        /// Byte offset where parsing stopped.
+        index: usize,
+    },
+    /// A fragment or wrapper name that does not exist.
+    UnknownFragment {
+        /// Byte offset of the name.
+        index: usize,
+    },
+    /// A key, hash, number or threshold that is malformed or out of
+    /// range, or the wrong number of arguments.
+    InvalidArgument {
+        /// Byte offset of the argument, or of the fragment for a wrong
+        /// argument count.
+        index: usize,
+    },
+    /// `multi()` in tapscript or `multi_a()` in P2WSH.
+    InvalidContext {
+        /// Byte offset of the fragment.
+        index: usize,
+    },
+    /// A fragment's subexpressions have types it does not accept, or the
+    /// whole expression is not of type B.
+    TypeCheck {
+        /// Byte offset of the expression, including its wrappers.
+        index: usize,
+    },
+    /// The script is not the compilation of any Miniscript.
+    NotMiniscript,
+    /// The available signatures, preimages and timelocks cannot satisfy
+    /// the expression.
+    Unsatisfiable,
+}
+
+impl fmt::Display for MiniscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MiniscriptError::Syntax { index } => write!(f, "syntax error at {index}"),
+            MiniscriptError::UnknownFragment { index } => {
+                write!(f, "unknown miniscript fragment at {index}")
+            }
+            MiniscriptError::InvalidArgument { index } => {
+                write!(f, "invalid argument at {index}")
+            }
+            MiniscriptError::InvalidContext { index } => {
+                write!(f, "fragment at {index} not allowed in this context")
+            }
+            MiniscriptError::TypeCheck { index } => {
+                write!(f, "expression at {index} does not type check")
+            }
+            MiniscriptError::NotMiniscript => write!(f, "script is not miniscript"),
+            MiniscriptError::Unsatisfiable => write!(f, "cannot satisfy miniscript"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MiniscriptError {}
+
+/// The script a Miniscript compiles into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// A P2WSH witness script: 33-byte compressed keys and `multi()`.
+    Wsh,
+    /// A BIP342 tapscript leaf: 32-byte x-only keys and `multi_a()`.
+    Tap,
+}
+
+/// The basic type of an expression: what it expects on the stack and
+/// what it leaves there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseType {
+    /// Base: consumes its inputs from the top of the stack and pushes a
+    /// nonzero value on satisfaction, an exact 0 on dissatisfaction.
+    B,
+    /// Verify: like B, but pushes nothing and aborts instead of
+    /// dissatisfying.
+    V,
+    /// Key: like B, but pushes a public key for a following
+    /// OP_CHECKSIG.
+    K,
+    /// Wrapped: like B, but takes its inputs from one element below the
+    /// top of the stack.
+    W,
+}
+
+/// The type of an expression: its base type and correctness properties.
+///
+/// Displays as the base type followed by the properties that hold, e.g.
+/// `Bondu` for `pk()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Type {
+    /// The basic type.
+    pub base: BaseType,
+    /// Zero-arg: always consumes exactly 0 stack elements.
+    pub z: bool,
+    /// One-arg: always consumes exactly 1 stack element.
+    pub o: bool,
+    /// Nonzero: every satisfaction has a nonzero top stack element.
+    pub n: bool,
+    /// Dissatisfiable: has a dissatisfaction needing no signature.
+    pub d: bool,
+    /// Unit: on satisfaction pushes exactly 1.
+    pub u: bool,
+}
+
+impl Type {
+    fn new(base: BaseType, properties: &str) -> Type {
+        Type {
+            base,
+            z: properties.contains('z'),
+            o: properties.contains('o'),
+            n: properties.contains('n'),
+            d: properties.contains('d'),
+            u: properties.contains('u'),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let base = match self.base {
+            BaseType::B => "B",
+            BaseType::V => "V",
+            BaseType::K => "K",
+            BaseType::W => "W",
+        };
+        f.write_str(base)?;
+        let properties = [
+            (self.z, 'z'),
+            (self.o, 'o'),
+            (self.n, 'n'),
+            (self.d, 'd'),
+            (self.u, 'u'),
+        ];
+        for (_, name) in properties.iter().filter(|(set, _)| *set) {
+            write!(f, "{name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A Miniscript fragment.
+///
+/// Sugar is expanded when parsing: `pk(K)` is `c:pk_k(K)`, `pkh(K)` is
+/// `c:pk_h(K)`, `and_n(X,Y)` is `andor(X,Y,0)`, and the wrappers `t:`,
+/// `l:` and `u:` are `and_v(X,1)`, `or_i(0,X)` and `or_i(X,0)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Terminal {
+    /// `0`
+    False,
+    /// `1`
+    True,
+    /// `pk_k(K)`: pushes the key.
+    PkK(Vec<u8>),
+    /// `pk_h(K)`: checks a key's HASH160 and leaves it on the stack.
+    PkH(Vec<u8>),
+    /// `pk_h` for which only the key hash is known, as lifted from a
+    /// script. Written `expr_raw_pkh(H)`.
+    RawPkH([u8; 20]),
+    /// `older(n)`: relative timelock (OP_CHECKSEQUENCEVERIFY).
+    Older(u32),
+    /// `after(n)`: absolute timelock (OP_CHECKLOCKTIMEVERIFY).
+    After(u32),
+    /// `sha256(H)`: a 32-byte preimage of H.
+    Sha256([u8; 32]),
+    /// `hash256(H)`: a 32-byte preimage of H, with H in script byte
+    /// order.
+    Hash256([u8; 32]),
+    /// `ripemd160(H)`: a 32-byte preimage of H.
+    Ripemd160([u8; 20]),
+    /// `hash160(H)`: a 32-byte preimage of H.
+    Hash160([u8; 20]),
+    /// `andor(X,Y,Z)`: X and Y, or Z.
+    AndOr(Box<Miniscript>, Box<Miniscript>, Box<Miniscript>),
+    /// `and_v(X,Y)`
+    AndV(Box<Miniscript>, Box<Miniscript>),
+    /// `and_b(X,Y)`
+    AndB(Box<Miniscript>, Box<Miniscript>),
+    /// `or_b(X,Z)`
+    OrB(Box<Miniscript>, Box<Miniscript>),
+    /// `or_c(X,Z)`
+    OrC(Box<Miniscript>, Box<Miniscript>),
+    /// `or_d(X,Z)`
+    OrD(Box<Miniscript>, Box<Miniscript>),
+    /// `or_i(X,Z)`
+    OrI(Box<Miniscript>, Box<Miniscript>),
+    /// `thresh(k,X1,...,Xn)`: at least k of the subexpressions.
+    Thresh(usize, Vec<Miniscript>),
+    /// `multi(k,K1,...,Kn)`: OP_CHECKMULTISIG, P2WSH only.
+    Multi(usize, Vec<Vec<u8>>),
+    /// `multi_a(k,K1,...,Kn)`: OP_CHECKSIGADD, tapscript only.
+    MultiA(usize, Vec<Vec<u8>>),
+    /// `a:X`: runs X on the alt stack.
+    Alt(Box<Miniscript>),
+    /// `s:X`: swaps the top two elements first.
+    Swap(Box<Miniscript>),
+    /// `c:X`: OP_CHECKSIG on the key X leaves.
+    Check(Box<Miniscript>),
+    /// `d:X`: `OP_DUP OP_IF X OP_ENDIF`.
+    DupIf(Box<Miniscript>),
+    /// `v:X`: X followed by OP_VERIFY, or its VERIFY form.
+    Verify(Box<Miniscript>),
+    /// `j:X`: skips X when the top element is empty.
+    NonZero(Box<Miniscript>),
+    /// `n:X`: X followed by OP_0NOTEQUAL.
+    ZeroNotEqual(Box<Miniscript>),
+}
+
+/// A type-checked Miniscript expression.
+///
+/// ```rust
+/// use mini_bitcoin_script::miniscript::{Context, Miniscript};
+///
+/// let key = "02".to_string() + &"11".repeat(32);
+/// let ms = Miniscript::parse(&format!("or_d(pk({key}),older(144))"), Context::Wsh).unwrap();
+/// assert_eq!(ms.ty().to_string(), "Bo");
+///
+/// let lifted = Miniscript::from_script(&ms.to_script(), Context::Wsh).unwrap();
+/// assert_eq!(lifted, ms);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Miniscript {
+    node: Terminal,
+    ty: Type,
+    ctx: Context,
+}
+
+impl Miniscript {
+    /// Parses a Miniscript expression. The expression must be of type B.
+    ///
+    /// Keys are hex: 33-byte compressed keys in P2WSH and 32-byte x-only
+    /// keys in tapscript.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`MiniscriptError`] locating the first problem.
+    pub fn parse(s: &str, ctx: Context) -> Result<Miniscript, MiniscriptError> {
+        let mut pos = 0;
+        let tree = parse_tree(s, &mut pos)?;
+        if pos != s.len() {
+            return Err(MiniscriptError::Syntax { index: pos });
+        }
+        let ms = from_tree(&tree, ctx)?;
+        if ms.ty.base != BaseType::B {
+            return Err(MiniscriptError::TypeCheck { index: 0 });
+        }
+        Ok(ms)
+    }
+
+    /// Lifts a script back to the Miniscript it was compiled from.
+    ///
+    /// `pk_h()` comes back as `expr_raw_pkh()`, since the script holds
+    /// only the key hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MiniscriptError::NotMiniscript`] if the script does not
+    /// parse, or is not exactly the compilation of a type B expression.
+    pub fn from_script(script: &Script, ctx: Context) -> Result<Miniscript, MiniscriptError> {
+        let tokens = script
+            .tokens()
+            .map_err(|_| MiniscriptError::NotMiniscript)?;
+        Miniscript::from_tokens(&tokens, ctx)
+    }
+
+    /// Lifts a token sequence; see [`Miniscript::from_script`].
+    pub fn from_tokens(tokens: &[Token], ctx: Context) -> Result<Miniscript, MiniscriptError> {
+        let mut lifter = Lifter {
+            tokens: tokens.to_vec(),
+            ctx,
+        };
+        let (ms, start) = lifter.chain(tokens.len())?;
+        // Lifting is permissive about number encodings and the like;
+        // recompiling catches anything that is not canonical.
+        if start != 0 || ms.ty.base != BaseType::B || ms.to_tokens() != tokens {
+            return Err(MiniscriptError::NotMiniscript);
+        }
+        Ok(ms)
+    }
+
+    /// The fragment at the root of the expression.
+    pub fn node(&self) -> &Terminal {
+        &self.node
+    }
+
+    /// The expression's type.
+    pub fn ty(&self) -> Type {
+        self.ty
+    }
+
+    /// The script context the expression was checked for.
+    pub fn context(&self) -> Context {
+        self.ctx
+    }
+
+    /// Compiles the expression to tokens.
+    pub fn to_tokens(&self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        self.compile(&mut tokens);
+        tokens
+    }
+
+    /// Compiles the expression to a script: the P2WSH witness script or
+    /// the tapscript leaf.
+    pub fn to_script(&self) -> ScriptBuf {
+        let mut builder = ScriptBuilder::new();
+        for token in self.to_tokens() {
+            builder = match token {
+                Token::PushData(data) => builder.push_slice(&data),
+                Token::Op(op) => builder.push_opcode(op),
+            };
+        }
+        builder.into_script()
+    }
+
+    /// Builds the smallest witness that satisfies the expression with the
+    /// given data, bottom stack element first and without the script
+    /// itself.
+    ///
+    /// The result is not guaranteed to be non-malleable: a third party
+    /// may be able to change a witness that dissatisfies some branch.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MiniscriptError::Unsatisfiable`] if no combination of the
+    /// available signatures, preimages and timelocks satisfies it.
+    pub fn satisfy(&self, satisfier: &Satisfier) -> Result<Vec<Vec<u8>>, MiniscriptError> {
+        self.satisfactions(satisfier)
+            .0
+            .ok_or(MiniscriptError::Unsatisfiable)
+    }
+
+    fn new(node: Terminal, ctx: Context) -> Option<Miniscript> {
+        let ty = type_check(&node, ctx)?;
+        Some(Miniscript { node, ty, ctx })
+    }
+
+    fn compile(&self, out: &mut Vec<Token>) {
+        use Opcode::*;
+        let op = |out: &mut Vec<Token>, op| out.push(Token::Op(op));
+        match &self.node {
+            Terminal::False => op(out, Op0),
+            Terminal::True => op(out, Op1),
+            Terminal::PkK(key) => out.push(Token::PushData(key.clone())),
+            Terminal::PkH(key) => compile_pkh(out, &hash::hash160(key)),
+            Terminal::RawPkH(hash) => compile_pkh(out, hash),
+            Terminal::Older(n) => {
+                out.push(num_token(i64::from(*n)));
+                op(out, OpCheckSequenceVerify);
+            }
+            Terminal::After(n) => {
+                out.push(num_token(i64::from(*n)));
+                op(out, OpCheckLockTimeVerify);
+            }
+            Terminal::Sha256(h) => compile_hash(out, OpSha256, h),
+            Terminal::Hash256(h) => compile_hash(out, OpHash256, h),
+            Terminal::Ripemd160(h) => compile_hash(out, OpRipemd160, h),
+            Terminal::Hash160(h) => compile_hash(out, OpHash160, h),
+            Terminal::AndOr(x, y, z) => {
+                x.compile(out);
+                op(out, OpNotIf);
+                z.compile(out);
+                op(out, OpElse);
+                y.compile(out);
+                op(out, OpEndIf);
+            }
+            Terminal::AndV(x, y) => {
+                x.compile(out);
+                y.compile(out);
+            }
+            Terminal::AndB(x, y) => {
+                x.compile(out);
+                y.compile(out);
+                op(out, OpBoolAnd);
+            }
+            Terminal::OrB(x, z) => {
+                x.compile(out);
+                z.compile(out);
+                op(out, OpBoolOr);
+            }
+            Terminal::OrC(x, z) => {
+                x.compile(out);
+                op(out, OpNotIf);
+                z.compile(out);
+                op(out, OpEndIf);
+            }
+            Terminal::OrD(x, z) => {
+                x.compile(out);
+                op(out, OpIfDup);
+                op(out, OpNotIf);
+                z.compile(out);
+                op(out, OpEndIf);
+            }
+            Terminal::OrI(x, z) => {
+                op(out, OpIf);
+                x.compile(out);
+                op(out, OpElse);
+                z.compile(out);
+                op(out, OpEndIf);
+            }
+            Terminal::Thresh(k, subs) => {
+                for (i, sub) in subs.iter().enumerate() {
+                    sub.compile(out);
+                    if i > 0 {
+                        op(out, OpAdd);
+                    }
+                }
+                out.push(num_token(*k as i64));
+                op(out, OpEqual);
+            }
+            Terminal::Multi(k, keys) => {
+                out.push(num_token(*k as i64));
+                out.extend(keys.iter().map(|key| Token::PushData(key.clone())));
+                out.push(num_token(keys.len() as i64));
+                op(out, OpCheckMultiSig);
+            }
+            Terminal::MultiA(k, keys) => {
+                for (i, key) in keys.iter().enumerate() {
+                    out.push(Token::PushData(key.clone()));
+                    op(out, if i == 0 { OpCheckSig } else { OpCheckSigAdd });
+                }
+                out.push(num_token(*k as i64));
+                op(out, OpNumEqual);
+            }
+            Terminal::Alt(x) => {
+                op(out, OpToAltStack);
+                x.compile(out);
+                op(out, OpFromAltStack);
+            }
+            Terminal::Swap(x) => {
+                op(out, OpSwap);
+                x.compile(out);
+            }
+            Terminal::Check(x) => {
+                x.compile(out);
+                op(out, OpCheckSig);
+            }
+            Terminal::DupIf(x) => {
+                op(out, OpDup);
+                op(out, OpIf);
+                x.compile(out);
+                op(out, OpEndIf);
+            }
+            Terminal::Verify(x) => {
+                x.compile(out);
+                let verify = match out.last() {
+                    Some(Token::Op(OpEqual)) => Some(OpEqualVerify),
+                    Some(Token::Op(OpCheckSig)) => Some(OpCheckSigVerify),
+                    Some(Token::Op(OpCheckMultiSig)) => Some(OpCheckMultiSigVerify),
+                    Some(Token::Op(OpNumEqual)) => Some(OpNumEqualVerify),
+                    _ => None,
+                };
+                match verify {
+                    Some(verify) => *out.last_mut().unwrap() = Token::Op(verify),
+                    None => op(out, OpVerify),
+                }
+            }
+            Terminal::NonZero(x) => {
+                op(out, OpSize);
+                op(out, Op0NotEqual);
+                op(out, OpIf);
+                x.compile(out);
+                op(out, OpEndIf);
+            }
+            Terminal::ZeroNotEqual(x) => {
+                x.compile(out);
+                op(out, Op0NotEqual);
+            }
+        }
+    }
+
+    /// The smallest satisfaction and dissatisfaction, if any.
+    fn satisfactions(&self, s: &Satisfier) -> (Option<Witness>, Option<Witness>) {
+        let empty = || Some(vec![vec![]]);
+        let one = || Some(vec![vec![1]]);
+        match &self.node {
+            Terminal::False => (None, Some(vec![])),
+            Terminal::True => (Some(vec![]), None),
+            Terminal::PkK(key) => (s.signature(key).map(|sig| vec![sig]), empty()),
+            Terminal::PkH(key) => (
+                s.signature(key).map(|sig| vec![sig, key.clone()]),
+                Some(vec![vec![], key.clone()]),
+            ),
+            Terminal::RawPkH(h) => {
+                let key = s
+                    .signatures
+                    .keys()
+                    .find(|key| hash::hash160(key) == *h)
+                    .cloned();
+                match key {
+                    Some(key) => (
+                        s.signature(&key).map(|sig| vec![sig, key.clone()]),
+                        Some(vec![vec![], key]),
+                    ),
+                    None => (None, None),
+                }
+            }
+            Terminal::Older(n) => {
+                let ok = s.sequence.map_or(false, |seq| {
+                    check_sequence(i64::from(*n), seq, None).is_ok()
+                });
+                (ok.then(Vec::new), None)
+            }
+            Terminal::After(n) => {
+                let ok = s.lock_time.map_or(false, |lock_time| {
+                    check_lock_time(i64::from(*n), lock_time, s.sequence).is_ok()
+                });
+                (ok.then(Vec::new), None)
+            }
+            Terminal::Sha256(h) => s.preimage(|p| hash::sha256(p)[..] == h[..]),
+            Terminal::Hash256(h) => s.preimage(|p| hash::hash256(p)[..] == h[..]),
+            Terminal::Ripemd160(h) => s.preimage(|p| hash::ripemd160(p)[..] == h[..]),
+            Terminal::Hash160(h) => s.preimage(|p| hash::hash160(p)[..] == h[..]),
+            Terminal::AndOr(x, y, z) => {
+                let (xs, xd) = x.satisfactions(s);
+                let (ys, _) = y.satisfactions(s);
+                let (zs, zd) = z.satisfactions(s);
+                (smallest(cat(&ys, &xs), cat(&zs, &xd)), cat(&zd, &xd))
+            }
+            Terminal::AndV(x, y) => {
+                let (xs, _) = x.satisfactions(s);
+                let (ys, yd) = y.satisfactions(s);
+                (cat(&ys, &xs), cat(&yd, &xs))
+            }
+            Terminal::AndB(x, y) => {
+                let (xs, xd) = x.satisfactions(s);
+                let (ys, yd) = y.satisfactions(s);
+                (cat(&ys, &xs), cat(&yd, &xd))
+            }
+            Terminal::OrB(x, z) => {
+                let (xs, xd) = x.satisfactions(s);
+                let (zs, zd) = z.satisfactions(s);
+                (smallest(cat(&zd, &xs), cat(&zs, &xd)), cat(&zd, &xd))
+            }
+            Terminal::OrC(x, z) | Terminal::OrD(x, z) => {
+                let (xs, xd) = x.satisfactions(s);
+                let (zs, zd) = z.satisfactions(s);
+                let dsat = match self.node {
+                    Terminal::OrD(..) => cat(&zd, &xd),
+                    _ => None,
+                };
+                (smallest(xs, cat(&zs, &xd)), dsat)
+            }
+            Terminal::OrI(x, z) => {
+                let (xs, xd) = x.satisfactions(s);
+                let (zs, zd) = z.satisfactions(s);
+                (
+                    smallest(cat(&xs, &one()), cat(&zs, &empty())),
+                    smallest(cat(&xd, &one()), cat(&zd, &empty())),
+                )
+            }
+            Terminal::Thresh(k, subs) => {
+                let options: Vec<_> = subs.iter().map(|sub| sub.satisfactions(s)).collect();
+                let dsat = options
+                    .iter()
+                    .rev()
+                    .try_fold(Vec::new(), |acc, (_, d)| cat(&Some(acc), d));
+                (thresh_satisfaction(*k, &options), dsat)
+            }
+            Terminal::Multi(k, keys) => {
+                let sigs: Vec<_> = keys
+                    .iter()
+                    .filter_map(|key| s.signature(key))
+                    .take(*k)
+                    .collect();
+                let sat = (sigs.len() == *k).then(|| [vec![vec![]], sigs].concat());
+                (sat, Some(vec![vec![]; k + 1]))
+            }
+            Terminal::MultiA(k, keys) => {
+                let mut needed = *k;
+                let mut witness: Vec<Vec<u8>> = keys
+                    .iter()
+                    .map(|key| match s.signature(key) {
+                        Some(sig) if needed > 0 => {
+                            needed -= 1;
+                            sig
+                        }
+                        _ => vec![],
+                    })
+                    .collect();
+                // The first key's OP_CHECKSIG consumes the top element.
+                witness.reverse();
+                (
+                    (needed == 0).then_some(witness),
+                    Some(vec![vec![]; keys.len()]),
+                )
+            }
+            Terminal::Alt(x) | Terminal::Swap(x) | Terminal::Check(x) => x.satisfactions(s),
+            Terminal::ZeroNotEqual(x) => x.satisfactions(s),
+            Terminal::DupIf(x) => (cat(&x.satisfactions(s).0, &one()), empty()),
+            Terminal::Verify(x) => (x.satisfactions(s).0, None),
+            Terminal::NonZero(x) => (x.satisfactions(s).0, empty()),
+        }
+    }
+}
+
+impl fmt::Display for Miniscript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ms = self;
+        let mut wrapped = false;
+        loop {
+            let (wrapper, inner) = match &ms.node {
+                Terminal::Check(x) if matches!(x.node, Terminal::PkK(_) | Terminal::PkH(_)) => {
+                    break
+                }
+                Terminal::Alt(x) => ('a', x),
+                Terminal::Swap(x) => ('s', x),
+                Terminal::Check(x) => ('c', x),
+                Terminal::DupIf(x) => ('d', x),
+                Terminal::Verify(x) => ('v', x),
+                Terminal::NonZero(x) => ('j', x),
+                Terminal::ZeroNotEqual(x) => ('n', x),
+                Terminal::AndV(x, y) if y.node == Terminal::True => ('t', x),
+                Terminal::OrI(x, z) if x.node == Terminal::False => ('l', z),
+                Terminal::OrI(x, z) if z.node == Terminal::False => ('u', x),
+                _ => break,
+            };
+            write!(f, "{wrapper}")?;
+            wrapped = true;
+            ms = inner;
+        }
+        if wrapped {
+            f.write_str(":")?;
+        }
+
+        match &ms.node {
+            Terminal::False => f.write_str("0"),
+            Terminal::True => f.write_str("1"),
+            Terminal::PkK(key) => write!(f, "pk_k({})", encode_hex(key)),
+            Terminal::PkH(key) => write!(f, "pk_h({})", encode_hex(key)),
+            Terminal::RawPkH(h) => write!(f, "expr_raw_pkh({})", encode_hex(h)),
+            Terminal::Older(n) => write!(f, "older({n})"),
+            Terminal::After(n) => write!(f, "after({n})"),
+            Terminal::Sha256(h) => write!(f, "sha256({})", encode_hex(h)),
+            Terminal::Hash256(h) => write!(f, "hash256({})", encode_hex(h)),
+            Terminal::Ripemd160(h) => write!(f, "ripemd160({})", encode_hex(h)),
+            Terminal::Hash160(h) => write!(f, "hash160({})", encode_hex(h)),
+            Terminal::AndOr(x, y, z) if z.node == Terminal::False => {
+                write!(f, "and_n({x},{y})")
+            }
+            Terminal::AndOr(x, y, z) => write!(f, "andor({x},{y},{z})"),
+            Terminal::AndV(x, y) => write!(f, "and_v({x},{y})"),
+            Terminal::AndB(x, y) => write!(f, "and_b({x},{y})"),
+            Terminal::OrB(x, z) => write!(f, "or_b({x},{z})"),
+            Terminal::OrC(x, z) => write!(f, "or_c({x},{z})"),
+            Terminal::OrD(x, z) => write!(f, "or_d({x},{z})"),
+            Terminal::OrI(x, z) => write!(f, "or_i({x},{z})"),
+            Terminal::Thresh(k, subs) => {
+                write!(f, "thresh({k}")?;
+                for sub in subs {
+                    write!(f, ",{sub}")?;
+                }
+                f.write_str(")")
+            }
+            Terminal::Multi(k, keys) | Terminal::MultiA(k, keys) => {
+                let name = match ms.node {
+                    Terminal::Multi(..) => "multi",
+                    _ => "multi_a",
+                };
+                write!(f, "{name}({k}")?;
+                for key in keys {
+                    write!(f, ",{}", encode_hex(key))?;
+                }
+                f.write_str(")")
+            }
+            Terminal::Check(x) => match &x.node {
+                Terminal::PkK(key) => write!(f, "pk({})", encode_hex(key)),
+                Terminal::PkH(key) => write!(f, "pkh({})", encode_hex(key)),
+                _ => unreachable!("other c: wrappers are written as prefixes"),
+            },
+            _ => unreachable!("wrappers are written as prefixes"),
+        }
+    }
+}
+
+/// What is available to satisfy a Miniscript.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Satisfier {
+    /// Signatures, including the hash type byte, by public key as it
+    /// appears in the script.
+    pub signatures: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// Known hash preimages, matched against hash fragments by hashing.
+    pub preimages: Vec<Vec<u8>>,
+    /// The spending transaction's nLockTime, for `after()`. `None`
+    /// satisfies no `after()`.
+    pub lock_time: Option<u32>,
+    /// The spending input's nSequence, for `older()` and `after()`.
+    /// `None` satisfies no `older()`.
+    pub sequence: Option<u32>,
+}
+
+impl Satisfier {
+    fn signature(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.signatures.get(key).cloned()
+    }
+
+    /// Satisfies a hash fragment with a matching 32-byte preimage;
+    /// dissatisfies it with 32 zero bytes.
+    fn preimage(&self, matches: impl Fn(&[u8]) -> bool) -> (Option<Witness>, Option<Witness>) {
+        let sat = self
+            .preimages
+            .iter()
+            .find(|p| p.len() == 32 && matches(p))
+            .map(|p| vec![p.clone()]);
+        (sat, Some(vec![vec![0; 32]]))
+    }
+}
+
+/// Witness stack elements, bottom first.
+type Witness = Vec<Vec<u8>>;
+
+/// Stacks `top` above `bottom`.
+fn cat(bottom: &Option<Witness>, top: &Option<Witness>) -> Option<Witness> {
+    match (bottom, top) {
+        (Some(bottom), Some(top)) => Some([&bottom[..], &top[..]].concat()),
+        _ => None,
+    }
+}
+
+/// Serialized size of a witness, counting one length byte per element.
+fn witness_size(witness: &Witness) -> usize {
+    witness.iter().map(|item| item.len() + 1).sum()
+}
+
+fn smallest(a: Option<Witness>, b: Option<Witness>) -> Option<Witness> {
+    match (a, b) {
+        (Some(a), Some(b)) if witness_size(&b) < witness_size(&a) => Some(b),
+        (Some(a), _) => Some(a),
+        (None, b) => b,
+    }
+}
+
+/// Satisfies exactly `k` subexpressions of a `thresh()`, preferring
+/// those whose satisfaction adds least over their dissatisfaction.
+fn thresh_satisfaction(
+    k: usize,
+    options: &[(Option<Witness>, Option<Witness>)],
+) -> Option<Witness> {
+    let mut order: Vec<usize> = (0..options.len())
+        .filter(|&i| options[i].0.is_some())
+        .collect();
+    // Subexpressions without a dissatisfaction must be satisfied.
+    order.sort_by_key(|&i| match &options[i] {
+        (Some(sat), Some(dsat)) => (1, witness_size(sat) as isize - witness_size(dsat) as isize),
+        _ => (0, 0),
+    });
+    order.truncate(k);
+    if order.len() < k {
+        return None;
+    }
+    // Subexpression 1 runs first, so its witness goes on top.
+    options
+        .iter()
+        .enumerate()
+        .rev()
+        .try_fold(Vec::new(), |acc, (i, (sat, dsat))| {
+            let part = if order.contains(&i) { sat } else { dsat };
+            cat(&Some(acc), part)
+        })
+}
+
+fn compile_pkh(out: &mut Vec<Token>, hash: &[u8; 20]) {
+    out.push(Token::Op(Opcode::OpDup));
+    out.push(Token::Op(Opcode::OpHash160));
+    out.push(Token::PushData(hash.to_vec()));
+    out.push(Token::Op(Opcode::OpEqualVerify));
+}
+
+fn compile_hash(out: &mut Vec<Token>, op: Opcode, hash: &[u8]) {
+    out.push(Token::Op(Opcode::OpSize));
+    out.push(num_token(32));
+    out.push(Token::Op(Opcode::OpEqualVerify));
+    out.push(Token::Op(op));
+    out.push(Token::PushData(hash.to_vec()));
+    out.push(Token::Op(Opcode::OpEqual));
+}
+
+/// A number as its shortest push: OP_0, OP_1–OP_16 or a script number.
+fn num_token(n: i64) -> Token {
+    match n {
+        0 => Token::Op(Opcode::Op0),
+        1..=16 => Token::Op(Opcode::from_byte(0x50 + n as u8).expect("OP_1 to OP_16")),
+        _ => Token::PushData(encode_num(n)),
+    }
+}
+
+/// Reads a number pushed by [`num_token`], in any encoding.
+fn token_num(token: &Token) -> Option<i64> {
+    match token {
+        Token::Op(op) => match op.to_byte() {
+            0x00 => Some(0),
+            byte @ 0x51..=0x60 => Some(i64::from(byte - 0x50)),
+            _ => None,
+        },
+        Token::PushData(data) => decode_num(data, 5).ok(),
+    }
+}
+
+fn valid_key(key: &[u8], ctx: Context) -> bool {
+    match ctx {
+        Context::Wsh => pubkey_encoding(key) == Some(PubkeyEncoding::Compressed),
+        Context::Tap => key.len() == 32,
+    }
+}
+
+/// Computes a fragment's type from its children's, per the Miniscript
+/// typing rules. `None` if the children's types are not accepted.
+fn type_check(node: &Terminal, ctx: Context) -> Option<Type> {
+    use BaseType::*;
+    let is = |ms: &Miniscript, base| ms.ty.base == base;
+    let ty = match node {
+        Terminal::False => Type::new(B, "zud"),
+        Terminal::True => Type::new(B, "zu"),
+        Terminal::PkK(_) => Type::new(K, "ondu"),
+        Terminal::PkH(_) | Terminal::RawPkH(_) => Type::new(K, "ndu"),
+        Terminal::Older(_) | Terminal::After(_) => Type::new(B, "z"),
+        Terminal::Sha256(_)
+        | Terminal::Hash256(_)
+        | Terminal::Ripemd160(_)
+        | Terminal::Hash160(_) => Type::new(B, "ondu"),
+        Terminal::AndOr(x, y, z) => {
+            let (x, y, z) = (&x.ty, &y.ty, &z.ty);
+            if x.base != B || !x.d || !x.u || y.base != z.base || y.base == W {
+                return None;
+            }
+            Type {
+                base: y.base,
+                z: x.z && y.z && z.z,
+                o: (x.z && y.o && z.o) || (x.o && y.z && z.z),
+                n: false,
+                d: z.d,
+                u: y.u && z.u,
+            }
+        }
+        Terminal::AndV(x, y) => {
+            if !is(x, V) || is(y, W) {
+                return None;
+            }
+            let (x, y) = (&x.ty, &y.ty);
+            Type {
+                base: y.base,
+                z: x.z && y.z,
+                o: (x.z && y.o) || (x.o && y.z),
+                n: x.n || (x.z && y.n),
+                d: false,
+                u: y.u,
+            }
+        }
+        Terminal::AndB(x, y) => {
+            if !is(x, B) || !is(y, W) {
+                return None;
+            }
+            let (x, y) = (&x.ty, &y.ty);
+            Type {
+                base: B,
+                z: x.z && y.z,
+                o: (x.z && y.o) || (x.o && y.z),
+                n: x.n || (x.z && y.n),
+                d: x.d && y.d,
+                u: true,
+            }
+        }
+        Terminal::OrB(x, z) => {
+            let (x, z) = (&x.ty, &z.ty);
+            if x.base != B || !x.d || z.base != W || !z.d {
+                return None;
+            }
+            Type {
+                base: B,
+                z: x.z && z.z,
+                o: (x.z && z.o) || (x.o && z.z),
+                n: false,
+                d: true,
+                u: true,
+            }
+        }
+        Terminal::OrC(x, z) | Terminal::OrD(x, z) => {
+            let or_d = matches!(node, Terminal::OrD(..));
+            let (x, z) = (&x.ty, &z.ty);
+            if x.base != B || !x.d || !x.u || z.base != if or_d { B } else { V } {
+                return None;
+            }
+            Type {
+                base: z.base,
+                z: x.z && z.z,
+                o: x.o && z.z,
+                n: false,
+                d: or_d && z.d,
+                u: or_d && z.u,
+            }
+        }
+        Terminal::OrI(x, z) => {
+            let (x, z) = (&x.ty, &z.ty);
+            if x.base != z.base || x.base == W {
+                return None;
+            }
+            Type {
+                base: x.base,
+                z: false,
+                o: x.z && z.z,
+                n: false,
+                d: x.d || z.d,
+                u: x.u && z.u,
+            }
+        }
+        Terminal::Thresh(k, subs) => {
+            let (first, rest) = subs.split_first()?;
+            let valid = *k >= 1
+                && *k <= subs.len()
+                && is(first, B)
+                && rest.iter().all(|sub| is(sub, W))
+                && subs.iter().all(|sub| sub.ty.d && sub.ty.u);
+            if !valid {
+                return None;
+            }
+            let zero_args = subs.iter().filter(|sub| sub.ty.z).count();
+            let one_args = subs.iter().filter(|sub| sub.ty.o).count();
+            Type {
+                base: B,
+                z: zero_args == subs.len(),
+                o: one_args == 1 && zero_args == subs.len() - 1,
+                n: false,
+                d: true,
+                u: true,
+            }
+        }
+        Terminal::Multi(k, keys) | Terminal::MultiA(k, keys) => {
+            if *k == 0 || *k > keys.len() {
+                return None;
+            }
+            match node {
+                Terminal::Multi(..) => Type::new(B, "ndu"),
+                _ => Type::new(B, "du"),
+            }
+        }
+        Terminal::Alt(x) | Terminal::Swap(x) => {
+            let swap = matches!(node, Terminal::Swap(_));
+            if !is(x, B) || (swap && !x.ty.o) {
+                return None;
+            }
+            Type {
+                d: x.ty.d,
+                u: x.ty.u,
+                ..Type::new(W, "")
+            }
+        }
+        Terminal::Check(x) => {
+            if !is(x, K) {
+                return None;
+            }
+            Type {
+                base: B,
+                u: true,
+                ..x.ty
+            }
+        }
+        Terminal::DupIf(x) => {
+            if !is(x, V) || !x.ty.z {
+                return None;
+            }
+            // OP_IF only enforces a unit argument in tapscript.
+            Type {
+                u: ctx == Context::Tap,
+                ..Type::new(B, "ond")
+            }
+        }
+        Terminal::Verify(x) => {
+            if !is(x, B) {
+                return None;
+            }
+            Type {
+                base: V,
+                z: x.ty.z,
+                o: x.ty.o,
+                n: x.ty.n,
+                d: false,
+                u: false,
+            }
+        }
+        Terminal::NonZero(x) => {
+            if !is(x, B) || !x.ty.n {
+                return None;
+            }
+            Type {
+                o: x.ty.o,
+                u: x.ty.u,
+                ..Type::new(B, "nd")
+            }
+        }
+        Terminal::ZeroNotEqual(x) => {
+            if !is(x, B) {
+                return None;
+            }
+            Type { u: true, ..x.ty }
+        }
+    };
+    Some(ty)
+}
+
+/// A parsed `name(args...)` expression.
+struct Tree<'a> {
+    name: &'a str,
+    index: usize,
+    args: Vec<Tree<'a>>,
+}
+
+fn parse_tree<'a>(s: &'a str, pos: &mut usize) -> Result<Tree<'a>, MiniscriptError> {
+    let bytes = s.as_bytes();
+    let index = *pos;
+    while *pos < bytes.len() && !matches!(bytes[*pos], b'(' | b')' | b',') {
+        *pos += 1;
+    }
+    let name = &s[index..*pos];
+    if name.is_empty() {
+        return Err(MiniscriptError::Syntax { index });
+    }
+
+    let mut args = Vec::new();
+    if bytes.get(*pos) == Some(&b'(') {
+        loop {
+            *pos += 1;
+            args.push(parse_tree(s, pos)?);
+            match bytes.get(*pos) {
+                Some(b',') => {}
+                Some(b')') => break,
+                _ => return Err(MiniscriptError::Syntax { index: *pos }),
+            }
+        }
+        *pos += 1;
+    }
+    Ok(Tree { name, index, args })
+}
+
+fn from_tree(tree: &Tree<'_>, ctx: Context) -> Result<Miniscript, MiniscriptError> {
+    let index = tree.index;
+    let (wrappers, name) = match tree.name.find(':') {
+        Some(colon) => (&tree.name[..colon], &tree.name[colon + 1..]),
+        None => ("", tree.name),
+    };
+    let make = |node| Miniscript::new(node, ctx).ok_or(MiniscriptError::TypeCheck { index });
+    let leaf = |node| Box::new(Miniscript::new(node, ctx).expect("leaves always type check"));
+
+    let mut ms = make(fragment(tree, name, ctx)?)?;
+    for wrapper in wrappers.bytes().rev() {
+        let x = Box::new(ms);
+        let node = match wrapper {
+            b'a' => Terminal::Alt(x),
+            b's' => Terminal::Swap(x),
+            b'c' => Terminal::Check(x),
+            b'd' => Terminal::DupIf(x),
+            b'v' => Terminal::Verify(x),
+            b'j' => Terminal::NonZero(x),
+            b'n' => Terminal::ZeroNotEqual(x),
+            b't' => Terminal::AndV(x, leaf(Terminal::True)),
+            b'l' => Terminal::OrI(leaf(Terminal::False), x),
+            b'u' => Terminal::OrI(x, leaf(Terminal::False)),
+            _ => return Err(MiniscriptError::UnknownFragment { index }),
+        };
+        ms = make(node)?;
+    }
+    Ok(ms)
+}
+
+/// Parses the fragment part of an expression, without its wrappers.
+fn fragment(tree: &Tree<'_>, name: &str, ctx: Context) -> Result<Terminal, MiniscriptError> {
+    let index = tree.index + tree.name.len() - name.len();
+    let args = &tree.args;
+    let arity = |n: usize| {
+        if args.len() == n {
+            Ok(())
+        } else {
+            Err(MiniscriptError::InvalidArgument { index })
+        }
+    };
+    let sub = |i: usize| from_tree(&args[i], ctx).map(Box::new);
+    let key = |arg: &Tree<'_>| {
+        leaf_hex(arg)
+            .filter(|key| valid_key(key, ctx))
+            .ok_or(MiniscriptError::InvalidArgument { index: arg.index })
+    };
+    let checked = |node| Box::new(Miniscript::new(node, ctx).expect("keys always type check"));
+
+    let node = match name {
+        "0" | "1" => {
+            arity(0)?;
+            if name == "0" {
+                Terminal::False
+            } else {
+                Terminal::True
+            }
+        }
+        "pk_k" | "pk_h" | "pk" | "pkh" => {
+            arity(1)?;
+            let key = key(&args[0])?;
+            match name {
+                "pk_k" => Terminal::PkK(key),
+                "pk_h" => Terminal::PkH(key),
+                "pk" => Terminal::Check(checked(Terminal::PkK(key))),
+                _ => Terminal::Check(checked(Terminal::PkH(key))),
+            }
+        }
+        "expr_raw_pkh" => {
+            arity(1)?;
+            Terminal::RawPkH(leaf_array(&args[0])?)
+        }
+        "older" | "after" => {
+            arity(1)?;
+            let n = leaf_number(&args[0])?;
+            if n == 0 || n >= MAX_TIMELOCK as usize {
+                return Err(MiniscriptError::InvalidArgument {
+                    index: args[0].index,
+                });
+            }
+            if name == "older" {
+                Terminal::Older(n as u32)
+            } else {
+                Terminal::After(n as u32)
+            }
+        }
+        "sha256" => {
+            arity(1)?;
+            Terminal::Sha256(leaf_array(&args[0])?)
+        }
+        "hash256" => {
+            arity(1)?;
+            Terminal::Hash256(leaf_array(&args[0])?)
+        }
+        "ripemd160" => {
+            arity(1)?;
+            Terminal::Ripemd160(leaf_array(&args[0])?)
+        }
+        "hash160" => {
+            arity(1)?;
+            Terminal::Hash160(leaf_array(&args[0])?)
+        }
+        "andor" => {
+            arity(3)?;
+            Terminal::AndOr(sub(0)?, sub(1)?, sub(2)?)
+        }
+        "and_n" => {
+            arity(2)?;
+            Terminal::AndOr(sub(0)?, sub(1)?, checked(Terminal::False))
+        }
+        "and_v" | "and_b" | "or_b" | "or_c" | "or_d" | "or_i" => {
+            arity(2)?;
+            let (x, y) = (sub(0)?, sub(1)?);
+            match name {
+                "and_v" => Terminal::AndV(x, y),
+                "and_b" => Terminal::AndB(x, y),
+                "or_b" => Terminal::OrB(x, y),
+                "or_c" => Terminal::OrC(x, y),
+                "or_d" => Terminal::OrD(x, y),
+                _ => Terminal::OrI(x, y),
+            }
+        }
+        "thresh" | "multi" | "multi_a" => {
+            let (first, rest) = args
+                .split_first()
+                .ok_or(MiniscriptError::InvalidArgument { index })?;
+            let k = leaf_number(first)?;
+            if k == 0 || k > rest.len() {
+                return Err(MiniscriptError::InvalidArgument { index: first.index });
+            }
+            match name {
+                "thresh" => {
+                    let subs = rest
+                        .iter()
+                        .map(|arg| from_tree(arg, ctx))
+                        .collect::<Result<_, _>>()?;
+                    Terminal::Thresh(k, subs)
+                }
+                _ => {
+                    let (allowed, max_keys) = match name {
+                        "multi" => (Context::Wsh, MAX_PUBKEYS_PER_MULTISIG),
+                        _ => (Context::Tap, MAX_PUBKEYS_PER_MULTI_A),
+                    };
+                    if ctx != allowed {
+                        return Err(MiniscriptError::InvalidContext { index });
+                    }
+                    if rest.len() > max_keys {
+                        return Err(MiniscriptError::InvalidArgument { index });
+                    }
+                    let keys = rest.iter().map(key).collect::<Result<_, _>>()?;
+                    if name == "multi" {
+                        Terminal::Multi(k, keys)
+                    } else {
+                        Terminal::MultiA(k, keys)
+                    }
+                }
+            }
+        }
+        _ => return Err(MiniscriptError::UnknownFragment { index }),
+    };
+    Ok(node)
+}
+
+fn leaf_hex(arg: &Tree<'_>) -> Option<Vec<u8>> {
+    if !arg.args.is_empty() {
+        return None;
+    }
+    decode_hex(arg.name).ok()
+}
+
+fn leaf_array<const N: usize>(arg: &Tree<'_>) -> Result<[u8; N], MiniscriptError> {
+    leaf_hex(arg)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(MiniscriptError::InvalidArgument { index: arg.index })
+}
+
+fn leaf_number(arg: &Tree<'_>) -> Result<usize, MiniscriptError> {
+    let invalid = MiniscriptError::InvalidArgument { index: arg.index };
+    if !arg.args.is_empty() || !arg.name.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid);
+    }
+    arg.name.parse().map_err(|_| invalid)
+}
+
+/// Decodes a script back to Miniscript, reading tokens from the end.
+///
+/// Every fragment is recognizable by its last opcode, except that
+/// `and_v(X,Y)` is just X followed by Y. A run of fragments between two
+/// opcodes that never end one (OP_IF, OP_ELSE, OP_SWAP, ...) is read as
+/// a right-nested chain of `and_v`s; a fragment's first operand, such as
+/// the X of `or_d(X,Z)` or the key of `c:`, is taken to be the shortest
+/// fragment that fits, leaving the rest to the enclosing chain.
+struct Lifter {
+    tokens: Vec<Token>,
+    ctx: Context,
+}
+
+type Lifted = Result<(Miniscript, usize), MiniscriptError>;
+
+impl Lifter {
+    fn make(&self, node: Terminal) -> Result<Miniscript, MiniscriptError> {
+        Miniscript::new(node, self.ctx).ok_or(MiniscriptError::NotMiniscript)
+    }
+
+    /// The token `back` positions before `end`.
+    fn at(&self, end: usize, back: usize) -> Option<&Token> {
+        end.checked_sub(back).and_then(|i| self.tokens.get(i))
+    }
+
+    fn op_at(&self, end: usize, back: usize) -> Option<Opcode> {
+        match self.at(end, back) {
+            Some(Token::Op(op)) => Some(*op),
+            _ => None,
+        }
+    }
+
+    fn num_at(&self, end: usize, back: usize) -> Result<i64, MiniscriptError> {
+        self.at(end, back)
+            .and_then(token_num)
+            .ok_or(MiniscriptError::NotMiniscript)
+    }
+
+    fn key_at(&self, end: usize, back: usize) -> Result<Vec<u8>, MiniscriptError> {
+        match self.at(end, back) {
+            Some(Token::PushData(key)) if valid_key(key, self.ctx) => Ok(key.clone()),
+            _ => Err(MiniscriptError::NotMiniscript),
+        }
+    }
+
+    fn push_at<const N: usize>(&self, end: usize, back: usize) -> Option<[u8; N]> {
+        match self.at(end, back) {
+            Some(Token::PushData(data)) => data.as_slice().try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Reads fragments ending at `end` back to the nearest opcode that
+    /// cannot end one, joining them with `and_v`.
+    fn chain(&mut self, end: usize) -> Lifted {
+        let (mut ms, mut start) = self.fragment(end)?;
+        while start > 0 {
+            use Opcode::*;
+            if let Some(OpIf | OpNotIf | OpElse | OpToAltStack | OpSwap | OpIfDup) =
+                self.op_at(start, 1)
+            {
+                break;
+            }
+            let (x, x_start) = self.fragment(start)?;
+            ms = self.make(Terminal::AndV(Box::new(x), Box::new(ms)))?;
+            start = x_start;
+        }
+        Ok((ms, start))
+    }
+
+    /// Reads a W expression ending at `end`: `a:X` or `s:X`.
+    fn wrapped(&mut self, end: usize) -> Lifted {
+        if self.op_at(end, 1) == Some(Opcode::OpFromAltStack) {
+            return self.fragment(end);
+        }
+        let (x, start) = self.chain(end)?;
+        if self.op_at(start, 1) != Some(Opcode::OpSwap) {
+            return Err(MiniscriptError::NotMiniscript);
+        }
+        Ok((self.make(Terminal::Swap(Box::new(x)))?, start - 1))
+    }
+
+    /// Reads the single fragment, with its wrappers, ending at `end`.
+    fn fragment(&mut self, end: usize) -> Lifted {
+        use Opcode::*;
+        let op = match self.at(end, 1) {
+            Some(Token::Op(op)) => *op,
+            Some(Token::PushData(_)) => {
+                let key = self.key_at(end, 1)?;
+                return Ok((self.make(Terminal::PkK(key))?, end - 1));
+            }
+            None => return Err(MiniscriptError::NotMiniscript),
+        };
+        let wrap = |lifter: &mut Self, wrapper: fn(Box<Miniscript>) -> Terminal| -> Lifted {
+            let (x, start) = lifter.fragment(end - 1)?;
+            Ok((lifter.make(wrapper(Box::new(x)))?, start))
+        };
+
+        let (node, start) = match op {
+            Op0 => (Terminal::False, end - 1),
+            Op1 => (Terminal::True, end - 1),
+            OpEqualVerify => {
+                let pkh =
+                    self.op_at(end, 4) == Some(OpDup) && self.op_at(end, 3) == Some(OpHash160);
+                match self.push_at(end, 2) {
+                    Some(hash) if pkh => (Terminal::RawPkH(hash), end - 4),
+                    _ => return self.verify(end, OpEqual),
+                }
+            }
+            OpCheckSigVerify => return self.verify(end, OpCheckSig),
+            OpCheckMultiSigVerify => return self.verify(end, OpCheckMultiSig),
+            OpNumEqualVerify => return self.verify(end, OpNumEqual),
+            OpVerify => return wrap(self, Terminal::Verify),
+            OpCheckSig => return wrap(self, Terminal::Check),
+            Op0NotEqual => return wrap(self, Terminal::ZeroNotEqual),
+            OpCheckSequenceVerify | OpCheckLockTimeVerify => {
+                let n = u32::try_from(self.num_at(end, 2)?)
+                    .map_err(|_| MiniscriptError::NotMiniscript)?;
+                if op == OpCheckSequenceVerify {
+                    (Terminal::Older(n), end - 2)
+                } else {
+                    (Terminal::After(n), end - 2)
+                }
+            }
+            OpEqual => match self.hash(end) {
+                Some(node) => (node, end - 6),
+                None => return self.thresh(end),
+            },
+            OpBoolAnd | OpBoolOr => {
+                let (y, y_start) = self.wrapped(end - 1)?;
+                let (x, start) = self.fragment(y_start)?;
+                let (x, y) = (Box::new(x), Box::new(y));
+                match op {
+                    OpBoolAnd => (Terminal::AndB(x, y), start),
+                    _ => (Terminal::OrB(x, y), start),
+                }
+            }
+            OpCheckMultiSig => {
+                let n = usize::try_from(self.num_at(end, 2)?)
+                    .map_err(|_| MiniscriptError::NotMiniscript)?;
+                if n > MAX_PUBKEYS_PER_MULTISIG || self.ctx != Context::Wsh {
+                    return Err(MiniscriptError::NotMiniscript);
+                }
+                let keys = (0..n)
+                    .rev()
+                    .map(|i| self.key_at(end, 3 + i))
+                    .collect::<Result<_, _>>()?;
+                let k = usize::try_from(self.num_at(end, n + 3)?)
+                    .map_err(|_| MiniscriptError::NotMiniscript)?;
+                (Terminal::Multi(k, keys), end - n - 3)
+            }
+            OpNumEqual => {
+                if self.ctx != Context::Tap {
+                    return Err(MiniscriptError::NotMiniscript);
+                }
+                let k = usize::try_from(self.num_at(end, 2)?)
+                    .map_err(|_| MiniscriptError::NotMiniscript)?;
+                let mut pos = end - 2;
+                let mut keys = Vec::new();
+                loop {
+                    let op = self.op_at(pos, 1);
+                    if !matches!(op, Some(OpCheckSig | OpCheckSigAdd)) {
+                        return Err(MiniscriptError::NotMiniscript);
+                    }
+                    keys.push(self.key_at(pos, 2)?);
+                    pos -= 2;
+                    if op == Some(OpCheckSig) {
+                        break;
+                    }
+                }
+                keys.reverse();
+                (Terminal::MultiA(k, keys), pos)
+            }
+            OpFromAltStack => {
+                let (x, start) = self.chain(end - 1)?;
+                if self.op_at(start, 1) != Some(OpToAltStack) {
+                    return Err(MiniscriptError::NotMiniscript);
+                }
+                (Terminal::Alt(Box::new(x)), start - 1)
+            }
+            OpEndIf => return self.end_if(end),
+            _ => return Err(MiniscriptError::NotMiniscript),
+        };
+        Ok((self.make(node)?, start))
+    }
+
+    /// Reads `v:X` where X's last opcode was merged into a VERIFY form,
+    /// by putting the plain opcode back and reading X.
+    fn verify(&mut self, end: usize, plain: Opcode) -> Lifted {
+        self.tokens[end - 1] = Token::Op(plain);
+        let (x, start) = self.fragment(end)?;
+        Ok((self.make(Terminal::Verify(Box::new(x)))?, start))
+    }
+
+    /// `OP_SIZE <32> OP_EQUALVERIFY <hash op> <H> OP_EQUAL`
+    fn hash(&self, end: usize) -> Option<Terminal> {
+        let prefix = self.op_at(end, 6) == Some(Opcode::OpSize)
+            && self.at(end, 5).and_then(token_num) == Some(32)
+            && self.op_at(end, 4) == Some(Opcode::OpEqualVerify);
+        if !prefix {
+            return None;
+        }
+        match self.op_at(end, 3)? {
+            Opcode::OpSha256 => self.push_at(end, 2).map(Terminal::Sha256),
+            Opcode::OpHash256 => self.push_at(end, 2).map(Terminal::Hash256),
+            Opcode::OpRipemd160 => self.push_at(end, 2).map(Terminal::Ripemd160),
+            Opcode::OpHash160 => self.push_at(end, 2).map(Terminal::Hash160),
+            _ => None,
+        }
+    }
+
+    /// `X1 X2 OP_ADD ... Xn OP_ADD <k> OP_EQUAL`
+    fn thresh(&mut self, end: usize) -> Lifted {
+        let k =
+            usize::try_from(self.num_at(end, 2)?).map_err(|_| MiniscriptError::NotMiniscript)?;
+        let mut pos = end - 2;
+        let mut subs = Vec::new();
+        while self.op_at(pos, 1) == Some(Opcode::OpAdd) {
+            let (w, start) = self.wrapped(pos - 1)?;
+            subs.push(w);
+            pos = start;
+        }
+        let (first, start) = self.fragment(pos)?;
+        subs.push(first);
+        subs.reverse();
+        Ok((self.make(Terminal::Thresh(k, subs))?, start))
+    }
+
+    /// The fragments ending in OP_ENDIF: `andor`, `or_c`, `or_d`, `or_i`,
+    /// `d:` and `j:`.
+    fn end_if(&mut self, end: usize) -> Lifted {
+        use Opcode::*;
+        let (last, start) = self.chain(end - 1)?;
+        let last = Box::new(last);
+        let (node, start) = match self.op_at(start, 1) {
+            Some(OpElse) => {
+                let (first, first_start) = self.chain(start - 1)?;
+                let first = Box::new(first);
+                match self.op_at(first_start, 1) {
+                    Some(OpIf) => (Terminal::OrI(first, last), first_start - 1),
+                    Some(OpNotIf) => {
+                        let (x, x_start) = self.fragment(first_start - 1)?;
+                        (Terminal::AndOr(Box::new(x), last, first), x_start)
+                    }
+                    _ => return Err(MiniscriptError::NotMiniscript),
+                }
+            }
+            Some(OpIf) if self.op_at(start, 2) == Some(OpDup) => (Terminal::DupIf(last), start - 2),
+            Some(OpIf)
+                if self.op_at(start, 2) == Some(Op0NotEqual)
+                    && self.op_at(start, 3) == Some(OpSize) =>
+            {
+                (Terminal::NonZero(last), start - 3)
+            }
+            Some(OpNotIf) if self.op_at(start, 2) == Some(OpIfDup) => {
+                let (x, x_start) = self.fragment(start - 2)?;
+                (Terminal::OrD(Box::new(x), last), x_start)
+            }
+            Some(OpNotIf) => {
+                let (x, x_start) = self.fragment(start - 1)?;
+                (Terminal::OrC(Box::new(x), last), x_start)
+            }
+            _ => return Err(MiniscriptError::NotMiniscript),
+        };
+        Ok((self.make(node)?, start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+
+    fn key(byte: u8) -> String {
+        encode_hex(&[&[0x02][..], &[byte; 32]].concat())
+    }
+
+    #[test]
+    fn types() {
+        let ty = |s: &str| {
+            Miniscript::parse(s, Context::Wsh)
+                .map(|ms| ms.ty().to_string())
+                .unwrap()
+        };
+        assert_eq!(ty(&format!("pk({})", key(1))), "Bondu");
+        assert_eq!(ty(&format!("pkh({})", key(1))), "Bndu");
+        assert_eq!(ty("older(144)"), "Bz");
+        assert_eq!(ty(&format!("or_d(pk({}),older(144))", key(1))), "Bo");
+        assert_eq!(
+            ty(&format!("and_v(v:pk({}),pk({}))", key(1), key(2))),
+            "Bnu"
+        );
+        assert_eq!(
+            ty(&format!("thresh(2,pk({}),s:pk({}))", key(1), key(2))),
+            "Bdu"
+        );
+    }
+
+    #[test]
+    fn wrappers_display_as_prefixes() {
+        let s = format!("and_v(vc:pk_k({}),tv:older(10))", key(1));
+        let ms = Miniscript::parse(&s, Context::Wsh).unwrap();
+        assert_eq!(
+            ms.to_string(),
+            format!("and_v(v:pk({}),tv:older(10))", key(1))
+        );
+        assert_eq!(
+            Miniscript::parse(&format!("and_n(pk({}),1)", key(1)), Context::Wsh)
+                .unwrap()
+                .to_string(),
+            format!("and_n(pk({}),1)", key(1))
+        );
+    }
+
+    #[test]
+    fn compiles_verify_forms() {
+        let ms = Miniscript::parse(&format!("and_v(v:pk({}),1)", key(1)), Context::Wsh).unwrap();
+        let tokens = ms.to_tokens();
+        assert_eq!(tokens[1], Token::Op(Opcode::OpCheckSigVerify));
+        assert_eq!(tokens.len(), 3);
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(num_token(16), Token::Op(Opcode::Op16));
+        assert_eq!(num_token(17), Token::PushData(vec![17]));
+        assert_eq!(token_num(&num_token(144)), Some(144));
+        assert_eq!(token_num(&Token::Op(Opcode::OpDup)), None);
+    }
+}
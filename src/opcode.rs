@@ -43,6 +43,9 @@ pub enum Opcode {
     OpOver,
     OpSwap,
     OpTuck,
+    OpToAltStack,
+    OpFromAltStack,
+    OpIfDup,
 
     // Splice
     OpSize,
@@ -54,6 +57,27 @@ pub enum Opcode {
     // Logic
     OpNot,
 
+    // Arithmetic
+    Op1Add,
+    Op1Sub,
+    OpNegate,
+    OpAbs,
+    Op0NotEqual,
+    OpAdd,
+    OpSub,
+    OpBoolAnd,
+    OpBoolOr,
+    OpNumEqual,
+    OpNumEqualVerify,
+    OpNumNotEqual,
+    OpLessThan,
+    OpGreaterThan,
+    OpLessThanOrEqual,
+    OpGreaterThanOrEqual,
+    OpMin,
+    OpMax,
+    OpWithin,
+
     // Crypto
     OpRipemd160,
     OpSha256,
@@ -61,6 +85,30 @@ pub enum Opcode {
     OpHash256,
     OpCheckSig,
     OpCheckSigVerify,
+    OpCheckMultiSig,
+    OpCheckMultiSigVerify,
+    OpCheckSigAdd,
+
+    // Locktime
+    OpCheckLockTimeVerify,
+    OpCheckSequenceVerify,
+
+    // Disabled since 2010; OP_SUCCESSx in tapscript
+    OpCat,
+    OpSubStr,
+    OpLeft,
+    OpRight,
+    OpInvert,
+    OpAnd,
+    OpOr,
+    OpXor,
+    Op2Mul,
+    Op2Div,
+    OpMul,
+    OpDiv,
+    OpMod,
+    OpLShift,
+    OpRShift,
 }
 
 impl Opcode {
@@ -96,8 +144,11 @@ impl Opcode {
             0x68 => Some(Opcode::OpEndIf),
             0x69 => Some(Opcode::OpVerify),
             0x6a => Some(Opcode::OpReturn),
+            0x6b => Some(Opcode::OpToAltStack),
+            0x6c => Some(Opcode::OpFromAltStack),
             0x6d => Some(Opcode::Op2Drop),
             0x6e => Some(Opcode::Op2Dup),
+            0x73 => Some(Opcode::OpIfDup),
             0x74 => Some(Opcode::OpDepth),
             0x75 => Some(Opcode::OpDrop),
             0x76 => Some(Opcode::OpDup),
@@ -109,12 +160,51 @@ impl Opcode {
             0x87 => Some(Opcode::OpEqual),
             0x88 => Some(Opcode::OpEqualVerify),
             0x91 => Some(Opcode::OpNot),
+            0x8b => Some(Opcode::Op1Add),
+            0x8c => Some(Opcode::Op1Sub),
+            0x8f => Some(Opcode::OpNegate),
+            0x90 => Some(Opcode::OpAbs),
+            0x92 => Some(Opcode::Op0NotEqual),
+            0x93 => Some(Opcode::OpAdd),
+            0x94 => Some(Opcode::OpSub),
+            0x9a => Some(Opcode::OpBoolAnd),
+            0x9b => Some(Opcode::OpBoolOr),
+            0x9c => Some(Opcode::OpNumEqual),
+            0x9d => Some(Opcode::OpNumEqualVerify),
+            0x9e => Some(Opcode::OpNumNotEqual),
+            0x9f => Some(Opcode::OpLessThan),
+            0xa0 => Some(Opcode::OpGreaterThan),
+            0xa1 => Some(Opcode::OpLessThanOrEqual),
+            0xa2 => Some(Opcode::OpGreaterThanOrEqual),
+            0xa3 => Some(Opcode::OpMin),
+            0xa4 => Some(Opcode::OpMax),
+            0xa5 => Some(Opcode::OpWithin),
             0xa6 => Some(Opcode::OpRipemd160),
             0xa8 => Some(Opcode::OpSha256),
             0xa9 => Some(Opcode::OpHash160),
             0xaa => Some(Opcode::OpHash256),
             0xac => Some(Opcode::OpCheckSig),
             0xad => Some(Opcode::OpCheckSigVerify),
+            0xae => Some(Opcode::OpCheckMultiSig),
+            0xaf => Some(Opcode::OpCheckMultiSigVerify),
+            0xb1 => Some(Opcode::OpCheckLockTimeVerify),
+            0xb2 => Some(Opcode::OpCheckSequenceVerify),
+            0xba => Some(Opcode::OpCheckSigAdd),
+            0x7e => Some(Opcode::OpCat),
+            0x7f => Some(Opcode::OpSubStr),
+            0x80 => Some(Opcode::OpLeft),
+            0x81 => Some(Opcode::OpRight),
+            0x83 => Some(Opcode::OpInvert),
+            0x84 => Some(Opcode::OpAnd),
+            0x85 => Some(Opcode::OpOr),
+            0x86 => Some(Opcode::OpXor),
+            0x8d => Some(Opcode::Op2Mul),
+            0x8e => Some(Opcode::Op2Div),
+            0x95 => Some(Opcode::OpMul),
+            0x96 => Some(Opcode::OpDiv),
+            0x97 => Some(Opcode::OpMod),
+            0x98 => Some(Opcode::OpLShift),
+            0x99 => Some(Opcode::OpRShift),
             _ => None,
         }
     }
@@ -147,8 +237,11 @@ impl Opcode {
             Opcode::OpEndIf => 0x68,
             Opcode::OpVerify => 0x69,
             Opcode::OpReturn => 0x6a,
+            Opcode::OpToAltStack => 0x6b,
+            Opcode::OpFromAltStack => 0x6c,
             Opcode::Op2Drop => 0x6d,
             Opcode::Op2Dup => 0x6e,
+            Opcode::OpIfDup => 0x73,
             Opcode::OpDepth => 0x74,
             Opcode::OpDrop => 0x75,
             Opcode::OpDup => 0x76,
@@ -160,19 +253,57 @@ impl Opcode {
             Opcode::OpEqual => 0x87,
             Opcode::OpEqualVerify => 0x88,
             Opcode::OpNot => 0x91,
+            Opcode::Op1Add => 0x8b,
+            Opcode::Op1Sub => 0x8c,
+            Opcode::OpNegate => 0x8f,
+            Opcode::OpAbs => 0x90,
+            Opcode::Op0NotEqual => 0x92,
+            Opcode::OpAdd => 0x93,
+            Opcode::OpSub => 0x94,
+            Opcode::OpBoolAnd => 0x9a,
+            Opcode::OpBoolOr => 0x9b,
+            Opcode::OpNumEqual => 0x9c,
+            Opcode::OpNumEqualVerify => 0x9d,
+            Opcode::OpNumNotEqual => 0x9e,
+            Opcode::OpLessThan => 0x9f,
+            Opcode::OpGreaterThan => 0xa0,
+            Opcode::OpLessThanOrEqual => 0xa1,
+            Opcode::OpGreaterThanOrEqual => 0xa2,
+            Opcode::OpMin => 0xa3,
+            Opcode::OpMax => 0xa4,
+            Opcode::OpWithin => 0xa5,
             Opcode::OpRipemd160 => 0xa6,
             Opcode::OpSha256 => 0xa8,
             Opcode::OpHash160 => 0xa9,
             Opcode::OpHash256 => 0xaa,
             Opcode::OpCheckSig => 0xac,
             Opcode::OpCheckSigVerify => 0xad,
+            Opcode::OpCheckMultiSig => 0xae,
+            Opcode::OpCheckMultiSigVerify => 0xaf,
+            Opcode::OpCheckLockTimeVerify => 0xb1,
+            Opcode::OpCheckSequenceVerify => 0xb2,
+            Opcode::OpCheckSigAdd => 0xba,
+            Opcode::OpCat => 0x7e,
+            Opcode::OpSubStr => 0x7f,
+            Opcode::OpLeft => 0x80,
+            Opcode::OpRight => 0x81,
+            Opcode::OpInvert => 0x83,
+            Opcode::OpAnd => 0x84,
+            Opcode::OpOr => 0x85,
+            Opcode::OpXor => 0x86,
+            Opcode::Op2Mul => 0x8d,
+            Opcode::Op2Div => 0x8e,
+            Opcode::OpMul => 0x95,
+            Opcode::OpDiv => 0x96,
+            Opcode::OpMod => 0x97,
+            Opcode::OpLShift => 0x98,
+            Opcode::OpRShift => 0x99,
         }
     }
-}
 
-impl std::fmt::Display for Opcode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = match self {
+    /// Returns the canonical `OP_*` name of this opcode.
+    pub fn name(self) -> &'static str {
+        match self {
             Opcode::Op0 => "OP_0",
             Opcode::Op1Negate => "OP_1NEGATE",
             Opcode::Op1 => "OP_1",
@@ -198,8 +329,11 @@ impl std::fmt::Display for Opcode {
             Opcode::OpEndIf => "OP_ENDIF",
             Opcode::OpVerify => "OP_VERIFY",
             Opcode::OpReturn => "OP_RETURN",
+            Opcode::OpToAltStack => "OP_TOALTSTACK",
+            Opcode::OpFromAltStack => "OP_FROMALTSTACK",
             Opcode::Op2Drop => "OP_2DROP",
             Opcode::Op2Dup => "OP_2DUP",
+            Opcode::OpIfDup => "OP_IFDUP",
             Opcode::OpDepth => "OP_DEPTH",
             Opcode::OpDrop => "OP_DROP",
             Opcode::OpDup => "OP_DUP",
@@ -211,20 +345,134 @@ impl std::fmt::Display for Opcode {
             Opcode::OpEqual => "OP_EQUAL",
             Opcode::OpEqualVerify => "OP_EQUALVERIFY",
             Opcode::OpNot => "OP_NOT",
+            Opcode::Op1Add => "OP_1ADD",
+            Opcode::Op1Sub => "OP_1SUB",
+            Opcode::OpNegate => "OP_NEGATE",
+            Opcode::OpAbs => "OP_ABS",
+            Opcode::Op0NotEqual => "OP_0NOTEQUAL",
+            Opcode::OpAdd => "OP_ADD",
+            Opcode::OpSub => "OP_SUB",
+            Opcode::OpBoolAnd => "OP_BOOLAND",
+            Opcode::OpBoolOr => "OP_BOOLOR",
+            Opcode::OpNumEqual => "OP_NUMEQUAL",
+            Opcode::OpNumEqualVerify => "OP_NUMEQUALVERIFY",
+            Opcode::OpNumNotEqual => "OP_NUMNOTEQUAL",
+            Opcode::OpLessThan => "OP_LESSTHAN",
+            Opcode::OpGreaterThan => "OP_GREATERTHAN",
+            Opcode::OpLessThanOrEqual => "OP_LESSTHANOREQUAL",
+            Opcode::OpGreaterThanOrEqual => "OP_GREATERTHANOREQUAL",
+            Opcode::OpMin => "OP_MIN",
+            Opcode::OpMax => "OP_MAX",
+            Opcode::OpWithin => "OP_WITHIN",
             Opcode::OpRipemd160 => "OP_RIPEMD160",
             Opcode::OpSha256 => "OP_SHA256",
             Opcode::OpHash160 => "OP_HASH160",
             Opcode::OpHash256 => "OP_HASH256",
             Opcode::OpCheckSig => "OP_CHECKSIG",
             Opcode::OpCheckSigVerify => "OP_CHECKSIGVERIFY",
-        };
-        write!(f, "{name}")
+            Opcode::OpCheckMultiSig => "OP_CHECKMULTISIG",
+            Opcode::OpCheckMultiSigVerify => "OP_CHECKMULTISIGVERIFY",
+            Opcode::OpCheckLockTimeVerify => "OP_CHECKLOCKTIMEVERIFY",
+            Opcode::OpCheckSequenceVerify => "OP_CHECKSEQUENCEVERIFY",
+            Opcode::OpCat => "OP_CAT",
+            Opcode::OpSubStr => "OP_SUBSTR",
+            Opcode::OpLeft => "OP_LEFT",
+            Opcode::OpRight => "OP_RIGHT",
+            Opcode::OpInvert => "OP_INVERT",
+            Opcode::OpAnd => "OP_AND",
+            Opcode::OpOr => "OP_OR",
+            Opcode::OpXor => "OP_XOR",
+            Opcode::Op2Mul => "OP_2MUL",
+            Opcode::Op2Div => "OP_2DIV",
+            Opcode::OpMul => "OP_MUL",
+            Opcode::OpDiv => "OP_DIV",
+            Opcode::OpMod => "OP_MOD",
+            Opcode::OpLShift => "OP_LSHIFT",
+            Opcode::OpRShift => "OP_RSHIFT",
+            Opcode::OpCheckSigAdd => "OP_CHECKSIGADD",
+        }
+    }
+
+    /// Returns `true` for the splice, bitwise and arithmetic opcodes
+    /// disabled in 2010 (OP_CAT, OP_MUL, ...).
+    ///
+    /// A script containing one fails even if the opcode sits in an
+    /// unexecuted branch, except in tapscript, where these bytes are
+    /// OP_SUCCESSx and make the script succeed (BIP342).
+    pub fn is_disabled(self) -> bool {
+        matches!(
+            self,
+            Opcode::OpCat
+                | Opcode::OpSubStr
+                | Opcode::OpLeft
+                | Opcode::OpRight
+                | Opcode::OpInvert
+                | Opcode::OpAnd
+                | Opcode::OpOr
+                | Opcode::OpXor
+                | Opcode::Op2Mul
+                | Opcode::Op2Div
+                | Opcode::OpMul
+                | Opcode::OpDiv
+                | Opcode::OpMod
+                | Opcode::OpLShift
+                | Opcode::OpRShift
+        )
+    }
+
+    /// Look up an opcode by its `OP_*` name.
+    ///
+    /// The `OP_` prefix is optional and matching is case-sensitive, so both
+    /// `"OP_DUP"` and `"DUP"` resolve to [`Opcode::OpDup`]. The aliases
+    /// `OP_FALSE` and `OP_TRUE` are accepted for `OP_0` and `OP_1`, and
+    /// `OP_NOP2` and `OP_NOP3` for the timelock opcodes that replaced them.
+    pub fn from_name(name: &str) -> Option<Opcode> {
+        let bare = name.strip_prefix("OP_").unwrap_or(name);
+        match bare {
+            "FALSE" => return Some(Opcode::Op0),
+            "TRUE" => return Some(Opcode::Op1),
+            "NOP2" => return Some(Opcode::OpCheckLockTimeVerify),
+            "NOP3" => return Some(Opcode::OpCheckSequenceVerify),
+            _ => {}
+        }
+        (0..=u8::MAX)
+            .filter_map(Opcode::from_byte)
+            .find(|op| &op.name()[3..] == bare)
+    }
+}
+
+impl core::fmt::Display for Opcode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Serializes as the canonical `OP_*` name, e.g. `"OP_CHECKSIG"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Opcode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// Accepts any name understood by [`Opcode::from_name`].
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Opcode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use alloc::format;
+        use alloc::string::String;
+        use serde::de::Error;
+
+        let name = String::deserialize(deserializer)?;
+        Opcode::from_name(&name)
+            .ok_or_else(|| D::Error::custom(format!("unknown opcode name: {name}")))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec::Vec;
 
     #[test]
     fn roundtrip_all_opcodes() {
@@ -254,6 +502,9 @@ mod tests {
             Opcode::OpEndIf,
             Opcode::OpVerify,
             Opcode::OpReturn,
+            Opcode::OpToAltStack,
+            Opcode::OpFromAltStack,
+            Opcode::OpIfDup,
             Opcode::Op2Drop,
             Opcode::Op2Dup,
             Opcode::OpDepth,
@@ -267,12 +518,51 @@ mod tests {
             Opcode::OpEqual,
             Opcode::OpEqualVerify,
             Opcode::OpNot,
+            Opcode::Op1Add,
+            Opcode::Op1Sub,
+            Opcode::OpNegate,
+            Opcode::OpAbs,
+            Opcode::Op0NotEqual,
+            Opcode::OpAdd,
+            Opcode::OpSub,
+            Opcode::OpBoolAnd,
+            Opcode::OpBoolOr,
+            Opcode::OpNumEqual,
+            Opcode::OpNumEqualVerify,
+            Opcode::OpNumNotEqual,
+            Opcode::OpLessThan,
+            Opcode::OpGreaterThan,
+            Opcode::OpLessThanOrEqual,
+            Opcode::OpGreaterThanOrEqual,
+            Opcode::OpMin,
+            Opcode::OpMax,
+            Opcode::OpWithin,
             Opcode::OpRipemd160,
             Opcode::OpSha256,
             Opcode::OpHash160,
             Opcode::OpHash256,
             Opcode::OpCheckSig,
             Opcode::OpCheckSigVerify,
+            Opcode::OpCheckMultiSig,
+            Opcode::OpCheckMultiSigVerify,
+            Opcode::OpCheckSigAdd,
+            Opcode::OpCheckLockTimeVerify,
+            Opcode::OpCheckSequenceVerify,
+            Opcode::OpCat,
+            Opcode::OpSubStr,
+            Opcode::OpLeft,
+            Opcode::OpRight,
+            Opcode::OpInvert,
+            Opcode::OpAnd,
+            Opcode::OpOr,
+            Opcode::OpXor,
+            Opcode::Op2Mul,
+            Opcode::Op2Div,
+            Opcode::OpMul,
+            Opcode::OpDiv,
+            Opcode::OpMod,
+            Opcode::OpLShift,
+            Opcode::OpRShift,
         ];
 
         for opcode in &opcodes {
@@ -304,6 +594,20 @@ mod tests {
         assert_eq!(Opcode::from_byte(0xff), None);
     }
 
+    #[test]
+    fn disabled_opcodes() {
+        let disabled: Vec<u8> = (0..=u8::MAX)
+            .filter(|&b| Opcode::from_byte(b).map_or(false, Opcode::is_disabled))
+            .collect();
+        let expected: Vec<u8> = (0x7e..=0x81)
+            .chain(0x83..=0x86)
+            .chain([0x8d, 0x8e])
+            .chain(0x95..=0x99)
+            .collect();
+        assert_eq!(disabled, expected);
+        assert!(!Opcode::OpAdd.is_disabled());
+    }
+
     #[test]
     fn display_formatting() {
         assert_eq!(format!("{}", Opcode::OpDup), "OP_DUP");
@@ -311,4 +615,23 @@ mod tests {
         assert_eq!(format!("{}", Opcode::Op0), "OP_0");
         assert_eq!(format!("{}", Opcode::OpCheckSig), "OP_CHECKSIG");
     }
+
+    #[test]
+    fn from_name_lookup() {
+        assert_eq!(Opcode::from_name("OP_DUP"), Some(Opcode::OpDup));
+        assert_eq!(Opcode::from_name("HASH160"), Some(Opcode::OpHash160));
+        assert_eq!(Opcode::from_name("OP_16"), Some(Opcode::Op16));
+        assert_eq!(Opcode::from_name("OP_FALSE"), Some(Opcode::Op0));
+        assert_eq!(Opcode::from_name("OP_TRUE"), Some(Opcode::Op1));
+        assert_eq!(Opcode::from_name("op_dup"), None);
+        assert_eq!(Opcode::from_name("OP_ADD"), Some(Opcode::OpAdd));
+        assert_eq!(
+            Opcode::from_name("NOP2"),
+            Some(Opcode::OpCheckLockTimeVerify)
+        );
+        assert_eq!(Opcode::from_name("OP_SUB"), Some(Opcode::OpSub));
+        assert_eq!(Opcode::from_name("OP_ROT"), None);
+        assert_eq!(Opcode::from_name("OP_CAT"), Some(Opcode::OpCat));
+        assert_eq!(Opcode::from_name(""), None);
+    }
 }
@@ -0,0 +1,447 @@
+use alloc::vec::Vec;
+
+use crate::classify::{classify, is_push_only, last_push, witness_program, OutputType};
+use crate::sigops::p2sh_sigop_count;
+use crate::transaction::{compact_size_len, Transaction, TxOut};
+
+/// Highest transaction version relayed (v3 is BIP431 TRUC).
+pub const MAX_STANDARD_VERSION: i32 = 3;
+
+/// Largest relayed transaction weight.
+pub const MAX_STANDARD_TX_WEIGHT: usize = 400_000;
+
+/// Largest relayed scriptSig: enough for a 15-of-15 P2SH multisig with
+/// compressed keys.
+pub const MAX_STANDARD_SCRIPTSIG_SIZE: usize = 1_650;
+
+/// Largest relayed OP_RETURN output script, including the OP_RETURN.
+pub const MAX_OP_RETURN_RELAY: usize = 83;
+
+/// Most keys in a relayed bare multisig output.
+pub const MAX_BARE_MULTISIG_KEYS: u8 = 3;
+
+/// Most sigops in a relayed P2SH redeem script.
+pub const MAX_P2SH_SIGOPS: usize = 15;
+
+/// Largest relayed P2WSH witness script.
+pub const MAX_STANDARD_P2WSH_SCRIPT_SIZE: usize = 3_600;
+
+/// Most P2WSH witness items, not counting the witness script.
+pub const MAX_STANDARD_P2WSH_STACK_ITEMS: usize = 100;
+
+/// Largest P2WSH or tapscript witness item, not counting the script.
+pub const MAX_STANDARD_WITNESS_ITEM_SIZE: usize = 80;
+
+/// Default dust relay fee rate, in satoshis per 1000 virtual bytes.
+pub const DUST_RELAY_FEE: u64 = 3_000;
+
+/// Scripts larger than this can never be spent (consensus).
+const MAX_SCRIPT_SIZE: usize = 10_000;
+
+const ANNEX_TAG: u8 = 0x50;
+
+/// Pay-to-Anchor: `OP_1 <0x4e73>`, a keyless output anyone can spend to
+/// bump the fee of its transaction.
+const PAY_TO_ANCHOR: [u8; 4] = [0x51, 0x02, 0x4e, 0x73];
+const TAPSCRIPT_LEAF_VERSION: u8 = 0xc0;
+
+/// A reason a transaction would not be relayed by Bitcoin Core with
+/// default settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// Version outside 1 to [`MAX_STANDARD_VERSION`].
+    Version(i32),
+    /// Weight above [`MAX_STANDARD_TX_WEIGHT`].
+    TxWeight(usize),
+    /// scriptSig larger than [`MAX_STANDARD_SCRIPTSIG_SIZE`].
+    ScriptSigSize {
+        /// Index of the input.
+        input: usize,
+        /// scriptSig size in bytes.
+        size: usize,
+    },
+    /// scriptSig containing a non-push opcode.
+    ScriptSigNotPushOnly {
+        /// Index of the input.
+        input: usize,
+    },
+    /// Output script matching no standard template.
+    NonStandardOutput {
+        /// Index of the output.
+        output: usize,
+    },
+    /// Bare multisig output with more than [`MAX_BARE_MULTISIG_KEYS`] keys,
+    /// which Bitcoin Core treats as any other non-standard output.
+    BareMultisig {
+        /// Index of the output.
+        output: usize,
+        /// Number of keys in the script.
+        keys: u8,
+    },
+    /// OP_RETURN output script larger than [`MAX_OP_RETURN_RELAY`].
+    OpReturnSize {
+        /// Index of the output.
+        output: usize,
+        /// Script size in bytes.
+        size: usize,
+    },
+    /// More than one OP_RETURN output.
+    MultipleOpReturn,
+    /// Output worth less than it would cost to spend.
+    Dust {
+        /// Index of the output.
+        output: usize,
+        /// Output value in satoshis.
+        value: u64,
+        /// The output's [`dust_threshold`].
+        threshold: u64,
+    },
+    /// Fewer or more prevouts than inputs were supplied.
+    PrevoutCount {
+        /// Number of inputs.
+        expected: usize,
+        /// Number of prevouts supplied.
+        actual: usize,
+    },
+    /// Input spending a non-standard or future-version witness output.
+    NonStandardInput {
+        /// Index of the input.
+        input: usize,
+    },
+    /// P2SH redeem script with more than [`MAX_P2SH_SIGOPS`] sigops.
+    P2shSigops {
+        /// Index of the input.
+        input: usize,
+        /// Accurate sigop count of the redeem script.
+        sigops: usize,
+    },
+    /// Witness on an input whose output is not a witness program.
+    UnexpectedWitness {
+        /// Index of the input.
+        input: usize,
+    },
+    /// P2WSH witness script larger than
+    /// [`MAX_STANDARD_P2WSH_SCRIPT_SIZE`].
+    WitnessScriptSize {
+        /// Index of the input.
+        input: usize,
+        /// Witness script size in bytes.
+        size: usize,
+    },
+    /// P2WSH witness with more than [`MAX_STANDARD_P2WSH_STACK_ITEMS`]
+    /// items below the witness script.
+    WitnessStackItems {
+        /// Index of the input.
+        input: usize,
+        /// Number of items below the witness script.
+        count: usize,
+    },
+    /// P2WSH or tapscript witness item larger than
+    /// [`MAX_STANDARD_WITNESS_ITEM_SIZE`].
+    WitnessItemSize {
+        /// Index of the input.
+        input: usize,
+        /// Index of the item within the witness.
+        item: usize,
+        /// Item size in bytes.
+        size: usize,
+    },
+    /// Taproot witness with an annex, which is reserved for future use.
+    Annex {
+        /// Index of the input.
+        input: usize,
+    },
+    /// Witness on an input spending a Pay-to-Anchor output, which must be
+    /// spent with an empty witness.
+    AnchorWitness {
+        /// Index of the input.
+        input: usize,
+    },
+}
+
+impl PolicyViolation {
+    /// Bitcoin Core's reject reason for this violation, as reported by
+    /// `testmempoolaccept`.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            PolicyViolation::Version(_) => "version",
+            PolicyViolation::TxWeight(_) => "tx-size",
+            PolicyViolation::ScriptSigSize { .. } => "scriptsig-size",
+            PolicyViolation::ScriptSigNotPushOnly { .. } => "scriptsig-not-pushonly",
+            PolicyViolation::NonStandardOutput { .. }
+            | PolicyViolation::BareMultisig { .. }
+            | PolicyViolation::OpReturnSize { .. } => "scriptpubkey",
+            PolicyViolation::MultipleOpReturn => "multi-op-return",
+            PolicyViolation::Dust { .. } => "dust",
+            PolicyViolation::PrevoutCount { .. } => "bad-txns-inputs-missingorspent",
+            PolicyViolation::NonStandardInput { .. } | PolicyViolation::P2shSigops { .. } => {
+                "bad-txns-nonstandard-inputs"
+            }
+            PolicyViolation::UnexpectedWitness { .. }
+            | PolicyViolation::WitnessScriptSize { .. }
+            | PolicyViolation::WitnessStackItems { .. }
+            | PolicyViolation::WitnessItemSize { .. }
+            | PolicyViolation::Annex { .. }
+            | PolicyViolation::AnchorWitness { .. } => "bad-witness-nonstandard",
+        }
+    }
+}
+
+impl core::fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PolicyViolation::Version(version) => write!(f, "non-standard version {version}"),
+            PolicyViolation::TxWeight(weight) => write!(f, "weight {weight} exceeds limit"),
+            PolicyViolation::ScriptSigSize { input, size } => {
+                write!(f, "input {input}: scriptSig of {size} bytes exceeds limit")
+            }
+            PolicyViolation::ScriptSigNotPushOnly { input } => {
+                write!(f, "input {input}: scriptSig is not push-only")
+            }
+            PolicyViolation::NonStandardOutput { output } => {
+                write!(f, "output {output}: non-standard script")
+            }
+            PolicyViolation::BareMultisig { output, keys } => {
+                write!(f, "output {output}: bare multisig with {keys} keys")
+            }
+            PolicyViolation::OpReturnSize { output, size } => {
+                write!(
+                    f,
+                    "output {output}: OP_RETURN script of {size} bytes exceeds limit"
+                )
+            }
+            PolicyViolation::MultipleOpReturn => write!(f, "more than one OP_RETURN output"),
+            PolicyViolation::Dust {
+                output,
+                value,
+                threshold,
+            } => write!(
+                f,
+                "output {output}: {value} sat is below the dust threshold of {threshold}"
+            ),
+            PolicyViolation::PrevoutCount { expected, actual } => {
+                write!(f, "expected {expected} prevouts, got {actual}")
+            }
+            PolicyViolation::NonStandardInput { input } => {
+                write!(f, "input {input}: spends a non-standard output")
+            }
+            PolicyViolation::P2shSigops { input, sigops } => {
+                write!(f, "input {input}: redeem script has {sigops} sigops")
+            }
+            PolicyViolation::UnexpectedWitness { input } => {
+                write!(f, "input {input}: witness on a non-witness output")
+            }
+            PolicyViolation::WitnessScriptSize { input, size } => {
+                write!(
+                    f,
+                    "input {input}: witness script of {size} bytes exceeds limit"
+                )
+            }
+            PolicyViolation::WitnessStackItems { input, count } => {
+                write!(f, "input {input}: {count} witness items exceed limit")
+            }
+            PolicyViolation::WitnessItemSize { input, item, size } => {
+                write!(
+                    f,
+                    "input {input}: witness item {item} of {size} bytes exceeds limit"
+                )
+            }
+            PolicyViolation::Annex { input } => write!(f, "input {input}: taproot annex"),
+            PolicyViolation::AnchorWitness { input } => {
+                write!(f, "input {input}: witness on a pay-to-anchor output")
+            }
+        }
+    }
+}
+
+/// The smallest value an output can hold without being dust at
+/// [`DUST_RELAY_FEE`]: the fee to create and later spend it. Zero for
+/// unspendable outputs.
+///
+/// ```rust
+/// use mini_bitcoin_script::builder;
+/// use mini_bitcoin_script::policy::dust_threshold;
+///
+/// assert_eq!(dust_threshold(&builder::p2pkh(&[0; 20])), 546);
+/// assert_eq!(dust_threshold(&builder::p2wpkh(&[0; 20])), 294);
+/// assert_eq!(dust_threshold(&builder::p2tr(&[0; 32])), 330);
+/// ```
+pub fn dust_threshold(script_pubkey: &[u8]) -> u64 {
+    if script_pubkey.first() == Some(&0x6a) || script_pubkey.len() > MAX_SCRIPT_SIZE {
+        return 0;
+    }
+    // Serialized output: value, length prefix, script.
+    let output_size = 8 + compact_size_len(script_pubkey.len()) + script_pubkey.len();
+    // Input spending it: outpoint, sequence, and a typical P2PKH scriptSig
+    // (107 bytes plus prefix), or its witness equivalent at a quarter.
+    let input_size = if witness_program(script_pubkey).is_some() {
+        32 + 4 + 1 + 107 / 4 + 4
+    } else {
+        32 + 4 + 1 + 107 + 4
+    };
+    (output_size + input_size) as u64 * DUST_RELAY_FEE / 1000
+}
+
+/// Checks the rules Bitcoin Core applies to a transaction on its own
+/// (`IsStandardTx`): version, weight, scriptSig size and push-only-ness,
+/// output templates, OP_RETURN size and count, bare multisig keys, and
+/// dust.
+pub fn check_transaction(tx: &Transaction) -> Vec<PolicyViolation> {
+    let mut violations = Vec::new();
+
+    if !(1..=MAX_STANDARD_VERSION).contains(&tx.version) {
+        violations.push(PolicyViolation::Version(tx.version));
+    }
+    let weight = tx.weight();
+    if weight > MAX_STANDARD_TX_WEIGHT {
+        violations.push(PolicyViolation::TxWeight(weight));
+    }
+
+    for (input, txin) in tx.inputs.iter().enumerate() {
+        let size = txin.script_sig.len();
+        if size > MAX_STANDARD_SCRIPTSIG_SIZE {
+            violations.push(PolicyViolation::ScriptSigSize { input, size });
+        }
+        if !is_push_only(&txin.script_sig) {
+            violations.push(PolicyViolation::ScriptSigNotPushOnly { input });
+        }
+    }
+
+    let mut op_returns = 0;
+    for (output, txout) in tx.outputs.iter().enumerate() {
+        match classify(&txout.script_pubkey) {
+            OutputType::NonStandard => {
+                violations.push(PolicyViolation::NonStandardOutput { output });
+                continue;
+            }
+            OutputType::NullData => {
+                op_returns += 1;
+                let size = txout.script_pubkey.len();
+                if size > MAX_OP_RETURN_RELAY {
+                    violations.push(PolicyViolation::OpReturnSize { output, size });
+                }
+                continue;
+            }
+            OutputType::Multisig { keys, .. } if keys > MAX_BARE_MULTISIG_KEYS => {
+                violations.push(PolicyViolation::BareMultisig { output, keys });
+            }
+            _ => {}
+        }
+        let threshold = dust_threshold(&txout.script_pubkey);
+        if txout.value < threshold {
+            violations.push(PolicyViolation::Dust {
+                output,
+                value: txout.value,
+                threshold,
+            });
+        }
+    }
+    if op_returns > 1 {
+        violations.push(PolicyViolation::MultipleOpReturn);
+    }
+
+    violations
+}
+
+/// Checks the rules that depend on the outputs being spent
+/// (`AreInputsStandard` and `IsWitnessStandard`): spent output types,
+/// P2SH redeem script sigops, P2WSH and tapscript witness limits, and
+/// empty Pay-to-Anchor (`OP_1 <0x4e73>`) witnesses.
+///
+/// `prevouts` are the spent outputs, in input order.
+pub fn check_inputs(tx: &Transaction, prevouts: &[TxOut]) -> Vec<PolicyViolation> {
+    if prevouts.len() != tx.inputs.len() {
+        return alloc::vec![PolicyViolation::PrevoutCount {
+            expected: tx.inputs.len(),
+            actual: prevouts.len(),
+        }];
+    }
+
+    let mut violations = Vec::new();
+    for (input, (txin, prevout)) in tx.inputs.iter().zip(prevouts).enumerate() {
+        let script_pubkey = &prevout.script_pubkey;
+        if script_pubkey[..] == PAY_TO_ANCHOR {
+            if !txin.witness.is_empty() {
+                violations.push(PolicyViolation::AnchorWitness { input });
+            }
+            continue;
+        }
+        let output_type = classify(script_pubkey);
+        match output_type {
+            OutputType::NonStandard | OutputType::WitnessUnknown { .. } => {
+                violations.push(PolicyViolation::NonStandardInput { input });
+            }
+            OutputType::P2sh => {
+                let sigops = p2sh_sigop_count(script_pubkey, &txin.script_sig);
+                if sigops > MAX_P2SH_SIGOPS {
+                    violations.push(PolicyViolation::P2shSigops { input, sigops });
+                }
+            }
+            _ => {}
+        }
+
+        let witness = &txin.witness;
+        if witness.is_empty() {
+            continue;
+        }
+        let nested = match output_type {
+            OutputType::P2sh => last_push(&txin.script_sig),
+            _ => None,
+        };
+        let program = witness_program(nested.unwrap_or(script_pubkey));
+        match program {
+            Some((0, program)) if program.len() == 32 => {
+                let (script, items) = match witness.split_last() {
+                    Some(split) => split,
+                    None => continue,
+                };
+                if script.len() > MAX_STANDARD_P2WSH_SCRIPT_SIZE {
+                    violations.push(PolicyViolation::WitnessScriptSize {
+                        input,
+                        size: script.len(),
+                    });
+                }
+                if items.len() > MAX_STANDARD_P2WSH_STACK_ITEMS {
+                    violations.push(PolicyViolation::WitnessStackItems {
+                        input,
+                        count: items.len(),
+                    });
+                }
+                check_item_sizes(input, items, &mut violations);
+            }
+            Some((1, program)) if program.len() == 32 && nested.is_none() => {
+                check_taproot_witness(input, witness, &mut violations);
+            }
+            Some(_) => {}
+            None => violations.push(PolicyViolation::UnexpectedWitness { input }),
+        }
+    }
+    violations
+}
+
+fn check_taproot_witness(input: usize, witness: &[Vec<u8>], violations: &mut Vec<PolicyViolation>) {
+    let mut stack = witness;
+    if stack.len() >= 2 && stack[stack.len() - 1].first() == Some(&ANNEX_TAG) {
+        violations.push(PolicyViolation::Annex { input });
+        stack = &stack[..stack.len() - 1];
+    }
+    // Script path: items, script, control block.
+    if stack.len() >= 2 {
+        let control = &stack[stack.len() - 1];
+        if control.first().map(|b| b & 0xfe) == Some(TAPSCRIPT_LEAF_VERSION) {
+            check_item_sizes(input, &stack[..stack.len() - 2], violations);
+        }
+    }
+}
+
+fn check_item_sizes(input: usize, items: &[Vec<u8>], violations: &mut Vec<PolicyViolation>) {
+    for (item, data) in items.iter().enumerate() {
+        if data.len() > MAX_STANDARD_WITNESS_ITEM_SIZE {
+            violations.push(PolicyViolation::WitnessItemSize {
+                input,
+                item,
+                size: data.len(),
+            });
+        }
+    }
+}
@@ -0,0 +1,1251 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::base64::{decode_base64, encode_base64};
+use crate::bip32::{KeyOrigin, Xpub};
+use crate::builder::{self, ScriptBuilder};
+use crate::classify::{classify, is_p2sh, multisig_keys, witness_program, OutputType};
+use crate::flags::VerifyFlags;
+use crate::hash;
+use crate::signature::pubkey_encoding;
+use crate::transaction::{
+    write_bytes, write_compact_size, DecodeError, OutPoint, Reader, Transaction, TxIn, TxOut,
+};
+use crate::verify::{verify_inputs, VerifyError, VerifyOpts};
+
+/// `psbt` followed by 0xff: the first bytes of every PSBT.
+const MAGIC: &[u8; 5] = b"psbt\xff";
+
+const GLOBAL_UNSIGNED_TX: u64 = 0x00;
+const GLOBAL_XPUB: u64 = 0x01;
+const GLOBAL_TX_VERSION: u64 = 0x02;
+const GLOBAL_FALLBACK_LOCKTIME: u64 = 0x03;
+const GLOBAL_INPUT_COUNT: u64 = 0x04;
+const GLOBAL_OUTPUT_COUNT: u64 = 0x05;
+const GLOBAL_TX_MODIFIABLE: u64 = 0x06;
+const GLOBAL_VERSION: u64 = 0xfb;
+
+const IN_NON_WITNESS_UTXO: u64 = 0x00;
+const IN_WITNESS_UTXO: u64 = 0x01;
+const IN_PARTIAL_SIG: u64 = 0x02;
+const IN_SIGHASH_TYPE: u64 = 0x03;
+const IN_REDEEM_SCRIPT: u64 = 0x04;
+const IN_WITNESS_SCRIPT: u64 = 0x05;
+const IN_BIP32_DERIVATION: u64 = 0x06;
+const IN_FINAL_SCRIPTSIG: u64 = 0x07;
+const IN_FINAL_SCRIPTWITNESS: u64 = 0x08;
+const IN_PREVIOUS_TXID: u64 = 0x0e;
+const IN_OUTPUT_INDEX: u64 = 0x0f;
+const IN_SEQUENCE: u64 = 0x10;
+const IN_REQUIRED_TIME_LOCKTIME: u64 = 0x11;
+const IN_REQUIRED_HEIGHT_LOCKTIME: u64 = 0x12;
+const IN_TAP_KEY_SIG: u64 = 0x13;
+
+const OUT_REDEEM_SCRIPT: u64 = 0x00;
+const OUT_WITNESS_SCRIPT: u64 = 0x01;
+const OUT_BIP32_DERIVATION: u64 = 0x02;
+const OUT_AMOUNT: u64 = 0x03;
+const OUT_SCRIPT: u64 = 0x04;
+
+/// Fields that only version 2 PSBTs may contain (BIP370), per map.
+const GLOBAL_V2_ONLY: &[u64] = &[
+    GLOBAL_TX_VERSION,
+    GLOBAL_FALLBACK_LOCKTIME,
+    GLOBAL_INPUT_COUNT,
+    GLOBAL_OUTPUT_COUNT,
+    GLOBAL_TX_MODIFIABLE,
+];
+const IN_V2_ONLY: &[u64] = &[
+    IN_PREVIOUS_TXID,
+    IN_OUTPUT_INDEX,
+    IN_SEQUENCE,
+    IN_REQUIRED_TIME_LOCKTIME,
+    IN_REQUIRED_HEIGHT_LOCKTIME,
+];
+const OUT_V2_ONLY: &[u64] = &[OUT_AMOUNT, OUT_SCRIPT];
+
+/// Locktimes below this are block heights; at or above it, Unix times.
+const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+/// Why a PSBT could not be parsed, finalized or extracted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PsbtError {
+    /// The data does not start with the `psbt\xff` magic bytes.
+    InvalidMagic,
+
+    /// The text is not valid base64.
+    Base64,
+
+    /// The key-value maps are truncated or malformed.
+    Decode(DecodeError),
+
+    /// A key appears twice in the same map.
+    DuplicateKey,
+
+    /// A field's key data or value is malformed.
+    InvalidField {
+        /// The field's key type.
+        key_type: u64,
+    },
+
+    /// A field this PSBT version requires is missing.
+    MissingField {
+        /// The field's key type.
+        key_type: u64,
+    },
+
+    /// A field is not allowed in this PSBT version.
+    UnexpectedField {
+        /// The field's key type.
+        key_type: u64,
+    },
+
+    /// The PSBT version is neither 0 nor 2.
+    UnsupportedVersion(u32),
+
+    /// The unsigned transaction of a version 0 PSBT has a scriptSig or a
+    /// witness.
+    UnsignedTxNotEmpty,
+
+    /// Some inputs need a height locktime and others a time locktime.
+    LockTimeConflict,
+
+    /// The input index is out of range.
+    InputIndex {
+        /// The requested index.
+        index: usize,
+    },
+
+    /// The input has neither a witness nor a non-witness UTXO.
+    MissingUtxo {
+        /// The input's index.
+        index: usize,
+    },
+
+    /// The input's non-witness UTXO is not the transaction it spends, or
+    /// lacks the spent output.
+    UtxoMismatch {
+        /// The input's index.
+        index: usize,
+    },
+
+    /// The input's output type is not one the finalizer handles, or the
+    /// signatures or scripts it needs are missing.
+    CannotFinalize {
+        /// The input's index.
+        index: usize,
+    },
+
+    /// The input has no final scriptSig or witness.
+    NotFinalized {
+        /// The input's index.
+        index: usize,
+    },
+
+    /// The extracted transaction's input failed verification.
+    Verify {
+        /// The input's index.
+        index: usize,
+        /// Why it failed.
+        error: VerifyError,
+    },
+}
+
+impl From<DecodeError> for PsbtError {
+    fn from(err: DecodeError) -> Self {
+        PsbtError::Decode(err)
+    }
+}
+
+impl fmt::Display for PsbtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PsbtError::InvalidMagic => write!(f, "missing psbt magic bytes"),
+            PsbtError::Base64 => write!(f, "invalid base64"),
+            PsbtError::Decode(err) => write!(f, "malformed psbt: {err}"),
+            PsbtError::DuplicateKey => write!(f, "duplicate key"),
+            PsbtError::InvalidField { key_type } => {
+                write!(f, "invalid field of type 0x{key_type:02x}")
+            }
+            PsbtError::MissingField { key_type } => {
+                write!(f, "missing field of type 0x{key_type:02x}")
+            }
+            PsbtError::UnexpectedField { key_type } => {
+                write!(
+                    f,
+                    "field of type 0x{key_type:02x} not allowed in this version"
+                )
+            }
+            PsbtError::UnsupportedVersion(version) => {
+                write!(f, "unsupported psbt version {version}")
+            }
+            PsbtError::UnsignedTxNotEmpty => {
+                write!(f, "unsigned transaction has scriptSigs or witnesses")
+            }
+            PsbtError::LockTimeConflict => {
+                write!(f, "inputs require both height and time locktimes")
+            }
+            PsbtError::InputIndex { index } => write!(f, "input {index} out of range"),
+            PsbtError::MissingUtxo { index } => write!(f, "input {index} has no utxo"),
+            PsbtError::UtxoMismatch { index } => {
+                write!(
+                    f,
+                    "input {index} non-witness utxo does not match its outpoint"
+                )
+            }
+            PsbtError::CannotFinalize { index } => write!(f, "cannot finalize input {index}"),
+            PsbtError::NotFinalized { index } => write!(f, "input {index} is not finalized"),
+            PsbtError::Verify { index, error } => {
+                write!(f, "input {index} failed verification: {error}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PsbtError {}
+
+/// A partially signed Bitcoin transaction: BIP174 version 0 or BIP370
+/// version 2.
+///
+/// Both versions are held in the same form. The unsigned transaction is
+/// kept as per-input and per-output fields, as version 2 stores it, and
+/// rebuilt by [`Psbt::unsigned_tx`]; version 0 PSBTs are written with it
+/// as a whole transaction again.
+///
+/// `Display` and `FromStr` use base64, as PSBTs are usually passed
+/// around; [`Psbt::serialize`] and [`Psbt::deserialize`] use the binary
+/// format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Psbt {
+    /// PSBT version: 0 or 2.
+    pub version: u32,
+    /// Version of the transaction.
+    pub tx_version: i32,
+    /// Locktime used when no input requires one; `None` means 0. For a
+    /// version 0 PSBT, the unsigned transaction's locktime.
+    pub fallback_lock_time: Option<u32>,
+    /// Version 2 flags for which inputs and outputs may still be added
+    /// or changed.
+    pub tx_modifiable: Option<u8>,
+    /// Extended public keys used by the inputs and outputs, with their
+    /// origins.
+    pub xpubs: Vec<(Xpub, KeyOrigin)>,
+    /// Global fields this crate does not interpret, by full key.
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// Inputs, in transaction order.
+    pub inputs: Vec<PsbtInput>,
+    /// Outputs, in transaction order.
+    pub outputs: Vec<PsbtOutput>,
+}
+
+/// A PSBT input: the outpoint it spends and what signers and finalizers
+/// know about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsbtInput {
+    /// The output being spent.
+    pub previous_output: OutPoint,
+    /// Sequence number.
+    pub sequence: u32,
+    /// Version 2 only: the smallest Unix-time locktime this input needs.
+    pub required_time_lock_time: Option<u32>,
+    /// Version 2 only: the smallest block-height locktime this input
+    /// needs.
+    pub required_height_lock_time: Option<u32>,
+    /// The whole transaction holding the spent output.
+    pub non_witness_utxo: Option<Transaction>,
+    /// The spent output, for segwit inputs.
+    pub witness_utxo: Option<TxOut>,
+    /// ECDSA signatures by public key.
+    pub partial_sigs: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// The sighash type signers should use.
+    pub sighash_type: Option<u32>,
+    /// The P2SH redeem script.
+    pub redeem_script: Option<Vec<u8>>,
+    /// The P2WSH witness script.
+    pub witness_script: Option<Vec<u8>>,
+    /// Key origins by public key.
+    pub bip32_derivation: BTreeMap<Vec<u8>, KeyOrigin>,
+    /// The finished scriptSig.
+    pub final_script_sig: Option<Vec<u8>>,
+    /// The finished witness.
+    pub final_script_witness: Option<Vec<Vec<u8>>>,
+    /// The Schnorr signature for a Taproot key path spend.
+    pub tap_key_sig: Option<Vec<u8>>,
+    /// Fields this crate does not interpret, by full key.
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl PsbtInput {
+    /// An input spending `previous_output` with a final sequence number
+    /// and nothing else known.
+    pub fn new(previous_output: OutPoint) -> Self {
+        PsbtInput {
+            previous_output,
+            sequence: 0xffff_ffff,
+            required_time_lock_time: None,
+            required_height_lock_time: None,
+            non_witness_utxo: None,
+            witness_utxo: None,
+            partial_sigs: BTreeMap::new(),
+            sighash_type: None,
+            redeem_script: None,
+            witness_script: None,
+            bip32_derivation: BTreeMap::new(),
+            final_script_sig: None,
+            final_script_witness: None,
+            tap_key_sig: None,
+            unknown: BTreeMap::new(),
+        }
+    }
+
+    /// Whether a final scriptSig or witness has been set.
+    pub fn is_finalized(&self) -> bool {
+        self.final_script_sig.is_some() || self.final_script_witness.is_some()
+    }
+
+    /// The scriptSig and witness spending `script_pubkey` with the
+    /// signatures and scripts at hand.
+    fn final_scripts(&self, script_pubkey: &[u8]) -> Option<(Vec<u8>, Vec<Vec<u8>>)> {
+        if witness_program(script_pubkey).is_some() {
+            return Some((Vec::new(), self.witness_for(script_pubkey)?));
+        }
+        if !is_p2sh(script_pubkey) {
+            return Some((push_all(&self.satisfy(script_pubkey)?), Vec::new()));
+        }
+
+        let redeem_script = self.redeem_script.as_deref()?;
+        if builder::p2sh(&hash::hash160(redeem_script)) != script_pubkey {
+            return None;
+        }
+        let push_redeem = ScriptBuilder::new().push_slice(redeem_script).into_bytes();
+        if witness_program(redeem_script).is_some() {
+            return Some((push_redeem, self.witness_for(redeem_script)?));
+        }
+        let mut script_sig = push_all(&self.satisfy(redeem_script)?);
+        script_sig.extend(push_redeem);
+        Some((script_sig, Vec::new()))
+    }
+
+    /// The witness spending a P2WPKH, P2WSH or Taproot key path output.
+    fn witness_for(&self, script_pubkey: &[u8]) -> Option<Vec<Vec<u8>>> {
+        match witness_program(script_pubkey)? {
+            (0, program) if program.len() == 20 => {
+                let mut key_hash = [0; 20];
+                key_hash.copy_from_slice(program);
+                self.satisfy(&builder::p2pkh(&key_hash))
+            }
+            (0, program) if program.len() == 32 => {
+                let witness_script = self.witness_script.as_ref()?;
+                if hash::sha256(witness_script) != program {
+                    return None;
+                }
+                let mut items = self.satisfy(witness_script)?;
+                items.push(witness_script.clone());
+                Some(items)
+            }
+            (1, program) if program.len() == 32 => Some(vec![self.tap_key_sig.clone()?]),
+            _ => None,
+        }
+    }
+
+    /// The stack items satisfying a P2PK, P2PKH or multisig script with
+    /// the partial signatures.
+    fn satisfy(&self, script: &[u8]) -> Option<Vec<Vec<u8>>> {
+        match classify(script) {
+            OutputType::P2pk => {
+                let key = &script[1..script.len() - 1];
+                Some(vec![self.partial_sigs.get(key)?.clone()])
+            }
+            OutputType::P2pkh => {
+                let (key, sig) = self
+                    .partial_sigs
+                    .iter()
+                    .find(|(key, _)| hash::hash160(key)[..] == script[3..23])?;
+                Some(vec![sig.clone(), key.clone()])
+            }
+            OutputType::Multisig { .. } => {
+                let (required, keys) = multisig_keys(script)?;
+                // The dummy item OP_CHECKMULTISIG pops, then signatures
+                // in key order.
+                let mut items = vec![Vec::new()];
+                items.extend(
+                    keys.iter()
+                        .filter_map(|key| self.partial_sigs.get(*key))
+                        .take(required)
+                        .cloned(),
+                );
+                (items.len() == required + 1).then_some(items)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A PSBT output: the output itself and what is known about its script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsbtOutput {
+    /// Amount in satoshis.
+    pub amount: u64,
+    /// Locking script.
+    pub script_pubkey: Vec<u8>,
+    /// The P2SH redeem script.
+    pub redeem_script: Option<Vec<u8>>,
+    /// The P2WSH witness script.
+    pub witness_script: Option<Vec<u8>>,
+    /// Key origins by public key.
+    pub bip32_derivation: BTreeMap<Vec<u8>, KeyOrigin>,
+    /// Fields this crate does not interpret, by full key.
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl PsbtOutput {
+    /// An output paying `amount` to `script_pubkey` with nothing else
+    /// known.
+    pub fn new(amount: u64, script_pubkey: Vec<u8>) -> Self {
+        PsbtOutput {
+            amount,
+            script_pubkey,
+            redeem_script: None,
+            witness_script: None,
+            bip32_derivation: BTreeMap::new(),
+            unknown: BTreeMap::new(),
+        }
+    }
+}
+
+impl Psbt {
+    /// Creates a version 0 PSBT for `tx`, with nothing known about its
+    /// inputs yet.
+    ///
+    /// # Errors
+    ///
+    /// [`PsbtError::UnsignedTxNotEmpty`] if any input has a scriptSig or
+    /// witness.
+    pub fn from_unsigned_tx(tx: &Transaction) -> Result<Psbt, PsbtError> {
+        if tx
+            .inputs
+            .iter()
+            .any(|input| !input.script_sig.is_empty() || !input.witness.is_empty())
+        {
+            return Err(PsbtError::UnsignedTxNotEmpty);
+        }
+        Ok(Psbt {
+            version: 0,
+            tx_version: tx.version,
+            fallback_lock_time: Some(tx.lock_time),
+            tx_modifiable: None,
+            xpubs: Vec::new(),
+            unknown: BTreeMap::new(),
+            inputs: tx
+                .inputs
+                .iter()
+                .map(|input| PsbtInput {
+                    sequence: input.sequence,
+                    ..PsbtInput::new(input.previous_output)
+                })
+                .collect(),
+            outputs: tx
+                .outputs
+                .iter()
+                .map(|output| PsbtOutput::new(output.value, output.script_pubkey.clone()))
+                .collect(),
+        })
+    }
+
+    /// The transaction being signed, with empty scriptSigs and witnesses.
+    ///
+    /// The locktime follows BIP370: the fallback locktime if no input
+    /// requires one, otherwise the largest required height (preferred when
+    /// every constrained input accepts one) or time.
+    ///
+    /// # Errors
+    ///
+    /// [`PsbtError::LockTimeConflict`] if some inputs accept only a
+    /// height and others only a time.
+    pub fn unsigned_tx(&self) -> Result<Transaction, PsbtError> {
+        Ok(self.transaction(self.lock_time()?))
+    }
+
+    fn transaction(&self, lock_time: u32) -> Transaction {
+        Transaction {
+            version: self.tx_version,
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| TxIn {
+                    sequence: input.sequence,
+                    ..TxIn::new(input.previous_output)
+                })
+                .collect(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|output| TxOut {
+                    value: output.amount,
+                    script_pubkey: output.script_pubkey.clone(),
+                })
+                .collect(),
+            lock_time,
+        }
+    }
+
+    fn lock_time(&self) -> Result<u32, PsbtError> {
+        let constrained: Vec<&PsbtInput> = self
+            .inputs
+            .iter()
+            .filter(|input| {
+                input.required_height_lock_time.is_some() || input.required_time_lock_time.is_some()
+            })
+            .collect();
+        if constrained.is_empty() {
+            return Ok(self.fallback_lock_time.unwrap_or(0));
+        }
+        let max = |field: fn(&PsbtInput) -> Option<u32>| {
+            constrained
+                .iter()
+                .map(|input| field(input))
+                .collect::<Option<Vec<u32>>>()
+                .map(|values| values.into_iter().max().unwrap_or(0))
+        };
+        max(|input| input.required_height_lock_time)
+            .or_else(|| max(|input| input.required_time_lock_time))
+            .ok_or(PsbtError::LockTimeConflict)
+    }
+
+    /// The output input `index` spends: its witness UTXO, or the output
+    /// of its non-witness UTXO.
+    ///
+    /// # Errors
+    ///
+    /// [`PsbtError::InputIndex`], [`PsbtError::MissingUtxo`], or
+    /// [`PsbtError::UtxoMismatch`] if the non-witness UTXO's txid or
+    /// outputs do not match the outpoint.
+    pub fn spent_output(&self, index: usize) -> Result<&TxOut, PsbtError> {
+        let input = self
+            .inputs
+            .get(index)
+            .ok_or(PsbtError::InputIndex { index })?;
+        if let Some(output) = &input.witness_utxo {
+            return Ok(output);
+        }
+        let tx = input
+            .non_witness_utxo
+            .as_ref()
+            .ok_or(PsbtError::MissingUtxo { index })?;
+        if tx.txid() != input.previous_output.txid {
+            return Err(PsbtError::UtxoMismatch { index });
+        }
+        tx.outputs
+            .get(input.previous_output.vout as usize)
+            .ok_or(PsbtError::UtxoMismatch { index })
+    }
+
+    /// Builds the final scriptSig and witness of input `index` from its
+    /// partial signatures and scripts, then clears the fields only
+    /// signers need (BIP174). Already finalized inputs are left alone.
+    ///
+    /// Handles P2PK, P2PKH, bare multisig, P2SH, P2WPKH and P2WSH, with
+    /// P2PK, P2PKH or multisig inner scripts and P2SH-wrapped segwit, plus
+    /// Taproot key path spends.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`Psbt::spent_output`], and
+    /// [`PsbtError::CannotFinalize`] for other output types or missing
+    /// signatures or scripts.
+    pub fn finalize_input(&mut self, index: usize) -> Result<(), PsbtError> {
+        let script_pubkey = self.spent_output(index)?.script_pubkey.clone();
+        let input = &mut self.inputs[index];
+        if input.is_finalized() {
+            return Ok(());
+        }
+        let (script_sig, witness) = input
+            .final_scripts(&script_pubkey)
+            .ok_or(PsbtError::CannotFinalize { index })?;
+
+        input.final_script_sig = (!script_sig.is_empty()).then_some(script_sig);
+        input.final_script_witness = (!witness.is_empty()).then_some(witness);
+        input.partial_sigs.clear();
+        input.sighash_type = None;
+        input.redeem_script = None;
+        input.witness_script = None;
+        input.bip32_derivation.clear();
+        input.tap_key_sig = None;
+        Ok(())
+    }
+
+    /// Finalizes every input it can; see [`Psbt::finalize_input`].
+    ///
+    /// # Errors
+    ///
+    /// The first input's error, after trying all of them.
+    pub fn finalize(&mut self) -> Result<(), PsbtError> {
+        let mut result = Ok(());
+        for index in 0..self.inputs.len() {
+            if let Err(err) = self.finalize_input(index) {
+                result = result.and(Err(err));
+            }
+        }
+        result
+    }
+
+    /// The signed transaction, after running every input through the
+    /// engine with `flags` to confirm it is valid.
+    ///
+    /// Without the `secp256k1` feature, signatures are checked in stub
+    /// mode, so only the scripts' logic is confirmed.
+    ///
+    /// # Errors
+    ///
+    /// [`PsbtError::NotFinalized`], [`PsbtError::LockTimeConflict`], the
+    /// errors of [`Psbt::spent_output`], and [`PsbtError::Verify`] for an
+    /// input that fails verification.
+    pub fn extract_tx(&self, flags: VerifyFlags) -> Result<Transaction, PsbtError> {
+        self.extract_tx_with_opts(&flags.into())
+    }
+
+    /// [`Psbt::extract_tx`] with [`VerifyOpts`], e.g. to share a signature
+    /// cache.
+    ///
+    /// # Errors
+    ///
+    /// As for [`Psbt::extract_tx`].
+    pub fn extract_tx_with_opts(&self, opts: &VerifyOpts) -> Result<Transaction, PsbtError> {
+        let mut tx = self.unsigned_tx()?;
+        let mut prevouts = Vec::with_capacity(self.inputs.len());
+        for (index, input) in self.inputs.iter().enumerate() {
+            if !input.is_finalized() {
+                return Err(PsbtError::NotFinalized { index });
+            }
+            tx.inputs[index].script_sig = input.final_script_sig.clone().unwrap_or_default();
+            tx.inputs[index].witness = input.final_script_witness.clone().unwrap_or_default();
+            prevouts.push(self.spent_output(index)?.clone());
+        }
+        for (index, result) in verify_inputs(&tx, &prevouts, opts).into_iter().enumerate() {
+            result.map_err(|error| PsbtError::Verify { index, error })?;
+        }
+        Ok(tx)
+    }
+
+    /// Serializes the PSBT in the binary format, writing the fields in
+    /// key type order.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+
+        if self.version == 0 {
+            let tx = self.transaction(self.fallback_lock_time.unwrap_or(0));
+            write_pair(&mut out, GLOBAL_UNSIGNED_TX, &[], &tx.serialize());
+        }
+        for (xpub, origin) in &self.xpubs {
+            write_pair(
+                &mut out,
+                GLOBAL_XPUB,
+                &xpub.encode(),
+                &encode_origin(origin),
+            );
+        }
+        if self.version >= 2 {
+            write_pair(
+                &mut out,
+                GLOBAL_TX_VERSION,
+                &[],
+                &self.tx_version.to_le_bytes(),
+            );
+            if let Some(lock_time) = self.fallback_lock_time {
+                write_pair(
+                    &mut out,
+                    GLOBAL_FALLBACK_LOCKTIME,
+                    &[],
+                    &lock_time.to_le_bytes(),
+                );
+            }
+            write_pair(
+                &mut out,
+                GLOBAL_INPUT_COUNT,
+                &[],
+                &compact_size(self.inputs.len()),
+            );
+            write_pair(
+                &mut out,
+                GLOBAL_OUTPUT_COUNT,
+                &[],
+                &compact_size(self.outputs.len()),
+            );
+            if let Some(flags) = self.tx_modifiable {
+                write_pair(&mut out, GLOBAL_TX_MODIFIABLE, &[], &[flags]);
+            }
+        }
+        if self.version != 0 {
+            write_pair(&mut out, GLOBAL_VERSION, &[], &self.version.to_le_bytes());
+        }
+        write_unknown(&mut out, &self.unknown);
+
+        for input in &self.inputs {
+            self.encode_input(&mut out, input);
+        }
+        for output in &self.outputs {
+            self.encode_output(&mut out, output);
+        }
+        out
+    }
+
+    fn encode_input(&self, out: &mut Vec<u8>, input: &PsbtInput) {
+        if let Some(tx) = &input.non_witness_utxo {
+            write_pair(out, IN_NON_WITNESS_UTXO, &[], &tx.serialize());
+        }
+        if let Some(output) = &input.witness_utxo {
+            let mut value = output.value.to_le_bytes().to_vec();
+            write_bytes(&mut value, &output.script_pubkey);
+            write_pair(out, IN_WITNESS_UTXO, &[], &value);
+        }
+        for (key, sig) in &input.partial_sigs {
+            write_pair(out, IN_PARTIAL_SIG, key, sig);
+        }
+        if let Some(sighash_type) = input.sighash_type {
+            write_pair(out, IN_SIGHASH_TYPE, &[], &sighash_type.to_le_bytes());
+        }
+        if let Some(script) = &input.redeem_script {
+            write_pair(out, IN_REDEEM_SCRIPT, &[], script);
+        }
+        if let Some(script) = &input.witness_script {
+            write_pair(out, IN_WITNESS_SCRIPT, &[], script);
+        }
+        for (key, origin) in &input.bip32_derivation {
+            write_pair(out, IN_BIP32_DERIVATION, key, &encode_origin(origin));
+        }
+        if let Some(script) = &input.final_script_sig {
+            write_pair(out, IN_FINAL_SCRIPTSIG, &[], script);
+        }
+        if let Some(witness) = &input.final_script_witness {
+            let mut value = compact_size(witness.len());
+            for item in witness {
+                write_bytes(&mut value, item);
+            }
+            write_pair(out, IN_FINAL_SCRIPTWITNESS, &[], &value);
+        }
+        if self.version >= 2 {
+            let outpoint = &input.previous_output;
+            write_pair(out, IN_PREVIOUS_TXID, &[], &outpoint.txid);
+            write_pair(out, IN_OUTPUT_INDEX, &[], &outpoint.vout.to_le_bytes());
+            if input.sequence != 0xffff_ffff {
+                write_pair(out, IN_SEQUENCE, &[], &input.sequence.to_le_bytes());
+            }
+            if let Some(lock_time) = input.required_time_lock_time {
+                write_pair(
+                    out,
+                    IN_REQUIRED_TIME_LOCKTIME,
+                    &[],
+                    &lock_time.to_le_bytes(),
+                );
+            }
+            if let Some(lock_time) = input.required_height_lock_time {
+                write_pair(
+                    out,
+                    IN_REQUIRED_HEIGHT_LOCKTIME,
+                    &[],
+                    &lock_time.to_le_bytes(),
+                );
+            }
+        }
+        if let Some(sig) = &input.tap_key_sig {
+            write_pair(out, IN_TAP_KEY_SIG, &[], sig);
+        }
+        write_unknown(out, &input.unknown);
+    }
+
+    fn encode_output(&self, out: &mut Vec<u8>, output: &PsbtOutput) {
+        if let Some(script) = &output.redeem_script {
+            write_pair(out, OUT_REDEEM_SCRIPT, &[], script);
+        }
+        if let Some(script) = &output.witness_script {
+            write_pair(out, OUT_WITNESS_SCRIPT, &[], script);
+        }
+        for (key, origin) in &output.bip32_derivation {
+            write_pair(out, OUT_BIP32_DERIVATION, key, &encode_origin(origin));
+        }
+        if self.version >= 2 {
+            write_pair(out, OUT_AMOUNT, &[], &output.amount.to_le_bytes());
+            write_pair(out, OUT_SCRIPT, &[], &output.script_pubkey);
+        }
+        write_unknown(out, &output.unknown);
+    }
+
+    /// Parses a PSBT in the binary format.
+    ///
+    /// # Errors
+    ///
+    /// [`PsbtError::InvalidMagic`], [`PsbtError::Decode`] for truncated
+    /// maps or trailing data, [`PsbtError::DuplicateKey`], and the field
+    /// errors for values that are malformed, missing or not allowed in
+    /// the PSBT's version.
+    pub fn deserialize(bytes: &[u8]) -> Result<Psbt, PsbtError> {
+        let data = bytes
+            .strip_prefix(&MAGIC[..])
+            .ok_or(PsbtError::InvalidMagic)?;
+        let mut reader = Reader::new(data);
+
+        let mut version = 0;
+        let mut unsigned_tx = None;
+        let mut tx_version = None;
+        let mut input_count = None;
+        let mut output_count = None;
+        let mut psbt = Psbt {
+            version: 0,
+            tx_version: 0,
+            fallback_lock_time: None,
+            tx_modifiable: None,
+            xpubs: Vec::new(),
+            unknown: BTreeMap::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        };
+        let global = read_map(&mut reader)?;
+        // The version decides which other fields are allowed.
+        if let Some(pair) = global.iter().find(|pair| pair.key_type == GLOBAL_VERSION) {
+            version = pair.value(Reader::read_u32)?;
+        }
+        if version != 0 && version != 2 {
+            return Err(PsbtError::UnsupportedVersion(version));
+        }
+        for pair in &global {
+            pair.check_version(version, GLOBAL_V2_ONLY)?;
+            match pair.key_type {
+                GLOBAL_UNSIGNED_TX if version == 0 => {
+                    unsigned_tx = Some(pair.value(Transaction::decode)?);
+                }
+                GLOBAL_UNSIGNED_TX => {
+                    return Err(PsbtError::UnexpectedField {
+                        key_type: pair.key_type,
+                    })
+                }
+                GLOBAL_XPUB => {
+                    let xpub = Xpub::decode(pair.key_data).map_err(|_| pair.invalid())?;
+                    psbt.xpubs.push((xpub, pair.origin()?));
+                }
+                GLOBAL_TX_VERSION => tx_version = Some(pair.value(Reader::read_u32)? as i32),
+                GLOBAL_FALLBACK_LOCKTIME => {
+                    psbt.fallback_lock_time = Some(pair.value(Reader::read_u32)?);
+                }
+                GLOBAL_INPUT_COUNT => input_count = Some(pair.value(Reader::read_compact_size)?),
+                GLOBAL_OUTPUT_COUNT => {
+                    output_count = Some(pair.value(Reader::read_compact_size)?);
+                }
+                GLOBAL_TX_MODIFIABLE => psbt.tx_modifiable = Some(pair.value(Reader::read_u8)?),
+                GLOBAL_VERSION => {}
+                _ => pair.insert_unknown(&mut psbt.unknown),
+            }
+        }
+        psbt.version = version;
+
+        if version == 0 {
+            let tx = unsigned_tx.ok_or(PsbtError::MissingField {
+                key_type: GLOBAL_UNSIGNED_TX,
+            })?;
+            let Psbt {
+                tx_version,
+                fallback_lock_time,
+                inputs,
+                outputs,
+                ..
+            } = Psbt::from_unsigned_tx(&tx)?;
+            psbt = Psbt {
+                tx_version,
+                fallback_lock_time,
+                inputs,
+                outputs,
+                ..psbt
+            };
+            for input in &mut psbt.inputs {
+                decode_input(&read_map(&mut reader)?, version, input)?;
+            }
+            for output in &mut psbt.outputs {
+                decode_output(&read_map(&mut reader)?, version, output)?;
+            }
+        } else {
+            let missing = |key_type| PsbtError::MissingField { key_type };
+            psbt.tx_version = tx_version.ok_or(missing(GLOBAL_TX_VERSION))?;
+            let input_count = input_count.ok_or(missing(GLOBAL_INPUT_COUNT))?;
+            let output_count = output_count.ok_or(missing(GLOBAL_OUTPUT_COUNT))?;
+            // Every map takes at least its separator byte.
+            for _ in 0..reader.count(input_count, 1)? {
+                let mut input = PsbtInput::new(OutPoint::default());
+                decode_input(&read_map(&mut reader)?, version, &mut input)?;
+                psbt.inputs.push(input);
+            }
+            for _ in 0..reader.count(output_count, 1)? {
+                let mut output = PsbtOutput::new(0, Vec::new());
+                decode_output(&read_map(&mut reader)?, version, &mut output)?;
+                psbt.outputs.push(output);
+            }
+        }
+        reader.finish()?;
+        Ok(psbt)
+    }
+}
+
+impl fmt::Display for Psbt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_base64(&self.serialize()))
+    }
+}
+
+impl FromStr for Psbt {
+    type Err = PsbtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Psbt::deserialize(&decode_base64(s.trim()).ok_or(PsbtError::Base64)?)
+    }
+}
+
+/// One key-value pair of a PSBT map.
+struct Pair<'a> {
+    key: &'a [u8],
+    key_type: u64,
+    key_data: &'a [u8],
+    value: &'a [u8],
+}
+
+impl<'a> Pair<'a> {
+    fn invalid(&self) -> PsbtError {
+        PsbtError::InvalidField {
+            key_type: self.key_type,
+        }
+    }
+
+    /// Parses the whole value with `read`, for a field with no key data.
+    fn value<T>(
+        &self,
+        read: impl FnOnce(&mut Reader<'a>) -> Result<T, DecodeError>,
+    ) -> Result<T, PsbtError> {
+        if !self.key_data.is_empty() {
+            return Err(self.invalid());
+        }
+        let mut reader = Reader::new(self.value);
+        let value = read(&mut reader).map_err(|_| self.invalid())?;
+        reader.finish().map_err(|_| self.invalid())?;
+        Ok(value)
+    }
+
+    /// The value as raw bytes, for a field with no key data.
+    fn bytes(&self) -> Result<Vec<u8>, PsbtError> {
+        self.value(|reader| reader.read_bytes(self.value.len()))
+            .map(<[u8]>::to_vec)
+    }
+
+    /// The key data as a public key, for fields keyed by one.
+    fn pubkey(&self) -> Result<Vec<u8>, PsbtError> {
+        match pubkey_encoding(self.key_data) {
+            Some(_) => Ok(self.key_data.to_vec()),
+            None => Err(self.invalid()),
+        }
+    }
+
+    /// The value as a key origin: a fingerprint then little-endian path
+    /// steps.
+    fn origin(&self) -> Result<KeyOrigin, PsbtError> {
+        if self.value.len() < 4 || self.value.len() % 4 != 0 {
+            return Err(self.invalid());
+        }
+        let mut fingerprint = [0; 4];
+        fingerprint.copy_from_slice(&self.value[..4]);
+        let path = self.value[4..]
+            .chunks(4)
+            .map(|step| u32::from_le_bytes([step[0], step[1], step[2], step[3]]))
+            .collect();
+        Ok(KeyOrigin { fingerprint, path })
+    }
+
+    fn check_version(&self, version: u32, v2_only: &[u64]) -> Result<(), PsbtError> {
+        if version == 0 && v2_only.contains(&self.key_type) {
+            return Err(PsbtError::UnexpectedField {
+                key_type: self.key_type,
+            });
+        }
+        Ok(())
+    }
+
+    fn insert_unknown(&self, unknown: &mut BTreeMap<Vec<u8>, Vec<u8>>) {
+        unknown.insert(self.key.to_vec(), self.value.to_vec());
+    }
+}
+
+/// Reads key-value pairs up to the zero-length key that ends a map.
+fn read_map<'a>(reader: &mut Reader<'a>) -> Result<Vec<Pair<'a>>, PsbtError> {
+    let mut pairs = Vec::new();
+    let mut keys = BTreeSet::new();
+    loop {
+        let key = reader.read_var_bytes()?;
+        if key.is_empty() {
+            return Ok(pairs);
+        }
+        let value = reader.read_var_bytes()?;
+        if !keys.insert(key) {
+            return Err(PsbtError::DuplicateKey);
+        }
+        let mut key_reader = Reader::new(key);
+        let key_type = key_reader.read_compact_size()?;
+        let key_data = &key[key.len() - key_reader.remaining()..];
+        pairs.push(Pair {
+            key,
+            key_type,
+            key_data,
+            value,
+        });
+    }
+}
+
+fn decode_input(pairs: &[Pair<'_>], version: u32, input: &mut PsbtInput) -> Result<(), PsbtError> {
+    let (mut txid, mut vout) = (None, None);
+    for pair in pairs {
+        pair.check_version(version, IN_V2_ONLY)?;
+        match pair.key_type {
+            IN_NON_WITNESS_UTXO => input.non_witness_utxo = Some(pair.value(Transaction::decode)?),
+            IN_WITNESS_UTXO => input.witness_utxo = Some(pair.value(Reader::read_txout)?),
+            IN_PARTIAL_SIG => {
+                input
+                    .partial_sigs
+                    .insert(pair.pubkey()?, pair.value.to_vec());
+            }
+            IN_SIGHASH_TYPE => input.sighash_type = Some(pair.value(Reader::read_u32)?),
+            IN_REDEEM_SCRIPT => input.redeem_script = Some(pair.bytes()?),
+            IN_WITNESS_SCRIPT => input.witness_script = Some(pair.bytes()?),
+            IN_BIP32_DERIVATION => {
+                input
+                    .bip32_derivation
+                    .insert(pair.pubkey()?, pair.origin()?);
+            }
+            IN_FINAL_SCRIPTSIG => input.final_script_sig = Some(pair.bytes()?),
+            IN_FINAL_SCRIPTWITNESS => {
+                input.final_script_witness = Some(pair.value(|reader| {
+                    let count = reader.read_compact_size()?;
+                    (0..reader.count(count, 1)?)
+                        .map(|_| reader.read_var_bytes().map(<[u8]>::to_vec))
+                        .collect()
+                })?);
+            }
+            IN_PREVIOUS_TXID => txid = Some(pair.value(Reader::read_array)?),
+            IN_OUTPUT_INDEX => vout = Some(pair.value(Reader::read_u32)?),
+            IN_SEQUENCE => input.sequence = pair.value(Reader::read_u32)?,
+            IN_REQUIRED_TIME_LOCKTIME => {
+                let lock_time = pair.value(Reader::read_u32)?;
+                if lock_time < LOCKTIME_THRESHOLD {
+                    return Err(pair.invalid());
+                }
+                input.required_time_lock_time = Some(lock_time);
+            }
+            IN_REQUIRED_HEIGHT_LOCKTIME => {
+                let lock_time = pair.value(Reader::read_u32)?;
+                if lock_time == 0 || lock_time >= LOCKTIME_THRESHOLD {
+                    return Err(pair.invalid());
+                }
+                input.required_height_lock_time = Some(lock_time);
+            }
+            IN_TAP_KEY_SIG => {
+                let sig = pair.bytes()?;
+                if sig.len() != 64 && sig.len() != 65 {
+                    return Err(pair.invalid());
+                }
+                input.tap_key_sig = Some(sig);
+            }
+            _ => pair.insert_unknown(&mut input.unknown),
+        }
+    }
+    if version >= 2 {
+        let missing = |key_type| PsbtError::MissingField { key_type };
+        input.previous_output = OutPoint {
+            txid: txid.ok_or(missing(IN_PREVIOUS_TXID))?,
+            vout: vout.ok_or(missing(IN_OUTPUT_INDEX))?,
+        };
+    }
+    Ok(())
+}
+
+fn decode_output(
+    pairs: &[Pair<'_>],
+    version: u32,
+    output: &mut PsbtOutput,
+) -> Result<(), PsbtError> {
+    let (mut amount, mut script) = (None, None);
+    for pair in pairs {
+        pair.check_version(version, OUT_V2_ONLY)?;
+        match pair.key_type {
+            OUT_REDEEM_SCRIPT => output.redeem_script = Some(pair.bytes()?),
+            OUT_WITNESS_SCRIPT => output.witness_script = Some(pair.bytes()?),
+            OUT_BIP32_DERIVATION => {
+                output
+                    .bip32_derivation
+                    .insert(pair.pubkey()?, pair.origin()?);
+            }
+            OUT_AMOUNT => amount = Some(pair.value(Reader::read_u64)?),
+            OUT_SCRIPT => script = Some(pair.bytes()?),
+            _ => pair.insert_unknown(&mut output.unknown),
+        }
+    }
+    if version >= 2 {
+        let missing = |key_type| PsbtError::MissingField { key_type };
+        output.amount = amount.ok_or(missing(OUT_AMOUNT))?;
+        output.script_pubkey = script.ok_or(missing(OUT_SCRIPT))?;
+    }
+    Ok(())
+}
+
+/// Appends one key-value pair: the key type and key data, then the value.
+fn write_pair(out: &mut Vec<u8>, key_type: u64, key_data: &[u8], value: &[u8]) {
+    let mut key = Vec::with_capacity(1 + key_data.len());
+    write_compact_size(&mut key, key_type);
+    key.extend_from_slice(key_data);
+    write_bytes(out, &key);
+    write_bytes(out, value);
+}
+
+/// Appends the unknown fields, then the separator ending the map.
+fn write_unknown(out: &mut Vec<u8>, unknown: &BTreeMap<Vec<u8>, Vec<u8>>) {
+    for (key, value) in unknown {
+        write_bytes(out, key);
+        write_bytes(out, value);
+    }
+    out.push(0x00);
+}
+
+/// A scriptSig pushing `items` in order.
+fn push_all(items: &[Vec<u8>]) -> Vec<u8> {
+    items
+        .iter()
+        .fold(ScriptBuilder::new(), |script, item| script.push_slice(item))
+        .into_bytes()
+}
+
+fn encode_origin(origin: &KeyOrigin) -> Vec<u8> {
+    let mut out = origin.fingerprint.to_vec();
+    for step in &origin.path {
+        out.extend_from_slice(&step.to_le_bytes());
+    }
+    out
+}
+
+fn compact_size(n: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_compact_size(&mut out, n as u64);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Psbt {
+        let mut input = PsbtInput::new(OutPoint {
+            txid: [0x11; 32],
+            vout: 3,
+        });
+        input.sequence = 0xffff_fffd;
+        input.witness_utxo = Some(TxOut {
+            value: 10_000,
+            script_pubkey: builder::p2wpkh(&[0x22; 20]),
+        });
+        input.sighash_type = Some(1);
+        input.unknown.insert(vec![0xfc, 0x01], vec![0xaa]);
+        Psbt {
+            version: 0,
+            tx_version: 2,
+            fallback_lock_time: Some(0),
+            tx_modifiable: None,
+            xpubs: Vec::new(),
+            unknown: BTreeMap::new(),
+            inputs: vec![input],
+            outputs: vec![PsbtOutput::new(9_000, builder::p2wpkh(&[0x33; 20]))],
+        }
+    }
+
+    #[test]
+    fn roundtrip_both_versions() {
+        let v0 = sample();
+        let bytes = v0.serialize();
+        assert!(bytes.starts_with(b"psbt\xff\x01\x00"));
+        assert_eq!(Psbt::deserialize(&bytes), Ok(v0.clone()));
+        assert_eq!(v0.to_string().parse::<Psbt>(), Ok(v0.clone()));
+
+        let v2 = Psbt {
+            version: 2,
+            tx_modifiable: Some(0x03),
+            ..v0
+        };
+        let bytes = v2.serialize();
+        assert_eq!(Psbt::deserialize(&bytes), Ok(v2.clone()));
+        assert_eq!(v2.unsigned_tx(), sample().unsigned_tx());
+    }
+
+    #[test]
+    fn version_rules() {
+        // A version 2 field in a version 0 PSBT.
+        let mut bytes = MAGIC.to_vec();
+        write_pair(
+            &mut bytes,
+            GLOBAL_UNSIGNED_TX,
+            &[],
+            &sample().unsigned_tx().unwrap().serialize(),
+        );
+        write_pair(&mut bytes, GLOBAL_INPUT_COUNT, &[], &[1]);
+        bytes.push(0x00);
+        assert_eq!(
+            Psbt::deserialize(&bytes),
+            Err(PsbtError::UnexpectedField {
+                key_type: GLOBAL_INPUT_COUNT
+            })
+        );
+
+        let mut bytes = MAGIC.to_vec();
+        write_pair(&mut bytes, GLOBAL_VERSION, &[], &2u32.to_le_bytes());
+        write_pair(&mut bytes, GLOBAL_TX_VERSION, &[], &2u32.to_le_bytes());
+        bytes.push(0x00);
+        assert_eq!(
+            Psbt::deserialize(&bytes),
+            Err(PsbtError::MissingField {
+                key_type: GLOBAL_INPUT_COUNT
+            })
+        );
+
+        let mut bytes = MAGIC.to_vec();
+        write_pair(&mut bytes, GLOBAL_VERSION, &[], &1u32.to_le_bytes());
+        bytes.push(0x00);
+        assert_eq!(
+            Psbt::deserialize(&bytes),
+            Err(PsbtError::UnsupportedVersion(1))
+        );
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let mut bytes = MAGIC.to_vec();
+        write_pair(&mut bytes, 0xfc, &[0x01], &[0xaa]);
+        write_pair(&mut bytes, 0xfc, &[0x01], &[0xbb]);
+        bytes.push(0x00);
+        assert_eq!(Psbt::deserialize(&bytes), Err(PsbtError::DuplicateKey));
+    }
+
+    #[test]
+    fn lock_time_selection() {
+        let mut psbt = Psbt {
+            version: 2,
+            fallback_lock_time: Some(7),
+            ..sample()
+        };
+        psbt.inputs.push(PsbtInput::new(OutPoint::default()));
+        assert_eq!(psbt.unsigned_tx().unwrap().lock_time, 7);
+
+        psbt.inputs[0].required_time_lock_time = Some(600_000_000);
+        assert_eq!(psbt.unsigned_tx().unwrap().lock_time, 600_000_000);
+
+        // Heights win when every constrained input accepts one.
+        psbt.inputs[0].required_height_lock_time = Some(800_000);
+        psbt.inputs[1].required_height_lock_time = Some(800_100);
+        assert_eq!(psbt.unsigned_tx().unwrap().lock_time, 800_100);
+
+        psbt.inputs[0].required_height_lock_time = None;
+        assert_eq!(psbt.unsigned_tx(), Err(PsbtError::LockTimeConflict));
+    }
+}
@@ -1,7 +1,442 @@
+use alloc::borrow::{Borrow, ToOwned};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+use core::str::FromStr;
+
+use crate::builder::ScriptBuilder;
+use crate::classify::{self, read_instruction, OutputType};
 use crate::engine::{execute_on_stack, ExecuteOpts};
 use crate::error::ScriptError;
+use crate::hash;
+use crate::hex::{decode_hex, encode_hex};
+use crate::opcode::Opcode;
+use crate::sigops;
 use crate::stack::{is_true, Stack};
-use crate::tokenizer::parse_script;
+use crate::token::Token;
+use crate::tokenizer::{parse_asm, parse_script};
+
+/// Opcodes that appear in standard scripts but have no [`Opcode`] variant
+/// because the engine does not execute them. Named in ASM output and
+/// accepted by [`ScriptBuf`]'s `FromStr`.
+const EXTRA_OPCODE_NAMES: [(&str, u8); 1] = [("OP_CODESEPARATOR", 0xab)];
+
+/// A borrowed script: raw script bytes with script-aware methods.
+///
+/// `Script` is to [`ScriptBuf`] what `str` is to `String`. It does not
+/// require the bytes to parse; methods that need instructions report
+/// malformed pushes as they reach them.
+///
+/// ```rust
+/// use mini_bitcoin_script::script::Script;
+///
+/// let script = Script::from_bytes(&[0x51, 0x51, 0x87]);
+/// assert_eq!(script.to_string(), "OP_1 OP_1 OP_EQUAL");
+/// assert_eq!(script.to_hex(), "515187");
+/// ```
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Script([u8]);
+
+impl Script {
+    /// Wraps a byte slice as a script, without copying.
+    pub fn from_bytes(bytes: &[u8]) -> &Script {
+        // SAFETY: `Script` is `repr(transparent)` over `[u8]`, so the
+        // pointer cast preserves layout and the slice metadata.
+        unsafe { &*(bytes as *const [u8] as *const Script) }
+    }
+
+    /// The raw script bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Length in bytes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` for the empty script.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Copies the script into an owned [`ScriptBuf`].
+    pub fn to_script_buf(&self) -> ScriptBuf {
+        ScriptBuf(self.0.to_vec())
+    }
+
+    /// Iterates over the script's instructions without allocating.
+    ///
+    /// A push running past the end of the script yields
+    /// [`ScriptError::UnexpectedEndOfScript`] and ends the iteration.
+    pub fn instructions(&self) -> Instructions<'_> {
+        Instructions {
+            bytes: &self.0,
+            pos: 0,
+        }
+    }
+
+    /// Parses the script into engine tokens; see
+    /// [`parse_script`].
+    pub fn tokens(&self) -> Result<Vec<Token>, ScriptError> {
+        parse_script(&self.0)
+    }
+
+    /// The standard template this script matches, as a scriptPubKey.
+    pub fn output_type(&self) -> OutputType {
+        classify::classify(&self.0)
+    }
+
+    /// Returns `true` for `<pubkey> OP_CHECKSIG`.
+    pub fn is_p2pk(&self) -> bool {
+        self.output_type() == OutputType::P2pk
+    }
+
+    /// Returns `true` for
+    /// `OP_DUP OP_HASH160 <20 bytes> OP_EQUALVERIFY OP_CHECKSIG`.
+    pub fn is_p2pkh(&self) -> bool {
+        self.output_type() == OutputType::P2pkh
+    }
+
+    /// Returns `true` for `OP_HASH160 <20 bytes> OP_EQUAL`.
+    pub fn is_p2sh(&self) -> bool {
+        classify::is_p2sh(&self.0)
+    }
+
+    /// Returns `true` for a version 0, 20-byte witness program.
+    pub fn is_p2wpkh(&self) -> bool {
+        self.output_type() == OutputType::P2wpkh
+    }
+
+    /// Returns `true` for a version 0, 32-byte witness program.
+    pub fn is_p2wsh(&self) -> bool {
+        self.output_type() == OutputType::P2wsh
+    }
+
+    /// Returns `true` for a version 1, 32-byte witness program.
+    pub fn is_p2tr(&self) -> bool {
+        self.output_type() == OutputType::P2tr
+    }
+
+    /// Returns `true` for a standard bare multisig script.
+    pub fn is_multisig(&self) -> bool {
+        matches!(self.output_type(), OutputType::Multisig { .. })
+    }
+
+    /// Returns `true` if the script starts with OP_RETURN, making any
+    /// output carrying it unspendable.
+    pub fn is_op_return(&self) -> bool {
+        self.0.first() == Some(&Opcode::OpReturn.to_byte())
+    }
+
+    /// Returns `true` for any witness program, known version or not.
+    pub fn is_witness_program(&self) -> bool {
+        self.witness_program().is_some()
+    }
+
+    /// The witness version and program, if this is a witness program.
+    pub fn witness_program(&self) -> Option<(u8, &[u8])> {
+        classify::witness_program(&self.0)
+    }
+
+    /// Returns `true` if the script contains only pushes.
+    pub fn is_push_only(&self) -> bool {
+        classify::is_push_only(&self.0)
+    }
+
+    /// Legacy sigop count; see [`sigops::legacy_sigop_count`].
+    pub fn legacy_sigop_count(&self) -> usize {
+        sigops::legacy_sigop_count(&self.0)
+    }
+
+    /// Accurate sigop count; see [`sigops::accurate_sigop_count`].
+    pub fn accurate_sigop_count(&self) -> usize {
+        sigops::accurate_sigop_count(&self.0)
+    }
+
+    /// Lowercase hex encoding of the script bytes.
+    pub fn to_hex(&self) -> String {
+        encode_hex(&self.0)
+    }
+
+    /// HASH160 of the script: the hash a P2SH output commits to.
+    pub fn script_hash(&self) -> [u8; 20] {
+        hash::hash160(&self.0)
+    }
+
+    /// SHA-256 of the script: the hash a P2WSH output commits to.
+    pub fn wscript_hash(&self) -> [u8; 32] {
+        hash::sha256(&self.0)
+    }
+}
+
+/// Formats the script as ASM: opcode names and `<hex>` pushes. Opcodes
+/// with no name here print as `0x..`; a truncated push prints `[error]`.
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, instruction) in self.instructions().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            match instruction {
+                Ok(instruction) => write!(f, "{instruction}")?,
+                Err(_) => f.write_str("[error]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AsRef<[u8]> for Script {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<Script> for Script {
+    fn as_ref(&self) -> &Script {
+        self
+    }
+}
+
+impl<'a> From<&'a [u8]> for &'a Script {
+    fn from(bytes: &'a [u8]) -> Self {
+        Script::from_bytes(bytes)
+    }
+}
+
+impl ToOwned for Script {
+    type Owned = ScriptBuf;
+
+    fn to_owned(&self) -> ScriptBuf {
+        self.to_script_buf()
+    }
+}
+
+/// An owned script. Dereferences to [`Script`] for all read-only methods.
+///
+/// Parses from ASM (as printed by `Display`) or from hex:
+///
+/// ```rust
+/// use mini_bitcoin_script::script::ScriptBuf;
+///
+/// let from_asm: ScriptBuf = "OP_HASH160 <0000000000000000000000000000000000000000> OP_EQUAL"
+///     .parse()
+///     .unwrap();
+/// let from_hex: ScriptBuf = "a914000000000000000000000000000000000000000087".parse().unwrap();
+/// assert_eq!(from_asm, from_hex);
+/// assert!(from_asm.is_p2sh());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScriptBuf(Vec<u8>);
+
+impl ScriptBuf {
+    /// Creates an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wraps script bytes.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        ScriptBuf(bytes)
+    }
+
+    /// Decodes a hex-encoded script.
+    pub fn from_hex(hex: &str) -> Result<Self, ScriptError> {
+        decode_hex(hex).map(ScriptBuf).map_err(ScriptError::from)
+    }
+
+    /// Borrows the script.
+    pub fn as_script(&self) -> &Script {
+        Script::from_bytes(&self.0)
+    }
+
+    /// Consumes the script and returns its bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for ScriptBuf {
+    type Target = Script;
+
+    fn deref(&self) -> &Script {
+        self.as_script()
+    }
+}
+
+impl Borrow<Script> for ScriptBuf {
+    fn borrow(&self) -> &Script {
+        self.as_script()
+    }
+}
+
+impl AsRef<Script> for ScriptBuf {
+    fn as_ref(&self) -> &Script {
+        self.as_script()
+    }
+}
+
+impl AsRef<[u8]> for ScriptBuf {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for ScriptBuf {
+    fn from(bytes: Vec<u8>) -> Self {
+        ScriptBuf(bytes)
+    }
+}
+
+impl From<&Script> for ScriptBuf {
+    fn from(script: &Script) -> Self {
+        script.to_script_buf()
+    }
+}
+
+impl From<ScriptBuf> for Vec<u8> {
+    fn from(script: ScriptBuf) -> Self {
+        script.0
+    }
+}
+
+impl From<ScriptBuf> for Box<Script> {
+    fn from(script: ScriptBuf) -> Self {
+        let bytes: Box<[u8]> = script.0.into_boxed_slice();
+        // SAFETY: as in `Script::from_bytes`; `Box<[u8]>` and `Box<Script>`
+        // have the same layout.
+        unsafe { Box::from_raw(Box::into_raw(bytes) as *mut Script) }
+    }
+}
+
+impl fmt::Display for ScriptBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_script().fmt(f)
+    }
+}
+
+/// Parses hex if the whole string decodes as hex, otherwise ASM.
+///
+/// ASM words are `OP_*` names (the `OP_` prefix is optional), `<hex>`
+/// pushes, and `0x..` raw bytes spliced in as-is. Pushes are re-encoded
+/// minimally. Words made of hex digits that do not decode, such as `ADD`
+/// or `0`, are read as ASM; an even-length one such as `10` is hex.
+///
+/// # Errors
+///
+/// [`ScriptError::InvalidAsm`] for unknown words and
+/// [`ScriptError::InvalidHex`] for malformed hex, including a hex-digit
+/// string that is neither valid hex nor ASM.
+impl FromStr for ScriptBuf {
+    type Err = ScriptError;
+
+    fn from_str(s: &str) -> Result<Self, ScriptError> {
+        let s = s.trim();
+        if !s.is_empty() && s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return ScriptBuf::from_hex(s).or_else(|err| from_asm(s).map_err(|_| err));
+        }
+        from_asm(s)
+    }
+}
+
+/// Parses the ASM form accepted by [`ScriptBuf::from_str`].
+fn from_asm(s: &str) -> Result<ScriptBuf, ScriptError> {
+    let mut bytes = Vec::new();
+    for word in s.split_whitespace() {
+        if let Some(hex) = word.strip_prefix("0x") {
+            bytes.extend(decode_hex(hex)?);
+            continue;
+        }
+        let bare = word.strip_prefix("OP_").unwrap_or(word);
+        if let Some((_, byte)) = EXTRA_OPCODE_NAMES
+            .iter()
+            .find(|(name, _)| &name[3..] == bare)
+        {
+            bytes.push(*byte);
+            continue;
+        }
+        for token in parse_asm(word)? {
+            match token {
+                Token::Op(opcode) => bytes.push(opcode.to_byte()),
+                Token::PushData(data) => {
+                    bytes.extend(ScriptBuilder::new().push_slice(&data).into_bytes())
+                }
+            }
+        }
+    }
+    Ok(ScriptBuf(bytes))
+}
+
+/// One instruction of a [`Script`], borrowing its push data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    /// Data pushed by a direct push or OP_PUSHDATA1/2/4.
+    PushBytes(&'a [u8]),
+    /// Any other opcode byte, including OP_0 and OP_1–OP_16.
+    Op(u8),
+}
+
+impl Instruction<'_> {
+    /// The engine opcode, for non-push instructions it supports.
+    pub fn opcode(&self) -> Option<Opcode> {
+        match self {
+            Instruction::PushBytes(_) => None,
+            Instruction::Op(byte) => Opcode::from_byte(*byte),
+        }
+    }
+}
+
+impl fmt::Display for Instruction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::PushBytes(data) => write!(f, "<{}>", encode_hex(data)),
+            Instruction::Op(byte) => {
+                if let Some(opcode) = Opcode::from_byte(*byte) {
+                    return write!(f, "{opcode}");
+                }
+                match EXTRA_OPCODE_NAMES.iter().find(|(_, b)| b == byte) {
+                    Some((name, _)) => f.write_str(name),
+                    None => write!(f, "0x{byte:02x}"),
+                }
+            }
+        }
+    }
+}
+
+/// Iterator over a script's instructions; see [`Script::instructions`].
+#[derive(Debug, Clone)]
+pub struct Instructions<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<Instruction<'a>, ScriptError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.bytes.len() {
+            return None;
+        }
+        match read_instruction(self.bytes, self.pos) {
+            Some((_, Some(data), next)) => {
+                self.pos = next;
+                Some(Ok(Instruction::PushBytes(data)))
+            }
+            Some((op, None, next)) => {
+                self.pos = next;
+                Some(Ok(Instruction::Op(op)))
+            }
+            None => {
+                self.pos = self.bytes.len();
+                Some(Err(ScriptError::UnexpectedEndOfScript))
+            }
+        }
+    }
+}
 
 /// Validates a Pay-to-Public-Key-Hash (P2PKH) script pair.
 ///
@@ -14,13 +449,17 @@ use crate::tokenizer::parse_script;
 /// Returns `Ok(true)` if the combined execution succeeds (top stack
 /// element is truthy after both phases).
 ///
-/// OP_CHECKSIG uses stub mode (always succeeds). For real ECDSA
+/// OP_CHECKSIG uses stub mode (any non-empty signature succeeds). For real ECDSA
 /// verification, use [`validate_p2pkh_with_opts`] with a sighash and
 /// the `secp256k1` feature enabled.
 ///
-/// Both arguments are raw script bytes (not hex). Use
-/// [`crate::hex::decode_hex`] to convert hex strings first.
-pub fn validate_p2pkh(script_sig: &[u8], script_pubkey: &[u8]) -> Result<bool, ScriptError> {
+/// Both arguments are raw script bytes (not hex): byte slices, vectors,
+/// or [`Script`]/[`ScriptBuf`]. Use [`ScriptBuf::from_hex`] to convert hex
+/// strings first.
+pub fn validate_p2pkh(
+    script_sig: impl AsRef<[u8]>,
+    script_pubkey: impl AsRef<[u8]>,
+) -> Result<bool, ScriptError> {
     validate_p2pkh_with_opts(script_sig, script_pubkey, &ExecuteOpts::default())
 }
 
@@ -29,12 +468,12 @@ pub fn validate_p2pkh(script_sig: &[u8], script_pubkey: &[u8]) -> Result<bool, S
 /// See [`validate_p2pkh`] for details. The `opts` parameter controls
 /// OP_CHECKSIG behavior via [`ExecuteOpts::sighash`].
 pub fn validate_p2pkh_with_opts(
-    script_sig: &[u8],
-    script_pubkey: &[u8],
+    script_sig: impl AsRef<[u8]>,
+    script_pubkey: impl AsRef<[u8]>,
     opts: &ExecuteOpts,
 ) -> Result<bool, ScriptError> {
-    let sig_tokens = parse_script(script_sig)?;
-    let pk_tokens = parse_script(script_pubkey)?;
+    let sig_tokens = parse_script(script_sig.as_ref())?;
+    let pk_tokens = parse_script(script_pubkey.as_ref())?;
 
     let mut stack = Stack::new();
 
@@ -55,6 +494,7 @@ pub fn validate_p2pkh_with_opts(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::flags::VerifyFlags;
     use crate::hash;
 
     /// Builds a scriptSig that pushes a fake signature and a public key.
@@ -93,7 +533,7 @@ mod tests {
         let script_sig = build_script_sig(fake_sig, fake_pubkey);
         let script_pubkey = build_script_pubkey(&pubkey_hash);
 
-        // Stub CHECKSIG always succeeds, so this should pass
+        // Stub CHECKSIG accepts the non-empty signature, so this should pass
         let result = validate_p2pkh(&script_sig, &script_pubkey).unwrap();
         assert!(result);
     }
@@ -109,7 +549,7 @@ mod tests {
 
         // OP_EQUALVERIFY should fail
         let err = validate_p2pkh(&script_sig, &script_pubkey).unwrap_err();
-        assert!(matches!(err, ScriptError::VerifyFailed));
+        assert!(matches!(err, ScriptError::EqualVerifyFailed));
     }
 
     #[test]
@@ -119,7 +559,7 @@ mod tests {
 
         // Empty scriptSig means stack is empty when scriptPubKey runs,
         // OP_DUP will fail with StackUnderflow
-        let err = validate_p2pkh(&[], &script_pubkey).unwrap_err();
+        let err = validate_p2pkh([], &script_pubkey).unwrap_err();
         assert!(matches!(err, ScriptError::StackUnderflow));
     }
 
@@ -132,7 +572,11 @@ mod tests {
         let script_sig = build_script_sig(fake_sig, fake_pubkey);
         let script_pubkey = build_script_pubkey(&pubkey_hash);
 
-        let opts = ExecuteOpts { sighash: None };
+        let opts = ExecuteOpts {
+            sighash: None,
+            flags: VerifyFlags::NONE,
+            ..ExecuteOpts::default()
+        };
         let result = validate_p2pkh_with_opts(&script_sig, &script_pubkey, &opts).unwrap();
         assert!(result);
     }
@@ -147,4 +591,29 @@ mod tests {
         let err = validate_p2pkh(&script_sig, &script_pubkey).unwrap_err();
         assert!(matches!(err, ScriptError::OpReturnEncountered));
     }
+
+    #[test]
+    fn instructions_report_truncated_push() {
+        let script = Script::from_bytes(&[0x51, 0x02, 0xaa]);
+        let instructions: Vec<_> = script.instructions().collect();
+        assert_eq!(
+            instructions,
+            vec![
+                Ok(Instruction::Op(0x51)),
+                Err(ScriptError::UnexpectedEndOfScript)
+            ]
+        );
+        assert_eq!(script.to_string(), "OP_1 [error]");
+    }
+
+    #[test]
+    fn asm_names_opcodes_outside_the_engine() {
+        let script = Script::from_bytes(&[0x52, 0xae, 0xab, 0xfe]);
+        assert_eq!(
+            script.to_string(),
+            "OP_2 OP_CHECKMULTISIG OP_CODESEPARATOR 0xfe"
+        );
+        let parsed: ScriptBuf = script.to_string().parse().unwrap();
+        assert_eq!(parsed.as_script(), script);
+    }
 }
@@ -0,0 +1,196 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use crate::hash;
+
+/// A bounded, thread-safe cache of signatures already found valid.
+///
+/// Verifying a transaction for the mempool and again when it is mined
+/// repeats every signature check. With a cache set in
+/// [`ExecuteOpts::sig_cache`](crate::engine::ExecuteOpts::sig_cache), the
+/// `secp256k1`-backed checker skips ECDSA and Schnorr verification for
+/// any (sighash, public key, signature) triple it has already accepted.
+///
+/// Only valid signatures are stored, so a cache can never turn a failing
+/// check into a passing one. Entries are 32-byte hashes of the triple;
+/// once `capacity` is reached the oldest is evicted. Share one cache
+/// between threads by wrapping it in an [`Arc`](std::sync::Arc).
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use mini_bitcoin_script::engine::ExecuteOpts;
+/// use mini_bitcoin_script::sigcache::SignatureCache;
+///
+/// let cache = Arc::new(SignatureCache::new(10_000));
+/// let opts = ExecuteOpts {
+///     sig_cache: Some(Arc::clone(&cache)),
+///     ..ExecuteOpts::default()
+/// };
+/// # let _ = opts;
+/// assert_eq!((cache.hits(), cache.misses()), (0, 0));
+/// ```
+#[derive(Debug)]
+pub struct SignatureCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Cached keys, with their insertion order for eviction.
+#[derive(Debug, Default)]
+struct Entries {
+    set: HashSet<[u8; 32]>,
+    order: VecDeque<[u8; 32]>,
+}
+
+impl SignatureCache {
+    /// The capacity of [`SignatureCache::default`], in entries: about
+    /// 2 MiB of keys.
+    pub const DEFAULT_CAPACITY: usize = 1 << 16;
+
+    /// Creates an empty cache holding at most `capacity` signatures. A
+    /// capacity of zero stores nothing.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The maximum number of signatures held.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of signatures currently held.
+    pub fn len(&self) -> usize {
+        self.lock().order.len()
+    }
+
+    /// Returns `true` if no signature is held.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of checks answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of checks that had to verify the signature.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Removes every entry and resets the counters.
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.set.clear();
+        entries.order.clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    /// Returns whether `sig` is valid for `pubkey` and `sighash`, calling
+    /// `verify` only if the triple is not cached, and caching it if
+    /// `verify` accepts it.
+    ///
+    /// The engine calls this for every signature check when a cache is
+    /// set; call it directly to cache checks made outside the engine.
+    pub fn verify_with(
+        &self,
+        sighash: &[u8; 32],
+        pubkey: &[u8],
+        sig: &[u8],
+        verify: impl FnOnce() -> bool,
+    ) -> bool {
+        let key = entry_key(sighash, pubkey, sig);
+        if self.lock().set.contains(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        // Verify without holding the lock, so threads check in parallel.
+        let valid = verify();
+        if valid && self.capacity > 0 {
+            let mut entries = self.lock();
+            if entries.set.insert(key) {
+                entries.order.push_back(key);
+                if entries.order.len() > self.capacity {
+                    let oldest = entries.order.pop_front().expect("cache is over capacity");
+                    entries.set.remove(&oldest);
+                }
+            }
+        }
+        valid
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        // The entries are consistent after every statement, so a panic
+        // elsewhere while holding the lock leaves nothing to repair.
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for SignatureCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+/// SHA-256 of the length-prefixed triple, so that no two triples share a
+/// key.
+fn entry_key(sighash: &[u8; 32], pubkey: &[u8], sig: &[u8]) -> [u8; 32] {
+    let mut data = Vec::with_capacity(32 + 8 + pubkey.len() + sig.len());
+    data.extend_from_slice(sighash);
+    data.extend_from_slice(&(pubkey.len() as u32).to_le_bytes());
+    data.extend_from_slice(pubkey);
+    data.extend_from_slice(&(sig.len() as u32).to_le_bytes());
+    data.extend_from_slice(sig);
+    hash::sha256(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caches_only_valid_signatures() {
+        let cache = SignatureCache::new(4);
+        let sighash = [1; 32];
+        assert!(cache.verify_with(&sighash, b"key", b"sig", || true));
+        assert!(cache.verify_with(&sighash, b"key", b"sig", || unreachable!()));
+        assert!(!cache.verify_with(&sighash, b"key", b"bad", || false));
+        assert!(!cache.verify_with(&sighash, b"key", b"bad", || false));
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+        assert_eq!(cache.len(), 1);
+
+        // Moving bytes between the key and the signature is a new entry.
+        assert!(!cache.verify_with(&sighash, b"keys", b"ig", || false));
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!((cache.hits(), cache.misses()), (0, 0));
+    }
+
+    #[test]
+    fn evicts_oldest_entry() {
+        let cache = SignatureCache::new(2);
+        for n in 0..3u8 {
+            cache.verify_with(&[n; 32], b"key", b"sig", || true);
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.verify_with(&[0; 32], b"key", b"sig", || false));
+        assert!(cache.verify_with(&[2; 32], b"key", b"sig", || unreachable!()));
+
+        let disabled = SignatureCache::new(0);
+        disabled.verify_with(&[0; 32], b"key", b"sig", || true);
+        assert!(disabled.is_empty());
+    }
+}
@@ -0,0 +1,852 @@
+use alloc::vec::Vec;
+
+use crate::builder::ScriptBuilder;
+use crate::hash;
+use crate::signature::{SIGHASH_ANYONECANPAY, SIGHASH_NONE, SIGHASH_SINGLE};
+use crate::transaction::{
+    write_bytes, write_compact_size, write_outpoint, write_txout, Transaction, TxOut,
+};
+
+/// The value legacy signature hashing returns for SIGHASH_SINGLE without a
+/// matching output: the integer 1, little-endian. Signing it is a known
+/// consensus quirk that must be preserved.
+const UINT256_ONE: [u8; 32] = {
+    let mut one = [0u8; 32];
+    one[0] = 1;
+    one
+};
+
+/// OP_CODESEPARATOR, stripped from legacy script codes before hashing.
+const OP_CODESEPARATOR: u8 = 0xab;
+
+/// Why a signature hash could not be computed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SighashError {
+    /// The input index is not less than the number of inputs.
+    InputIndex {
+        /// Requested input index.
+        index: usize,
+        /// Number of inputs in the transaction.
+        inputs: usize,
+    },
+
+    /// Taproot hashing needs exactly one spent output per input.
+    PrevoutCount {
+        /// Number of inputs in the transaction.
+        expected: usize,
+        /// Number of spent outputs supplied.
+        actual: usize,
+    },
+
+    /// The hash type is not valid for Taproot (BIP341).
+    InvalidHashType(u8),
+
+    /// Taproot SIGHASH_SINGLE was used on an input with no matching output.
+    SingleWithoutOutput,
+}
+
+impl core::fmt::Display for SighashError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SighashError::InputIndex { index, inputs } => {
+                write!(f, "input index {index} out of range for {inputs} inputs")
+            }
+            SighashError::PrevoutCount { expected, actual } => {
+                write!(f, "expected {expected} spent outputs, got {actual}")
+            }
+            SighashError::InvalidHashType(hash_type) => {
+                write!(f, "invalid taproot sighash type 0x{hash_type:02x}")
+            }
+            SighashError::SingleWithoutOutput => {
+                write!(f, "SIGHASH_SINGLE input has no output with the same index")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SighashError {}
+
+fn check_index(tx: &Transaction, input_index: usize) -> Result<(), SighashError> {
+    if input_index < tx.inputs.len() {
+        Ok(())
+    } else {
+        Err(SighashError::InputIndex {
+            index: input_index,
+            inputs: tx.inputs.len(),
+        })
+    }
+}
+
+fn check_prevouts(tx: &Transaction, prevouts: &[TxOut]) -> Result<(), SighashError> {
+    if prevouts.len() != tx.inputs.len() {
+        return Err(SighashError::PrevoutCount {
+            expected: tx.inputs.len(),
+            actual: prevouts.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Computes the pre-segwit signature hash (Bitcoin Core's `SignatureHash`
+/// with `SigVersion::BASE`).
+///
+/// `script_code` is the script being executed: the scriptPubKey for bare
+/// scripts, or the redeem script for P2SH. OP_CODESEPARATORs are removed
+/// before hashing; the signature itself is not, so pass the script code
+/// through [`find_and_delete`] first. The full `hash_type` byte is committed to, so it must be
+/// the byte appended to the signature.
+///
+/// SIGHASH_SINGLE on an input without a matching output returns the
+/// little-endian integer 1, as consensus requires.
+///
+/// # Errors
+///
+/// Returns [`SighashError::InputIndex`] if `input_index` is out of range.
+pub fn legacy_sighash(
+    tx: &Transaction,
+    input_index: usize,
+    script_code: &[u8],
+    hash_type: u8,
+) -> Result<[u8; 32], SighashError> {
+    check_index(tx, input_index)?;
+
+    let base = hash_type & 0x1f;
+    let anyone_can_pay = hash_type & SIGHASH_ANYONECANPAY != 0;
+    if base == SIGHASH_SINGLE && input_index >= tx.outputs.len() {
+        return Ok(UINT256_ONE);
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&tx.version.to_le_bytes());
+
+    // Inputs: only ours under ANYONECANPAY; ours carries the script code,
+    // the others an empty script (and sequence 0 under NONE/SINGLE).
+    let inputs: Vec<usize> = if anyone_can_pay {
+        alloc::vec![input_index]
+    } else {
+        (0..tx.inputs.len()).collect()
+    };
+    write_compact_size(&mut out, inputs.len() as u64);
+    for i in inputs {
+        let input = &tx.inputs[i];
+        write_outpoint(&mut out, &input.previous_output);
+        if i == input_index {
+            write_bytes(&mut out, &remove_codeseparators(script_code));
+        } else {
+            write_bytes(&mut out, &[]);
+        }
+        let sequence = if i != input_index && (base == SIGHASH_NONE || base == SIGHASH_SINGLE) {
+            0
+        } else {
+            input.sequence
+        };
+        out.extend_from_slice(&sequence.to_le_bytes());
+    }
+
+    // Outputs: none under NONE; under SINGLE, blanked placeholders up to
+    // ours; otherwise all of them.
+    match base {
+        SIGHASH_NONE => write_compact_size(&mut out, 0),
+        SIGHASH_SINGLE => {
+            write_compact_size(&mut out, input_index as u64 + 1);
+            for _ in 0..input_index {
+                out.extend_from_slice(&u64::MAX.to_le_bytes());
+                write_bytes(&mut out, &[]);
+            }
+            write_txout(&mut out, &tx.outputs[input_index]);
+        }
+        _ => {
+            write_compact_size(&mut out, tx.outputs.len() as u64);
+            for output in &tx.outputs {
+                write_txout(&mut out, output);
+            }
+        }
+    }
+
+    out.extend_from_slice(&tx.lock_time.to_le_bytes());
+    out.extend_from_slice(&u32::from(hash_type).to_le_bytes());
+    Ok(hash::hash256(&out))
+}
+
+/// The transaction-wide hashes BIP143 commits to, computed once and
+/// shared by every input's signature hash.
+///
+/// Without it, hashing each input rehashes every outpoint, sequence and
+/// output, so signing or verifying all inputs is quadratic in the
+/// transaction size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegwitV0Midstate {
+    /// HASH256 of every input's outpoint.
+    pub hash_prevouts: [u8; 32],
+    /// HASH256 of every input's sequence number.
+    pub hash_sequence: [u8; 32],
+    /// HASH256 of every output.
+    pub hash_outputs: [u8; 32],
+}
+
+impl SegwitV0Midstate {
+    /// Hashes the outpoints, sequences and outputs of `tx`.
+    pub fn new(tx: &Transaction) -> Self {
+        Self {
+            hash_prevouts: hash::hash256(&prevouts_bytes(tx)),
+            hash_sequence: hash::hash256(&sequences_bytes(tx)),
+            hash_outputs: hash::hash256(&outputs_bytes(&tx.outputs)),
+        }
+    }
+}
+
+/// Computes the BIP143 signature hash for segwit v0 inputs.
+///
+/// `script_code` is the P2PKH script for the key hash when spending
+/// P2WPKH, or the witness script when spending P2WSH. `amount` is the
+/// value of the output being spent, in satoshis.
+///
+/// When hashing several inputs of one transaction, compute a
+/// [`SegwitV0Midstate`] once and use [`segwit_v0_sighash_with_midstate`].
+///
+/// # Errors
+///
+/// Returns [`SighashError::InputIndex`] if `input_index` is out of range.
+pub fn segwit_v0_sighash(
+    tx: &Transaction,
+    input_index: usize,
+    script_code: &[u8],
+    amount: u64,
+    hash_type: u8,
+) -> Result<[u8; 32], SighashError> {
+    check_index(tx, input_index)?;
+    let midstate = SegwitV0Midstate::new(tx);
+    segwit_v0_sighash_with_midstate(tx, &midstate, input_index, script_code, amount, hash_type)
+}
+
+/// [`segwit_v0_sighash`] using hashes already computed for `tx`.
+///
+/// `midstate` must come from [`SegwitV0Midstate::new`] on this same
+/// transaction; a stale one gives a wrong digest.
+///
+/// # Errors
+///
+/// Returns [`SighashError::InputIndex`] if `input_index` is out of range.
+pub fn segwit_v0_sighash_with_midstate(
+    tx: &Transaction,
+    midstate: &SegwitV0Midstate,
+    input_index: usize,
+    script_code: &[u8],
+    amount: u64,
+    hash_type: u8,
+) -> Result<[u8; 32], SighashError> {
+    check_index(tx, input_index)?;
+
+    let base = hash_type & 0x1f;
+    let anyone_can_pay = hash_type & SIGHASH_ANYONECANPAY != 0;
+    let zero = [0u8; 32];
+
+    let hash_prevouts = if anyone_can_pay {
+        zero
+    } else {
+        midstate.hash_prevouts
+    };
+    let hash_sequence = if anyone_can_pay || base == SIGHASH_SINGLE || base == SIGHASH_NONE {
+        zero
+    } else {
+        midstate.hash_sequence
+    };
+    let hash_outputs = if base != SIGHASH_SINGLE && base != SIGHASH_NONE {
+        midstate.hash_outputs
+    } else if base == SIGHASH_SINGLE && input_index < tx.outputs.len() {
+        hash::hash256(&outputs_bytes(&tx.outputs[input_index..=input_index]))
+    } else {
+        zero
+    };
+
+    let input = &tx.inputs[input_index];
+    let mut out = Vec::new();
+    out.extend_from_slice(&tx.version.to_le_bytes());
+    out.extend_from_slice(&hash_prevouts);
+    out.extend_from_slice(&hash_sequence);
+    write_outpoint(&mut out, &input.previous_output);
+    write_bytes(&mut out, script_code);
+    out.extend_from_slice(&amount.to_le_bytes());
+    out.extend_from_slice(&input.sequence.to_le_bytes());
+    out.extend_from_slice(&hash_outputs);
+    out.extend_from_slice(&tx.lock_time.to_le_bytes());
+    out.extend_from_slice(&u32::from(hash_type).to_le_bytes());
+    Ok(hash::hash256(&out))
+}
+
+/// The transaction-wide hashes BIP341 commits to, computed once and
+/// shared by every Taproot input's signature hash.
+///
+/// Like [`SegwitV0Midstate`], it keeps verifying every input linear in the
+/// transaction size instead of quadratic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaprootMidstate {
+    /// SHA256 of every input's outpoint.
+    pub sha_prevouts: [u8; 32],
+    /// SHA256 of the amount of every output spent.
+    pub sha_amounts: [u8; 32],
+    /// SHA256 of the scriptPubKey of every output spent.
+    pub sha_script_pubkeys: [u8; 32],
+    /// SHA256 of every input's sequence number.
+    pub sha_sequences: [u8; 32],
+    /// SHA256 of every output.
+    pub sha_outputs: [u8; 32],
+}
+
+impl TaprootMidstate {
+    /// Hashes the outpoints, sequences and outputs of `tx` and the outputs
+    /// it spends, `prevouts`, in input order.
+    pub fn new(tx: &Transaction, prevouts: &[TxOut]) -> Self {
+        let mut amounts = Vec::with_capacity(prevouts.len() * 8);
+        let mut scripts = Vec::new();
+        for prevout in prevouts {
+            amounts.extend_from_slice(&prevout.value.to_le_bytes());
+            write_bytes(&mut scripts, &prevout.script_pubkey);
+        }
+        Self {
+            sha_prevouts: hash::sha256(&prevouts_bytes(tx)),
+            sha_amounts: hash::sha256(&amounts),
+            sha_script_pubkeys: hash::sha256(&scripts),
+            sha_sequences: hash::sha256(&sequences_bytes(tx)),
+            sha_outputs: hash::sha256(&outputs_bytes(&tx.outputs)),
+        }
+    }
+}
+
+/// Computes the BIP341 signature hash for a Taproot key path spend.
+///
+/// `prevouts` holds the output spent by every input, in input order;
+/// Taproot signatures commit to all of their amounts and scripts. Annexes
+/// are not supported. `hash_type` 0x00 (SIGHASH_DEFAULT) behaves like
+/// SIGHASH_ALL and is signalled by a 64-byte signature.
+///
+/// When hashing several inputs of one transaction, compute a
+/// [`TaprootMidstate`] once and use
+/// [`taproot_key_spend_sighash_with_midstate`].
+///
+/// # Errors
+///
+/// Returns [`SighashError::InputIndex`], [`SighashError::PrevoutCount`],
+/// [`SighashError::InvalidHashType`] for anything but 0x00–0x03 and
+/// 0x81–0x83, and [`SighashError::SingleWithoutOutput`].
+pub fn taproot_key_spend_sighash(
+    tx: &Transaction,
+    input_index: usize,
+    prevouts: &[TxOut],
+    hash_type: u8,
+) -> Result<[u8; 32], SighashError> {
+    check_index(tx, input_index)?;
+    check_prevouts(tx, prevouts)?;
+    let midstate = TaprootMidstate::new(tx, prevouts);
+    taproot_key_spend_sighash_with_midstate(tx, &midstate, input_index, prevouts, hash_type)
+}
+
+/// [`taproot_key_spend_sighash`] using hashes already computed for `tx`
+/// and `prevouts`.
+///
+/// `midstate` must come from [`TaprootMidstate::new`] on this same
+/// transaction and prevouts; a stale one gives a wrong digest.
+///
+/// # Errors
+///
+/// As [`taproot_key_spend_sighash`].
+pub fn taproot_key_spend_sighash_with_midstate(
+    tx: &Transaction,
+    midstate: &TaprootMidstate,
+    input_index: usize,
+    prevouts: &[TxOut],
+    hash_type: u8,
+) -> Result<[u8; 32], SighashError> {
+    check_index(tx, input_index)?;
+    check_prevouts(tx, prevouts)?;
+    if !matches!(hash_type, 0x00..=0x03 | 0x81..=0x83) {
+        return Err(SighashError::InvalidHashType(hash_type));
+    }
+
+    let output_type = if hash_type == 0x00 {
+        0x01
+    } else {
+        hash_type & 0x03
+    };
+    let anyone_can_pay = hash_type & SIGHASH_ANYONECANPAY != 0;
+
+    // Epoch byte, then SigMsg.
+    let mut out = alloc::vec![0x00, hash_type];
+    out.extend_from_slice(&tx.version.to_le_bytes());
+    out.extend_from_slice(&tx.lock_time.to_le_bytes());
+
+    if !anyone_can_pay {
+        out.extend_from_slice(&midstate.sha_prevouts);
+        out.extend_from_slice(&midstate.sha_amounts);
+        out.extend_from_slice(&midstate.sha_script_pubkeys);
+        out.extend_from_slice(&midstate.sha_sequences);
+    }
+    if output_type != SIGHASH_NONE && output_type != SIGHASH_SINGLE {
+        out.extend_from_slice(&midstate.sha_outputs);
+    }
+
+    // spend_type: key path (ext_flag 0), no annex.
+    out.push(0x00);
+
+    let input = &tx.inputs[input_index];
+    if anyone_can_pay {
+        let prevout = &prevouts[input_index];
+        write_outpoint(&mut out, &input.previous_output);
+        out.extend_from_slice(&prevout.value.to_le_bytes());
+        write_bytes(&mut out, &prevout.script_pubkey);
+        out.extend_from_slice(&input.sequence.to_le_bytes());
+    } else {
+        out.extend_from_slice(&(input_index as u32).to_le_bytes());
+    }
+
+    if output_type == SIGHASH_SINGLE {
+        let output = tx
+            .outputs
+            .get(input_index)
+            .ok_or(SighashError::SingleWithoutOutput)?;
+        out.extend_from_slice(&hash::sha256(&outputs_bytes(core::slice::from_ref(output))));
+    }
+
+    Ok(hash::tagged_hash("TapSighash", &out))
+}
+
+fn prevouts_bytes(tx: &Transaction) -> Vec<u8> {
+    let mut out = Vec::with_capacity(tx.inputs.len() * 36);
+    for input in &tx.inputs {
+        write_outpoint(&mut out, &input.previous_output);
+    }
+    out
+}
+
+fn sequences_bytes(tx: &Transaction) -> Vec<u8> {
+    let mut out = Vec::with_capacity(tx.inputs.len() * 4);
+    for input in &tx.inputs {
+        out.extend_from_slice(&input.sequence.to_le_bytes());
+    }
+    out
+}
+
+fn outputs_bytes(outputs: &[TxOut]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for output in outputs {
+        write_txout(&mut out, output);
+    }
+    out
+}
+
+/// Copies `script` without its OP_CODESEPARATORs, stepping over push data
+/// so that 0xab bytes inside pushes are kept. A truncated trailing push is
+/// copied as-is.
+fn remove_codeseparators(script: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(script.len());
+    let mut pos = 0;
+    while pos < script.len() {
+        let end = instruction_end(script, pos);
+        if script[pos] != OP_CODESEPARATOR {
+            out.extend_from_slice(&script[pos..end]);
+        }
+        pos = end;
+    }
+    out
+}
+
+/// Removes every push of `sig` from `script_code`, as legacy
+/// OP_CHECKSIG(VERIFY) and OP_CHECKMULTISIG(VERIFY) do before hashing
+/// (Bitcoin Core's `FindAndDelete`).
+///
+/// The pattern is `sig` pushed with the shortest length encoding, and only
+/// matches that start on an instruction boundary are removed, so the same
+/// bytes inside a larger push are kept. Segwit and Taproot sighashes skip
+/// this step.
+pub fn find_and_delete(script_code: &[u8], sig: &[u8]) -> Vec<u8> {
+    let pattern = ScriptBuilder::new().push_slice(sig).into_bytes();
+    let mut out = Vec::with_capacity(script_code.len());
+    let mut pos = 0;
+    while pos < script_code.len() {
+        if script_code[pos..].starts_with(&pattern) {
+            pos += pattern.len();
+            continue;
+        }
+        let end = instruction_end(script_code, pos);
+        out.extend_from_slice(&script_code[pos..end]);
+        pos = end;
+    }
+    out
+}
+
+/// The end of the instruction starting at `pos`, clamped to the script
+/// length for truncated pushes.
+fn instruction_end(script: &[u8], pos: usize) -> usize {
+    let opcode = script[pos];
+    let (header, data_len) = match opcode {
+        0x01..=0x4b => (1, opcode as usize),
+        0x4c if pos + 1 < script.len() => (2, script[pos + 1] as usize),
+        0x4d if pos + 2 < script.len() => (
+            3,
+            u16::from_le_bytes([script[pos + 1], script[pos + 2]]) as usize,
+        ),
+        0x4e if pos + 4 < script.len() => {
+            let len = [
+                script[pos + 1],
+                script[pos + 2],
+                script[pos + 3],
+                script[pos + 4],
+            ];
+            (5, u32::from_le_bytes(len) as usize)
+        }
+        0x4c..=0x4e => (script.len() - pos, 0),
+        _ => (1, 0),
+    };
+    (pos + header + data_len).min(script.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::{OutPoint, TxIn};
+    use alloc::vec;
+    use hex_literal::hex;
+
+    /// The unsigned transaction from BIP143's native P2WPKH example.
+    fn bip143_p2wpkh_tx() -> Transaction {
+        Transaction {
+            version: 1,
+            inputs: vec![
+                TxIn {
+                    previous_output: OutPoint {
+                        txid: hex!(
+                            "fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f"
+                        ),
+                        vout: 0,
+                    },
+                    script_sig: vec![],
+                    sequence: 0xffff_ffee,
+                    witness: vec![],
+                },
+                TxIn {
+                    previous_output: OutPoint {
+                        txid: hex!(
+                            "ef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a"
+                        ),
+                        vout: 1,
+                    },
+                    script_sig: vec![],
+                    sequence: 0xffff_ffff,
+                    witness: vec![],
+                },
+            ],
+            outputs: vec![
+                TxOut {
+                    value: 112_340_000,
+                    script_pubkey: hex!("76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac")
+                        .to_vec(),
+                },
+                TxOut {
+                    value: 223_450_000,
+                    script_pubkey: hex!("76a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac")
+                        .to_vec(),
+                },
+            ],
+            lock_time: 17,
+        }
+    }
+
+    #[test]
+    fn bip143_native_p2wpkh() {
+        let tx = bip143_p2wpkh_tx();
+        let script_code = hex!("76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac");
+        let sighash = segwit_v0_sighash(&tx, 1, &script_code, 600_000_000, 0x01).unwrap();
+        assert_eq!(
+            sighash,
+            hex!("c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670")
+        );
+    }
+
+    #[test]
+    fn bip143_midstate() {
+        let tx = bip143_p2wpkh_tx();
+        let midstate = SegwitV0Midstate::new(&tx);
+        assert_eq!(
+            midstate.hash_prevouts,
+            hex!("96b827c8483d4e9b96712b6713a7b68d6e8003a781feba36c31143470b4efd37")
+        );
+        assert_eq!(
+            midstate.hash_sequence,
+            hex!("52b0a642eea2fb7ae638c36f6252b6750293dbe574a806984b8e4d8548339a3b")
+        );
+        assert_eq!(
+            midstate.hash_outputs,
+            hex!("863ef3e1a92afbfdb97f31ad0fc7683ee943e9abcf2501590ff8f6551f47e5e5")
+        );
+        let script_code = hex!("76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac");
+        for hash_type in [0x01, 0x02, 0x03, 0x81, 0x82, 0x83] {
+            assert_eq!(
+                segwit_v0_sighash_with_midstate(&tx, &midstate, 1, &script_code, 1, hash_type),
+                segwit_v0_sighash(&tx, 1, &script_code, 1, hash_type)
+            );
+        }
+    }
+
+    #[test]
+    fn legacy_single_without_output_is_one() {
+        let mut tx = bip143_p2wpkh_tx();
+        tx.outputs.truncate(1);
+        let sighash = legacy_sighash(&tx, 1, &[], SIGHASH_SINGLE).unwrap();
+        assert_eq!(sighash, UINT256_ONE);
+    }
+
+    #[test]
+    fn legacy_ignores_codeseparators() {
+        let tx = bip143_p2wpkh_tx();
+        let plain = legacy_sighash(&tx, 0, &hex!("51 52"), 0x01).unwrap();
+        let separated = legacy_sighash(&tx, 0, &hex!("51 ab 52"), 0x01).unwrap();
+        assert_eq!(plain, separated);
+    }
+
+    #[test]
+    fn codeseparator_inside_push_is_kept() {
+        assert_eq!(
+            remove_codeseparators(&hex!("ab 02ab ab ab")),
+            hex!("02abab")
+        );
+        assert_eq!(remove_codeseparators(&hex!("4c")), hex!("4c"));
+    }
+
+    #[test]
+    fn find_and_delete_removes_aligned_pushes() {
+        // Both consecutive pushes of the signature go; the copy inside the
+        // OP_PUSHDATA1 push and the unaligned bytes stay.
+        assert_eq!(
+            find_and_delete(&hex!("02abcd 02abcd 51 4c02abcd 0302abcd"), &hex!("abcd")),
+            hex!("51 4c02abcd 0302abcd")
+        );
+        assert_eq!(
+            find_and_delete(&hex!("51 52"), &hex!("abcd")),
+            hex!("51 52")
+        );
+    }
+
+    #[test]
+    fn legacy_commits_to_hash_type() {
+        let tx = bip143_p2wpkh_tx();
+        let all = legacy_sighash(&tx, 0, &[0x51], 0x01).unwrap();
+        let none = legacy_sighash(&tx, 0, &[0x51], 0x02).unwrap();
+        let acp = legacy_sighash(&tx, 0, &[0x51], 0x81).unwrap();
+        assert_ne!(all, none);
+        assert_ne!(all, acp);
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        let tx = bip143_p2wpkh_tx();
+        let err = legacy_sighash(&tx, 2, &[], 0x01).unwrap_err();
+        assert_eq!(
+            err,
+            SighashError::InputIndex {
+                index: 2,
+                inputs: 2
+            }
+        );
+        assert!(segwit_v0_sighash(&tx, 5, &[], 0, 0x01).is_err());
+    }
+
+    #[test]
+    fn taproot_argument_checks() {
+        let tx = bip143_p2wpkh_tx();
+        let prevouts = vec![
+            TxOut {
+                value: 1,
+                script_pubkey: vec![],
+            };
+            2
+        ];
+        assert_eq!(
+            taproot_key_spend_sighash(&tx, 0, &prevouts[..1], 0x00),
+            Err(SighashError::PrevoutCount {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            taproot_key_spend_sighash(&tx, 0, &prevouts, 0x04),
+            Err(SighashError::InvalidHashType(0x04))
+        );
+
+        // SIGHASH_DEFAULT and SIGHASH_ALL commit to the same data but differ
+        // in the hash type byte itself.
+        let default = taproot_key_spend_sighash(&tx, 0, &prevouts, 0x00).unwrap();
+        let all = taproot_key_spend_sighash(&tx, 0, &prevouts, 0x01).unwrap();
+        assert_ne!(default, all);
+
+        let mut single = tx.clone();
+        single.outputs.truncate(1);
+        assert_eq!(
+            taproot_key_spend_sighash(&single, 1, &prevouts, 0x03),
+            Err(SighashError::SingleWithoutOutput)
+        );
+    }
+
+    #[test]
+    fn taproot_midstate() {
+        let tx = bip143_p2wpkh_tx();
+        let prevouts = vec![
+            TxOut {
+                value: 1,
+                script_pubkey: vec![0x51, 0x20],
+            },
+            TxOut {
+                value: 2,
+                script_pubkey: vec![0x00],
+            },
+        ];
+        let midstate = TaprootMidstate::new(&tx, &prevouts);
+        assert_eq!(midstate.sha_prevouts, hash::sha256(&prevouts_bytes(&tx)));
+        for index in 0..2 {
+            for hash_type in [0x00, 0x01, 0x02, 0x03, 0x81, 0x82, 0x83] {
+                assert_eq!(
+                    taproot_key_spend_sighash_with_midstate(
+                        &tx, &midstate, index, &prevouts, hash_type
+                    ),
+                    taproot_key_spend_sighash(&tx, index, &prevouts, hash_type)
+                );
+            }
+        }
+        assert_eq!(
+            taproot_key_spend_sighash_with_midstate(&tx, &midstate, 0, &prevouts[..1], 0x00),
+            Err(SighashError::PrevoutCount {
+                expected: 2,
+                actual: 1
+            })
+        );
+    }
+
+    /// The transaction and spent outputs of BIP341's `keyPathSpending`
+    /// test vector.
+    fn bip341_key_path_vector() -> (Transaction, Vec<TxOut>) {
+        let tx = Transaction::deserialize(&hex!(
+            "02000000097de20cbff686da83a54981d2b9bab3586f4ca7e48f57f5b55963115f3b334e9c010000000000000000d7b7cab57b1393ace2d064f4d4a2cb8af6def61273e127517d44759b6dafdd990000000000fffffffff8e1f583384333689228c5d28eac13366be082dc57441760d957275419a418420000000000fffffffff0689180aa63b30cb162a73c6d2a38b7eeda2a83ece74310fda0843ad604853b0100000000feffffffaa5202bdf6d8ccd2ee0f0202afbbb7461d9264a25e5bfd3c5a52ee1239e0ba6c0000000000feffffff956149bdc66faa968eb2be2d2faa29718acbfe3941215893a2a3446d32acd050000000000000000000e664b9773b88c09c32cb70a2a3e4da0ced63b7ba3b22f848531bbb1d5d5f4c94010000000000000000e9aa6b8e6c9de67619e6a3924ae25696bb7b694bb677a632a74ef7eadfd4eabf0000000000ffffffffa778eb6a263dc090464cd125c466b5a99667720b1c110468831d058aa1b82af10100000000ffffffff0200ca9a3b000000001976a91406afd46bcdfd22ef94ac122aa11f241244a37ecc88ac807840cb0000000020ac9a87f5594be208f8532db38cff670c450ed2fea8fcdefcc9a663f78bab962b0065cd1d"
+        ))
+        .unwrap();
+        let prevouts = [
+            (
+                &hex!("512053a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343")[..],
+                420_000_000,
+            ),
+            (
+                &hex!("5120147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3"),
+                462_000_000,
+            ),
+            (
+                &hex!("76a914751e76e8199196d454941c45d1b3a323f1433bd688ac"),
+                294_000_000,
+            ),
+            (
+                &hex!("5120e4d810fd50586274face62b8a807eb9719cef49c04177cc6b76a9a4251d5450e"),
+                504_000_000,
+            ),
+            (
+                &hex!("512091b64d5324723a985170e4dc5a0f84c041804f2cd12660fa5dec09fc21783605"),
+                630_000_000,
+            ),
+            (
+                &hex!("00147dd65592d0ab2fe0d0257d571abf032cd9db93dc"),
+                378_000_000,
+            ),
+            (
+                &hex!("512075169f4001aa68f15bbed28b218df1d0a62cbbcf1188c6665110c293c907b831"),
+                672_000_000,
+            ),
+            (
+                &hex!("5120712447206d7a5238acc7ff53fbe94a3b64539ad291c7cdbc490b7577e4b17df5"),
+                546_000_000,
+            ),
+            (
+                &hex!("512077e30a5522dd9f894c3f8b8bd4c4b2cf82ca7da8a3ea6a239655c39c050ab220"),
+                588_000_000,
+            ),
+        ]
+        .iter()
+        .map(|(script, value)| TxOut {
+            value: *value,
+            script_pubkey: script.to_vec(),
+        })
+        .collect();
+        (tx, prevouts)
+    }
+
+    #[test]
+    fn bip341_key_path_intermediary_hashes() {
+        let (tx, prevouts) = bip341_key_path_vector();
+        let midstate = TaprootMidstate::new(&tx, &prevouts);
+        assert_eq!(
+            midstate.sha_prevouts,
+            hex!("e3b33bb4ef3a52ad1fffb555c0d82828eb22737036eaeb02a235d82b909c4c3f")
+        );
+        assert_eq!(
+            midstate.sha_amounts,
+            hex!("58a6964a4f5f8f0b642ded0a8a553be7622a719da71d1f5befcefcdee8e0fde6")
+        );
+        assert_eq!(
+            midstate.sha_script_pubkeys,
+            hex!("23ad0f61ad2bca5ba6a7693f50fce988e17c3780bf2b1e720cfbb38fbdd52e21")
+        );
+        assert_eq!(
+            midstate.sha_sequences,
+            hex!("18959c7221ab5ce9e26c3cd67b22c24f8baa54bac281d8e6b05e400e6c3a957e")
+        );
+        assert_eq!(
+            midstate.sha_outputs,
+            hex!("a2e6dab7c1f0dcd297c8d61647fd17d821541ea69c3cc37dcbad7f90d4eb4bc5")
+        );
+    }
+
+    #[test]
+    fn bip341_key_path_sighashes() {
+        let (tx, prevouts) = bip341_key_path_vector();
+
+        // The vector's sigMsg for input 0, including the epoch byte.
+        let sig_msg = hex!(
+            "0003020000000065cd1de3b33bb4ef3a52ad1fffb555c0d82828eb22737036eaeb02a235d82b909c4c3f58a6964a4f5f8f0b642ded0a8a553be7622a719da71d1f5befcefcdee8e0fde623ad0f61ad2bca5ba6a7693f50fce988e17c3780bf2b1e720cfbb38fbdd52e2118959c7221ab5ce9e26c3cd67b22c24f8baa54bac281d8e6b05e400e6c3a957e0000000000d0418f0e9a36245b9a50ec87f8bf5be5bcae434337b87139c3a5b1f56e33cba0"
+        );
+        assert_eq!(
+            taproot_key_spend_sighash(&tx, 0, &prevouts, 0x03).unwrap(),
+            hash::tagged_hash("TapSighash", &sig_msg)
+        );
+
+        // Inputs 0 (SIGHASH_SINGLE) and 1 (SIGHASH_SINGLE|ANYONECANPAY).
+        let cases: [(usize, u8, [u8; 32]); 2] = [
+            (
+                0,
+                0x03,
+                hex!("2514a6272f85cfa0f45eb907fcb0d121b808ed37c6ea160a5a9046ed5526d555"),
+            ),
+            (
+                1,
+                0x83,
+                hex!("325a644af47e8a5a2591cda0ab0723978537318f10e6a63d4eed783b96a71a4d"),
+            ),
+        ];
+        let midstate = TaprootMidstate::new(&tx, &prevouts);
+        for (index, hash_type, expected) in cases {
+            assert_eq!(
+                taproot_key_spend_sighash(&tx, index, &prevouts, hash_type).unwrap(),
+                expected,
+                "input {index}"
+            );
+            assert_eq!(
+                taproot_key_spend_sighash_with_midstate(
+                    &tx, &midstate, index, &prevouts, hash_type
+                )
+                .unwrap(),
+                expected,
+                "input {index} with midstate"
+            );
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use secp256k1::{Keypair, Message, PublicKey, Scalar, Secp256k1, SecretKey, XOnlyPublicKey};
+
+use crate::builder::{self, ScriptBuilder};
+use crate::hash;
+use crate::opcode::Opcode;
+use crate::sighash::{self, SighashError};
+use crate::signature::is_defined_hashtype;
+use crate::token::Token;
+use crate::tokenizer::parse_script;
+use crate::transaction::{Transaction, TxOut};
+
+/// Why an input could not be signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignError {
+    /// The signature hash could not be computed.
+    Sighash(SighashError),
+
+    /// The hash type is not a defined ECDSA sighash type.
+    InvalidHashType(u8),
+
+    /// The spent output does not pay to the given key or script.
+    PrevoutMismatch,
+
+    /// The redeem script is not a standard `OP_m <pubkeys> OP_n
+    /// OP_CHECKMULTISIG` script.
+    NotMultisig,
+
+    /// Fewer keys matching the redeem script were given than it requires.
+    NotEnoughKeys {
+        /// Signatures the redeem script requires.
+        required: usize,
+        /// Matching keys supplied.
+        provided: usize,
+    },
+}
+
+impl From<SighashError> for SignError {
+    fn from(err: SighashError) -> Self {
+        SignError::Sighash(err)
+    }
+}
+
+impl core::fmt::Display for SignError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SignError::Sighash(err) => write!(f, "sighash: {err}"),
+            SignError::InvalidHashType(hash_type) => {
+                write!(f, "undefined sighash type 0x{hash_type:02x}")
+            }
+            SignError::PrevoutMismatch => {
+                write!(f, "spent output does not pay to the signing key or script")
+            }
+            SignError::NotMultisig => write!(f, "redeem script is not a multisig script"),
+            SignError::NotEnoughKeys { required, provided } => {
+                write!(f, "multisig needs {required} keys, {provided} supplied")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignError {}
+
+/// Signs a 32-byte sighash with ECDSA and returns the DER signature with
+/// `hash_type` appended, ready to push in a scriptSig or witness.
+///
+/// Nonces are derived deterministically (RFC 6979) and S is always low,
+/// so the result passes the DERSIG, LOW_S and STRICTENC rules.
+pub fn sign_ecdsa(secret_key: &SecretKey, sighash: &[u8; 32], hash_type: u8) -> Vec<u8> {
+    let secp = Secp256k1::signing_only();
+    let signature = secp.sign_ecdsa(&Message::from_digest(*sighash), secret_key);
+    let mut bytes = signature.serialize_der().to_vec();
+    bytes.push(hash_type);
+    bytes
+}
+
+/// Signs a 32-byte sighash with BIP340 Schnorr.
+///
+/// Returns 64 bytes for SIGHASH_DEFAULT (0x00) and 65 bytes — the
+/// signature plus `hash_type` — otherwise. No auxiliary randomness is
+/// used, so the output is deterministic.
+pub fn sign_schnorr(keypair: &Keypair, sighash: &[u8; 32], hash_type: u8) -> Vec<u8> {
+    let secp = Secp256k1::signing_only();
+    let signature = secp.sign_schnorr_no_aux_rand(&Message::from_digest(*sighash), keypair);
+    let mut bytes = signature.serialize().to_vec();
+    if hash_type != 0x00 {
+        bytes.push(hash_type);
+    }
+    bytes
+}
+
+/// Returns the BIP341 tweak for an internal key and optional script tree
+/// merkle root.
+fn tap_tweak(internal_key: &XOnlyPublicKey, merkle_root: Option<&[u8; 32]>) -> Scalar {
+    let mut data = internal_key.serialize().to_vec();
+    if let Some(root) = merkle_root {
+        data.extend_from_slice(root);
+    }
+    let tweak = hash::tagged_hash("TapTweak", &data);
+    // A tagged hash exceeding the curve order has negligible probability.
+    Scalar::from_be_bytes(tweak).expect("tap tweak within curve order")
+}
+
+/// Computes the Taproot output key for `internal_key` with no script tree
+/// (BIP86): `internal_key + H_TapTweak(internal_key)·G`.
+pub fn taproot_output_key(internal_key: &XOnlyPublicKey) -> XOnlyPublicKey {
+    taproot_script_output_key(internal_key, None)
+}
+
+/// Computes the Taproot output key for `internal_key` committing to a
+/// script tree with the given merkle root:
+/// `internal_key + H_TapTweak(internal_key || merkle_root)·G`. With no
+/// root this is [`taproot_output_key`].
+pub fn taproot_script_output_key(
+    internal_key: &XOnlyPublicKey,
+    merkle_root: Option<&[u8; 32]>,
+) -> XOnlyPublicKey {
+    let secp = Secp256k1::verification_only();
+    let (output_key, _parity) = internal_key
+        .add_tweak(&secp, &tap_tweak(internal_key, merkle_root))
+        .expect("tweaked key is valid");
+    output_key
+}
+
+/// Returns the keypair that signs for [`taproot_output_key`] of the
+/// secret key's x-only public key.
+pub fn taproot_tweaked_keypair(secret_key: &SecretKey) -> Keypair {
+    let secp = Secp256k1::new();
+    let keypair = Keypair::from_secret_key(&secp, secret_key);
+    let (internal_key, _parity) = keypair.x_only_public_key();
+    keypair
+        .add_xonly_tweak(&secp, &tap_tweak(&internal_key, None))
+        .expect("tweaked key is valid")
+}
+
+fn compressed_pubkey(secret_key: &SecretKey) -> [u8; 33] {
+    let secp = Secp256k1::signing_only();
+    PublicKey::from_secret_key(&secp, secret_key).serialize()
+}
+
+fn check_ecdsa_hash_type(hash_type: u8) -> Result<(), SignError> {
+    if is_defined_hashtype(&[hash_type]) {
+        Ok(())
+    } else {
+        Err(SignError::InvalidHashType(hash_type))
+    }
+}
+
+/// Signs a P2PKH input: sets its scriptSig to `<sig> <pubkey>`.
+///
+/// The compressed public key of `secret_key` must hash to the key hash in
+/// `prevout`.
+///
+/// # Errors
+///
+/// [`SignError::InvalidHashType`], [`SignError::PrevoutMismatch`], or
+/// [`SignError::Sighash`] for an out-of-range input index.
+pub fn sign_p2pkh(
+    tx: &mut Transaction,
+    input_index: usize,
+    secret_key: &SecretKey,
+    prevout: &TxOut,
+    hash_type: u8,
+) -> Result<(), SignError> {
+    check_ecdsa_hash_type(hash_type)?;
+    let pubkey = compressed_pubkey(secret_key);
+    let script_pubkey = builder::p2pkh(&hash::hash160(&pubkey));
+    if prevout.script_pubkey != script_pubkey {
+        return Err(SignError::PrevoutMismatch);
+    }
+
+    let sighash = sighash::legacy_sighash(tx, input_index, &script_pubkey, hash_type)?;
+    let signature = sign_ecdsa(secret_key, &sighash, hash_type);
+    tx.inputs[input_index].script_sig = ScriptBuilder::new()
+        .push_slice(&signature)
+        .push_slice(&pubkey)
+        .into_bytes();
+    Ok(())
+}
+
+/// Signs a P2WPKH input: empties its scriptSig and sets its witness to
+/// `[sig, pubkey]`.
+///
+/// # Errors
+///
+/// [`SignError::InvalidHashType`], [`SignError::PrevoutMismatch`], or
+/// [`SignError::Sighash`] for an out-of-range input index.
+pub fn sign_p2wpkh(
+    tx: &mut Transaction,
+    input_index: usize,
+    secret_key: &SecretKey,
+    prevout: &TxOut,
+    hash_type: u8,
+) -> Result<(), SignError> {
+    check_ecdsa_hash_type(hash_type)?;
+    let pubkey = compressed_pubkey(secret_key);
+    let pubkey_hash = hash::hash160(&pubkey);
+    if prevout.script_pubkey != builder::p2wpkh(&pubkey_hash) {
+        return Err(SignError::PrevoutMismatch);
+    }
+
+    // BIP143: the script code for P2WPKH is the equivalent P2PKH script.
+    let script_code = builder::p2pkh(&pubkey_hash);
+    let sighash =
+        sighash::segwit_v0_sighash(tx, input_index, &script_code, prevout.value, hash_type)?;
+    let signature = sign_ecdsa(secret_key, &sighash, hash_type);
+
+    let input = &mut tx.inputs[input_index];
+    input.script_sig.clear();
+    input.witness = vec![signature, pubkey.to_vec()];
+    Ok(())
+}
+
+/// Signs a P2SH-wrapped bare multisig input: sets its scriptSig to
+/// `OP_0 <sig>... <redeem_script>`.
+///
+/// `secret_keys` may be given in any order and may include keys the
+/// redeem script does not use; signatures are placed in the order of the
+/// script's public keys (compressed or uncompressed), as OP_CHECKMULTISIG
+/// requires. The leading OP_0 is the dummy element consumed by its
+/// off-by-one bug.
+///
+/// # Errors
+///
+/// [`SignError::NotMultisig`], [`SignError::NotEnoughKeys`],
+/// [`SignError::InvalidHashType`], [`SignError::PrevoutMismatch`], or
+/// [`SignError::Sighash`] for an out-of-range input index.
+pub fn sign_p2sh_multisig(
+    tx: &mut Transaction,
+    input_index: usize,
+    secret_keys: &[SecretKey],
+    redeem_script: &[u8],
+    prevout: &TxOut,
+    hash_type: u8,
+) -> Result<(), SignError> {
+    check_ecdsa_hash_type(hash_type)?;
+    if prevout.script_pubkey != builder::p2sh(&hash::hash160(redeem_script)) {
+        return Err(SignError::PrevoutMismatch);
+    }
+    let (required, pubkeys) = parse_multisig(redeem_script).ok_or(SignError::NotMultisig)?;
+
+    let secp = Secp256k1::signing_only();
+    let signers: Vec<&SecretKey> = pubkeys
+        .iter()
+        .filter_map(|pubkey| {
+            secret_keys.iter().find(|key| {
+                let public = PublicKey::from_secret_key(&secp, key);
+                public.serialize()[..] == pubkey[..]
+                    || public.serialize_uncompressed()[..] == pubkey[..]
+            })
+        })
+        .take(required)
+        .collect();
+    if signers.len() < required {
+        return Err(SignError::NotEnoughKeys {
+            required,
+            provided: signers.len(),
+        });
+    }
+
+    let sighash = sighash::legacy_sighash(tx, input_index, redeem_script, hash_type)?;
+    let mut script_sig = ScriptBuilder::new().push_opcode(Opcode::Op0);
+    for key in signers {
+        script_sig = script_sig.push_slice(&sign_ecdsa(key, &sighash, hash_type));
+    }
+    tx.inputs[input_index].script_sig = script_sig.push_slice(redeem_script).into_bytes();
+    Ok(())
+}
+
+/// Signs a Taproot key path input (BIP86, no script tree): empties its
+/// scriptSig and sets its witness to the single Schnorr signature.
+///
+/// `prevouts` holds the output spent by every input, in input order.
+///
+/// # Errors
+///
+/// [`SignError::PrevoutMismatch`] if `prevouts[input_index]` does not pay
+/// to the tweaked key, or [`SignError::Sighash`] for invalid arguments.
+pub fn sign_p2tr_key_path(
+    tx: &mut Transaction,
+    input_index: usize,
+    secret_key: &SecretKey,
+    prevouts: &[TxOut],
+    hash_type: u8,
+) -> Result<(), SignError> {
+    let keypair = taproot_tweaked_keypair(secret_key);
+    let (output_key, _parity) = keypair.x_only_public_key();
+    match prevouts.get(input_index) {
+        Some(prevout) if prevout.script_pubkey == builder::p2tr(&output_key.serialize()) => {}
+        Some(_) => return Err(SignError::PrevoutMismatch),
+        None => {}
+    }
+
+    let sighash = sighash::taproot_key_spend_sighash(tx, input_index, prevouts, hash_type)?;
+    let signature = sign_schnorr(&keypair, &sighash, hash_type);
+
+    let input = &mut tx.inputs[input_index];
+    input.script_sig.clear();
+    input.witness = vec![signature];
+    Ok(())
+}
+
+/// Splits `OP_m <pubkey>... OP_n OP_CHECKMULTISIG` into `m` and the keys.
+fn parse_multisig(script: &[u8]) -> Option<(usize, Vec<Vec<u8>>)> {
+    let tokens = parse_script(script).ok()?;
+    let (last, tokens) = tokens.split_last()?;
+    if *last != Token::Op(Opcode::OpCheckMultiSig) {
+        return None;
+    }
+    let small_int = |token: &Token| match token {
+        Token::Op(op) => match op.to_byte() {
+            byte @ 0x51..=0x60 => Some((byte - 0x50) as usize),
+            _ => None,
+        },
+        Token::PushData(_) => None,
+    };
+
+    let (first, rest) = tokens.split_first()?;
+    let (last, keys) = rest.split_last()?;
+    let required = small_int(first)?;
+    let total = small_int(last)?;
+    let pubkeys: Vec<Vec<u8>> = keys
+        .iter()
+        .map(|token| match token {
+            Token::PushData(data) => Some(data.clone()),
+            Token::Op(_) => None,
+        })
+        .collect::<Option<_>>()?;
+    if pubkeys.len() != total || required > total {
+        return None;
+    }
+    Some((required, pubkeys))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_multisig_roundtrip() {
+        let a = [0x02; 33];
+        let b = [0x03; 33];
+        let script = builder::multisig(2, &[&a, &b]).unwrap();
+        let (required, keys) = parse_multisig(&script).unwrap();
+        assert_eq!(required, 2);
+        assert_eq!(keys, vec![a.to_vec(), b.to_vec()]);
+
+        assert!(parse_multisig(&script[..script.len() - 1]).is_none());
+        assert!(parse_multisig(&builder::p2pkh(&[0; 20])).is_none());
+    }
+
+    #[test]
+    fn schnorr_signature_length_follows_hash_type() {
+        let key = SecretKey::from_slice(&[0x01; 32]).unwrap();
+        let keypair = taproot_tweaked_keypair(&key);
+        assert_eq!(sign_schnorr(&keypair, &[0; 32], 0x00).len(), 64);
+        assert_eq!(sign_schnorr(&keypair, &[0; 32], 0x01).len(), 65);
+    }
+
+    #[test]
+    fn rejects_undefined_ecdsa_hash_type() {
+        assert_eq!(
+            check_ecdsa_hash_type(0x04),
+            Err(SignError::InvalidHashType(0x04))
+        );
+        assert_eq!(check_ecdsa_hash_type(0x83), Ok(()));
+    }
+}
@@ -0,0 +1,458 @@
+use crate::error::ScriptError;
+use crate::flags::VerifyFlags;
+
+/// Sign all inputs and all outputs.
+pub const SIGHASH_ALL: u8 = 0x01;
+
+/// Sign all inputs and no outputs.
+pub const SIGHASH_NONE: u8 = 0x02;
+
+/// Sign all inputs and the output with the same index.
+pub const SIGHASH_SINGLE: u8 = 0x03;
+
+/// Modifier: sign only this input.
+pub const SIGHASH_ANYONECANPAY: u8 = 0x80;
+
+/// Half the secp256k1 group order, big-endian. S values above this are "high".
+const HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// How a serialized public key is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PubkeyEncoding {
+    /// 33 bytes, prefix `0x02` or `0x03`.
+    Compressed,
+    /// 65 bytes, prefix `0x04`.
+    Uncompressed,
+    /// 65 bytes, prefix `0x06` or `0x07`. Valid in consensus, rejected by
+    /// STRICTENC.
+    Hybrid,
+}
+
+/// Classifies a public key by its length and prefix byte.
+///
+/// Returns `None` if the bytes match none of the three encodings. This is
+/// a format check only; it does not verify that the point is on the curve.
+pub fn pubkey_encoding(pubkey: &[u8]) -> Option<PubkeyEncoding> {
+    match (pubkey.len(), pubkey.first()) {
+        (33, Some(0x02 | 0x03)) => Some(PubkeyEncoding::Compressed),
+        (65, Some(0x04)) => Some(PubkeyEncoding::Uncompressed),
+        (65, Some(0x06 | 0x07)) => Some(PubkeyEncoding::Hybrid),
+        _ => None,
+    }
+}
+
+/// Returns `true` if `sig` is a strict DER signature followed by a hash
+/// type byte, per BIP66 (`IsValidSignatureEncoding` in Bitcoin Core).
+///
+/// Format: `0x30 [total-len] 0x02 [R-len] [R] 0x02 [S-len] [S] [hashtype]`,
+/// where R and S are minimally encoded, non-negative big-endian integers.
+pub fn is_valid_signature_encoding(sig: &[u8]) -> bool {
+    // Minimum and maximum size constraints.
+    if sig.len() < 9 || sig.len() > 73 {
+        return false;
+    }
+    // A signature is of type compound and the length covers everything
+    // except the type, length and hashtype bytes.
+    if sig[0] != 0x30 || sig[1] as usize != sig.len() - 3 {
+        return false;
+    }
+
+    let len_r = sig[3] as usize;
+    if 5 + len_r >= sig.len() {
+        return false;
+    }
+    let len_s = sig[5 + len_r] as usize;
+    if len_r + len_s + 7 != sig.len() {
+        return false;
+    }
+
+    // R: integer, non-empty, non-negative, no unnecessary leading zero.
+    if sig[2] != 0x02 || len_r == 0 || sig[4] & 0x80 != 0 {
+        return false;
+    }
+    if len_r > 1 && sig[4] == 0x00 && sig[5] & 0x80 == 0 {
+        return false;
+    }
+
+    // S: same rules.
+    if sig[len_r + 4] != 0x02 || len_s == 0 || sig[len_r + 6] & 0x80 != 0 {
+        return false;
+    }
+    if len_s > 1 && sig[len_r + 6] == 0x00 && sig[len_r + 7] & 0x80 == 0 {
+        return false;
+    }
+
+    true
+}
+
+/// Returns `true` if the hash type byte (the last byte of `sig`) is
+/// `SIGHASH_ALL`, `SIGHASH_NONE` or `SIGHASH_SINGLE`, optionally combined
+/// with `SIGHASH_ANYONECANPAY`.
+pub fn is_defined_hashtype(sig: &[u8]) -> bool {
+    match sig.last() {
+        Some(&hash_type) => {
+            let base = hash_type & !SIGHASH_ANYONECANPAY;
+            (SIGHASH_ALL..=SIGHASH_SINGLE).contains(&base)
+        }
+        None => false,
+    }
+}
+
+/// Checks that `sig` is strict DER (BIP66).
+///
+/// # Errors
+///
+/// Returns [`ScriptError::SigDer`] if the encoding is not strict DER.
+pub fn check_der_encoding(sig: &[u8]) -> Result<(), ScriptError> {
+    if is_valid_signature_encoding(sig) {
+        Ok(())
+    } else {
+        Err(ScriptError::SigDer)
+    }
+}
+
+/// Checks that `sig` is strict DER with an S value no greater than half
+/// the curve order (BIP62 rule 5).
+///
+/// # Errors
+///
+/// Returns [`ScriptError::SigDer`] if the encoding is not strict DER, or
+/// [`ScriptError::SigHighS`] if S is high.
+pub fn check_low_s(sig: &[u8]) -> Result<(), ScriptError> {
+    check_der_encoding(sig)?;
+
+    let len_r = sig[3] as usize;
+    let len_s = sig[5 + len_r] as usize;
+    let s = &sig[len_r + 6..len_r + 6 + len_s];
+
+    // Strip the sign-padding zero(s) and compare as big-endian integers.
+    let first_nonzero = s.iter().position(|&b| b != 0).unwrap_or(s.len());
+    let s = &s[first_nonzero..];
+    let is_low = match s.len() {
+        n if n < 32 => true,
+        32 => s <= &HALF_ORDER[..],
+        _ => false,
+    };
+
+    if is_low {
+        Ok(())
+    } else {
+        Err(ScriptError::SigHighS)
+    }
+}
+
+/// Checks that the hash type byte of `sig` is defined.
+///
+/// # Errors
+///
+/// Returns [`ScriptError::SigHashType`] if the hash type is undefined or
+/// `sig` is empty.
+pub fn check_hashtype(sig: &[u8]) -> Result<(), ScriptError> {
+    if is_defined_hashtype(sig) {
+        Ok(())
+    } else {
+        Err(ScriptError::SigHashType)
+    }
+}
+
+/// Checks that `pubkey` is compressed or uncompressed (not hybrid).
+///
+/// # Errors
+///
+/// Returns [`ScriptError::PubkeyType`] for hybrid or malformed keys.
+pub fn check_pubkey(pubkey: &[u8]) -> Result<(), ScriptError> {
+    match pubkey_encoding(pubkey) {
+        Some(PubkeyEncoding::Compressed | PubkeyEncoding::Uncompressed) => Ok(()),
+        _ => Err(ScriptError::PubkeyType),
+    }
+}
+
+/// Checks that `pubkey` is compressed, as required for segwit v0 keys.
+///
+/// # Errors
+///
+/// Returns [`ScriptError::WitnessPubkeyType`] for any other encoding.
+pub fn check_compressed_pubkey(pubkey: &[u8]) -> Result<(), ScriptError> {
+    match pubkey_encoding(pubkey) {
+        Some(PubkeyEncoding::Compressed) => Ok(()),
+        _ => Err(ScriptError::WitnessPubkeyType),
+    }
+}
+
+/// Applies the signature encoding rules selected by `flags`, in Bitcoin
+/// Core's order: DER, then low S, then hash type.
+///
+/// An empty signature always passes — it is the canonical way to make a
+/// signature check fail without failing the script.
+///
+/// # Errors
+///
+/// Returns [`ScriptError::SigDer`] under DERSIG, LOW_S or STRICTENC,
+/// [`ScriptError::SigHighS`] under LOW_S, and [`ScriptError::SigHashType`]
+/// under STRICTENC.
+pub fn check_signature_encoding(sig: &[u8], flags: VerifyFlags) -> Result<(), ScriptError> {
+    if sig.is_empty() {
+        return Ok(());
+    }
+    if flags.intersects(VerifyFlags::DERSIG | VerifyFlags::LOW_S | VerifyFlags::STRICTENC) {
+        check_der_encoding(sig)?;
+    }
+    if flags.contains(VerifyFlags::LOW_S) {
+        check_low_s(sig)?;
+    }
+    if flags.contains(VerifyFlags::STRICTENC) {
+        check_hashtype(sig)?;
+    }
+    Ok(())
+}
+
+/// Applies the public key encoding rules selected by `flags`.
+///
+/// # Errors
+///
+/// Returns [`ScriptError::PubkeyType`] under STRICTENC for keys that are
+/// neither compressed nor uncompressed.
+pub fn check_pubkey_encoding(pubkey: &[u8], flags: VerifyFlags) -> Result<(), ScriptError> {
+    if flags.contains(VerifyFlags::STRICTENC) {
+        check_pubkey(pubkey)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Builds `0x30 len 0x02 len(R) R 0x02 len(S) S hashtype`.
+    fn der(r: &[u8], s: &[u8], hash_type: u8) -> Vec<u8> {
+        let mut sig = vec![0x30, (r.len() + s.len() + 4) as u8, 0x02, r.len() as u8];
+        sig.extend_from_slice(r);
+        sig.push(0x02);
+        sig.push(s.len() as u8);
+        sig.extend_from_slice(s);
+        sig.push(hash_type);
+        sig
+    }
+
+    // ── DER ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn minimal_der_is_valid() {
+        assert!(is_valid_signature_encoding(&der(&[1], &[1], 0x01)));
+    }
+
+    #[test]
+    fn typical_der_is_valid() {
+        let mut r = vec![0x00];
+        r.extend_from_slice(&[0x81; 32]);
+        let sig = der(&r, &[0x22; 32], 0x01);
+        assert_eq!(sig.len(), 72);
+        assert!(is_valid_signature_encoding(&sig));
+    }
+
+    #[test]
+    fn der_size_limits() {
+        assert!(!is_valid_signature_encoding(&[0x30; 8]));
+        // 74 bytes: one longer than the largest valid signature.
+        let mut r = vec![0x00];
+        r.extend_from_slice(&[0x81; 32]);
+        let mut s = vec![0x00];
+        s.extend_from_slice(&[0x81; 33]);
+        assert!(!is_valid_signature_encoding(&der(&r, &s, 0x01)));
+    }
+
+    #[test]
+    fn der_wrong_lengths() {
+        let mut sig = der(&[1], &[1], 0x01);
+        sig[1] += 1;
+        assert!(!is_valid_signature_encoding(&sig));
+
+        let mut sig = der(&[1], &[1], 0x01);
+        sig[3] = 2; // R length overruns into S
+        assert!(!is_valid_signature_encoding(&sig));
+    }
+
+    #[test]
+    fn der_wrong_markers() {
+        let mut sig = der(&[1], &[1], 0x01);
+        sig[0] = 0x31;
+        assert!(!is_valid_signature_encoding(&sig));
+
+        let mut sig = der(&[1], &[1], 0x01);
+        sig[2] = 0x03;
+        assert!(!is_valid_signature_encoding(&sig));
+
+        let mut sig = der(&[1], &[1], 0x01);
+        sig[5] = 0x03;
+        assert!(!is_valid_signature_encoding(&sig));
+    }
+
+    #[test]
+    fn der_negative_values() {
+        assert!(!is_valid_signature_encoding(&der(&[0x80], &[1], 0x01)));
+        assert!(!is_valid_signature_encoding(&der(&[1], &[0x80], 0x01)));
+    }
+
+    #[test]
+    fn der_unnecessary_padding() {
+        assert!(!is_valid_signature_encoding(&der(
+            &[0x00, 0x01],
+            &[1],
+            0x01
+        )));
+        assert!(!is_valid_signature_encoding(&der(
+            &[1],
+            &[0x00, 0x01],
+            0x01
+        )));
+        // Padding is required when the high bit is set.
+        assert!(is_valid_signature_encoding(&der(
+            &[0x00, 0x80],
+            &[0x00, 0x80],
+            0x01
+        )));
+    }
+
+    #[test]
+    fn der_zero_length_integers() {
+        assert!(!is_valid_signature_encoding(&der(&[], &[1, 1], 0x01)));
+        assert!(!is_valid_signature_encoding(&der(&[1, 1], &[], 0x01)));
+    }
+
+    // ── Low S ────────────────────────────────────────────────────────
+
+    #[test]
+    fn low_s_boundary() {
+        assert_eq!(check_low_s(&der(&[1], &HALF_ORDER, 0x01)), Ok(()));
+
+        let mut above = HALF_ORDER;
+        above[31] += 1;
+        assert_eq!(
+            check_low_s(&der(&[1], &above, 0x01)),
+            Err(ScriptError::SigHighS)
+        );
+    }
+
+    #[test]
+    fn high_s_with_padding() {
+        let mut s = vec![0x00];
+        s.extend_from_slice(&[0xff; 32]);
+        assert_eq!(
+            check_low_s(&der(&[1], &s, 0x01)),
+            Err(ScriptError::SigHighS)
+        );
+    }
+
+    #[test]
+    fn low_s_requires_der() {
+        assert_eq!(check_low_s(&[0x30; 9]), Err(ScriptError::SigDer));
+    }
+
+    // ── Hash type ────────────────────────────────────────────────────
+
+    #[test]
+    fn defined_hashtypes() {
+        for hash_type in [0x01, 0x02, 0x03, 0x81, 0x82, 0x83] {
+            assert_eq!(check_hashtype(&der(&[1], &[1], hash_type)), Ok(()));
+        }
+        for hash_type in [0x00, 0x04, 0x80, 0x84, 0x41] {
+            assert_eq!(
+                check_hashtype(&der(&[1], &[1], hash_type)),
+                Err(ScriptError::SigHashType)
+            );
+        }
+        assert_eq!(check_hashtype(&[]), Err(ScriptError::SigHashType));
+    }
+
+    // ── Public keys ──────────────────────────────────────────────────
+
+    #[test]
+    fn pubkey_encodings() {
+        let mut compressed = [0x02; 33];
+        assert_eq!(
+            pubkey_encoding(&compressed),
+            Some(PubkeyEncoding::Compressed)
+        );
+        compressed[0] = 0x03;
+        assert_eq!(
+            pubkey_encoding(&compressed),
+            Some(PubkeyEncoding::Compressed)
+        );
+
+        let mut full = [0x04; 65];
+        assert_eq!(pubkey_encoding(&full), Some(PubkeyEncoding::Uncompressed));
+        full[0] = 0x06;
+        assert_eq!(pubkey_encoding(&full), Some(PubkeyEncoding::Hybrid));
+
+        assert_eq!(pubkey_encoding(&[0x04; 33]), None);
+        assert_eq!(pubkey_encoding(&[0x02; 65]), None);
+        assert_eq!(pubkey_encoding(&[]), None);
+    }
+
+    #[test]
+    fn pubkey_checks() {
+        assert_eq!(check_pubkey(&[0x02; 33]), Ok(()));
+        assert_eq!(check_pubkey(&[0x04; 65]), Ok(()));
+        let mut hybrid = [0x04; 65];
+        hybrid[0] = 0x07;
+        assert_eq!(check_pubkey(&hybrid), Err(ScriptError::PubkeyType));
+
+        assert_eq!(check_compressed_pubkey(&[0x03; 33]), Ok(()));
+        assert_eq!(
+            check_compressed_pubkey(&[0x04; 65]),
+            Err(ScriptError::WitnessPubkeyType)
+        );
+    }
+
+    // ── Flag-driven checks ───────────────────────────────────────────
+
+    #[test]
+    fn no_flags_accepts_anything() {
+        assert_eq!(
+            check_signature_encoding(&[0xde, 0xad], VerifyFlags::NONE),
+            Ok(())
+        );
+        assert_eq!(check_pubkey_encoding(&[0x00], VerifyFlags::NONE), Ok(()));
+    }
+
+    #[test]
+    fn empty_signature_always_passes() {
+        let all = VerifyFlags::DERSIG | VerifyFlags::LOW_S | VerifyFlags::STRICTENC;
+        assert_eq!(check_signature_encoding(&[], all), Ok(()));
+    }
+
+    #[test]
+    fn flags_select_rules() {
+        let bad_hashtype = der(&[1], &[1], 0x00);
+        assert_eq!(
+            check_signature_encoding(&bad_hashtype, VerifyFlags::DERSIG),
+            Ok(())
+        );
+        assert_eq!(
+            check_signature_encoding(&bad_hashtype, VerifyFlags::STRICTENC),
+            Err(ScriptError::SigHashType)
+        );
+
+        let high_s = der(&[1], &[0x00, 0xff], 0x01);
+        assert_eq!(
+            check_signature_encoding(&high_s, VerifyFlags::DERSIG),
+            Ok(())
+        );
+        let mut s = vec![0x00];
+        s.extend_from_slice(&[0xff; 32]);
+        assert_eq!(
+            check_signature_encoding(&der(&[1], &s, 0x01), VerifyFlags::LOW_S),
+            Err(ScriptError::SigHighS)
+        );
+
+        assert_eq!(
+            check_signature_encoding(&[0x30; 9], VerifyFlags::STRICTENC),
+            Err(ScriptError::SigDer)
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::vec::Vec;
+
+use crate::classify::{is_p2sh, is_push_only, last_push, read_instruction, witness_program};
+
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKSIGVERIFY: u8 = 0xad;
+const OP_CHECKMULTISIG: u8 = 0xae;
+const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
+
+/// Sigops charged for an OP_CHECKMULTISIG whose key count is not known.
+pub const MAX_PUBKEYS_PER_MULTISIG: usize = 20;
+
+/// Weight units per legacy or P2SH sigop when computing sigop cost.
+pub const WITNESS_SCALE_FACTOR: usize = 4;
+
+/// Legacy sigop count: OP_CHECKSIG(VERIFY) counts 1, and
+/// OP_CHECKMULTISIG(VERIFY) counts 20 regardless of its key count.
+///
+/// This is what consensus counts for every scriptSig and scriptPubKey in
+/// a block.
+pub fn legacy_sigop_count(script: &[u8]) -> usize {
+    count(script, false)
+}
+
+/// Accurate sigop count: like [`legacy_sigop_count`], but an
+/// OP_CHECKMULTISIG directly preceded by OP_1–OP_16 counts that many.
+///
+/// ```rust
+/// use mini_bitcoin_script::builder;
+/// use mini_bitcoin_script::sigops::{accurate_sigop_count, legacy_sigop_count};
+///
+/// let script = builder::multisig(2, &[&[0x02; 33], &[0x03; 33]]).unwrap();
+/// assert_eq!(legacy_sigop_count(&script), 20);
+/// assert_eq!(accurate_sigop_count(&script), 2);
+/// ```
+pub fn accurate_sigop_count(script: &[u8]) -> usize {
+    count(script, true)
+}
+
+/// Sigops in the redeem script of a P2SH spend, counted accurately.
+///
+/// Returns 0 if `script_pubkey` is not P2SH, or if `script_sig` is not
+/// push-only (such a spend is invalid anyway). The redeem script is the
+/// last push of `script_sig`.
+pub fn p2sh_sigop_count(script_pubkey: &[u8], script_sig: &[u8]) -> usize {
+    if !is_p2sh(script_pubkey) || !is_push_only(script_sig) {
+        return 0;
+    }
+    match last_push(script_sig) {
+        Some(redeem_script) => accurate_sigop_count(redeem_script),
+        None => 0,
+    }
+}
+
+/// Sigops of a witness spend: 1 for P2WPKH, the accurate count of the
+/// witness script (the last witness item) for P2WSH, and 0 otherwise.
+/// Handles native outputs and programs nested in P2SH.
+///
+/// Taproot spends count 0 here; tapscript limits signature checks by
+/// witness size instead (BIP342).
+pub fn witness_sigop_count(script_sig: &[u8], script_pubkey: &[u8], witness: &[Vec<u8>]) -> usize {
+    if let Some((version, program)) = witness_program(script_pubkey) {
+        return program_sigops(version, program, witness);
+    }
+    if is_p2sh(script_pubkey) && is_push_only(script_sig) {
+        if let Some((version, program)) = last_push(script_sig).and_then(witness_program) {
+            return program_sigops(version, program, witness);
+        }
+    }
+    0
+}
+
+/// Total sigop cost of one input, as counted against the block limit of
+/// 80,000: `(legacy + P2SH) * 4 + witness`.
+///
+/// `legacy` covers only the input's scriptSig; the legacy sigops of the
+/// spent scriptPubKey are charged to the transaction that created it.
+pub fn input_sigop_cost(script_sig: &[u8], script_pubkey: &[u8], witness: &[Vec<u8>]) -> usize {
+    let legacy = legacy_sigop_count(script_sig) + p2sh_sigop_count(script_pubkey, script_sig);
+    legacy * WITNESS_SCALE_FACTOR + witness_sigop_count(script_sig, script_pubkey, witness)
+}
+
+/// Walks raw bytes rather than tokens: Core counts scripts the tokenizer
+/// would reject, stopping at the first truncated push.
+fn count(script: &[u8], accurate: bool) -> usize {
+    let mut total = 0;
+    let mut last_op = 0xff;
+    let mut pos = 0;
+    while let Some((op, _, next)) = read_instruction(script, pos) {
+        match op {
+            OP_CHECKSIG | OP_CHECKSIGVERIFY => total += 1,
+            OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
+                total += match last_op {
+                    OP_1..=OP_16 if accurate => (last_op - OP_1 + 1) as usize,
+                    _ => MAX_PUBKEYS_PER_MULTISIG,
+                }
+            }
+            _ => {}
+        }
+        last_op = op;
+        pos = next;
+    }
+    total
+}
+
+fn program_sigops(version: u8, program: &[u8], witness: &[Vec<u8>]) -> usize {
+    match (version, program.len(), witness.last()) {
+        (0, 20, _) => 1,
+        (0, 32, Some(witness_script)) => accurate_sigop_count(witness_script),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_at_malformed_push() {
+        // OP_CHECKSIG, then a push claiming 5 bytes with 1 present.
+        assert_eq!(legacy_sigop_count(&[OP_CHECKSIG, 0x05, OP_CHECKSIG]), 1);
+    }
+
+    #[test]
+    fn multisig_without_key_count() {
+        // OP_CHECKMULTISIG at the start, or after a non-OP_N.
+        assert_eq!(accurate_sigop_count(&[OP_CHECKMULTISIG]), 20);
+        assert_eq!(
+            accurate_sigop_count(&[0x01, 0x03, OP_CHECKMULTISIGVERIFY]),
+            20
+        );
+        assert_eq!(accurate_sigop_count(&[OP_16, OP_CHECKMULTISIGVERIFY]), 16);
+    }
+}
@@ -0,0 +1,464 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::hex::{decode_hex, decode_hex_array, encode_hex, ReversedHex};
+use crate::transaction::{
+    write_compact_size, write_outpoint, write_txout, DecodeError, OutPoint, Reader, TxOut,
+};
+use crate::verify::UtxoProvider;
+
+/// The first four bytes of a binary snapshot.
+const MAGIC: [u8; 4] = *b"utxo";
+
+/// The binary format version [`UtxoSnapshot::serialize`] writes.
+const VERSION: u32 = 1;
+
+/// The smallest encoded entry: an outpoint, an amount and an empty script.
+const MIN_ENTRY_SIZE: usize = 36 + 8 + 1;
+
+/// The header line [`UtxoSnapshot::to_csv`] writes and
+/// [`UtxoSnapshot::from_csv`] skips.
+const CSV_HEADER: &str = "txid,vout,amount,script_pubkey";
+
+/// A set of unspent outputs held in memory, for validating transactions
+/// without a node.
+///
+/// [`verify_tx`](crate::verify::verify_tx) and
+/// [`validate_block`](crate::block::validate_block) need the outputs a
+/// transaction spends. A snapshot holds just those, so historic spends
+/// can be replayed offline: import them once from a CSV or (with the
+/// `serde` feature) JSON dump, and store the result with
+/// [`UtxoSnapshot::serialize`].
+///
+/// The binary format is:
+///
+/// | Field   | Size        | Contents                                  |
+/// |---------|-------------|-------------------------------------------|
+/// | magic   | 4           | `utxo`                                    |
+/// | version | 4           | `1`, little-endian                        |
+/// | count   | CompactSize | number of entries                         |
+/// | entries | variable    | outpoint, amount and scriptPubKey, each   |
+///
+/// Each entry is encoded as in a transaction: a 32-byte txid, a 4-byte
+/// output index, an 8-byte amount and a length-prefixed scriptPubKey.
+/// Entries are sorted by outpoint, so a snapshot has a single encoding.
+///
+/// ```rust
+/// use mini_bitcoin_script::snapshot::UtxoSnapshot;
+/// use mini_bitcoin_script::transaction::OutPoint;
+/// use mini_bitcoin_script::verify::UtxoProvider;
+///
+/// let csv = "\
+/// txid,vout,amount,script_pubkey
+/// 0437cd7f8525ceed2324359c2d0ba26006d92d856a9c20fa0241106ee5a597c9,0,5000000000,51
+/// ";
+/// let snapshot = UtxoSnapshot::from_csv(csv).unwrap();
+/// assert_eq!(UtxoSnapshot::deserialize(&snapshot.serialize()), Ok(snapshot.clone()));
+///
+/// let (outpoint, _) = snapshot.iter().next().unwrap();
+/// assert_eq!(snapshot.utxo(outpoint).unwrap().value, 5_000_000_000);
+/// assert!(snapshot.utxo(&OutPoint::default()).is_none());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UtxoSnapshot {
+    utxos: BTreeMap<OutPoint, TxOut>,
+}
+
+impl UtxoSnapshot {
+    /// Creates an empty snapshot.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of outputs held.
+    pub fn len(&self) -> usize {
+        self.utxos.len()
+    }
+
+    /// Returns `true` if no output is held.
+    pub fn is_empty(&self) -> bool {
+        self.utxos.is_empty()
+    }
+
+    /// The output at `outpoint`, if held.
+    pub fn get(&self, outpoint: &OutPoint) -> Option<&TxOut> {
+        self.utxos.get(outpoint)
+    }
+
+    /// Adds an output, returning the one it replaces.
+    pub fn insert(&mut self, outpoint: OutPoint, output: TxOut) -> Option<TxOut> {
+        self.utxos.insert(outpoint, output)
+    }
+
+    /// Removes and returns the output at `outpoint`.
+    pub fn remove(&mut self, outpoint: &OutPoint) -> Option<TxOut> {
+        self.utxos.remove(outpoint)
+    }
+
+    /// The outputs held, in outpoint order.
+    pub fn iter(&self) -> impl Iterator<Item = (&OutPoint, &TxOut)> {
+        self.utxos.iter()
+    }
+
+    /// Encodes the snapshot in the binary format.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        write_compact_size(&mut out, self.utxos.len() as u64);
+        for (outpoint, output) in &self.utxos {
+            write_outpoint(&mut out, outpoint);
+            write_txout(&mut out, output);
+        }
+        out
+    }
+
+    /// Decodes a snapshot written by [`UtxoSnapshot::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// A [`SnapshotError`] if the magic bytes or version are wrong, the
+    /// data is truncated or has trailing bytes, or the entries are not in
+    /// strictly increasing outpoint order.
+    pub fn deserialize(bytes: &[u8]) -> Result<UtxoSnapshot, SnapshotError> {
+        let mut reader = Reader::new(bytes);
+        if reader.read_array::<4>()? != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = reader.read_u32()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let count = reader.read_compact_size()?;
+        let count = reader.count(count, MIN_ENTRY_SIZE)?;
+
+        let mut utxos = BTreeMap::new();
+        let mut last = None;
+        for index in 0..count {
+            let outpoint = reader.read_outpoint()?;
+            if last.map_or(false, |last| outpoint <= last) {
+                return Err(SnapshotError::Unsorted { index });
+            }
+            last = Some(outpoint);
+            utxos.insert(outpoint, reader.read_txout()?);
+        }
+        reader.finish()?;
+        Ok(UtxoSnapshot { utxos })
+    }
+
+    /// Imports outputs from CSV lines of the form
+    /// `txid,vout,amount,script_pubkey`.
+    ///
+    /// The txid is in the usual reversed display order, the amount in
+    /// satoshis, and the scriptPubKey in hex. Fields may be padded with
+    /// whitespace. Blank lines, lines starting with `#` and a
+    /// `txid,vout,amount,script_pubkey` header are skipped.
+    ///
+    /// # Errors
+    ///
+    /// A [`SnapshotError`] naming the first line (counting from 1) that
+    /// does not have four valid fields or repeats an outpoint.
+    pub fn from_csv(csv: &str) -> Result<UtxoSnapshot, SnapshotError> {
+        let mut snapshot = UtxoSnapshot::new();
+        for (index, line) in csv.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line == CSV_HEADER {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != 4 {
+                return Err(SnapshotError::FieldCount {
+                    line: line_number,
+                    found: fields.len(),
+                });
+            }
+            let invalid = |field| SnapshotError::InvalidField {
+                line: line_number,
+                field,
+            };
+            let outpoint = OutPoint {
+                txid: parse_txid(fields[0]).ok_or_else(|| invalid(Field::Txid))?,
+                vout: fields[1].parse().map_err(|_| invalid(Field::Vout))?,
+            };
+            let output = TxOut {
+                value: fields[2].parse().map_err(|_| invalid(Field::Amount))?,
+                script_pubkey: decode_hex(fields[3]).map_err(|_| invalid(Field::ScriptPubKey))?,
+            };
+            if snapshot.insert(outpoint, output).is_some() {
+                return Err(SnapshotError::DuplicateOutpoint {
+                    line: line_number,
+                    outpoint,
+                });
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Exports the snapshot as CSV, with a header line, in the format
+    /// [`UtxoSnapshot::from_csv`] reads.
+    pub fn to_csv(&self) -> String {
+        use core::fmt::Write;
+
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+        for (outpoint, output) in &self.utxos {
+            // Writing to a String cannot fail.
+            let _ = writeln!(
+                csv,
+                "{},{},{},{}",
+                ReversedHex(&outpoint.txid),
+                outpoint.vout,
+                output.value,
+                encode_hex(&output.script_pubkey)
+            );
+        }
+        csv
+    }
+}
+
+impl UtxoProvider for UtxoSnapshot {
+    fn utxo(&self, outpoint: &OutPoint) -> Option<TxOut> {
+        self.utxos.get(outpoint).cloned()
+    }
+}
+
+impl FromIterator<(OutPoint, TxOut)> for UtxoSnapshot {
+    fn from_iter<I: IntoIterator<Item = (OutPoint, TxOut)>>(iter: I) -> Self {
+        UtxoSnapshot {
+            utxos: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<(OutPoint, TxOut)> for UtxoSnapshot {
+    fn extend<I: IntoIterator<Item = (OutPoint, TxOut)>>(&mut self, iter: I) {
+        self.utxos.extend(iter);
+    }
+}
+
+/// Parses a txid in display order into internal byte order.
+fn parse_txid(hex: &str) -> Option<[u8; 32]> {
+    let mut txid = decode_hex_array::<32>(hex).ok()?;
+    txid.reverse();
+    Some(txid)
+}
+
+/// A field of an imported record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// The txid of the output's transaction.
+    Txid,
+    /// The output's index in its transaction.
+    Vout,
+    /// The output's amount, in satoshis.
+    Amount,
+    /// The output's scriptPubKey.
+    ScriptPubKey,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Field::Txid => "txid",
+            Field::Vout => "vout",
+            Field::Amount => "amount",
+            Field::ScriptPubKey => "script_pubkey",
+        })
+    }
+}
+
+/// Why a snapshot could not be read or imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The data does not start with the snapshot magic bytes.
+    BadMagic,
+
+    /// The snapshot uses a format version this crate cannot read.
+    UnsupportedVersion(u32),
+
+    /// The binary data is truncated or malformed.
+    Decode(DecodeError),
+
+    /// An entry's outpoint is not greater than the one before it.
+    Unsorted {
+        /// Index of the entry in the snapshot.
+        index: usize,
+    },
+
+    /// A CSV line does not have four fields.
+    FieldCount {
+        /// Line number, counting from 1.
+        line: usize,
+        /// Number of fields found.
+        found: usize,
+    },
+
+    /// A field of a CSV line is not valid.
+    InvalidField {
+        /// Line number, counting from 1.
+        line: usize,
+        /// The invalid field.
+        field: Field,
+    },
+
+    /// A CSV line repeats an outpoint.
+    DuplicateOutpoint {
+        /// Line number, counting from 1.
+        line: usize,
+        /// The repeated outpoint.
+        outpoint: OutPoint,
+    },
+}
+
+impl From<DecodeError> for SnapshotError {
+    fn from(err: DecodeError) -> Self {
+        SnapshotError::Decode(err)
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a UTXO snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {version}")
+            }
+            SnapshotError::Decode(err) => write!(f, "invalid snapshot: {err}"),
+            SnapshotError::Unsorted { index } => {
+                write!(f, "snapshot entry {index} is out of order")
+            }
+            SnapshotError::FieldCount { line, found } => {
+                write!(f, "line {line}: expected 4 fields, found {found}")
+            }
+            SnapshotError::InvalidField { line, field } => {
+                write!(f, "line {line}: invalid {field}")
+            }
+            SnapshotError::DuplicateOutpoint { line, outpoint } => write!(
+                f,
+                "line {line}: duplicate outpoint {}:{}",
+                ReversedHex(&outpoint.txid),
+                outpoint.vout
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SnapshotError {}
+
+/// A snapshot entry as it appears in a JSON dump.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Record {
+    txid: String,
+    vout: u32,
+    #[serde(alias = "value", deserialize_with = "deserialize_amount")]
+    amount: u64,
+    #[serde(alias = "scriptPubKey")]
+    script_pubkey: String,
+}
+
+/// Reads an amount written as an integer number of satoshis, or as a
+/// decimal number of bitcoin such as Bitcoin Core's `50.00000000`.
+#[cfg(feature = "serde")]
+fn deserialize_amount<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    struct AmountVisitor;
+
+    impl serde::de::Visitor<'_> for AmountVisitor {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("satoshis or a decimal bitcoin amount")
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, sats: u64) -> Result<u64, E> {
+            Ok(sats)
+        }
+
+        fn visit_f64<E: serde::de::Error>(self, btc: f64) -> Result<u64, E> {
+            btc_to_sats(btc).ok_or_else(|| E::custom(format_args!("invalid amount {btc}")))
+        }
+    }
+
+    deserializer.deserialize_any(AmountVisitor)
+}
+
+/// Converts a bitcoin amount to satoshis exactly, or `None` if it is
+/// negative or not a whole number of satoshis.
+///
+/// `Display` prints the shortest decimal that reads back as `btc`. Any
+/// amount up to the coin supply with at most eight decimals is that
+/// decimal, since neighbouring doubles there are closer than a satoshi.
+#[cfg(feature = "serde")]
+fn btc_to_sats(btc: f64) -> Option<u64> {
+    use alloc::format;
+
+    if !btc.is_finite() || btc.is_sign_negative() {
+        return None;
+    }
+    let decimal = format!("{btc}");
+    let (whole, fraction) = decimal.split_once('.').unwrap_or((&decimal, ""));
+    if fraction.len() > 8 {
+        return None;
+    }
+    let fraction = format!("{fraction:0<8}");
+    whole
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(100_000_000)?
+        .checked_add(fraction.parse().ok()?)
+}
+
+/// Serializes as a list of `{"txid", "vout", "amount", "script_pubkey"}`
+/// records, with fields formatted as in [`UtxoSnapshot::to_csv`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for UtxoSnapshot {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use alloc::string::ToString;
+
+        serializer.collect_seq(self.utxos.iter().map(|(outpoint, output)| Record {
+            txid: ReversedHex(&outpoint.txid).to_string(),
+            vout: outpoint.vout,
+            amount: output.value,
+            script_pubkey: encode_hex(&output.script_pubkey),
+        }))
+    }
+}
+
+/// Deserializes a list of records as written by the `Serialize` impl.
+/// `value` and `scriptPubKey` are accepted for `amount` and
+/// `script_pubkey`, and decimal amounts are read as bitcoin rather than
+/// satoshis, so the `unspents` list from Bitcoin Core's `scantxoutset`
+/// RPC imports as is. Other fields are ignored.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for UtxoSnapshot {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let records = Vec::<Record>::deserialize(deserializer)?;
+        let mut snapshot = UtxoSnapshot::new();
+        for (index, record) in records.into_iter().enumerate() {
+            let invalid = |field| D::Error::custom(format_args!("record {index}: invalid {field}"));
+            let outpoint = OutPoint {
+                txid: parse_txid(&record.txid).ok_or_else(|| invalid(Field::Txid))?,
+                vout: record.vout,
+            };
+            let output = TxOut {
+                value: record.amount,
+                script_pubkey: decode_hex(&record.script_pubkey)
+                    .map_err(|_| invalid(Field::ScriptPubKey))?,
+            };
+            if snapshot.insert(outpoint, output).is_some() {
+                return Err(D::Error::custom(format_args!(
+                    "record {index}: duplicate outpoint {}:{}",
+                    ReversedHex(&outpoint.txid),
+                    outpoint.vout
+                )));
+            }
+        }
+        Ok(snapshot)
+    }
+}
@@ -1,3 +1,6 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
 use crate::error::ScriptError;
 
 /// Determines whether a byte slice is "true" under Bitcoin Script semantics.
@@ -34,6 +37,16 @@ impl Stack {
         Self { items: Vec::new() }
     }
 
+    /// Creates a stack from existing elements (index 0 = bottom).
+    pub(crate) fn from_items(items: Vec<Vec<u8>>) -> Self {
+        Self { items }
+    }
+
+    /// Consumes the stack and returns its elements (index 0 = bottom).
+    pub(crate) fn into_items(self) -> Vec<Vec<u8>> {
+        self.items
+    }
+
     /// Pushes a byte vector onto the top of the stack.
     pub(crate) fn push(&mut self, item: Vec<u8>) {
         self.items.push(item);
@@ -0,0 +1,528 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::analysis::MAX_PATHS;
+use crate::error::ScriptError;
+use crate::explore::{explore, Domain, Stop};
+use crate::opcode::Opcode;
+use crate::token::Token;
+
+/// A hash function applied by one of the hashing opcodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum HashFn {
+    /// OP_RIPEMD160.
+    Ripemd160,
+    /// OP_SHA256.
+    Sha256,
+    /// OP_HASH160: RIPEMD160(SHA256(x)).
+    Hash160,
+    /// OP_HASH256: SHA256(SHA256(x)).
+    Hash256,
+}
+
+impl HashFn {
+    fn name(self) -> &'static str {
+        match self {
+            HashFn::Ripemd160 => "ripemd160",
+            HashFn::Sha256 => "sha256",
+            HashFn::Hash160 => "hash160",
+            HashFn::Hash256 => "hash256",
+        }
+    }
+}
+
+/// A symbolic stack value.
+///
+/// Operations whose inputs are all constants are folded, so an `Expr`
+/// other than [`Expr::Const`] always depends on the spender's data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Expr {
+    /// Initial stack item `i`, counted from the bottom: `x0` is the first
+    /// witness item (or first scriptSig push) the path consumes.
+    Var(usize),
+    /// A known byte string.
+    Const(#[cfg_attr(feature = "serde", serde(serialize_with = "serialize_hex"))] Vec<u8>),
+    /// The result of OP_DEPTH: the number of initial stack items plus the
+    /// given offset.
+    Depth(i64),
+    /// A hash of a value.
+    Hash(HashFn, Box<Expr>),
+    /// OP_SIZE of a value.
+    Size(Box<Expr>),
+    /// OP_NOT of a value.
+    Not(Box<Expr>),
+    /// OP_EQUAL of two values.
+    Equal(Box<Expr>, Box<Expr>),
+    /// OP_0NOTEQUAL of a number.
+    NotZero(Box<Expr>),
+    /// OP_NEGATE of a number.
+    Negate(Box<Expr>),
+    /// OP_ABS of a number.
+    Abs(Box<Expr>),
+    /// OP_ADD of two numbers, or OP_1ADD of one and 1. OP_CHECKSIGADD is
+    /// the sum of its number and a [`Expr::CheckSig`].
+    Add(Box<Expr>, Box<Expr>),
+    /// OP_SUB of two numbers, or OP_1SUB of one and 1.
+    Sub(Box<Expr>, Box<Expr>),
+    /// OP_MIN of two numbers.
+    Min(Box<Expr>, Box<Expr>),
+    /// OP_MAX of two numbers.
+    Max(Box<Expr>, Box<Expr>),
+    /// OP_BOOLAND of two numbers.
+    BoolAnd(Box<Expr>, Box<Expr>),
+    /// OP_BOOLOR of two numbers.
+    BoolOr(Box<Expr>, Box<Expr>),
+    /// OP_NUMEQUAL of two numbers.
+    NumEqual(Box<Expr>, Box<Expr>),
+    /// OP_NUMNOTEQUAL of two numbers.
+    NumNotEqual(Box<Expr>, Box<Expr>),
+    /// OP_LESSTHAN of two numbers. OP_WITHIN is the [`Expr::BoolAnd`] of
+    /// a [`Expr::GreaterThanOrEqual`] and a `LessThan`.
+    LessThan(Box<Expr>, Box<Expr>),
+    /// OP_GREATERTHAN of two numbers.
+    GreaterThan(Box<Expr>, Box<Expr>),
+    /// OP_LESSTHANOREQUAL of two numbers.
+    LessThanOrEqual(Box<Expr>, Box<Expr>),
+    /// OP_GREATERTHANOREQUAL of two numbers.
+    GreaterThanOrEqual(Box<Expr>, Box<Expr>),
+    /// OP_CHECKSIG of a signature and a public key.
+    CheckSig(Box<Expr>, Box<Expr>),
+    /// OP_CHECKMULTISIG of signatures and public keys, each in script
+    /// order.
+    CheckMultiSig(Vec<Expr>, Vec<Expr>),
+}
+
+impl Expr {
+    /// Renumbers variables from pop order (0 = top) to stack order
+    /// (0 = bottom), given `n` consumed items.
+    fn renumber(&mut self, n: usize) {
+        match self {
+            Expr::Var(i) => *i = n - 1 - *i,
+            Expr::Const(_) | Expr::Depth(_) => {}
+            Expr::Hash(_, e)
+            | Expr::Size(e)
+            | Expr::Not(e)
+            | Expr::NotZero(e)
+            | Expr::Negate(e)
+            | Expr::Abs(e) => e.renumber(n),
+            Expr::Equal(a, b)
+            | Expr::Add(a, b)
+            | Expr::Sub(a, b)
+            | Expr::Min(a, b)
+            | Expr::Max(a, b)
+            | Expr::BoolAnd(a, b)
+            | Expr::BoolOr(a, b)
+            | Expr::NumEqual(a, b)
+            | Expr::NumNotEqual(a, b)
+            | Expr::LessThan(a, b)
+            | Expr::GreaterThan(a, b)
+            | Expr::LessThanOrEqual(a, b)
+            | Expr::GreaterThanOrEqual(a, b)
+            | Expr::CheckSig(a, b) => {
+                a.renumber(n);
+                b.renumber(n);
+            }
+            Expr::CheckMultiSig(sigs, pubkeys) => {
+                for e in sigs.iter_mut().chain(pubkeys) {
+                    e.renumber(n);
+                }
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for Expr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Expr::Var(i) => write!(f, "x{i}"),
+            Expr::Const(bytes) => write!(f, "{}", Token::PushData(bytes.clone())),
+            Expr::Depth(0) => write!(f, "depth"),
+            Expr::Depth(offset) if *offset > 0 => write!(f, "depth + {offset}"),
+            Expr::Depth(offset) => write!(f, "depth - {}", -offset),
+            Expr::Hash(func, e) => write!(f, "{}({e})", func.name()),
+            Expr::Size(e) => write!(f, "size({e})"),
+            Expr::Not(e) => write!(f, "not({e})"),
+            Expr::Equal(a, b) | Expr::NumEqual(a, b) => write!(f, "{a} == {b}"),
+            Expr::NotZero(e) => write!(f, "{e} != 0"),
+            Expr::Negate(e) => write!(f, "-({e})"),
+            Expr::Abs(e) => write!(f, "abs({e})"),
+            Expr::Add(a, b) => write!(f, "{a} + {b}"),
+            Expr::Sub(a, b) => write!(f, "{a} - {b}"),
+            Expr::Min(a, b) => write!(f, "min({a}, {b})"),
+            Expr::Max(a, b) => write!(f, "max({a}, {b})"),
+            Expr::NumNotEqual(a, b) => write!(f, "{a} != {b}"),
+            Expr::LessThan(a, b) => write!(f, "{a} < {b}"),
+            Expr::GreaterThan(a, b) => write!(f, "{a} > {b}"),
+            Expr::LessThanOrEqual(a, b) => write!(f, "{a} <= {b}"),
+            Expr::GreaterThanOrEqual(a, b) => write!(f, "{a} >= {b}"),
+            Expr::BoolAnd(a, b) => write!(f, "booland({a}, {b})"),
+            Expr::BoolOr(a, b) => write!(f, "boolor({a}, {b})"),
+            Expr::CheckSig(sig, pubkey) => write!(f, "checksig({sig}, {pubkey})"),
+            Expr::CheckMultiSig(sigs, pubkeys) => {
+                write!(f, "checkmultisig([")?;
+                write_list(f, sigs)?;
+                write!(f, "], [")?;
+                write_list(f, pubkeys)?;
+                write!(f, "])")
+            }
+        }
+    }
+}
+
+fn write_list(f: &mut core::fmt::Formatter<'_>, items: &[Expr]) -> core::fmt::Result {
+    for (i, e) in items.iter().enumerate() {
+        let sep = if i == 0 { "" } else { ", " };
+        write!(f, "{sep}{e}")?;
+    }
+    Ok(())
+}
+
+/// A condition a path places on the spender's data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Constraint {
+    /// The value must be true (non-zero).
+    IsTrue(Expr),
+    /// The value must be false (zero, negative zero or empty).
+    IsFalse(Expr),
+    /// OP_CHECKLOCKTIMEVERIFY: the transaction's locktime must be at least
+    /// the value, counting the same unit (height or time).
+    After(Expr),
+    /// OP_CHECKSEQUENCEVERIFY: the input's relative locktime must be at
+    /// least the value, counting the same unit (blocks or time).
+    Older(Expr),
+}
+
+impl Constraint {
+    fn expr_mut(&mut self) -> &mut Expr {
+        match self {
+            Constraint::IsTrue(e)
+            | Constraint::IsFalse(e)
+            | Constraint::After(e)
+            | Constraint::Older(e) => e,
+        }
+    }
+}
+
+impl core::fmt::Display for Constraint {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Constraint::IsTrue(e) => write!(f, "{e}"),
+            Constraint::IsFalse(Expr::Equal(a, b) | Expr::NumEqual(a, b)) => {
+                write!(f, "{a} != {b}")
+            }
+            Constraint::IsFalse(Expr::NumNotEqual(a, b)) => write!(f, "{a} == {b}"),
+            Constraint::IsFalse(Expr::LessThan(a, b)) => write!(f, "{a} >= {b}"),
+            Constraint::IsFalse(Expr::GreaterThan(a, b)) => write!(f, "{a} <= {b}"),
+            Constraint::IsFalse(Expr::LessThanOrEqual(a, b)) => write!(f, "{a} > {b}"),
+            Constraint::IsFalse(Expr::GreaterThanOrEqual(a, b)) => write!(f, "{a} < {b}"),
+            Constraint::IsFalse(e) => write!(f, "!{e}"),
+            Constraint::After(e) => write!(f, "locktime >= {e}"),
+            Constraint::Older(e) => write!(f, "sequence >= {e}"),
+        }
+    }
+}
+
+/// One execution path through a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SymbolicPath {
+    /// Branch decisions, as `(index of the OP_IF/OP_NOTIF, whether its
+    /// first branch ran)`, or for OP_IFDUP whether the value was true.
+    pub branches: Vec<(usize, bool)>,
+    /// Number of initial stack items the path reads (`x0` to `x{n-1}`).
+    pub inputs: usize,
+    /// Conditions that must all hold for the path to succeed, in the order
+    /// the script imposes them.
+    pub constraints: Vec<Constraint>,
+    /// Index of the token that fails unconditionally on this path (an
+    /// executed OP_RETURN, a VERIFY of a false constant, an empty alt
+    /// stack or an invalid constant operand), or the token count if the
+    /// path ends with a false constant on top, if any. Such a path cannot
+    /// be satisfied. An OP_CHECKMULTISIG whose key or signature count
+    /// comes from the witness is not modeled and also ends the path here.
+    pub fails_at: Option<usize>,
+}
+
+impl SymbolicPath {
+    /// Returns `true` unless the path fails unconditionally.
+    pub fn is_satisfiable(&self) -> bool {
+        self.fails_at.is_none()
+    }
+}
+
+/// The spending conditions of a script, one [`SymbolicPath`] per branch
+/// combination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SpendingConditions {
+    /// Every explored path, in script order (first branches first).
+    pub paths: Vec<SymbolicPath>,
+    /// Whether exploration stopped at [`MAX_PATHS`].
+    pub truncated: bool,
+}
+
+impl SpendingConditions {
+    /// The paths that can succeed.
+    pub fn satisfiable(&self) -> impl Iterator<Item = &SymbolicPath> {
+        self.paths.iter().filter(|path| path.is_satisfiable())
+    }
+}
+
+/// Renders one block per path:
+///
+/// ```text
+/// path 0 [0:if]: 2 inputs
+///   hash160(x1) == <...>
+///   checksig(x0, x1)
+/// ```
+impl core::fmt::Display for SpendingConditions {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, path) in self.paths.iter().enumerate() {
+            write!(f, "path {i}")?;
+            if !path.branches.is_empty() {
+                write!(f, " [")?;
+                for (j, (index, taken)) in path.branches.iter().enumerate() {
+                    let branch = if *taken { "if" } else { "else" };
+                    let sep = if j == 0 { "" } else { " " };
+                    write!(f, "{sep}{index}:{branch}")?;
+                }
+                write!(f, "]")?;
+            }
+            writeln!(f, ": {} inputs", path.inputs)?;
+            if let Some(index) = path.fails_at {
+                writeln!(f, "  unsatisfiable: fails at token {index}")?;
+                continue;
+            }
+            for constraint in &path.constraints {
+                writeln!(f, "  {constraint}")?;
+            }
+        }
+        if self.truncated {
+            writeln!(f, "(truncated after {MAX_PATHS} paths)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Executes `tokens` on a symbolic initial stack and returns the conditions
+/// each path places on it.
+///
+/// Items the script reads from below its own pushes become variables
+/// `x0`, `x1`, … numbered from the bottom, so for a witness script they
+/// are the witness items in serialization order. OP_IF/OP_NOTIF/OP_IFDUP on
+/// a symbolic value forks, adding the condition (or its negation) to each
+/// side. Timelock opcodes add [`Constraint::After`] and
+/// [`Constraint::Older`]. A successful path ends by requiring its top item
+/// to be true.
+///
+/// # Errors
+///
+/// Returns [`ScriptError::UnbalancedConditional`] if OP_IF/OP_NOTIF and
+/// OP_ENDIF do not pair up, and [`ScriptError::DisabledOpcode`] if the
+/// script contains a disabled opcode, executed or not.
+///
+/// ```rust
+/// use mini_bitcoin_script::symbolic::symbolic_execute;
+/// use mini_bitcoin_script::tokenizer::parse_asm;
+///
+/// let tokens = parse_asm("OP_DUP OP_HASH160 <89abcdefabbaabbaabbaabbaabbaabbaabbaabba> \
+///                         OP_EQUALVERIFY OP_CHECKSIG").unwrap();
+/// let conditions = symbolic_execute(&tokens).unwrap();
+/// let path = &conditions.paths[0];
+/// assert_eq!(path.inputs, 2);
+/// assert_eq!(
+///     path.constraints[0].to_string(),
+///     "hash160(x1) == <89abcdefabbaabbaabbaabbaabbaabbaabbaabba>"
+/// );
+/// assert_eq!(path.constraints[1].to_string(), "checksig(x0, x1)");
+/// ```
+pub fn symbolic_execute(tokens: &[Token]) -> Result<SpendingConditions, ScriptError> {
+    let exploration = explore(tokens, Constraints::default());
+    let mut paths = Vec::new();
+
+    for (mut path, outcome) in exploration.paths {
+        let fails_at = match outcome {
+            Ok(()) => match path.pop() {
+                Ok(top) => match Constraints::truth(&top) {
+                    Some(true) => None,
+                    Some(false) => Some(tokens.len()),
+                    None => {
+                        path.domain.assume(top, true);
+                        None
+                    }
+                },
+                Err(_) => Some(tokens.len()),
+            },
+            Err((_, Stop::Malformed)) => return Err(ScriptError::UnbalancedConditional),
+            Err((_, Stop::Disabled(byte))) => return Err(ScriptError::DisabledOpcode(byte)),
+            Err((index, Stop::Fails | Stop::Unmodeled | Stop::Underflow)) => Some(index),
+        };
+
+        let inputs = path.borrowed;
+        let mut constraints = path.domain.0;
+        for constraint in &mut constraints {
+            constraint.expr_mut().renumber(inputs);
+        }
+        paths.push(SymbolicPath {
+            branches: path.branches,
+            inputs,
+            constraints,
+            fails_at,
+        });
+    }
+
+    Ok(SpendingConditions {
+        paths,
+        truncated: exploration.truncated,
+    })
+}
+
+/// The constraints a path has collected so far.
+#[derive(Clone, Default)]
+struct Constraints(Vec<Constraint>);
+
+impl Domain for Constraints {
+    type Value = Expr;
+
+    fn constant(bytes: Vec<u8>) -> Expr {
+        Expr::Const(bytes)
+    }
+
+    fn known(value: &Expr) -> Option<&[u8]> {
+        match value {
+            Expr::Const(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn input(&mut self, index: usize) -> Result<Expr, Stop> {
+        Ok(Expr::Var(index))
+    }
+
+    fn depth(&self, offset: i64) -> Expr {
+        Expr::Depth(offset)
+    }
+
+    fn assume(&mut self, value: Expr, truth: bool) -> Expr {
+        self.0.push(if truth {
+            Constraint::IsTrue(value.clone())
+        } else {
+            Constraint::IsFalse(value.clone())
+        });
+        value
+    }
+
+    fn unary(op: Opcode, a: Expr) -> Expr {
+        let a = Box::new(a);
+        match op {
+            Opcode::OpNot => Expr::Not(a),
+            Opcode::Op0NotEqual => Expr::NotZero(a),
+            Opcode::OpNegate => Expr::Negate(a),
+            Opcode::OpAbs => Expr::Abs(a),
+            Opcode::OpSize => Expr::Size(a),
+            Opcode::OpRipemd160 => Expr::Hash(HashFn::Ripemd160, a),
+            Opcode::OpSha256 => Expr::Hash(HashFn::Sha256, a),
+            Opcode::OpHash160 => Expr::Hash(HashFn::Hash160, a),
+            _ => Expr::Hash(HashFn::Hash256, a),
+        }
+    }
+
+    fn binary(op: Opcode, a: Expr, b: Expr) -> Expr {
+        let (a, b) = (Box::new(a), Box::new(b));
+        match op {
+            Opcode::OpEqual => Expr::Equal(a, b),
+            Opcode::OpAdd => Expr::Add(a, b),
+            Opcode::OpSub => Expr::Sub(a, b),
+            Opcode::OpMin => Expr::Min(a, b),
+            Opcode::OpMax => Expr::Max(a, b),
+            Opcode::OpBoolAnd => Expr::BoolAnd(a, b),
+            Opcode::OpBoolOr => Expr::BoolOr(a, b),
+            Opcode::OpNumNotEqual => Expr::NumNotEqual(a, b),
+            Opcode::OpLessThan => Expr::LessThan(a, b),
+            Opcode::OpGreaterThan => Expr::GreaterThan(a, b),
+            Opcode::OpLessThanOrEqual => Expr::LessThanOrEqual(a, b),
+            Opcode::OpGreaterThanOrEqual => Expr::GreaterThanOrEqual(a, b),
+            Opcode::OpCheckSig => Expr::CheckSig(a, b),
+            _ => Expr::NumEqual(a, b),
+        }
+    }
+
+    fn multisig(sigs: Vec<Expr>, pubkeys: Vec<Expr>) -> Expr {
+        Expr::CheckMultiSig(sigs, pubkeys)
+    }
+
+    fn timelock(&mut self, op: Opcode, value: &Expr) {
+        self.0.push(if op == Opcode::OpCheckLockTimeVerify {
+            Constraint::After(value.clone())
+        } else {
+            Constraint::Older(value.clone())
+        });
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_hex<S: serde::Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&crate::hex::encode_hex(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::parse_asm;
+    use alloc::string::ToString;
+
+    #[test]
+    fn renumbers_from_bottom() {
+        // Reads three items; the top one is consumed first.
+        let tokens = parse_asm("OP_DROP OP_DROP OP_VERIFY OP_1").unwrap();
+        let conditions = symbolic_execute(&tokens).unwrap();
+        assert_eq!(conditions.paths[0].inputs, 3);
+        assert_eq!(
+            conditions.paths[0].constraints,
+            vec![Constraint::IsTrue(Expr::Var(0))]
+        );
+    }
+
+    #[test]
+    fn constants_fold() {
+        let tokens = parse_asm("OP_0 OP_SHA256 OP_SIZE OP_NIP").unwrap();
+        let conditions = symbolic_execute(&tokens).unwrap();
+        assert_eq!(conditions.paths[0].inputs, 0);
+        assert!(conditions.paths[0].constraints.is_empty());
+    }
+
+    #[test]
+    fn numeric_and_timelock_constraints() {
+        let tokens =
+            parse_asm("<900000> OP_CHECKSEQUENCEVERIFY OP_DROP OP_ADD OP_3 OP_NUMEQUAL").unwrap();
+        let conditions = symbolic_execute(&tokens).unwrap();
+        let text: Vec<_> = conditions.paths[0]
+            .constraints
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(text, ["sequence >= <900000>", "x0 + x1 == <03>"]);
+    }
+
+    #[test]
+    fn depth_display() {
+        assert_eq!(Expr::Depth(0).to_string(), "depth");
+        assert_eq!(Expr::Depth(2).to_string(), "depth + 2");
+        assert_eq!(Expr::Depth(-1).to_string(), "depth - 1");
+    }
+}
@@ -0,0 +1,272 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use secp256k1::{PublicKey, Secp256k1, SecretKey, XOnlyPublicKey};
+
+use crate::builder;
+use crate::engine::ExecuteOpts;
+use crate::flags::VerifyFlags;
+use crate::hash;
+use crate::sighash;
+use crate::sign;
+use crate::signature::SIGHASH_ALL;
+use crate::transaction::{OutPoint, Transaction, TxIn, TxOut};
+
+/// Value of the output every fixture's funding transaction creates.
+pub const FUNDING_VALUE: u64 = 100_000;
+
+/// Fee every fixture's spending transaction pays.
+pub const FEE: u64 = 1_000;
+
+/// A keypair derived deterministically from a seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestKey {
+    /// The private key.
+    pub secret_key: SecretKey,
+    /// The matching public key.
+    pub public_key: PublicKey,
+}
+
+impl TestKey {
+    /// Derives a keypair from `seed`: the secret key is SHA-256 of the
+    /// seed, re-hashed in the (astronomically unlikely) case it is not a
+    /// valid scalar. The same seed always yields the same key.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        let mut candidate = hash::sha256(seed);
+        let secret_key = loop {
+            match SecretKey::from_slice(&candidate) {
+                Ok(key) => break key,
+                Err(_) => candidate = hash::sha256(&candidate),
+            }
+        };
+        let public_key = PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret_key);
+        Self {
+            secret_key,
+            public_key,
+        }
+    }
+
+    /// The 33-byte compressed public key.
+    pub fn pubkey_bytes(&self) -> [u8; 33] {
+        self.public_key.serialize()
+    }
+
+    /// HASH160 of the compressed public key.
+    pub fn pubkey_hash(&self) -> [u8; 20] {
+        hash::hash160(&self.pubkey_bytes())
+    }
+
+    /// The x-only public key, used as a Taproot internal key.
+    pub fn x_only(&self) -> XOnlyPublicKey {
+        self.public_key.x_only_public_key().0
+    }
+}
+
+/// The output types [`fixture`] can build a spend for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptType {
+    /// Pay-to-Public-Key-Hash.
+    P2pkh,
+    /// Pay-to-Witness-Public-Key-Hash.
+    P2wpkh,
+    /// 2-of-3 multisig wrapped in P2SH.
+    P2shMultisig,
+    /// Taproot key path spend with no script tree (BIP86).
+    P2trKeyPath,
+}
+
+/// A funding transaction and a fully signed transaction spending its
+/// first output.
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    /// The output type being spent.
+    pub script_type: ScriptType,
+    /// The keys that control the funded output, in script order.
+    pub keys: Vec<TestKey>,
+    /// The redeem script, for P2SH types.
+    pub redeem_script: Option<Vec<u8>>,
+    /// Transaction creating the output to spend (output 0).
+    pub funding_tx: Transaction,
+    /// Transaction spending it (input 0), with scriptSig/witness filled in.
+    pub spending_tx: Transaction,
+}
+
+impl Fixture {
+    /// The output being spent.
+    pub fn prevout(&self) -> &TxOut {
+        &self.funding_tx.outputs[0]
+    }
+
+    /// The spending input's scriptSig.
+    pub fn script_sig(&self) -> &[u8] {
+        &self.spending_tx.inputs[0].script_sig
+    }
+
+    /// The spending input's witness.
+    pub fn witness(&self) -> &[Vec<u8>] {
+        &self.spending_tx.inputs[0].witness
+    }
+
+    /// The script the signatures commit to: the scriptPubKey for P2PKH,
+    /// the equivalent P2PKH script for P2WPKH, the redeem script for P2SH,
+    /// and the scriptPubKey for Taproot.
+    pub fn script_code(&self) -> Vec<u8> {
+        match self.script_type {
+            ScriptType::P2wpkh => builder::p2pkh(&self.keys[0].pubkey_hash()),
+            ScriptType::P2shMultisig => self.redeem_script.clone().unwrap_or_default(),
+            ScriptType::P2pkh | ScriptType::P2trKeyPath => self.prevout().script_pubkey.clone(),
+        }
+    }
+
+    /// The signature hash the spending input was signed over.
+    pub fn sighash(&self) -> [u8; 32] {
+        let tx = &self.spending_tx;
+        let result = match self.script_type {
+            ScriptType::P2pkh | ScriptType::P2shMultisig => {
+                sighash::legacy_sighash(tx, 0, &self.script_code(), SIGHASH_ALL)
+            }
+            ScriptType::P2wpkh => sighash::segwit_v0_sighash(
+                tx,
+                0,
+                &self.script_code(),
+                self.prevout().value,
+                SIGHASH_ALL,
+            ),
+            ScriptType::P2trKeyPath => {
+                sighash::taproot_key_spend_sighash(tx, 0, &[self.prevout().clone()], 0x00)
+            }
+        };
+        result.expect("fixture transactions are well-formed")
+    }
+
+    /// Execution options for verifying the spend in the engine: the
+    /// input's sighash plus the DERSIG, LOW_S, STRICTENC and NULLFAIL rules.
+    pub fn execute_opts(&self) -> ExecuteOpts {
+        ExecuteOpts {
+            sighash: Some(self.sighash()),
+            flags: VerifyFlags::DERSIG
+                | VerifyFlags::LOW_S
+                | VerifyFlags::STRICTENC
+                | VerifyFlags::NULLFAIL,
+            ..ExecuteOpts::default()
+        }
+    }
+}
+
+/// Builds a funding/spending transaction pair for `script_type`, with all
+/// keys derived from `seed`. Identical arguments give identical bytes.
+///
+/// The spending transaction sends [`FUNDING_VALUE`] minus [`FEE`] to a
+/// P2WPKH output and is signed with SIGHASH_ALL (SIGHASH_DEFAULT for
+/// Taproot).
+///
+/// ```rust
+/// use mini_bitcoin_script::script::validate_p2pkh_with_opts;
+/// use mini_bitcoin_script::test_utils::{fixture, ScriptType};
+///
+/// let fx = fixture(ScriptType::P2pkh, b"doc");
+/// let valid = validate_p2pkh_with_opts(
+///     fx.script_sig(),
+///     &fx.prevout().script_pubkey,
+///     &fx.execute_opts(),
+/// )
+/// .unwrap();
+/// assert!(valid);
+/// ```
+pub fn fixture(script_type: ScriptType, seed: &[u8]) -> Fixture {
+    let derive = |label: &[u8]| TestKey::from_seed(&[seed, label].concat());
+
+    let (keys, redeem_script, script_pubkey) = match script_type {
+        ScriptType::P2pkh => {
+            let key = derive(b"/p2pkh");
+            (vec![key], None, builder::p2pkh(&key.pubkey_hash()))
+        }
+        ScriptType::P2wpkh => {
+            let key = derive(b"/p2wpkh");
+            (vec![key], None, builder::p2wpkh(&key.pubkey_hash()))
+        }
+        ScriptType::P2shMultisig => {
+            let keys = vec![
+                derive(b"/multisig/0"),
+                derive(b"/multisig/1"),
+                derive(b"/multisig/2"),
+            ];
+            let pubkeys: Vec<[u8; 33]> = keys.iter().map(TestKey::pubkey_bytes).collect();
+            let redeem = builder::multisig(2, &[&pubkeys[0], &pubkeys[1], &pubkeys[2]])
+                .expect("2-of-3 is a valid multisig");
+            let script_pubkey = builder::p2sh(&hash::hash160(&redeem));
+            (keys, Some(redeem), script_pubkey)
+        }
+        ScriptType::P2trKeyPath => {
+            let key = derive(b"/p2tr");
+            let output_key = sign::taproot_output_key(&key.x_only());
+            (vec![key], None, builder::p2tr(&output_key.serialize()))
+        }
+    };
+
+    let funding_tx = Transaction {
+        version: 2,
+        inputs: vec![TxIn::new(OutPoint {
+            txid: hash::sha256(&[seed, b"/funding"].concat()),
+            vout: 0,
+        })],
+        outputs: vec![TxOut {
+            value: FUNDING_VALUE,
+            script_pubkey,
+        }],
+        lock_time: 0,
+    };
+
+    let recipient = derive(b"/recipient");
+    let mut spending_tx = Transaction {
+        version: 2,
+        inputs: vec![TxIn::new(OutPoint {
+            txid: funding_tx.txid(),
+            vout: 0,
+        })],
+        outputs: vec![TxOut {
+            value: FUNDING_VALUE - FEE,
+            script_pubkey: builder::p2wpkh(&recipient.pubkey_hash()),
+        }],
+        lock_time: 0,
+    };
+
+    let prevout = &funding_tx.outputs[0];
+    let signed = match script_type {
+        ScriptType::P2pkh => sign::sign_p2pkh(
+            &mut spending_tx,
+            0,
+            &keys[0].secret_key,
+            prevout,
+            SIGHASH_ALL,
+        ),
+        ScriptType::P2wpkh => sign::sign_p2wpkh(
+            &mut spending_tx,
+            0,
+            &keys[0].secret_key,
+            prevout,
+            SIGHASH_ALL,
+        ),
+        ScriptType::P2shMultisig => {
+            let signers = [keys[0].secret_key, keys[1].secret_key];
+            let redeem = redeem_script.as_deref().unwrap_or_default();
+            sign::sign_p2sh_multisig(&mut spending_tx, 0, &signers, redeem, prevout, SIGHASH_ALL)
+        }
+        ScriptType::P2trKeyPath => sign::sign_p2tr_key_path(
+            &mut spending_tx,
+            0,
+            &keys[0].secret_key,
+            core::slice::from_ref(prevout),
+            0x00,
+        ),
+    };
+    signed.expect("fixture keys match their outputs");
+
+    Fixture {
+        script_type,
+        keys,
+        redeem_script,
+        funding_tx,
+        spending_tx,
+    }
+}
@@ -1,3 +1,6 @@
+use alloc::vec::Vec;
+
+use crate::hex::encode_hex;
 use crate::opcode::Opcode;
 
 /// A parsed script element — either an opcode instruction or pushed data.
@@ -15,21 +18,49 @@ pub enum Token {
     Op(Opcode),
 }
 
-impl std::fmt::Display for Token {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Token {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            Token::PushData(data) => {
-                write!(f, "<")?;
-                for byte in data {
-                    write!(f, "{byte:02x}")?;
-                }
-                write!(f, ">")
-            }
+            Token::PushData(data) => write!(f, "<{}>", encode_hex(data)),
             Token::Op(opcode) => write!(f, "{opcode}"),
         }
     }
 }
 
+/// Serializes pushes as lowercase hex strings and opcodes as `OP_*` names.
+///
+/// The two cannot collide: every opcode name starts with `OP_`, which is
+/// not valid hex. An empty push serializes as `""`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Token {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Token::PushData(data) => serializer.serialize_str(&encode_hex(data)),
+            Token::Op(opcode) => serializer.serialize_str(opcode.name()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Token {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use alloc::format;
+        use alloc::string::String;
+        use serde::de::Error;
+
+        let s = String::deserialize(deserializer)?;
+        if s.starts_with("OP_") {
+            Opcode::from_name(&s)
+                .map(Token::Op)
+                .ok_or_else(|| D::Error::custom(format!("unknown opcode name: {s}")))
+        } else {
+            crate::hex::decode_hex(&s)
+                .map(Token::PushData)
+                .map_err(D::Error::custom)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use crate::error::ScriptError;
 use crate::hex::decode_hex;
 use crate::opcode::Opcode;
@@ -15,6 +17,8 @@ use crate::token::Token;
 /// Returns `ScriptError::UnexpectedEndOfScript` if a push-data instruction
 /// extends beyond the end of the byte slice, or
 /// `ScriptError::UnsupportedOpcode` for unrecognized byte values.
+/// Disabled opcodes (OP_CAT, OP_MUL, ...) parse; the engine rejects them
+/// when it reaches them, as they are OP_SUCCESSx in tapscript.
 pub fn parse_script(bytes: &[u8]) -> Result<Vec<Token>, ScriptError> {
     let mut tokens = Vec::new();
     let mut pos = 0;
@@ -102,6 +106,31 @@ pub fn parse_script_hex(hex: &str) -> Result<Vec<Token>, ScriptError> {
     parse_script(&bytes)
 }
 
+/// Parses a human-readable ASM string into tokens.
+///
+/// This is the inverse of [`Token`]'s `Display` form: whitespace-separated
+/// opcode names (`OP_DUP`, with or without the `OP_` prefix) and
+/// angle-bracketed hex pushes (`<89abcdef>`). An empty pair of brackets
+/// `<>` is an empty push.
+///
+/// Returns `ScriptError::InvalidAsm` for unknown opcode names or unclosed
+/// brackets, and `ScriptError::InvalidHex` for malformed push data.
+pub fn parse_asm(asm: &str) -> Result<Vec<Token>, ScriptError> {
+    let mut tokens = Vec::new();
+
+    for word in asm.split_whitespace() {
+        if let Some(rest) = word.strip_prefix('<') {
+            let hex = rest.strip_suffix('>').ok_or(ScriptError::InvalidAsm)?;
+            tokens.push(Token::PushData(decode_hex(hex)?));
+        } else {
+            let opcode = Opcode::from_name(word).ok_or(ScriptError::InvalidAsm)?;
+            tokens.push(Token::Op(opcode));
+        }
+    }
+
+    Ok(tokens)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,6 +209,19 @@ mod tests {
         assert!(matches!(err, ScriptError::UnsupportedOpcode(0x50)));
     }
 
+    #[test]
+    fn disabled_opcode() {
+        let tokens = parse_script(&[0x51, 0x7e, 0x95]).unwrap(); // OP_1 OP_CAT OP_MUL
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Op(Opcode::Op1),
+                Token::Op(Opcode::OpCat),
+                Token::Op(Opcode::OpMul)
+            ]
+        );
+    }
+
     #[test]
     fn op0_parses() {
         let tokens = parse_script(&[0x00]).unwrap();
@@ -231,4 +273,39 @@ mod tests {
         let tokens = parse_script(&[0x01, 0xff]).unwrap();
         assert_eq!(tokens, vec![Token::PushData(vec![0xff])]);
     }
+
+    #[test]
+    fn parse_asm_p2pkh() {
+        let asm = format!(
+            "OP_DUP OP_HASH160 <{}> OP_EQUALVERIFY OP_CHECKSIG",
+            "ab".repeat(20)
+        );
+        let tokens = parse_asm(&asm).unwrap();
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[2], Token::PushData(vec![0xab; 20]));
+        assert_eq!(tokens[4], Token::Op(Opcode::OpCheckSig));
+    }
+
+    #[test]
+    fn parse_asm_display_roundtrip() {
+        let tokens = parse_script_hex("0003aabbcc76a987").unwrap();
+        let asm = tokens
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(parse_asm(&asm).unwrap(), tokens);
+    }
+
+    #[test]
+    fn parse_asm_empty_push() {
+        assert_eq!(parse_asm("<>").unwrap(), vec![Token::PushData(vec![])]);
+    }
+
+    #[test]
+    fn parse_asm_errors() {
+        assert_eq!(parse_asm("OP_BOGUS"), Err(ScriptError::InvalidAsm));
+        assert_eq!(parse_asm("<abcd"), Err(ScriptError::InvalidAsm));
+        assert_eq!(parse_asm("<zz>"), Err(ScriptError::InvalidHex));
+    }
 }
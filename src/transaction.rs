@@ -0,0 +1,534 @@
+use alloc::vec::Vec;
+
+use crate::hash;
+
+/// A reference to a transaction output: the txid and output index.
+///
+/// The txid is stored in internal byte order (as hashed), which is the
+/// reverse of how block explorers display it. With the `serde` feature it
+/// serializes as hex in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OutPoint {
+    /// Hash of the transaction holding the output.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::hex::serialize_reversed_hex")
+    )]
+    pub txid: [u8; 32],
+    /// Index of the output within that transaction.
+    pub vout: u32,
+}
+
+/// A transaction input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxIn {
+    /// The output being spent.
+    pub previous_output: OutPoint,
+    /// Unlocking script (raw bytes).
+    pub script_sig: Vec<u8>,
+    /// Sequence number; `0xffffffff` disables relative locktime and RBF.
+    pub sequence: u32,
+    /// Segregated witness stack; empty for legacy inputs.
+    pub witness: Vec<Vec<u8>>,
+}
+
+impl TxIn {
+    /// Creates an input spending `previous_output` with an empty scriptSig,
+    /// empty witness and final sequence number.
+    pub fn new(previous_output: OutPoint) -> Self {
+        Self {
+            previous_output,
+            script_sig: Vec::new(),
+            sequence: 0xffff_ffff,
+            witness: Vec::new(),
+        }
+    }
+}
+
+/// A transaction output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxOut {
+    /// Amount in satoshis.
+    pub value: u64,
+    /// Locking script (raw bytes).
+    pub script_pubkey: Vec<u8>,
+}
+
+/// A Bitcoin transaction.
+///
+/// Only what is needed to compute txids and signature hashes is modelled;
+/// there is no consensus validation of amounts or locktimes here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    /// Transaction version (1 or 2 in practice).
+    pub version: i32,
+    /// Inputs, in order.
+    pub inputs: Vec<TxIn>,
+    /// Outputs, in order.
+    pub outputs: Vec<TxOut>,
+    /// Absolute locktime.
+    pub lock_time: u32,
+}
+
+impl Transaction {
+    /// Returns `true` for a coinbase transaction: a single input spending
+    /// the null outpoint (all-zero txid, index `0xffffffff`).
+    pub fn is_coinbase(&self) -> bool {
+        match self.inputs.as_slice() {
+            [input] => {
+                input.previous_output.txid == [0; 32] && input.previous_output.vout == u32::MAX
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if any input carries a witness.
+    pub fn has_witness(&self) -> bool {
+        self.inputs.iter().any(|input| !input.witness.is_empty())
+    }
+
+    /// Serializes the transaction in consensus format.
+    ///
+    /// Uses the BIP144 segwit format (marker, flag and witnesses) when any
+    /// input has a witness, and the legacy format otherwise.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out, self.has_witness());
+        out
+    }
+
+    /// Serializes the transaction in legacy format, omitting witnesses.
+    ///
+    /// This is the encoding hashed into the txid.
+    pub fn serialize_without_witness(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out, false);
+        out
+    }
+
+    /// Parses a transaction in consensus format, with or without BIP144
+    /// witness data. The whole of `bytes` must be used.
+    ///
+    /// As in Bitcoin Core, a marker byte of zero followed by a non-zero
+    /// flag is read as the segwit format, so a transaction with no inputs
+    /// can only be parsed if it has no outputs either.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`DecodeError`] for truncated or malformed data and for
+    /// bytes left over after the transaction.
+    pub fn deserialize(bytes: &[u8]) -> Result<Transaction, DecodeError> {
+        let mut reader = Reader::new(bytes);
+        let tx = Transaction::decode(&mut reader)?;
+        reader.finish()?;
+        Ok(tx)
+    }
+
+    /// Reads one transaction from `reader`, leaving it after the locktime.
+    pub(crate) fn decode(reader: &mut Reader<'_>) -> Result<Transaction, DecodeError> {
+        let version = reader.read_u32()? as i32;
+
+        let mut input_count = reader.read_compact_size()?;
+        let mut with_witness = false;
+        if input_count == 0 {
+            let flag = reader.read_u8()?;
+            if flag != 0x01 {
+                return Err(DecodeError::InvalidFlag(flag));
+            }
+            with_witness = true;
+            input_count = reader.read_compact_size()?;
+        }
+
+        let mut inputs = Vec::new();
+        for _ in 0..reader.count(input_count, 41)? {
+            let previous_output = reader.read_outpoint()?;
+            let script_sig = reader.read_var_bytes()?.to_vec();
+            let sequence = reader.read_u32()?;
+            inputs.push(TxIn {
+                previous_output,
+                script_sig,
+                sequence,
+                witness: Vec::new(),
+            });
+        }
+
+        let output_count = reader.read_compact_size()?;
+        let mut outputs = Vec::new();
+        for _ in 0..reader.count(output_count, 9)? {
+            outputs.push(reader.read_txout()?);
+        }
+
+        if with_witness {
+            for input in &mut inputs {
+                let item_count = reader.read_compact_size()?;
+                for _ in 0..reader.count(item_count, 1)? {
+                    input.witness.push(reader.read_var_bytes()?.to_vec());
+                }
+            }
+            if inputs.iter().all(|input| input.witness.is_empty()) {
+                return Err(DecodeError::SuperfluousWitness);
+            }
+        }
+
+        let lock_time = reader.read_u32()?;
+        Ok(Transaction {
+            version,
+            inputs,
+            outputs,
+            lock_time,
+        })
+    }
+
+    /// Returns the txid: HASH256 of the witness-stripped serialization,
+    /// in internal byte order.
+    pub fn txid(&self) -> [u8; 32] {
+        hash::hash256(&self.serialize_without_witness())
+    }
+
+    /// Returns the wtxid: HASH256 of the full serialization (BIP141).
+    ///
+    /// Equal to [`Transaction::txid`] for transactions without witnesses.
+    pub fn wtxid(&self) -> [u8; 32] {
+        hash::hash256(&self.serialize())
+    }
+
+    /// Returns the BIP141 weight: three times the witness-stripped size
+    /// plus the full size.
+    pub fn weight(&self) -> usize {
+        self.serialize_without_witness().len() * 3 + self.serialize().len()
+    }
+
+    /// Returns the virtual size: weight / 4, rounded up.
+    pub fn vsize(&self) -> usize {
+        (self.weight() + 3) / 4
+    }
+
+    fn encode(&self, out: &mut Vec<u8>, with_witness: bool) {
+        out.extend_from_slice(&self.version.to_le_bytes());
+        if with_witness {
+            out.extend_from_slice(&[0x00, 0x01]);
+        }
+
+        write_compact_size(out, self.inputs.len() as u64);
+        for input in &self.inputs {
+            write_outpoint(out, &input.previous_output);
+            write_bytes(out, &input.script_sig);
+            out.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+
+        write_compact_size(out, self.outputs.len() as u64);
+        for output in &self.outputs {
+            write_txout(out, output);
+        }
+
+        if with_witness {
+            for input in &self.inputs {
+                write_compact_size(out, input.witness.len() as u64);
+                for item in &input.witness {
+                    write_bytes(out, item);
+                }
+            }
+        }
+
+        out.extend_from_slice(&self.lock_time.to_le_bytes());
+    }
+}
+
+/// Why bytes could not be decoded as a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The data ended in the middle of a field.
+    UnexpectedEnd,
+
+    /// A CompactSize integer used more bytes than its value needs.
+    NonMinimalCompactSize,
+
+    /// The segwit flag byte after the zero marker is not 1.
+    InvalidFlag(u8),
+
+    /// The segwit format was used but every witness is empty.
+    SuperfluousWitness,
+
+    /// Bytes remain after the decoded data.
+    TrailingData,
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of data"),
+            DecodeError::NonMinimalCompactSize => write!(f, "non-minimal CompactSize"),
+            DecodeError::InvalidFlag(flag) => write!(f, "invalid segwit flag 0x{flag:02x}"),
+            DecodeError::SuperfluousWitness => write!(f, "segwit format with no witness data"),
+            DecodeError::TrailingData => write!(f, "trailing data after end"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// A cursor over consensus-encoded bytes.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    /// Whether every byte has been read.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos == self.data.len()
+    }
+
+    /// Number of bytes not yet read.
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    /// Fails with [`DecodeError::TrailingData`] unless every byte has
+    /// been read.
+    pub(crate) fn finish(&self) -> Result<(), DecodeError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::TrailingData)
+        }
+    }
+
+    pub(crate) fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.remaining() < len {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub(crate) fn read_array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, DecodeError> {
+        self.read_array().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64, DecodeError> {
+        self.read_array().map(u64::from_le_bytes)
+    }
+
+    /// Reads a CompactSize integer, rejecting non-minimal encodings.
+    pub(crate) fn read_compact_size(&mut self) -> Result<u64, DecodeError> {
+        let (n, min) = match self.read_u8()? {
+            0xfd => (u64::from(u16::from_le_bytes(self.read_array()?)), 0xfd),
+            0xfe => (u64::from(self.read_u32()?), 0x1_0000),
+            0xff => (self.read_u64()?, 0x1_0000_0000),
+            n => return Ok(u64::from(n)),
+        };
+        if n < min {
+            return Err(DecodeError::NonMinimalCompactSize);
+        }
+        Ok(n)
+    }
+
+    /// Checks that `n` items of at least `min_size` bytes each could fit
+    /// in the remaining data, so a corrupt count cannot cause a huge
+    /// allocation.
+    pub(crate) fn count(&self, n: u64, min_size: usize) -> Result<usize, DecodeError> {
+        if n.saturating_mul(min_size as u64) > self.remaining() as u64 {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        Ok(n as usize)
+    }
+
+    /// Reads a CompactSize length and that many bytes.
+    pub(crate) fn read_var_bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len = self.read_compact_size()?;
+        let len = self.count(len, 1)?;
+        self.read_bytes(len)
+    }
+
+    pub(crate) fn read_outpoint(&mut self) -> Result<OutPoint, DecodeError> {
+        Ok(OutPoint {
+            txid: self.read_array()?,
+            vout: self.read_u32()?,
+        })
+    }
+
+    pub(crate) fn read_txout(&mut self) -> Result<TxOut, DecodeError> {
+        Ok(TxOut {
+            value: self.read_u64()?,
+            script_pubkey: self.read_var_bytes()?.to_vec(),
+        })
+    }
+}
+
+/// Appends a Bitcoin CompactSize integer.
+pub(crate) fn write_compact_size(out: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => out.push(n as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+/// Appends a length-prefixed byte string.
+/// Number of bytes [`write_compact_size`] uses for `n`.
+pub(crate) fn compact_size_len(n: usize) -> usize {
+    match n as u64 {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+pub(crate) fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_compact_size(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Appends an outpoint: 32-byte txid then 4-byte little-endian index.
+pub(crate) fn write_outpoint(out: &mut Vec<u8>, outpoint: &OutPoint) {
+    out.extend_from_slice(&outpoint.txid);
+    out.extend_from_slice(&outpoint.vout.to_le_bytes());
+}
+
+/// Appends an output: 8-byte little-endian value then the script.
+pub(crate) fn write_txout(out: &mut Vec<u8>, output: &TxOut) {
+    out.extend_from_slice(&output.value.to_le_bytes());
+    write_bytes(out, &output.script_pubkey);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use hex_literal::hex;
+
+    #[test]
+    fn compact_size_boundaries() {
+        let encode = |n| {
+            let mut out = Vec::new();
+            write_compact_size(&mut out, n);
+            out
+        };
+        assert_eq!(encode(0xfc), vec![0xfc]);
+        assert_eq!(encode(0xfd), vec![0xfd, 0xfd, 0x00]);
+        assert_eq!(encode(0xffff), vec![0xfd, 0xff, 0xff]);
+        assert_eq!(encode(0x1_0000), vec![0xfe, 0x00, 0x00, 0x01, 0x00]);
+        assert_eq!(encode(0x1_0000_0000), vec![0xff, 0, 0, 0, 0, 1, 0, 0, 0]);
+    }
+
+    fn sample() -> Transaction {
+        Transaction {
+            version: 1,
+            inputs: vec![TxIn::new(OutPoint {
+                txid: [0x11; 32],
+                vout: 2,
+            })],
+            outputs: vec![TxOut {
+                value: 50_000,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+        }
+    }
+
+    #[test]
+    fn legacy_serialization() {
+        let tx = sample();
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&hex!("01000000 01"));
+        expected.extend_from_slice(&[0x11; 32]);
+        expected.extend_from_slice(&hex!("02000000 00 ffffffff"));
+        expected.extend_from_slice(&hex!("01 50c3000000000000 01 51"));
+        expected.extend_from_slice(&hex!("00000000"));
+        assert_eq!(tx.serialize(), expected);
+        assert_eq!(tx.txid(), tx.wtxid());
+    }
+
+    #[test]
+    fn witness_serialization() {
+        let mut tx = sample();
+        tx.inputs[0].witness = vec![vec![0xaa, 0xbb], vec![]];
+        let full = tx.serialize();
+        assert_eq!(&full[4..6], &[0x00, 0x01]);
+        assert!(full.ends_with(&hex!("02 02aabb 00 00000000")));
+
+        // The txid ignores the witness; the wtxid does not.
+        assert_eq!(tx.txid(), sample().txid());
+        assert_ne!(tx.txid(), tx.wtxid());
+    }
+
+    #[test]
+    fn deserialize_roundtrip() {
+        let mut tx = sample();
+        assert_eq!(Transaction::deserialize(&tx.serialize()), Ok(tx.clone()));
+        tx.inputs[0].witness = vec![vec![0xaa, 0xbb], vec![]];
+        let bytes = tx.serialize();
+        assert_eq!(Transaction::deserialize(&bytes), Ok(tx));
+
+        assert_eq!(
+            Transaction::deserialize(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Transaction::deserialize(&[&bytes[..], &[0]].concat()),
+            Err(DecodeError::TrailingData)
+        );
+        let mut flag = bytes.clone();
+        flag[5] = 0x02;
+        assert_eq!(
+            Transaction::deserialize(&flag),
+            Err(DecodeError::InvalidFlag(0x02))
+        );
+    }
+
+    #[test]
+    fn compact_size_must_be_minimal() {
+        let mut reader = Reader::new(&[0xfd, 0xfc, 0x00]);
+        assert_eq!(
+            reader.read_compact_size(),
+            Err(DecodeError::NonMinimalCompactSize)
+        );
+        let mut reader = Reader::new(&[0xfe, 0x00, 0x00, 0x01, 0x00]);
+        assert_eq!(reader.read_compact_size(), Ok(0x1_0000));
+        assert!(reader.is_empty());
+        // A count far larger than the data is rejected before allocating.
+        let mut reader = Reader::new(&[0xff, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(reader.read_var_bytes(), Err(DecodeError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn weight_counts_witness_once() {
+        let mut tx = sample();
+        let base = tx.serialize().len();
+        assert_eq!(tx.weight(), base * 4);
+
+        // Marker, flag, and a witness of one 2-byte item: 2 + 1 + 3 bytes.
+        tx.inputs[0].witness = vec![vec![0xaa, 0xbb]];
+        assert_eq!(tx.weight(), base * 4 + 6);
+        assert_eq!(tx.vsize(), base + 2);
+    }
+}
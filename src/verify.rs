@@ -0,0 +1,857 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::builder::{self, ScriptBuilder};
+use crate::classify::{is_p2sh, is_push_only, witness_program};
+use crate::engine::{execute_on_stack, ExecuteOpts, SigVersion, SighashFn};
+use crate::error::ScriptError;
+use crate::flags::VerifyFlags;
+use crate::hash;
+#[cfg(feature = "std")]
+use crate::sigcache::SignatureCache;
+use crate::sighash::{self, SegwitV0Midstate, SighashError, TaprootMidstate};
+use crate::stack::{is_true, Stack};
+use crate::tokenizer::parse_script;
+use crate::transaction::{OutPoint, Transaction, TxOut};
+
+/// Why a transaction input failed verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum VerifyError {
+    /// The input index or the number of spent outputs is wrong, so no
+    /// signature hash could be computed.
+    Sighash(SighashError),
+
+    /// A script failed, or the spend broke a P2SH or witness rule.
+    Script(ScriptError),
+
+    /// The input is a Taproot script path spend or carries an annex,
+    /// whose signature hashes are not implemented.
+    UnsupportedTaproot,
+}
+
+impl From<SighashError> for VerifyError {
+    fn from(err: SighashError) -> Self {
+        VerifyError::Sighash(err)
+    }
+}
+
+impl From<ScriptError> for VerifyError {
+    fn from(err: ScriptError) -> Self {
+        VerifyError::Script(err)
+    }
+}
+
+impl core::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VerifyError::Sighash(err) => write!(f, "sighash: {err}"),
+            VerifyError::Script(err) => write!(f, "script: {err}"),
+            VerifyError::UnsupportedTaproot => {
+                write!(
+                    f,
+                    "taproot script path spends and annexes are not supported"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifyError {}
+
+/// Options for [`verify_tx_with_opts`] and the other verifiers that take
+/// them.
+///
+/// The P2SH, locktime, segwit and Taproot rules are on by default;
+/// historic blocks need them switched off before their activation, as
+/// [`VerifyOpts::mainnet`] does.
+#[derive(Debug, Clone)]
+pub struct VerifyOpts {
+    /// Additional verification rules, e.g. strict signature encoding.
+    pub flags: VerifyFlags,
+
+    /// Run P2SH redeem scripts (BIP16).
+    pub p2sh: bool,
+
+    /// Enforce OP_CHECKLOCKTIMEVERIFY (BIP65). Without it the opcode is
+    /// OP_NOP2.
+    pub checklocktimeverify: bool,
+
+    /// Enforce OP_CHECKSEQUENCEVERIFY (BIP112). Without it the opcode is
+    /// OP_NOP3.
+    pub checksequenceverify: bool,
+
+    /// Apply the segwit rules (BIP141, BIP143). Without them witness
+    /// programs are ordinary scripts and witnesses are ignored.
+    pub segwit: bool,
+
+    /// Verify Taproot key path spends (BIP341). Without it version 1
+    /// witness programs are left for future soft forks and succeed.
+    pub taproot: bool,
+
+    /// A cache of signatures already verified, shared with every script
+    /// run. Checking a transaction for the mempool and again in a block
+    /// then verifies each signature once.
+    #[cfg(feature = "std")]
+    pub sig_cache: Option<Arc<SignatureCache>>,
+}
+
+impl Default for VerifyOpts {
+    fn default() -> Self {
+        VerifyOpts {
+            flags: VerifyFlags::NONE,
+            p2sh: true,
+            checklocktimeverify: true,
+            checksequenceverify: true,
+            segwit: true,
+            taproot: true,
+            #[cfg(feature = "std")]
+            sig_cache: None,
+        }
+    }
+}
+
+impl VerifyOpts {
+    /// The consensus rules mainnet enforced on blocks at `height`: P2SH
+    /// from 173,805, strict DER signatures (BIP66) from 363,725,
+    /// OP_CHECKLOCKTIMEVERIFY (BIP65) from 388,381,
+    /// OP_CHECKSEQUENCEVERIFY (BIP112) from 419,328, segwit from 481,824
+    /// and Taproot from 709,632.
+    pub fn mainnet(height: u32) -> Self {
+        let mut flags = VerifyFlags::NONE;
+        if height >= 363_725 {
+            flags.insert(VerifyFlags::DERSIG);
+        }
+        let mut opts = VerifyOpts::from(flags);
+        opts.p2sh = height >= 173_805;
+        opts.checklocktimeverify = height >= 388_381;
+        opts.checksequenceverify = height >= 419_328;
+        opts.segwit = height >= 481_824;
+        opts.taproot = height >= 709_632;
+        opts
+    }
+}
+
+impl From<VerifyFlags> for VerifyOpts {
+    fn from(flags: VerifyFlags) -> Self {
+        VerifyOpts {
+            flags,
+            ..VerifyOpts::default()
+        }
+    }
+}
+
+/// The largest amount of bitcoin, in satoshis, that can ever exist.
+pub const MAX_MONEY: u64 = 21_000_000 * 100_000_000;
+
+/// A source of unspent transaction outputs.
+///
+/// Implemented for [`BTreeMap`] and (with the `std` feature)
+/// `std::collections::HashMap` keyed by [`OutPoint`].
+pub trait UtxoProvider {
+    /// Returns the unspent output at `outpoint`, or `None` if it is
+    /// unknown or already spent.
+    fn utxo(&self, outpoint: &OutPoint) -> Option<TxOut>;
+}
+
+impl<P: UtxoProvider + ?Sized> UtxoProvider for &P {
+    fn utxo(&self, outpoint: &OutPoint) -> Option<TxOut> {
+        (**self).utxo(outpoint)
+    }
+}
+
+impl UtxoProvider for BTreeMap<OutPoint, TxOut> {
+    fn utxo(&self, outpoint: &OutPoint) -> Option<TxOut> {
+        self.get(outpoint).cloned()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: core::hash::BuildHasher> UtxoProvider for std::collections::HashMap<OutPoint, TxOut, S> {
+    fn utxo(&self, outpoint: &OutPoint) -> Option<TxOut> {
+        self.get(outpoint).cloned()
+    }
+}
+
+/// Why a transaction could not be verified as a whole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TxVerifyError {
+    /// The transaction has no inputs.
+    NoInputs,
+
+    /// The transaction has no outputs.
+    NoOutputs,
+
+    /// Two inputs spend the same output.
+    DuplicateInput {
+        /// Index of the second input spending it.
+        index: usize,
+    },
+
+    /// The provider does not know the output an input spends.
+    MissingUtxo {
+        /// Index of the input.
+        index: usize,
+        /// The output it spends.
+        outpoint: OutPoint,
+    },
+
+    /// An amount, or the total of the inputs or outputs, is above
+    /// [`MAX_MONEY`].
+    ValueOutOfRange,
+
+    /// The outputs spend more than the inputs provide.
+    NegativeFee {
+        /// Total value of the spent outputs.
+        inputs: u64,
+        /// Total value of the transaction's outputs.
+        outputs: u64,
+    },
+}
+
+impl core::fmt::Display for TxVerifyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TxVerifyError::NoInputs => write!(f, "transaction has no inputs"),
+            TxVerifyError::NoOutputs => write!(f, "transaction has no outputs"),
+            TxVerifyError::DuplicateInput { index } => {
+                write!(
+                    f,
+                    "input {index} spends an output already spent by the transaction"
+                )
+            }
+            TxVerifyError::MissingUtxo { index, outpoint } => write!(
+                f,
+                "input {index} spends unknown output {}:{}",
+                crate::hex::ReversedHex(&outpoint.txid),
+                outpoint.vout
+            ),
+            TxVerifyError::ValueOutOfRange => write!(f, "amount out of range"),
+            TxVerifyError::NegativeFee { inputs, outputs } => {
+                write!(f, "outputs ({outputs} sat) exceed inputs ({inputs} sat)")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TxVerifyError {}
+
+/// The result of [`verify_tx`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TxVerification {
+    /// The fee paid: the spent outputs' value less the outputs' value.
+    pub fee: u64,
+    /// The result of verifying each input, in input order.
+    pub inputs: Vec<Result<(), VerifyError>>,
+}
+
+impl TxVerification {
+    /// Returns `true` if every input verified.
+    pub fn is_valid(&self) -> bool {
+        self.inputs.iter().all(Result::is_ok)
+    }
+
+    /// Returns the first input that failed, with its error.
+    pub fn first_error(&self) -> Option<(usize, &VerifyError)> {
+        self.inputs
+            .iter()
+            .enumerate()
+            .find_map(|(index, result)| result.as_ref().err().map(|err| (index, err)))
+    }
+}
+
+/// Verifies every input of `tx`, looking up the outputs they spend in
+/// `utxos`.
+///
+/// The transaction must spend each output at most once, every amount
+/// must be in range, and the outputs must not spend more than the
+/// inputs; otherwise no input is verified. Each input is then checked
+/// with [`verify_input`], and its result recorded in
+/// [`TxVerification::inputs`]. With the `rayon` feature the inputs are
+/// checked in parallel on rayon's global thread pool.
+///
+/// ```rust
+/// # #[cfg(feature = "test-utils")] {
+/// use std::collections::HashMap;
+///
+/// use mini_bitcoin_script::flags::VerifyFlags;
+/// use mini_bitcoin_script::test_utils::{fixture, ScriptType, FEE};
+/// use mini_bitcoin_script::verify::verify_tx;
+///
+/// let fx = fixture(ScriptType::P2pkh, b"doc");
+/// let mut utxos = HashMap::new();
+/// utxos.insert(fx.spending_tx.inputs[0].previous_output, fx.prevout().clone());
+///
+/// let result = verify_tx(&fx.spending_tx, &utxos, VerifyFlags::NULLFAIL).unwrap();
+/// assert!(result.is_valid());
+/// assert_eq!(result.fee, FEE);
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// A [`TxVerifyError`] if the transaction is empty, spends an output
+/// twice or one `utxos` does not hold, or its amounts do not balance.
+/// Script failures are reported per input instead.
+pub fn verify_tx<P: UtxoProvider + ?Sized>(
+    tx: &Transaction,
+    utxos: &P,
+    flags: VerifyFlags,
+) -> Result<TxVerification, TxVerifyError> {
+    verify_tx_with_opts(tx, utxos, &flags.into())
+}
+
+/// [`verify_tx`] with [`VerifyOpts`], e.g. to share a signature cache.
+///
+/// # Errors
+///
+/// As for [`verify_tx`].
+pub fn verify_tx_with_opts<P: UtxoProvider + ?Sized>(
+    tx: &Transaction,
+    utxos: &P,
+    opts: &VerifyOpts,
+) -> Result<TxVerification, TxVerifyError> {
+    if tx.inputs.is_empty() {
+        return Err(TxVerifyError::NoInputs);
+    }
+    if tx.outputs.is_empty() {
+        return Err(TxVerifyError::NoOutputs);
+    }
+
+    let mut seen = BTreeSet::new();
+    let mut prevouts = Vec::with_capacity(tx.inputs.len());
+    for (index, input) in tx.inputs.iter().enumerate() {
+        let outpoint = input.previous_output;
+        if !seen.insert(outpoint) {
+            return Err(TxVerifyError::DuplicateInput { index });
+        }
+        let prevout = utxos
+            .utxo(&outpoint)
+            .ok_or(TxVerifyError::MissingUtxo { index, outpoint })?;
+        prevouts.push(prevout);
+    }
+
+    let inputs = total_value(&prevouts)?;
+    let outputs = total_value(&tx.outputs)?;
+    let fee = inputs
+        .checked_sub(outputs)
+        .ok_or(TxVerifyError::NegativeFee { inputs, outputs })?;
+
+    Ok(TxVerification {
+        fee,
+        inputs: verify_inputs(tx, &prevouts, opts),
+    })
+}
+
+/// Sums `outputs`, checking each amount and the total against
+/// [`MAX_MONEY`] as Bitcoin Core does.
+fn total_value(outputs: &[TxOut]) -> Result<u64, TxVerifyError> {
+    outputs.iter().try_fold(0u64, |total, output| {
+        if output.value > MAX_MONEY {
+            return Err(TxVerifyError::ValueOutOfRange);
+        }
+        // Both are at most MAX_MONEY, so this cannot overflow.
+        let total = total + output.value;
+        if total > MAX_MONEY {
+            return Err(TxVerifyError::ValueOutOfRange);
+        }
+        Ok(total)
+    })
+}
+
+/// Verifies input `index` of `tx` against the outputs it spends.
+///
+/// `prevouts` holds the output spent by every input, in input order, as
+/// Taproot signatures commit to all of them. The scriptSig, scriptPubKey
+/// and any redeem or witness script run as in Bitcoin Core, with the
+/// P2SH (BIP16) and segwit (BIP141, BIP143, BIP341 key path) rules on;
+/// `flags` adds the encoding rules. [`verify_input_with_opts`] can switch
+/// the soft-fork rules off for historic transactions.
+///
+/// Each signature is checked against the digest for its own sighash type
+/// byte, so a script may mix sighash types. Without the `secp256k1`
+/// feature, signatures are checked in stub mode and only the script logic
+/// is verified.
+///
+/// ```rust
+/// # #[cfg(feature = "test-utils")] {
+/// use mini_bitcoin_script::flags::VerifyFlags;
+/// use mini_bitcoin_script::test_utils::{fixture, ScriptType};
+/// use mini_bitcoin_script::verify::verify_input;
+///
+/// let fx = fixture(ScriptType::P2wpkh, b"doc");
+/// let prevouts = [fx.prevout().clone()];
+/// assert!(verify_input(&fx.spending_tx, 0, &prevouts, VerifyFlags::NULLFAIL).is_ok());
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// [`VerifyError::Sighash`] for a bad index or prevout count,
+/// [`VerifyError::Script`] for a failed spend and
+/// [`VerifyError::UnsupportedTaproot`] for Taproot spends other than a
+/// plain key path.
+pub fn verify_input(
+    tx: &Transaction,
+    index: usize,
+    prevouts: &[TxOut],
+    flags: VerifyFlags,
+) -> Result<(), VerifyError> {
+    verify_input_with_opts(tx, index, prevouts, &flags.into())
+}
+
+/// [`verify_input`] with [`VerifyOpts`], e.g. to share a signature cache.
+///
+/// # Errors
+///
+/// As for [`verify_input`].
+pub fn verify_input_with_opts(
+    tx: &Transaction,
+    index: usize,
+    prevouts: &[TxOut],
+    opts: &VerifyOpts,
+) -> Result<(), VerifyError> {
+    verify_spend(&TxData::new(tx, prevouts), index, opts)
+}
+
+/// Verifies every input of `tx`, sharing one [`SegwitV0Midstate`] and
+/// one [`TaprootMidstate`]; in parallel with the `rayon` feature.
+pub(crate) fn verify_inputs(
+    tx: &Transaction,
+    prevouts: &[TxOut],
+    opts: &VerifyOpts,
+) -> Vec<Result<(), VerifyError>> {
+    let data = TxData::new(tx, prevouts);
+    let verify = |index| verify_spend(&data, index, opts);
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        (0..tx.inputs.len()).into_par_iter().map(verify).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        (0..tx.inputs.len()).map(verify).collect()
+    }
+}
+
+/// The transaction data signature hashes commit to, owned so that the
+/// engine's [`SighashFn`]s can share it.
+struct TxData {
+    tx: Transaction,
+    prevouts: Vec<TxOut>,
+    midstate: SegwitV0Midstate,
+    taproot: TaprootMidstate,
+}
+
+impl TxData {
+    fn new(tx: &Transaction, prevouts: &[TxOut]) -> Arc<Self> {
+        Arc::new(TxData {
+            tx: tx.clone(),
+            prevouts: prevouts.to_vec(),
+            midstate: SegwitV0Midstate::new(tx),
+            taproot: TaprootMidstate::new(tx, prevouts),
+        })
+    }
+}
+
+fn verify_spend(data: &Arc<TxData>, index: usize, opts: &VerifyOpts) -> Result<(), VerifyError> {
+    let (tx, prevouts) = (&data.tx, &data.prevouts);
+    let input = tx.inputs.get(index).ok_or(SighashError::InputIndex {
+        index,
+        inputs: tx.inputs.len(),
+    })?;
+    if prevouts.len() != tx.inputs.len() {
+        return Err(SighashError::PrevoutCount {
+            expected: tx.inputs.len(),
+            actual: prevouts.len(),
+        }
+        .into());
+    }
+    let spend = Spend { data, index, opts };
+    let script_pubkey = &prevouts[index].script_pubkey;
+
+    let p2sh = opts.p2sh && is_p2sh(script_pubkey);
+    if p2sh && !is_push_only(&input.script_sig) {
+        return Err(ScriptError::SigPushOnly.into());
+    }
+    let mut stack = Stack::new();
+    execute_on_stack(
+        &parse_script(&input.script_sig)?,
+        &mut stack,
+        &spend.legacy_opts(&input.script_sig),
+    )?;
+    let mut items = stack.into_items();
+    spend.run_legacy(script_pubkey, items.clone())?;
+
+    // Without segwit, witness programs are ordinary scripts.
+    let segwit_program = |script| witness_program(script).filter(|_| opts.segwit);
+    if let Some((version, program)) = segwit_program(script_pubkey) {
+        if !input.script_sig.is_empty() {
+            return Err(ScriptError::WitnessMalleated.into());
+        }
+        return spend.verify_witness(version, program, false);
+    }
+
+    if p2sh {
+        // The scriptPubKey's OP_EQUAL succeeded, so the stack is not empty.
+        let redeem_script = items.pop().ok_or(ScriptError::StackUnderflow)?;
+        spend.run_legacy(&redeem_script, items)?;
+        if let Some((version, program)) = segwit_program(&redeem_script) {
+            let expected = ScriptBuilder::new().push_slice(&redeem_script).into_bytes();
+            if input.script_sig != expected {
+                return Err(ScriptError::WitnessMalleatedP2sh.into());
+            }
+            return spend.verify_witness(version, program, true);
+        }
+    }
+
+    if opts.segwit && !input.witness.is_empty() {
+        return Err(ScriptError::WitnessUnexpected.into());
+    }
+    Ok(())
+}
+
+/// The input being verified.
+struct Spend<'a> {
+    data: &'a Arc<TxData>,
+    index: usize,
+    opts: &'a VerifyOpts,
+}
+
+impl Spend<'_> {
+    fn opts(&self, sighash_fn: SighashFn, sig_version: SigVersion) -> ExecuteOpts {
+        let tx = &self.data.tx;
+        ExecuteOpts {
+            sighash: None,
+            sighash_fn: Some(sighash_fn),
+            sig_version,
+            flags: self.opts.flags,
+            lock_time: self.opts.checklocktimeverify.then_some(tx.lock_time),
+            sequence: Some(tx.inputs[self.index].sequence),
+            tx_version: Some(tx.version),
+            nop3: !self.opts.checksequenceverify,
+            #[cfg(feature = "std")]
+            sig_cache: self.opts.sig_cache.clone(),
+        }
+    }
+
+    /// Options for running a legacy script, hashing each signature with
+    /// `script_code`.
+    fn legacy_opts(&self, script_code: &[u8]) -> ExecuteOpts {
+        let (data, index, script_code) = (Arc::clone(self.data), self.index, script_code.to_vec());
+        let sighash_fn = SighashFn::new(move |sig| {
+            let hash_type = *sig.last()?;
+            let script_code = sighash::find_and_delete(&script_code, sig);
+            sighash::legacy_sighash(&data.tx, index, &script_code, hash_type).ok()
+        });
+        self.opts(sighash_fn, SigVersion::Base)
+    }
+
+    /// Runs a legacy script on `items`, which must leave a true top item.
+    fn run_legacy(&self, script: &[u8], items: Vec<Vec<u8>>) -> Result<(), VerifyError> {
+        let opts = self.legacy_opts(script);
+        let mut stack = Stack::from_items(items);
+        execute_on_stack(&parse_script(script)?, &mut stack, &opts)?;
+        match stack.into_items().last() {
+            Some(top) if is_true(top) => Ok(()),
+            _ => Err(ScriptError::ScriptFailed.into()),
+        }
+    }
+
+    fn verify_witness(&self, version: u8, program: &[u8], nested: bool) -> Result<(), VerifyError> {
+        let witness = &self.data.tx.inputs[self.index].witness;
+        match (version, program.len()) {
+            (0, 20) => {
+                if witness.len() != 2 {
+                    return Err(ScriptError::WitnessProgramMismatch.into());
+                }
+                let mut hash = [0; 20];
+                hash.copy_from_slice(program);
+                self.run_witness(&builder::p2pkh(&hash), witness.clone())
+            }
+            (0, 32) => {
+                let (script, items) = witness
+                    .split_last()
+                    .ok_or(ScriptError::WitnessProgramWitnessEmpty)?;
+                if hash::sha256(script) != program {
+                    return Err(ScriptError::WitnessProgramMismatch.into());
+                }
+                self.run_witness(script, items.to_vec())
+            }
+            (0, _) => Err(ScriptError::WitnessProgramWrongLength.into()),
+            (1, 32) if !nested && self.opts.taproot => self.verify_key_path(program),
+            // Unknown versions, and Taproot nested in P2SH, are left
+            // for future soft forks and succeed.
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs a version 0 witness script, which must leave exactly one true
+    /// item (BIP141).
+    fn run_witness(&self, script: &[u8], items: Vec<Vec<u8>>) -> Result<(), VerifyError> {
+        let (data, index, script_code) = (Arc::clone(self.data), self.index, script.to_vec());
+        let sighash_fn = SighashFn::new(move |sig| {
+            let hash_type = *sig.last()?;
+            let amount = data.prevouts[index].value;
+            sighash::segwit_v0_sighash_with_midstate(
+                &data.tx,
+                &data.midstate,
+                index,
+                &script_code,
+                amount,
+                hash_type,
+            )
+            .ok()
+        });
+        let mut stack = Stack::from_items(items);
+        let opts = self.opts(sighash_fn, SigVersion::WitnessV0);
+        execute_on_stack(&parse_script(script)?, &mut stack, &opts)?;
+        match stack.into_items().as_slice() {
+            [top] if is_true(top) => Ok(()),
+            [_] | [] => Err(ScriptError::ScriptFailed.into()),
+            _ => Err(ScriptError::CleanStack.into()),
+        }
+    }
+
+    fn verify_key_path(&self, output_key: &[u8]) -> Result<(), VerifyError> {
+        let (tx, prevouts) = (&self.data.tx, &self.data.prevouts);
+        let sig = match tx.inputs[self.index].witness.as_slice() {
+            [] => return Err(ScriptError::WitnessProgramWitnessEmpty.into()),
+            [sig] => sig,
+            _ => return Err(VerifyError::UnsupportedTaproot),
+        };
+        let hash_type = match sig.len() {
+            64 => 0x00,
+            65 if sig[64] != 0x00 => sig[64],
+            65 => return Err(ScriptError::SchnorrSigHashType.into()),
+            _ => return Err(ScriptError::SchnorrSigSize.into()),
+        };
+        let digest = sighash::taproot_key_spend_sighash_with_midstate(
+            tx,
+            &self.data.taproot,
+            self.index,
+            prevouts,
+            hash_type,
+        )
+        .map_err(|err| match err {
+            SighashError::InvalidHashType(_) | SighashError::SingleWithoutOutput => {
+                VerifyError::Script(ScriptError::SchnorrSigHashType)
+            }
+            err => VerifyError::Sighash(err),
+        })?;
+
+        #[cfg(feature = "secp256k1")]
+        {
+            if !crate::engine::verify_schnorr(sig, output_key, &digest) {
+                return Err(ScriptError::SchnorrSig.into());
+            }
+        }
+        let _ = (output_key, digest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opcode::Opcode;
+    use crate::transaction::{OutPoint, TxIn};
+    use alloc::vec;
+
+    fn spend(
+        script_pubkey: Vec<u8>,
+        script_sig: Vec<u8>,
+        witness: Vec<Vec<u8>>,
+    ) -> Result<(), VerifyError> {
+        let mut input = TxIn::new(OutPoint::default());
+        input.script_sig = script_sig;
+        input.witness = witness;
+        let tx = Transaction {
+            version: 2,
+            inputs: vec![input],
+            outputs: vec![],
+            lock_time: 0,
+        };
+        let prevout = TxOut {
+            value: 1000,
+            script_pubkey,
+        };
+        verify_input(&tx, 0, &[prevout], VerifyFlags::NONE)
+    }
+
+    #[test]
+    fn p2sh_runs_redeem_script() {
+        // Redeem script OP_1, and a scriptSig that pushes it.
+        let p2sh = builder::p2sh(&hash::hash160(&[0x51]));
+        assert_eq!(spend(p2sh.clone(), vec![0x01, 0x51], vec![]), Ok(()));
+        // Redeem script OP_0 leaves false.
+        let p2sh_false = builder::p2sh(&hash::hash160(&[0x00]));
+        assert_eq!(
+            spend(p2sh_false, vec![0x01, 0x00], vec![]),
+            Err(VerifyError::Script(ScriptError::ScriptFailed))
+        );
+        // scriptSig must be push-only.
+        assert_eq!(
+            spend(p2sh, vec![0x51, 0x76, 0x01, 0x51], vec![]),
+            Err(VerifyError::Script(ScriptError::SigPushOnly))
+        );
+    }
+
+    #[test]
+    fn p2wsh_rules() {
+        let script = vec![0x51];
+        let p2wsh = builder::p2wsh(&hash::sha256(&script));
+        assert_eq!(spend(p2wsh.clone(), vec![], vec![script.clone()]), Ok(()));
+        assert_eq!(
+            spend(p2wsh.clone(), vec![], vec![vec![0x52]]),
+            Err(VerifyError::Script(ScriptError::WitnessProgramMismatch))
+        );
+        assert_eq!(
+            spend(p2wsh.clone(), vec![0x00], vec![script.clone()]),
+            Err(VerifyError::Script(ScriptError::WitnessMalleated))
+        );
+        // An extra item left on the stack breaks the clean stack rule.
+        assert_eq!(
+            spend(p2wsh, vec![], vec![vec![0x01], script]),
+            Err(VerifyError::Script(ScriptError::CleanStack))
+        );
+        // A witness on a legacy output is not allowed.
+        assert_eq!(
+            spend(vec![0x51], vec![], vec![vec![0x01]]),
+            Err(VerifyError::Script(ScriptError::WitnessUnexpected))
+        );
+    }
+
+    #[test]
+    fn soft_fork_rules_follow_height() {
+        let mut input = TxIn::new(OutPoint::default());
+        // Pushes redeem script OP_0, which leaves false.
+        input.script_sig = vec![0x01, 0x00];
+        let tx = Transaction {
+            version: 1,
+            inputs: vec![input],
+            outputs: vec![],
+            lock_time: 0,
+        };
+        let prevouts = [TxOut {
+            value: 1000,
+            script_pubkey: builder::p2sh(&hash::hash160(&[0x00])),
+        }];
+        let result = |opts: &VerifyOpts| verify_input_with_opts(&tx, 0, &prevouts, opts);
+        assert_eq!(result(&VerifyOpts::mainnet(173_804)), Ok(()));
+        assert_eq!(
+            result(&VerifyOpts::mainnet(173_805)),
+            Err(VerifyError::Script(ScriptError::ScriptFailed))
+        );
+
+        // Before segwit a witness program is an anyone-can-spend script.
+        let mut tx = tx;
+        tx.inputs[0].script_sig.clear();
+        tx.inputs[0].witness = vec![vec![0x01]];
+        let prevouts = [TxOut {
+            value: 1000,
+            script_pubkey: builder::p2wpkh(&[0x11; 20]),
+        }];
+        let result = |opts: &VerifyOpts| verify_input_with_opts(&tx, 0, &prevouts, opts);
+        assert_eq!(result(&VerifyOpts::mainnet(481_823)), Ok(()));
+        assert_eq!(
+            result(&VerifyOpts::mainnet(481_824)),
+            Err(VerifyError::Script(ScriptError::WitnessProgramMismatch))
+        );
+        assert_eq!(VerifyOpts::mainnet(363_725).flags, VerifyFlags::DERSIG);
+    }
+
+    #[test]
+    fn locktime_rules_follow_height() {
+        let tx = Transaction {
+            version: 1,
+            inputs: vec![TxIn::new(OutPoint::default())],
+            outputs: vec![],
+            lock_time: 0,
+        };
+        let result = |script_pubkey: Vec<u8>, height: u32| {
+            let prevouts = [TxOut {
+                value: 1000,
+                script_pubkey,
+            }];
+            verify_input_with_opts(&tx, 0, &prevouts, &VerifyOpts::mainnet(height))
+        };
+        let unsatisfied = Err(VerifyError::Script(ScriptError::UnsatisfiedLocktime));
+
+        // <500> OP_CHECKLOCKTIMEVERIFY with lock time 0.
+        let cltv = vec![0x02, 0xf4, 0x01, 0xb1];
+        assert_eq!(result(cltv.clone(), 388_380), Ok(()));
+        assert_eq!(result(cltv, 388_381), unsatisfied);
+
+        // OP_1 OP_CHECKSEQUENCEVERIFY in a version 1 transaction.
+        let csv = vec![0x51, 0xb2];
+        assert_eq!(result(csv.clone(), 419_327), Ok(()));
+        assert_eq!(result(csv, 419_328), unsatisfied);
+    }
+
+    #[test]
+    fn legacy_sighash_deletes_the_signature() {
+        let tx = Transaction {
+            version: 1,
+            inputs: vec![TxIn::new(OutPoint::default())],
+            outputs: vec![],
+            lock_time: 0,
+        };
+        let data = TxData::new(&tx, &[]);
+        let sig = vec![0x30, 0x01];
+        // <sig> OP_DROP, which hashes as OP_DROP alone.
+        let script_code = builder::ScriptBuilder::new()
+            .push_slice(&sig)
+            .push_opcode(Opcode::OpDrop)
+            .into_bytes();
+        let verify_opts = VerifyOpts::default();
+        let spend = Spend {
+            data: &data,
+            index: 0,
+            opts: &verify_opts,
+        };
+        let opts = spend.legacy_opts(&script_code);
+        let expected = sighash::legacy_sighash(&data.tx, 0, &[0x75], 0x01).unwrap();
+        assert_eq!(opts.sighash_fn.unwrap().sighash(&sig), Some(expected));
+    }
+
+    #[test]
+    fn checksigadd_is_not_a_witness_v0_opcode() {
+        // OP_0 <33-byte key> OP_CHECKSIGADD with a junk signature.
+        let mut script = vec![0x00, 0x21];
+        script.extend_from_slice(&[0x02; 33]);
+        script.push(0xba);
+        let p2wsh = builder::p2wsh(&hash::sha256(&script));
+        assert_eq!(
+            spend(p2wsh, vec![], vec![vec![0x30, 0x01], script]),
+            Err(VerifyError::Script(ScriptError::UnsupportedOpcode(0xba)))
+        );
+    }
+
+    #[test]
+    fn taproot_script_path_unsupported() {
+        let p2tr = builder::p2tr(&[0x02; 32]);
+        assert_eq!(
+            spend(p2tr.clone(), vec![], vec![vec![0x51], vec![0xc0; 33]]),
+            Err(VerifyError::UnsupportedTaproot)
+        );
+        assert_eq!(
+            spend(p2tr, vec![], vec![vec![0x01; 63]]),
+            Err(VerifyError::Script(ScriptError::SchnorrSigSize))
+        );
+    }
+}
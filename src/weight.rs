@@ -0,0 +1,281 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::classify::{classify, OutputType};
+use crate::transaction::compact_size_len;
+
+/// Largest ECDSA signature with its sighash byte: a 72-byte DER encoding
+/// (both R and S 33 bytes) plus one.
+pub const ECDSA_SIG_MAX: usize = 73;
+
+/// Typical ECDSA signature with its sighash byte. Low-S signatures are 70
+/// or 71 bytes of DER, so 71 or 72 with the sighash byte, about half the
+/// time each; 72 is what wallets budget for.
+pub const ECDSA_SIG_EXPECTED: usize = 72;
+
+/// Schnorr signature with an explicit sighash byte.
+pub const SCHNORR_SIG_MAX: usize = 65;
+
+/// Schnorr signature using SIGHASH_DEFAULT (no sighash byte).
+pub const SCHNORR_SIG_EXPECTED: usize = 64;
+
+/// Compressed public key size. Estimates assume compressed keys, even for
+/// the `max` of a P2PKH spend: an uncompressed (65-byte) key adds 32 bytes
+/// to its scriptSig.
+pub const PUBKEY_SIZE: usize = 33;
+
+/// Bytes of an input outside its scriptSig: outpoint (36) and sequence (4).
+const INPUT_BASE_SIZE: usize = 40;
+
+/// Unlocking data sizes for one input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputSize {
+    /// scriptSig bytes, excluding its length prefix.
+    pub script_sig: usize,
+    /// Serialized witness bytes (item count, lengths and items); 0 for an
+    /// input without a witness.
+    pub witness: usize,
+}
+
+impl InputSize {
+    /// Weight of the whole input: four units per non-witness byte
+    /// (outpoint, scriptSig with length prefix, sequence) plus one per
+    /// witness byte.
+    ///
+    /// The transaction-wide segwit marker and flag (2 units) are not
+    /// included, nor is the 1-unit empty witness a non-witness input gets
+    /// when other inputs have witnesses.
+    pub fn weight(&self) -> usize {
+        let base = INPUT_BASE_SIZE + compact_size_len(self.script_sig) + self.script_sig;
+        base * 4 + self.witness
+    }
+
+    /// Virtual size of the input: weight / 4, rounded up.
+    pub fn vsize(&self) -> usize {
+        (self.weight() + 3) / 4
+    }
+}
+
+/// Worst-case and typical unlocking data sizes for spending an output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SatisfactionSize {
+    /// Sizes with every signature at its largest encoding. Public keys are
+    /// still assumed compressed; see [`PUBKEY_SIZE`].
+    pub max: InputSize,
+    /// Sizes with typical signature encodings.
+    pub expected: InputSize,
+}
+
+/// Why a size could not be estimated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EstimateError {
+    /// A P2SH output was given without its redeem script.
+    MissingRedeemScript,
+    /// A P2WSH output (native or nested) was given without its witness
+    /// script.
+    MissingWitnessScript,
+    /// The output, redeem script or witness script is of a type with no
+    /// known satisfaction: non-standard, unspendable or a future witness
+    /// version.
+    Unsupported(OutputType),
+}
+
+impl core::fmt::Display for EstimateError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EstimateError::MissingRedeemScript => write!(f, "P2SH output needs a redeem script"),
+            EstimateError::MissingWitnessScript => {
+                write!(f, "P2WSH output needs a witness script")
+            }
+            EstimateError::Unsupported(output_type) => {
+                write!(f, "cannot estimate satisfaction of {output_type:?}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EstimateError {}
+
+/// Estimates the scriptSig and witness needed to spend an output of type
+/// `output_type`.
+///
+/// P2SH outputs need `redeem_script`; P2WSH outputs, and P2SH outputs
+/// whose redeem script is P2WSH, need `witness_script`. Redeem and witness
+/// scripts may be P2PK, P2PKH or multisig; a P2SH redeem script may also
+/// be P2WPKH or P2WSH (nested segwit). Taproot outputs are estimated as
+/// key path spends.
+///
+/// ```rust
+/// use mini_bitcoin_script::classify::OutputType;
+/// use mini_bitcoin_script::weight::estimate_satisfaction;
+///
+/// let size = estimate_satisfaction(OutputType::P2wpkh, None, None).unwrap();
+/// assert_eq!(size.max.script_sig, 0);
+/// assert_eq!(size.max.witness, 109);
+/// assert_eq!(size.max.vsize(), 69);
+/// ```
+pub fn estimate_satisfaction(
+    output_type: OutputType,
+    redeem_script: Option<&[u8]>,
+    witness_script: Option<&[u8]>,
+) -> Result<SatisfactionSize, EstimateError> {
+    let max = estimate(
+        output_type,
+        redeem_script,
+        witness_script,
+        Signatures {
+            ecdsa: ECDSA_SIG_MAX,
+            schnorr: SCHNORR_SIG_MAX,
+        },
+    )?;
+    let expected = estimate(
+        output_type,
+        redeem_script,
+        witness_script,
+        Signatures {
+            ecdsa: ECDSA_SIG_EXPECTED,
+            schnorr: SCHNORR_SIG_EXPECTED,
+        },
+    )?;
+    Ok(SatisfactionSize { max, expected })
+}
+
+#[derive(Clone, Copy)]
+struct Signatures {
+    ecdsa: usize,
+    schnorr: usize,
+}
+
+fn estimate(
+    output_type: OutputType,
+    redeem_script: Option<&[u8]>,
+    witness_script: Option<&[u8]>,
+    sigs: Signatures,
+) -> Result<InputSize, EstimateError> {
+    let wsh = |sigs| -> Result<Vec<usize>, EstimateError> {
+        let script = witness_script.ok_or(EstimateError::MissingWitnessScript)?;
+        let mut items = script_items(classify(script), sigs)?;
+        items.push(script.len());
+        Ok(items)
+    };
+
+    let size = match output_type {
+        OutputType::P2pk | OutputType::P2pkh | OutputType::Multisig { .. } => InputSize {
+            script_sig: script_sig_len(&script_items(output_type, sigs)?),
+            witness: 0,
+        },
+        OutputType::P2wpkh | OutputType::P2wsh | OutputType::P2tr => {
+            let items = match output_type {
+                OutputType::P2wsh => wsh(sigs)?,
+                _ => script_items(output_type, sigs)?,
+            };
+            InputSize {
+                script_sig: 0,
+                witness: witness_len(&items),
+            }
+        }
+        OutputType::P2sh => {
+            let redeem = redeem_script.ok_or(EstimateError::MissingRedeemScript)?;
+            let redeem_type = classify(redeem);
+            match redeem_type {
+                OutputType::P2wpkh | OutputType::P2wsh => {
+                    let items = match redeem_type {
+                        OutputType::P2wsh => wsh(sigs)?,
+                        _ => script_items(redeem_type, sigs)?,
+                    };
+                    InputSize {
+                        script_sig: push_len(redeem.len()),
+                        witness: witness_len(&items),
+                    }
+                }
+                _ => {
+                    let mut items = script_items(redeem_type, sigs)?;
+                    items.push(redeem.len());
+                    InputSize {
+                        script_sig: script_sig_len(&items),
+                        witness: 0,
+                    }
+                }
+            }
+        }
+        OutputType::NonStandard | OutputType::NullData | OutputType::WitnessUnknown { .. } => {
+            return Err(EstimateError::Unsupported(output_type))
+        }
+    };
+    Ok(size)
+}
+
+/// Sizes of the stack items that satisfy a key-based script, bottom first.
+fn script_items(output_type: OutputType, sigs: Signatures) -> Result<Vec<usize>, EstimateError> {
+    let items = match output_type {
+        OutputType::P2pk => vec![sigs.ecdsa],
+        OutputType::P2pkh | OutputType::P2wpkh => vec![sigs.ecdsa, PUBKEY_SIZE],
+        OutputType::P2tr => vec![sigs.schnorr],
+        // The extra element consumed by OP_CHECKMULTISIG's off-by-one.
+        OutputType::Multisig { required, .. } => {
+            let mut items = vec![0];
+            items.extend(core::iter::repeat(sigs.ecdsa).take(required as usize));
+            items
+        }
+        _ => return Err(EstimateError::Unsupported(output_type)),
+    };
+    Ok(items)
+}
+
+/// Size of a minimal push of `len` bytes, including the opcode.
+fn push_len(len: usize) -> usize {
+    let prefix = match len {
+        0..=0x4b => 1,
+        0x4c..=0xff => 2,
+        0x100..=0xffff => 3,
+        _ => 5,
+    };
+    prefix + len
+}
+
+fn script_sig_len(items: &[usize]) -> usize {
+    items.iter().map(|&len| push_len(len)).sum()
+}
+
+fn witness_len(items: &[usize]) -> usize {
+    compact_size_len(items.len())
+        + items
+            .iter()
+            .map(|&len| compact_size_len(len) + len)
+            .sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_and_compact_sizes() {
+        assert_eq!(push_len(0), 1);
+        assert_eq!(push_len(75), 76);
+        assert_eq!(push_len(76), 78);
+        assert_eq!(push_len(256), 259);
+        assert_eq!(compact_size_len(252), 1);
+        assert_eq!(compact_size_len(253), 3);
+        assert_eq!(witness_len(&[]), 1);
+        assert_eq!(witness_len(&[0, 72]), 1 + 1 + 1 + 72);
+    }
+
+    #[test]
+    fn input_weight() {
+        let legacy = InputSize {
+            script_sig: 107,
+            witness: 0,
+        };
+        assert_eq!(legacy.weight(), (40 + 1 + 107) * 4);
+        assert_eq!(legacy.vsize(), 148);
+
+        let segwit = InputSize {
+            script_sig: 0,
+            witness: 107,
+        };
+        assert_eq!(segwit.weight(), 41 * 4 + 107);
+        assert_eq!(segwit.vsize(), 68);
+    }
+}
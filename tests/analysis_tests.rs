@@ -0,0 +1,132 @@
+use mini_bitcoin_script::analysis::{analyze, analyze_with_depth, FindingKind};
+use mini_bitcoin_script::builder;
+use mini_bitcoin_script::token::Token;
+use mini_bitcoin_script::tokenizer::{parse_asm, parse_script};
+
+fn asm(text: &str) -> Vec<Token> {
+    parse_asm(text).unwrap()
+}
+
+fn kinds(tokens: &[Token]) -> Vec<(usize, FindingKind)> {
+    analyze(tokens)
+        .findings
+        .iter()
+        .map(|f| (f.index, f.kind))
+        .collect()
+}
+
+#[test]
+fn p2pkh_needs_two_items() {
+    let tokens = parse_script(&builder::p2pkh(&[0xab; 20])).unwrap();
+    let analysis = analyze(&tokens);
+    assert_eq!(analysis.min_stack_depth, 2);
+    assert_eq!(analysis.paths, 1);
+    assert!(analysis.is_clean());
+}
+
+#[test]
+fn depth_counts_items_below_pushes() {
+    // OP_1 supplies one of OP_EQUAL's operands.
+    assert_eq!(analyze(&asm("OP_1 OP_EQUAL")).min_stack_depth, 1);
+    assert_eq!(analyze(&asm("OP_SWAP OP_DROP")).min_stack_depth, 2);
+    assert_eq!(analyze(&asm("OP_1")).min_stack_depth, 0);
+}
+
+#[test]
+fn guaranteed_underflow() {
+    let tokens = asm("OP_1 OP_EQUAL");
+    let analysis = analyze_with_depth(&tokens, 0);
+    assert_eq!(analysis.findings.len(), 1);
+    assert_eq!(analysis.findings[0].index, 1);
+    assert_eq!(analysis.findings[0].kind, FindingKind::StackUnderflow);
+
+    assert!(analyze_with_depth(&tokens, 1).is_clean());
+}
+
+#[test]
+fn underflow_on_one_branch_only() {
+    // The OP_IF branch drops two items; the OP_ELSE branch none.
+    let tokens = asm("OP_IF OP_2DROP OP_ENDIF OP_1");
+    let analysis = analyze_with_depth(&tokens, 2);
+    assert_eq!(analysis.paths, 2);
+    assert_eq!(analysis.findings.len(), 1);
+    let finding = &analysis.findings[0];
+    assert_eq!(finding.kind, FindingKind::StackUnderflow);
+    assert_eq!(finding.index, 1);
+    assert_eq!(finding.path, vec![(0, true)]);
+    // The OP_ELSE path only needs the condition.
+    assert_eq!(analysis.min_stack_depth, 1);
+}
+
+#[test]
+fn unbalanced_conditionals() {
+    assert_eq!(
+        kinds(&asm("OP_1 OP_IF OP_1")),
+        vec![(1, FindingKind::UnbalancedConditional)]
+    );
+    assert_eq!(
+        kinds(&asm("OP_1 OP_ENDIF")),
+        vec![(1, FindingKind::UnbalancedConditional)]
+    );
+    assert_eq!(
+        kinds(&asm("OP_ELSE OP_1")),
+        vec![(0, FindingKind::UnbalancedConditional)]
+    );
+    assert!(analyze(&asm("OP_1 OP_IF OP_1 OP_ELSE OP_1 OP_ENDIF")).is_clean());
+}
+
+#[test]
+fn unreachable_after_return() {
+    let found = kinds(&asm("OP_RETURN OP_1 OP_2"));
+    assert!(found.contains(&(0, FindingKind::AlwaysFails)));
+    assert!(found.contains(&(1, FindingKind::Unreachable)));
+
+    // OP_RETURN inside a branch does not make later code unreachable.
+    let found = kinds(&asm("OP_IF OP_RETURN OP_ENDIF OP_1"));
+    assert_eq!(found, vec![(1, FindingKind::AlwaysFails)]);
+
+    // A disabled opcode fails even where it is not executed.
+    let found = kinds(&asm("OP_IF OP_MUL OP_ENDIF OP_1"));
+    assert_eq!(found, vec![(1, FindingKind::AlwaysFails)]);
+}
+
+#[test]
+fn always_false_paths() {
+    assert_eq!(
+        kinds(&asm("OP_1 OP_2 OP_EQUAL")),
+        vec![(3, FindingKind::AlwaysFalse)]
+    );
+    assert_eq!(kinds(&asm("OP_0")), vec![(1, FindingKind::AlwaysFalse)]);
+    assert_eq!(
+        kinds(&asm("OP_IF OP_1 OP_ELSE OP_0 OP_ENDIF")),
+        vec![(5, FindingKind::AlwaysFalse)]
+    );
+    assert!(kinds(&asm("OP_2 OP_2 OP_EQUAL")).is_empty());
+}
+
+#[test]
+fn known_conditions_do_not_fork() {
+    let analysis = analyze(&asm("OP_0 OP_IF OP_RETURN OP_ENDIF OP_1"));
+    assert_eq!(analysis.paths, 1);
+    assert!(analysis.is_clean());
+}
+
+#[test]
+fn verify_of_known_false() {
+    assert_eq!(
+        kinds(&asm("OP_0 OP_VERIFY OP_1")),
+        vec![(1, FindingKind::AlwaysFails)]
+    );
+    assert_eq!(
+        kinds(&asm("<01> <02> OP_EQUALVERIFY OP_1")),
+        vec![(2, FindingKind::AlwaysFails)]
+    );
+}
+
+#[test]
+fn hashes_are_evaluated() {
+    // sha256("") is known, so the comparison is decided statically.
+    let tokens = asm("OP_0 OP_SHA256 \
+         <e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855> OP_EQUAL");
+    assert!(analyze(&tokens).is_clean());
+}
@@ -0,0 +1,194 @@
+use mini_bitcoin_script::bip32::{path_to_string, Bip32Error, KeyOrigin, Network, Xpub, HARDENED};
+use mini_bitcoin_script::descriptor::{Descriptor, DescriptorError};
+
+// BIP32 test vector 1.
+const MASTER: &str = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+const M_0H: &str = "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw";
+
+fn xpub(s: &str) -> Xpub {
+    s.parse().unwrap_or_else(|e| panic!("{s}: {e}"))
+}
+
+#[test]
+fn parses_vector_keys() {
+    let master = xpub(MASTER);
+    assert_eq!(master.network, Network::Mainnet);
+    assert_eq!(master.depth, 0);
+    assert_eq!(master.parent_fingerprint, [0; 4]);
+    assert_eq!(master.fingerprint(), [0x34, 0x42, 0x19, 0x3e]);
+
+    let child = xpub(M_0H);
+    assert_eq!(child.depth, 1);
+    assert_eq!(child.child_number, HARDENED);
+    assert_eq!(child.parent_fingerprint, master.fingerprint());
+    assert_eq!(child.to_string(), M_0H);
+}
+
+#[test]
+fn rejects_private_and_corrupt_keys() {
+    let xprv = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+    assert_eq!(xprv.parse::<Xpub>(), Err(Bip32Error::UnknownVersion));
+    let corrupt = MASTER.replace('F', "G");
+    assert_eq!(corrupt.parse::<Xpub>(), Err(Bip32Error::Base58));
+}
+
+#[test]
+fn origins_roundtrip() {
+    let origin: KeyOrigin = "d34db33f/48h/0'/2".parse().unwrap();
+    assert_eq!(origin.fingerprint, [0xd3, 0x4d, 0xb3, 0x3f]);
+    assert_eq!(origin.path, [48 | HARDENED, HARDENED, 2]);
+    assert_eq!(origin.to_string(), "d34db33f/48'/0'/2");
+    assert_eq!(path_to_string(&origin.path), "m/48'/0'/2");
+}
+
+#[cfg(feature = "secp256k1")]
+mod derivation {
+    use super::*;
+    use mini_bitcoin_script::hex::encode_hex;
+
+    const M_0H_1: &str = "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ";
+    const M_0H_1_2H: &str = "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5";
+    const M_0H_1_2H_2: &str = "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV";
+    const M_0H_1_2H_2_1000000000: &str = "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy";
+
+    // BIP32 test vector 2.
+    const VECTOR_2_MASTER: &str = "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB";
+    // BIP86 test vector account key, m/86'/0'/0'.
+    const BIP86_ACCOUNT: &str = "xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ";
+
+    #[test]
+    fn public_derivation_matches_vector() {
+        assert_eq!(xpub(M_0H).derive_child(1).unwrap().to_string(), M_0H_1);
+        let derived = xpub(M_0H_1_2H).derive_path(&[2, 1_000_000_000]).unwrap();
+        assert_eq!(derived.to_string(), M_0H_1_2H_2_1000000000);
+        assert_eq!(
+            xpub(M_0H_1_2H).derive_child(2).unwrap().to_string(),
+            M_0H_1_2H_2
+        );
+    }
+
+    #[test]
+    fn hardened_derivation_needs_private_key() {
+        assert_eq!(
+            xpub(MASTER).derive_child(HARDENED),
+            Err(Bip32Error::HardenedDerivation)
+        );
+    }
+
+    #[test]
+    fn ranged_descriptor_expands_to_derived_keys() {
+        let desc: Descriptor = format!("pkh([3442193e/0']{M_0H}/1/*)").parse().unwrap();
+        assert!(desc.is_ranged());
+        assert_eq!(desc.script_pubkey(), Err(DescriptorError::Ranged));
+
+        // Index 1 of `/1/*` from m/0' is m/0'/1/1.
+        let key = xpub(M_0H_1).derive_child(1).unwrap().public_key;
+        let expected = format!("pkh([3442193e/0'/1/1]{})", encode_hex(&key));
+        let at_one = desc.at_derivation_index(1).unwrap();
+        assert!(!at_one.is_ranged());
+        assert_eq!(at_one, expected.parse::<Descriptor>().unwrap());
+
+        let scripts = desc.script_pubkeys(0..3).unwrap();
+        assert_eq!(scripts.len(), 3);
+        assert_eq!(scripts[1], at_one.script_pubkey().unwrap());
+        assert_ne!(scripts[0], scripts[1]);
+    }
+
+    #[test]
+    fn unranged_extended_key_is_derived_directly() {
+        let desc: Descriptor = format!("wpkh({M_0H_1_2H}/2/1000000000)").parse().unwrap();
+        assert!(!desc.is_ranged());
+        let key = xpub(M_0H_1_2H_2_1000000000).public_key;
+        let expected: Descriptor = format!("wpkh({})", encode_hex(&key)).parse().unwrap();
+        assert_eq!(desc.script_pubkey(), expected.script_pubkey());
+        assert_eq!(
+            desc.script_pubkeys(0..2).unwrap(),
+            [
+                expected.script_pubkey().unwrap(),
+                expected.script_pubkey().unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn ranged_multisig_and_taproot() {
+        // BIP32 test vector 2's m/0 and test vector 1's m/0'/1, sorted.
+        let desc: Descriptor = format!("wsh(sortedmulti(1,{M_0H}/1,{VECTOR_2_MASTER}/*))")
+            .parse()
+            .unwrap();
+        let scripts = desc.script_pubkeys(0..4).unwrap();
+        assert_eq!(scripts.len(), 4);
+        assert_eq!(
+            scripts[0].to_hex(),
+            "0020400f059927c2e4c82d86850346afc7522bf591a5ca36ec0d422f88bfe4d6a6d3"
+        );
+        assert_ne!(scripts[0], scripts[1]);
+
+        // BIP86 test vectors: receive addresses 0 and 1, change address 0.
+        let desc: Descriptor = format!("tr([73c5da0a/86'/0'/0']{BIP86_ACCOUNT}/0/*)")
+            .parse()
+            .unwrap();
+        let scripts = desc.script_pubkeys(0..2).unwrap();
+        assert_eq!(
+            scripts[0].to_hex(),
+            "5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c"
+        );
+        assert_eq!(
+            scripts[1].to_hex(),
+            "5120a82f29944d65b86ae6b5e5cc75e294ead6c59391a1edc5e016e3498c67fc7bbb"
+        );
+        let change: Descriptor = format!("tr({BIP86_ACCOUNT}/1/0)").parse().unwrap();
+        assert_eq!(
+            change.script_pubkey().unwrap().to_hex(),
+            "5120882d74e5d0572d5a816cef0041a96b6c1de832f6f9676d9605c44d5e9a97d3dc"
+        );
+
+        let derived = desc.at_derivation_index(5).unwrap();
+        assert!(derived
+            .to_string()
+            .starts_with("tr([73c5da0a/86'/0'/0'/0/5]"));
+    }
+
+    #[test]
+    fn hardened_index_fails_to_derive() {
+        let desc: Descriptor = format!("pkh({MASTER}/*)").parse().unwrap();
+        assert_eq!(
+            desc.at_derivation_index(HARDENED),
+            Err(DescriptorError::Derivation)
+        );
+    }
+}
+
+#[cfg(not(feature = "secp256k1"))]
+#[test]
+fn derivation_needs_secp256k1() {
+    let desc: Descriptor = format!("pkh({MASTER}/0)").parse().unwrap();
+    assert_eq!(desc.script_pubkey(), Err(DescriptorError::Derivation));
+}
+
+#[test]
+fn extended_key_syntax() {
+    let ranged = format!("wpkh([d34db33f/84h/0h/0h]{MASTER}/0/*)");
+    let desc: Descriptor = ranged.parse().unwrap();
+    assert!(desc.is_ranged());
+    assert!(desc
+        .to_string()
+        .starts_with(&format!("wpkh([d34db33f/84'/0'/0']{MASTER}/0/*)#")));
+
+    // Hardened steps need the private key.
+    for bad in [
+        format!("pkh({MASTER}/0'/*)"),
+        format!("pkh({MASTER}/0/*')"),
+        format!("pkh({MASTER}/*/1)"),
+        format!("pkh({MASTER}/)"),
+        format!("pkh({}/0)", &MASTER[..MASTER.len() - 1]),
+    ] {
+        assert!(
+            matches!(
+                bad.parse::<Descriptor>(),
+                Err(DescriptorError::InvalidKey { index: 4 })
+            ),
+            "{bad}"
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::BTreeMap;
+
+use hex_literal::hex;
+use mini_bitcoin_script::block::{
+    validate_block, validate_block_with_opts, Block, BlockError, BlockHeader, TxFailure,
+};
+use mini_bitcoin_script::flags::VerifyFlags;
+use mini_bitcoin_script::hex::ReversedHex;
+use mini_bitcoin_script::snapshot::UtxoSnapshot;
+use mini_bitcoin_script::transaction::{DecodeError, OutPoint, Transaction, TxIn, TxOut};
+use mini_bitcoin_script::verify::{TxVerifyError, VerifyOpts};
+
+const GENESIS: [u8; 285] = hex!(
+    "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c"
+    "01"
+    "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000"
+);
+
+/// Block 170, whose second transaction spends the block 9 coinbase held
+/// in `data/mainnet.utxo`.
+const BLOCK_170: [u8; 490] = hex!(
+    "0100000055bd840a78798ad0da853f68974f3d183e2bd1db6a842c1feecf222a00000000ff104ccb05421ab93e63f8c3ce5c2c2e9dbb37de2764b3a3175c8166562cac7d51b96a49ffff001d283e9e70"
+    "02"
+    "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff0704ffff001d0102ffffffff0100f2052a01000000434104d46c4968bde02899d2aa0963367c7a6ce34eec332b32e42e5f3407e052d64ac625da6f0718e7b302140434bd725706957c092db53805b821a85b23a7ac61725bac00000000"
+    "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000"
+);
+
+#[test]
+fn replays_block_170_against_snapshot_file() {
+    let block = Block::deserialize(&BLOCK_170).unwrap();
+    assert_eq!(
+        ReversedHex(&block.block_hash()).to_string(),
+        "00000000d1145790a8694403d4063f323d499e655c83426834d4ce2f8dd4a2ee"
+    );
+    assert_eq!(block.check(), Ok(()));
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/mainnet.utxo");
+    let snapshot = UtxoSnapshot::deserialize(&std::fs::read(path).unwrap()).unwrap();
+    let result = validate_block_with_opts(&block, &snapshot, &VerifyOpts::mainnet(170)).unwrap();
+    assert!(result.is_valid(), "{:?}", result.failures);
+    assert_eq!(result.fees, 0);
+
+    // Without the block 9 coinbase the spend has nothing to spend.
+    let result = validate_block(&block, &BTreeMap::new(), VerifyFlags::NONE).unwrap();
+    assert!(matches!(
+        result.failures.as_slice(),
+        [(1, _, TxFailure::Tx(TxVerifyError::MissingUtxo { .. }))]
+    ));
+}
+
+#[test]
+fn genesis_block() {
+    let block = Block::deserialize(&GENESIS).unwrap();
+    assert_eq!(
+        ReversedHex(&block.block_hash()).to_string(),
+        "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+    );
+    assert_eq!(block.header.time, 1_231_006_505);
+    assert_eq!(block.txdata.len(), 1);
+    assert!(block.txdata[0].is_coinbase());
+    assert_eq!(block.compute_merkle_root(), Some(block.header.merkle_root));
+    assert_eq!(block.check(), Ok(()));
+    assert_eq!(block.serialize(), GENESIS);
+
+    let header = BlockHeader::deserialize(&GENESIS[..80]).unwrap();
+    assert_eq!(header, block.header);
+
+    // Only the coinbase, so there are no scripts to run.
+    let result = validate_block(&block, &BTreeMap::new(), VerifyFlags::NONE).unwrap();
+    assert!(result.is_valid());
+    assert_eq!(result.fees, 0);
+}
+
+#[test]
+fn rejects_malformed_blocks() {
+    assert_eq!(
+        Block::deserialize(&GENESIS[..284]),
+        Err(DecodeError::UnexpectedEnd)
+    );
+    let mut trailing = GENESIS.to_vec();
+    trailing.push(0);
+    assert_eq!(
+        Block::deserialize(&trailing),
+        Err(DecodeError::TrailingData)
+    );
+    assert_eq!(
+        BlockHeader::deserialize(&GENESIS[..81]),
+        Err(DecodeError::TrailingData)
+    );
+
+    let genesis = Block::deserialize(&GENESIS).unwrap();
+    let mut block = genesis.clone();
+    block.header.merkle_root[0] ^= 1;
+    assert_eq!(block.check(), Err(BlockError::MerkleRoot));
+
+    let mut block = genesis.clone();
+    block.txdata.clear();
+    assert_eq!(block.check(), Err(BlockError::NoTransactions));
+
+    let spend = Transaction {
+        version: 1,
+        inputs: vec![TxIn::new(OutPoint::default())],
+        outputs: vec![],
+        lock_time: 0,
+    };
+    let mut block = genesis.clone();
+    block.txdata.insert(0, spend);
+    assert_eq!(block.check(), Err(BlockError::NoCoinbase));
+
+    let mut block = genesis;
+    block.txdata.push(block.txdata[0].clone());
+    assert_eq!(block.check(), Err(BlockError::ExtraCoinbase { index: 1 }));
+}
+
+#[test]
+fn duplicated_transactions_are_mutated() {
+    let mut block = Block::deserialize(&GENESIS).unwrap();
+    let spend = Transaction {
+        version: 1,
+        inputs: vec![TxIn::new(OutPoint::default())],
+        outputs: vec![TxOut {
+            value: 1,
+            script_pubkey: vec![0x51],
+        }],
+        lock_time: 0,
+    };
+    let mut other = spend.clone();
+    other.lock_time = 1;
+    block.txdata.extend([other, spend.clone(), spend]);
+    // [coinbase, other, tx, tx] has the Merkle root of [coinbase, other, tx],
+    // so the root matches but the block is ambiguous.
+    block.header.merkle_root = block.compute_merkle_root().unwrap();
+    assert_eq!(block.check(), Err(BlockError::MutatedMerkle));
+}
+
+#[cfg(feature = "test-utils")]
+mod segwit {
+    use super::*;
+    use mini_bitcoin_script::error::ScriptError;
+    use mini_bitcoin_script::hash;
+    use mini_bitcoin_script::sigcache::SignatureCache;
+    use mini_bitcoin_script::test_utils::{fixture, Fixture, ScriptType, FEE, FUNDING_VALUE};
+    use mini_bitcoin_script::verify::{
+        verify_tx_with_opts, TxVerifyError, VerifyError, VerifyOpts,
+    };
+    use std::sync::Arc;
+
+    const FUNDING_FEE: u64 = 500;
+
+    fn flags() -> VerifyFlags {
+        VerifyFlags::DERSIG | VerifyFlags::LOW_S | VerifyFlags::STRICTENC | VerifyFlags::NULLFAIL
+    }
+
+    /// A block with a coinbase committing to `txs`, and a correct header
+    /// Merkle root.
+    fn segwit_block(txs: Vec<Transaction>) -> Block {
+        let mut coinbase_input = TxIn::new(OutPoint {
+            txid: [0; 32],
+            vout: u32::MAX,
+        });
+        coinbase_input.script_sig = vec![0x01, 0x2a];
+        coinbase_input.witness = vec![vec![0; 32]];
+        let coinbase = Transaction {
+            version: 2,
+            inputs: vec![coinbase_input],
+            outputs: vec![TxOut {
+                value: 50,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+        };
+        let mut block = Block {
+            header: BlockHeader {
+                version: 0x2000_0000,
+                prev_blockhash: [0x11; 32],
+                merkle_root: [0; 32],
+                time: 1_700_000_000,
+                bits: 0x207f_ffff,
+                nonce: 0,
+            },
+            txdata: std::iter::once(coinbase).chain(txs).collect(),
+        };
+
+        let mut data = block.compute_witness_root().unwrap().to_vec();
+        data.extend_from_slice(&[0; 32]);
+        let mut commitment = hex!("6a24aa21a9ed").to_vec();
+        commitment.extend_from_slice(&hash::hash256(&data));
+        block.txdata[0].outputs.push(TxOut {
+            value: 0,
+            script_pubkey: commitment,
+        });
+        block.header.merkle_root = block.compute_merkle_root().unwrap();
+        block
+    }
+
+    /// The UTXO set before the block: an anyone-can-spend output the
+    /// fixture's funding transaction spends.
+    fn snapshot(fx: &Fixture) -> BTreeMap<OutPoint, TxOut> {
+        let prevout = TxOut {
+            value: FUNDING_VALUE + FUNDING_FEE,
+            script_pubkey: vec![0x51],
+        };
+        BTreeMap::from([(fx.funding_tx.inputs[0].previous_output, prevout)])
+    }
+
+    #[test]
+    fn validates_spends_of_earlier_transactions() {
+        let fx = fixture(ScriptType::P2wpkh, b"block");
+        let block = segwit_block(vec![fx.funding_tx.clone(), fx.spending_tx.clone()]);
+        assert_eq!(block.check(), Ok(()));
+        assert_eq!(Block::deserialize(&block.serialize()), Ok(block.clone()));
+
+        let result = validate_block(&block, &snapshot(&fx), flags()).unwrap();
+        assert!(result.is_valid(), "{:?}", result.failures);
+        assert_eq!(result.fees, FUNDING_FEE + FEE);
+    }
+
+    #[test]
+    fn reports_failing_transactions() {
+        let fx = fixture(ScriptType::P2wpkh, b"block");
+        let mut tampered = fx.spending_tx.clone();
+        tampered.outputs[0].value -= 1;
+        let block = segwit_block(vec![fx.funding_tx.clone(), tampered.clone()]);
+        let result = validate_block(&block, &snapshot(&fx), flags()).unwrap();
+        assert_eq!(
+            result.failures,
+            [(
+                2,
+                tampered.txid(),
+                TxFailure::Inputs(vec![(0, VerifyError::Script(ScriptError::NullFail))])
+            )]
+        );
+
+        // Spending an output before the transaction creating it.
+        let block = segwit_block(vec![fx.spending_tx.clone(), fx.funding_tx.clone()]);
+        let result = validate_block(&block, &snapshot(&fx), flags()).unwrap();
+        let outpoint = fx.spending_tx.inputs[0].previous_output;
+        assert_eq!(
+            result.failures,
+            [(
+                1,
+                fx.spending_tx.txid(),
+                TxFailure::Tx(TxVerifyError::MissingUtxo { index: 0, outpoint })
+            )]
+        );
+    }
+
+    #[test]
+    fn block_reuses_mempool_signature_checks() {
+        let fx = fixture(ScriptType::P2wpkh, b"block");
+        let utxos = snapshot(&fx);
+        let cache = Arc::new(SignatureCache::default());
+        let opts = VerifyOpts {
+            flags: flags(),
+            sig_cache: Some(Arc::clone(&cache)),
+            ..VerifyOpts::default()
+        };
+
+        // The spend enters the mempool once its parent has.
+        let mut mempool = utxos.clone();
+        mempool.insert(
+            fx.spending_tx.inputs[0].previous_output,
+            fx.prevout().clone(),
+        );
+        let result = verify_tx_with_opts(&fx.spending_tx, &mempool, &opts).unwrap();
+        assert!(result.is_valid());
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+
+        let block = segwit_block(vec![fx.funding_tx.clone(), fx.spending_tx.clone()]);
+        let result = validate_block_with_opts(&block, &utxos, &opts).unwrap();
+        assert!(result.is_valid(), "{:?}", result.failures);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+    }
+
+    #[test]
+    fn checks_witness_commitment() {
+        let fx = fixture(ScriptType::P2wpkh, b"block");
+        let valid = segwit_block(vec![fx.funding_tx.clone(), fx.spending_tx.clone()]);
+
+        let mut block = valid.clone();
+        block.txdata[0].outputs.last_mut().unwrap().script_pubkey[10] ^= 1;
+        block.header.merkle_root = block.compute_merkle_root().unwrap();
+        assert_eq!(block.check(), Err(BlockError::WitnessCommitment));
+        assert_eq!(
+            validate_block(&block, &snapshot(&fx), flags()),
+            Err(BlockError::WitnessCommitment)
+        );
+
+        let mut block = valid.clone();
+        block.txdata[0].inputs[0].witness.clear();
+        assert_eq!(block.check(), Err(BlockError::WitnessNonce));
+
+        // Without a commitment the coinbase nonce is unexpected too, so
+        // only the spend is reported once it is gone.
+        let mut block = valid;
+        block.txdata[0].outputs.pop();
+        block.txdata[0].inputs[0].witness.clear();
+        block.header.merkle_root = block.compute_merkle_root().unwrap();
+        assert_eq!(
+            block.check(),
+            Err(BlockError::UnexpectedWitness { index: 2 })
+        );
+    }
+}
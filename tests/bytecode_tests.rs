@@ -0,0 +1,246 @@
+use mini_bitcoin_script::bytecode::CompiledScript;
+use mini_bitcoin_script::engine::{execute_on_items, execute_with_opts, ExecuteOpts, SigVersion};
+use mini_bitcoin_script::error::ScriptError;
+use mini_bitcoin_script::opcode::Opcode;
+use mini_bitcoin_script::token::Token;
+use mini_bitcoin_script::tokenizer::parse_asm;
+
+/// Runs `tokens` through the engine and as a compiled script, on a fresh
+/// stack and on `items`, and checks the two agree on results and final
+/// stacks. Returns the result on `items`.
+fn check(tokens: &[Token], items: &[Vec<u8>], opts: &ExecuteOpts) -> Result<(), ScriptError> {
+    let compiled = CompiledScript::compile(tokens);
+    assert_eq!(
+        compiled.execute(opts),
+        execute_with_opts(tokens, opts),
+        "{tokens:?}"
+    );
+
+    let mut expected = items.to_vec();
+    let expected_result = execute_on_items(tokens, &mut expected, opts);
+    let mut actual = items.to_vec();
+    let result = compiled.execute_on_items(&mut actual, opts);
+    assert_eq!(result, expected_result, "{tokens:?} on {items:?}");
+    assert_eq!(actual, expected, "{tokens:?} on {items:?}");
+    result
+}
+
+fn asm(asm: &str) -> Vec<Token> {
+    parse_asm(asm).unwrap()
+}
+
+#[test]
+fn conditionals() {
+    let opts = ExecuteOpts::default();
+    let scripts = [
+        "OP_IF OP_2 OP_ELSE OP_3 OP_ENDIF",
+        "OP_NOTIF OP_2 OP_ELSE OP_3 OP_ENDIF",
+        "OP_IF OP_2 OP_ENDIF OP_5",
+        // Each OP_ELSE toggles the branch.
+        "OP_IF OP_2 OP_ELSE OP_3 OP_ELSE OP_4 OP_ELSE OP_5 OP_ENDIF",
+        "OP_DUP OP_IF OP_IF OP_6 OP_ELSE OP_7 OP_ENDIF OP_ELSE OP_DROP OP_8 OP_ENDIF",
+        // OP_RETURN fails only when executed.
+        "OP_IF OP_RETURN OP_ENDIF OP_1",
+        "OP_IF OP_ELSE OP_ENDIF",
+    ];
+    for script in scripts {
+        let tokens = asm(script);
+        for condition in [vec![], vec![1], vec![0x80]] {
+            let _ = check(&tokens, &[condition.clone(), condition], &opts);
+        }
+    }
+}
+
+#[test]
+fn unbalanced_conditionals_fail_where_the_engine_does() {
+    let opts = ExecuteOpts::default();
+    let cases = [
+        ("OP_ENDIF", Err(ScriptError::UnbalancedConditional)),
+        ("OP_1 OP_ELSE", Err(ScriptError::UnbalancedConditional)),
+        ("OP_1 OP_IF OP_2", Err(ScriptError::UnbalancedConditional)),
+        (
+            "OP_0 OP_IF OP_RETURN",
+            Err(ScriptError::UnbalancedConditional),
+        ),
+        (
+            "OP_1 OP_IF OP_RETURN",
+            Err(ScriptError::OpReturnEncountered),
+        ),
+        // The engine stops at OP_RETURN before seeing the stray OP_ENDIF.
+        ("OP_RETURN OP_ENDIF", Err(ScriptError::OpReturnEncountered)),
+        ("OP_IF OP_ENDIF OP_ENDIF", Err(ScriptError::StackUnderflow)),
+        (
+            "OP_1 OP_IF OP_ENDIF OP_ENDIF",
+            Err(ScriptError::UnbalancedConditional),
+        ),
+    ];
+    for (script, expected) in cases {
+        assert_eq!(check(&asm(script), &[], &opts), expected, "{script}");
+    }
+}
+
+#[test]
+fn disabled_opcodes_fail_where_the_engine_does() {
+    let opts = ExecuteOpts::default();
+    let cases = [
+        (
+            "OP_0 OP_IF OP_CAT OP_ENDIF OP_1",
+            Err(ScriptError::DisabledOpcode(0x7e)),
+        ),
+        (
+            "OP_1 OP_IF OP_ELSE OP_MUL",
+            Err(ScriptError::DisabledOpcode(0x95)),
+        ),
+        (
+            "OP_0 OP_IF OP_ELSE OP_IF OP_LSHIFT",
+            Err(ScriptError::StackUnderflow),
+        ),
+        ("OP_RETURN OP_CAT", Err(ScriptError::OpReturnEncountered)),
+    ];
+    for (script, expected) in &cases {
+        assert_eq!(check(&asm(script), &[], &opts), *expected, "{script}");
+    }
+
+    let tapscript = ExecuteOpts {
+        sig_version: SigVersion::Tapscript,
+        ..ExecuteOpts::default()
+    };
+    for (script, _) in cases {
+        assert_eq!(
+            check(&asm(script), &[vec![]], &tapscript),
+            Ok(()),
+            "{script}"
+        );
+    }
+}
+
+#[test]
+fn fused_comparisons() {
+    let opts = ExecuteOpts::default();
+    for script in [
+        "<1234> OP_EQUAL",
+        "<1234> OP_EQUALVERIFY OP_1",
+        "OP_3 OP_EQUAL",
+        "OP_0 OP_EQUAL",
+        // A jump lands on OP_EQUAL, so the push before it cannot be fused.
+        "OP_IF <1234> OP_ELSE <5678> OP_ENDIF OP_EQUAL",
+        "OP_IF <1234> OP_ENDIF OP_EQUALVERIFY OP_1",
+    ] {
+        let tokens = asm(script);
+        for items in [vec![], vec![vec![0x12, 0x34]], vec![vec![3], vec![1]]] {
+            let _ = check(&tokens, &items, &opts);
+        }
+    }
+
+    let p2pkh = asm(
+        "OP_DUP OP_HASH160 <89abcdefabbaabbaabbaabbaabbaabbaabbaabba> OP_EQUALVERIFY OP_CHECKSIG",
+    );
+    assert_eq!(CompiledScript::compile(&p2pkh).len(), 4);
+}
+
+#[test]
+fn timelocks_and_alt_stack() {
+    let opts = ExecuteOpts {
+        lock_time: Some(500),
+        sequence: Some(0xffff_fffe),
+        ..ExecuteOpts::default()
+    };
+    for script in [
+        "OP_TOALTSTACK OP_1 OP_FROMALTSTACK",
+        "OP_FROMALTSTACK",
+        "OP_CHECKLOCKTIMEVERIFY",
+        "OP_CHECKSEQUENCEVERIFY",
+    ] {
+        for items in [vec![], vec![vec![0xf4, 0x01]], vec![vec![0xf5, 0x01]]] {
+            let _ = check(&asm(script), &items, &opts);
+        }
+    }
+}
+
+/// A small xorshift generator, so failures reproduce.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+#[test]
+fn matches_engine_on_random_scripts() {
+    let opcodes: Vec<Opcode> = (0..=u8::MAX).filter_map(Opcode::from_byte).collect();
+    let conditionals = [
+        Opcode::OpIf,
+        Opcode::OpNotIf,
+        Opcode::OpElse,
+        Opcode::OpEndIf,
+    ];
+    let pushes: [&[u8]; 6] = [&[], &[0], &[1], &[0x81], &[1, 2], &[0x80]];
+    let option_sets = [
+        ExecuteOpts::default(),
+        ExecuteOpts {
+            lock_time: Some(1),
+            sequence: Some(0),
+            ..ExecuteOpts::default()
+        },
+        ExecuteOpts {
+            sig_version: SigVersion::Tapscript,
+            ..ExecuteOpts::default()
+        },
+    ];
+
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..20_000 {
+        let tokens: Vec<Token> = (0..rng.below(12))
+            .map(|_| match rng.below(4) {
+                0 => Token::PushData(pushes[rng.below(pushes.len())].to_vec()),
+                1 => Token::Op(conditionals[rng.below(conditionals.len())]),
+                _ => Token::Op(opcodes[rng.below(opcodes.len())]),
+            })
+            .collect();
+        let items: Vec<Vec<u8>> = (0..rng.below(4))
+            .map(|_| pushes[rng.below(pushes.len())].to_vec())
+            .collect();
+        let _ = check(&tokens, &items, &option_sets[rng.below(option_sets.len())]);
+    }
+}
+
+#[cfg(feature = "test-utils")]
+mod signed {
+    use super::*;
+    use mini_bitcoin_script::test_utils::{fixture, ScriptType};
+    use mini_bitcoin_script::tokenizer::parse_script;
+
+    #[test]
+    fn signature_checks_match_engine() {
+        for script_type in [ScriptType::P2pkh, ScriptType::P2shMultisig] {
+            let fx = fixture(script_type, b"bytecode");
+            let script = match &fx.redeem_script {
+                Some(redeem_script) => redeem_script.clone(),
+                None => fx.prevout().script_pubkey.clone(),
+            };
+            let tokens = parse_script(&script).unwrap();
+            // The scriptSig's pushes, without a P2SH redeem script.
+            let mut items = Vec::new();
+            let script_sig = parse_script(fx.script_sig()).unwrap();
+            execute_on_items(&script_sig, &mut items, &ExecuteOpts::default()).unwrap();
+            if fx.redeem_script.is_some() {
+                items.pop();
+            }
+
+            let opts = fx.execute_opts();
+            check(&tokens, &items, &opts).unwrap();
+
+            let mut tampered = opts.clone();
+            tampered.sighash = Some([0x55; 32]);
+            assert!(check(&tokens, &items, &tampered).is_err());
+        }
+    }
+}
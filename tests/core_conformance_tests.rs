@@ -0,0 +1,886 @@
+//! Conformance harness against Bitcoin Core's JSON script test vectors.
+//!
+//! Reads vendored copies of Core's `script_tests.json`, `tx_valid.json` and
+//! `tx_invalid.json` from `tests/data/core/` (pinned to Core v27.1; see
+//! `tests/data/README.md` for how to fetch them). Script cases run through
+//! `verify_input_with_opts` in the crediting and spending transactions
+//! Core's `script_tests.cpp` builds, and transaction cases through
+//! `verify_tx`. Each file's pass/fail/skip counts are asserted against a
+//! recorded baseline, so both regressions and fixes show up. Missing files
+//! are reported and skipped. Without the `secp256k1` feature signatures
+//! are checked in stub mode, so cases that are invalid only for a bad
+//! signature are accepted.
+//!
+//! Run with output visible:
+//!
+//! ```sh
+//! cargo test --test core_conformance_tests -- --nocapture
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use mini_bitcoin_script::flags::VerifyFlags;
+use mini_bitcoin_script::hex::decode_hex;
+use mini_bitcoin_script::transaction::{OutPoint, Transaction, TxIn, TxOut};
+use mini_bitcoin_script::verify::{
+    verify_input_with_opts, verify_tx_with_opts, TxVerifyError, VerifyError, VerifyOpts,
+};
+use serde_json::Value;
+
+// ---------------------------------------------------------------------------
+// Core ASM dialect
+// ---------------------------------------------------------------------------
+
+/// Every opcode name Core's `ParseScript` understands (without `OP_`).
+///
+/// This is the full protocol table, not just what the engine implements, so
+/// that scripts using unsupported opcodes still assemble and are counted as
+/// failures rather than skipped.
+const CORE_OPCODES: &[(&str, u8)] = &[
+    ("RESERVED", 0x50),
+    ("NOP", 0x61),
+    ("VER", 0x62),
+    ("IF", 0x63),
+    ("NOTIF", 0x64),
+    ("VERIF", 0x65),
+    ("VERNOTIF", 0x66),
+    ("ELSE", 0x67),
+    ("ENDIF", 0x68),
+    ("VERIFY", 0x69),
+    ("RETURN", 0x6a),
+    ("TOALTSTACK", 0x6b),
+    ("FROMALTSTACK", 0x6c),
+    ("2DROP", 0x6d),
+    ("2DUP", 0x6e),
+    ("3DUP", 0x6f),
+    ("2OVER", 0x70),
+    ("2ROT", 0x71),
+    ("2SWAP", 0x72),
+    ("IFDUP", 0x73),
+    ("DEPTH", 0x74),
+    ("DROP", 0x75),
+    ("DUP", 0x76),
+    ("NIP", 0x77),
+    ("OVER", 0x78),
+    ("PICK", 0x79),
+    ("ROLL", 0x7a),
+    ("ROT", 0x7b),
+    ("SWAP", 0x7c),
+    ("TUCK", 0x7d),
+    ("CAT", 0x7e),
+    ("SUBSTR", 0x7f),
+    ("LEFT", 0x80),
+    ("RIGHT", 0x81),
+    ("SIZE", 0x82),
+    ("INVERT", 0x83),
+    ("AND", 0x84),
+    ("OR", 0x85),
+    ("XOR", 0x86),
+    ("EQUAL", 0x87),
+    ("EQUALVERIFY", 0x88),
+    ("RESERVED1", 0x89),
+    ("RESERVED2", 0x8a),
+    ("1ADD", 0x8b),
+    ("1SUB", 0x8c),
+    ("2MUL", 0x8d),
+    ("2DIV", 0x8e),
+    ("NEGATE", 0x8f),
+    ("ABS", 0x90),
+    ("NOT", 0x91),
+    ("0NOTEQUAL", 0x92),
+    ("ADD", 0x93),
+    ("SUB", 0x94),
+    ("MUL", 0x95),
+    ("DIV", 0x96),
+    ("MOD", 0x97),
+    ("LSHIFT", 0x98),
+    ("RSHIFT", 0x99),
+    ("BOOLAND", 0x9a),
+    ("BOOLOR", 0x9b),
+    ("NUMEQUAL", 0x9c),
+    ("NUMEQUALVERIFY", 0x9d),
+    ("NUMNOTEQUAL", 0x9e),
+    ("LESSTHAN", 0x9f),
+    ("GREATERTHAN", 0xa0),
+    ("LESSTHANOREQUAL", 0xa1),
+    ("GREATERTHANOREQUAL", 0xa2),
+    ("MIN", 0xa3),
+    ("MAX", 0xa4),
+    ("WITHIN", 0xa5),
+    ("RIPEMD160", 0xa6),
+    ("SHA1", 0xa7),
+    ("SHA256", 0xa8),
+    ("HASH160", 0xa9),
+    ("HASH256", 0xaa),
+    ("CODESEPARATOR", 0xab),
+    ("CHECKSIG", 0xac),
+    ("CHECKSIGVERIFY", 0xad),
+    ("CHECKMULTISIG", 0xae),
+    ("CHECKMULTISIGVERIFY", 0xaf),
+    ("NOP1", 0xb0),
+    ("CHECKLOCKTIMEVERIFY", 0xb1),
+    ("NOP2", 0xb1),
+    ("CHECKSEQUENCEVERIFY", 0xb2),
+    ("NOP3", 0xb2),
+    ("NOP4", 0xb3),
+    ("NOP5", 0xb4),
+    ("NOP6", 0xb5),
+    ("NOP7", 0xb6),
+    ("NOP8", 0xb7),
+    ("NOP9", 0xb8),
+    ("NOP10", 0xb9),
+    ("CHECKSIGADD", 0xba),
+];
+
+/// Encodes an integer as a minimal Script number (CScriptNum).
+fn script_num(n: i64) -> Vec<u8> {
+    let mut result = Vec::new();
+    let negative = n < 0;
+    let mut abs = n.unsigned_abs();
+    while abs > 0 {
+        result.push((abs & 0xff) as u8);
+        abs >>= 8;
+    }
+    if let Some(&last) = result.last() {
+        if last & 0x80 != 0 {
+            result.push(if negative { 0x80 } else { 0x00 });
+        } else if negative {
+            let len = result.len();
+            result[len - 1] |= 0x80;
+        }
+    }
+    result
+}
+
+/// Appends a data push using the smallest push opcode, like Core's
+/// `CScript::operator<<(std::vector<unsigned char>)`.
+fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+    match data.len() {
+        n if n < 0x4c => script.push(n as u8),
+        n if n <= 0xff => script.extend_from_slice(&[0x4c, n as u8]),
+        n if n <= 0xffff => {
+            script.push(0x4d);
+            script.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        n => {
+            script.push(0x4e);
+            script.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+    }
+    script.extend_from_slice(data);
+}
+
+/// Assembles Core's test ASM dialect into raw script bytes.
+///
+/// Mirrors `ParseScript` in Core's `core_read.cpp`:
+/// - decimal integers push a Script number (`-1` and `1`-`16` use `OP_N`),
+/// - `0x..` words are spliced in as raw bytes (not pushed),
+/// - `'text'` pushes the quoted bytes,
+/// - anything else is an opcode name, with or without `OP_`.
+fn parse_core_asm(asm: &str) -> Result<Vec<u8>, String> {
+    let mut script = Vec::new();
+
+    for word in asm.split_whitespace() {
+        let digits = word.strip_prefix('-').unwrap_or(word);
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            let n: i64 = word.parse().map_err(|_| format!("bad number: {word}"))?;
+            if !(-0xffff_ffff..=0xffff_ffff).contains(&n) {
+                return Err(format!("number out of range: {word}"));
+            }
+            match n {
+                0 => script.push(0x00),
+                -1 => script.push(0x4f),
+                1..=16 => script.push(0x50 + n as u8),
+                _ => push_data(&mut script, &script_num(n)),
+            }
+        } else if let Some(hex) = word.strip_prefix("0x").filter(|h| !h.is_empty()) {
+            let bytes = decode_hex(hex).map_err(|_| format!("bad hex: {word}"))?;
+            script.extend_from_slice(&bytes);
+        } else if word.len() >= 2 && word.starts_with('\'') && word.ends_with('\'') {
+            push_data(&mut script, &word.as_bytes()[1..word.len() - 1]);
+        } else {
+            let name = word.strip_prefix("OP_").unwrap_or(word);
+            let byte = CORE_OPCODES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, b)| *b)
+                .ok_or_else(|| format!("unknown opcode: {word}"))?;
+            script.push(byte);
+        }
+    }
+
+    Ok(script)
+}
+
+// ---------------------------------------------------------------------------
+// Flags and expected results
+// ---------------------------------------------------------------------------
+
+/// Every verification flag name used in Core's test vectors.
+///
+/// [`verify_opts`] maps them onto [`VerifyOpts`]; cases expecting a failure
+/// under a flag it cannot map are skipped.
+const CORE_FLAGS: &[&str] = &[
+    "NONE",
+    "P2SH",
+    "STRICTENC",
+    "DERSIG",
+    "LOW_S",
+    "SIGPUSHONLY",
+    "MINIMALDATA",
+    "NULLDUMMY",
+    "DISCOURAGE_UPGRADABLE_NOPS",
+    "CLEANSTACK",
+    "MINIMALIF",
+    "NULLFAIL",
+    "CHECKLOCKTIMEVERIFY",
+    "CHECKSEQUENCEVERIFY",
+    "WITNESS",
+    "DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM",
+    "WITNESS_PUBKEYTYPE",
+    "CONST_SCRIPTCODE",
+    "TAPROOT",
+    "DISCOURAGE_UPGRADABLE_PUBKEYTYPE",
+    "DISCOURAGE_OP_SUCCESS",
+    "DISCOURAGE_UPGRADABLE_TAPROOT_VERSION",
+];
+
+/// Parses a comma-separated Core flag string, rejecting unknown names.
+fn parse_core_flags(flags: &str) -> Result<Vec<&str>, String> {
+    flags
+        .split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(|f| {
+            CORE_FLAGS
+                .iter()
+                .find(|known| **known == f)
+                .copied()
+                .ok_or_else(|| format!("unknown flag: {f}"))
+        })
+        .collect()
+}
+
+/// Maps Core flag names to verification options: `P2SH`, `WITNESS`,
+/// `TAPROOT`, `CHECKLOCKTIMEVERIFY` and `CHECKSEQUENCEVERIFY` switch the
+/// matching soft-fork rules on, and the rest go through
+/// [`VerifyFlags::from_name`]. Also returns the names neither covers,
+/// which the options cannot honour.
+fn verify_opts<'a>(names: &[&'a str]) -> (VerifyOpts, Vec<&'a str>) {
+    let mut flags = VerifyFlags::NONE;
+    let mut unhonoured = Vec::new();
+    for name in names {
+        match VerifyFlags::from_name(name) {
+            Some(flag) => flags.insert(flag),
+            None if SOFT_FORK_FLAGS.contains(name) || *name == "NONE" => {}
+            None => unhonoured.push(*name),
+        }
+    }
+    let mut opts = VerifyOpts::from(flags);
+    opts.p2sh = names.contains(&"P2SH");
+    opts.segwit = names.contains(&"WITNESS");
+    opts.taproot = names.contains(&"TAPROOT");
+    opts.checklocktimeverify = names.contains(&"CHECKLOCKTIMEVERIFY");
+    opts.checksequenceverify = names.contains(&"CHECKSEQUENCEVERIFY");
+    (opts, unhonoured)
+}
+
+/// Core flags [`verify_opts`] maps onto [`VerifyOpts`]'s soft-fork
+/// switches.
+const SOFT_FORK_FLAGS: &[&str] = &[
+    "P2SH",
+    "WITNESS",
+    "TAPROOT",
+    "CHECKLOCKTIMEVERIFY",
+    "CHECKSEQUENCEVERIFY",
+];
+
+/// Why a case cannot be run faithfully, if it cannot.
+///
+/// Every flag only adds rules, so an unhonoured flag cannot turn an
+/// expected success into a failure, and such cases still run. A case
+/// expecting a failure may be expecting the one that flag causes, so it
+/// is skipped.
+fn unhonoured_reason(expect_ok: bool, unhonoured: &[&str]) -> Option<String> {
+    match unhonoured.first() {
+        Some(name) if !expect_ok => Some(format!("flag {name} not enforced")),
+        _ => None,
+    }
+}
+
+/// Returns the Core result name for a verification outcome, or `None` if
+/// the spend is a Taproot script path, which is not implemented.
+///
+/// A script failing with a false top element is
+/// [`ScriptError::ScriptFailed`](mini_bitcoin_script::error::ScriptError),
+/// whose code is Core's `EVAL_FALSE`.
+fn result_code(result: &Result<(), VerifyError>) -> Option<&'static str> {
+    match result {
+        Ok(()) => Some("OK"),
+        Err(VerifyError::Script(err)) => Some(err.code()),
+        Err(VerifyError::Sighash(_)) => Some("UNKNOWN_ERROR"),
+        Err(VerifyError::UnsupportedTaproot) => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Report
+// ---------------------------------------------------------------------------
+
+/// Outcome counts for one vector file.
+#[derive(Default)]
+struct Report {
+    name: String,
+    pass: usize,
+    /// Our verdict (accept / reject) differs from Core's.
+    fail: usize,
+    /// Both reject, but for a different reason.
+    wrong_error: usize,
+    skip: usize,
+    skip_reasons: BTreeMap<String, usize>,
+    /// Failures grouped by the result Core expected.
+    fail_by_expected: BTreeMap<String, usize>,
+}
+
+impl Report {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn skip(&mut self, reason: &str) {
+        self.skip += 1;
+        *self.skip_reasons.entry(reason.to_string()).or_insert(0) += 1;
+    }
+
+    fn total(&self) -> usize {
+        self.pass + self.fail + self.wrong_error + self.skip
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} cases — {} pass, {} fail, {} wrong error, {} skip",
+            self.name,
+            self.total(),
+            self.pass,
+            self.fail,
+            self.wrong_error,
+            self.skip
+        )?;
+        for (expected, count) in &self.fail_by_expected {
+            writeln!(f, "  fail (expected {expected}): {count}")?;
+        }
+        for (reason, count) in &self.skip_reasons {
+            writeln!(f, "  skip ({reason}): {count}")?;
+        }
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Runners
+// ---------------------------------------------------------------------------
+
+fn data_path(file: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("data")
+        .join(file)
+}
+
+/// Loads a JSON array, or `None` if the file has not been vendored.
+fn load_vectors(path: &Path) -> Option<Vec<Value>> {
+    let text = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&text) {
+        Ok(Value::Array(cases)) => Some(cases),
+        _ => panic!("{} is not a JSON array", path.display()),
+    }
+}
+
+/// Runs one `script_tests.json` entry and records the outcome.
+///
+/// Entries are `[scriptSig, scriptPubKey, flags, expected, comment?]`,
+/// optionally preceded by `[witness item hex..., amount in BTC]`.
+/// Single-string entries are section comments.
+fn run_script_case(case: &Value, report: &mut Report) {
+    let fields = match case.as_array() {
+        Some(fields) => fields,
+        None => return report.skip("malformed entry"),
+    };
+    let (witness, amount, fields) = match fields.split_first() {
+        Some((Value::Array(witness), rest)) => match parse_witness(witness) {
+            Some((witness, amount)) => (witness, amount, rest),
+            None => return report.skip("unparseable witness"),
+        },
+        _ => (Vec::new(), 0, &fields[..]),
+    };
+    if fields.len() < 4 {
+        // Comment rows carry no test.
+        return;
+    }
+
+    let text = |i: usize| fields[i].as_str().unwrap_or_default();
+    let script_sig = match parse_core_asm(text(0)) {
+        Ok(bytes) => bytes,
+        Err(_) => return report.skip("unparseable ASM"),
+    };
+    let script_pubkey = match parse_core_asm(text(1)) {
+        Ok(bytes) => bytes,
+        Err(_) => return report.skip("unparseable ASM"),
+    };
+    let names = match parse_core_flags(text(2)) {
+        Ok(names) => names,
+        Err(_) => return report.skip("unknown flag"),
+    };
+    let expected = text(3);
+    let (opts, unhonoured) = verify_opts(&names);
+    if let Some(reason) = unhonoured_reason(expected == "OK", &unhonoured) {
+        return report.skip(&reason);
+    }
+
+    let credit = crediting_tx(script_pubkey, amount);
+    let spend = spending_tx(&credit, script_sig, witness);
+    let result = verify_input_with_opts(&spend, 0, &credit.outputs, &opts);
+    match result_code(&result) {
+        Some(actual) => record(report, expected, actual),
+        None => report.skip("taproot script path (not implemented)"),
+    }
+}
+
+/// Parses a script case's witness array: hex stack items followed by the
+/// spent amount in bitcoin.
+fn parse_witness(items: &[Value]) -> Option<(Vec<Vec<u8>>, u64)> {
+    let (amount, items) = items.split_last()?;
+    let amount = (amount.as_f64()? * 100_000_000.0).round() as u64;
+    let witness = items
+        .iter()
+        .map(|item| decode_hex(item.as_str()?).ok())
+        .collect::<Option<_>>()?;
+    Some((witness, amount))
+}
+
+/// Core's `BuildCreditingTransaction`: a version 1 transaction with a
+/// coinbase-like input (`OP_0 OP_0`) paying `value` to `script_pubkey`.
+fn crediting_tx(script_pubkey: Vec<u8>, value: u64) -> Transaction {
+    let mut input = TxIn::new(OutPoint {
+        txid: [0; 32],
+        vout: u32::MAX,
+    });
+    input.script_sig = vec![0x00, 0x00];
+    Transaction {
+        version: 1,
+        inputs: vec![input],
+        outputs: vec![TxOut {
+            value,
+            script_pubkey,
+        }],
+        lock_time: 0,
+    }
+}
+
+/// Core's `BuildSpendingTransaction`: a version 1 transaction spending
+/// `credit`'s only output to an empty script.
+fn spending_tx(credit: &Transaction, script_sig: Vec<u8>, witness: Vec<Vec<u8>>) -> Transaction {
+    let mut input = TxIn::new(OutPoint {
+        txid: credit.txid(),
+        vout: 0,
+    });
+    input.script_sig = script_sig;
+    input.witness = witness;
+    Transaction {
+        version: 1,
+        inputs: vec![input],
+        outputs: vec![TxOut {
+            value: credit.outputs[0].value,
+            script_pubkey: Vec::new(),
+        }],
+        lock_time: 0,
+    }
+}
+
+fn record(report: &mut Report, expected: &str, actual: &str) {
+    if expected == actual {
+        report.pass += 1;
+    } else if expected != "OK" && actual != "OK" {
+        // Both reject, for different reasons.
+        report.wrong_error += 1;
+    } else {
+        report.fail += 1;
+        *report
+            .fail_by_expected
+            .entry(expected.to_string())
+            .or_insert(0) += 1;
+    }
+}
+
+fn run_script_tests(path: &Path, name: &str) -> Option<Report> {
+    let cases = load_vectors(path)?;
+    let mut report = Report::new(name);
+    for case in &cases {
+        run_script_case(case, &mut report);
+    }
+    Some(report)
+}
+
+fn run_tx_tests(path: &Path, name: &str, valid: bool) -> Option<Report> {
+    let cases = load_vectors(path)?;
+    let mut report = Report::new(name);
+    for case in &cases {
+        run_tx_case(case, valid, &mut report);
+    }
+    Some(report)
+}
+
+/// Runs one `tx_valid.json` or `tx_invalid.json` entry through
+/// `verify_tx` and records whether the transaction was accepted.
+///
+/// Entries are `[[[prevout txid, prevout index, scriptPubKey, amount?],
+/// ...], serialized transaction, flags]`. In `tx_valid.json` the flags are
+/// the ones to exclude from the full set; in `tx_invalid.json` they are
+/// the ones to apply, or `BADTX` for a transaction that fails Core's
+/// context-free `CheckTransaction`. Single-string entries are comments.
+fn run_tx_case(case: &Value, valid: bool, report: &mut Report) {
+    let fields = match case.as_array() {
+        Some(fields) if fields.len() >= 3 && fields[0].is_array() => fields,
+        _ => return,
+    };
+    let utxos: Option<BTreeMap<OutPoint, TxOut>> = fields[0]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(parse_prevout)
+        .collect();
+    let utxos = match utxos {
+        Some(utxos) => utxos,
+        None => return report.skip("malformed prevout"),
+    };
+    let tx = match fields[1]
+        .as_str()
+        .and_then(|hex| decode_hex(hex).ok())
+        .and_then(|bytes| Transaction::deserialize(&bytes).ok())
+    {
+        Some(tx) => tx,
+        None => return report.skip("undecodable transaction"),
+    };
+
+    let flags = fields[2].as_str().unwrap_or_default();
+    let names = if flags == "BADTX" {
+        CORE_FLAGS.to_vec()
+    } else {
+        match parse_core_flags(flags) {
+            Ok(names) if valid => CORE_FLAGS
+                .iter()
+                .copied()
+                .filter(|flag| !names.contains(flag))
+                .collect(),
+            Ok(names) => names,
+            Err(_) => return report.skip("unknown flag"),
+        }
+    };
+    let (opts, unhonoured) = verify_opts(&names);
+    // BADTX cases fail before any script runs, whatever the flags.
+    if let Some(reason) = unhonoured_reason(valid || flags == "BADTX", &unhonoured) {
+        return report.skip(&reason);
+    }
+
+    let accepted = match verify_tx_with_opts(&tx, &utxos, &opts) {
+        Ok(verification) => verification.is_valid(),
+        // The vectors' amounts are placeholders, mostly zero, and Core's
+        // harness does not check fees, so check the inputs on their own.
+        Err(TxVerifyError::NegativeFee { .. }) => inputs_verify(&tx, &utxos, &opts),
+        Err(_) => false,
+    };
+    let verdict = |ok| if ok { "OK" } else { "INVALID" };
+    record(report, verdict(valid), verdict(accepted));
+}
+
+/// Parses `[txid, index, scriptPubKey, amount?]`, with the txid in display
+/// order and index -1 for the null outpoint.
+fn parse_prevout(prevout: &Value) -> Option<(OutPoint, TxOut)> {
+    let fields = prevout.as_array()?;
+    let mut txid: [u8; 32] = decode_hex(fields.first()?.as_str()?)
+        .ok()?
+        .try_into()
+        .ok()?;
+    txid.reverse();
+    let vout = match fields.get(1)?.as_i64()? {
+        -1 => u32::MAX,
+        vout => u32::try_from(vout).ok()?,
+    };
+    let script_pubkey = parse_core_asm(fields.get(2)?.as_str()?).ok()?;
+    let value = match fields.get(3) {
+        Some(amount) => amount.as_u64()?,
+        None => 0,
+    };
+    Some((
+        OutPoint { txid, vout },
+        TxOut {
+            value,
+            script_pubkey,
+        },
+    ))
+}
+
+fn inputs_verify(tx: &Transaction, utxos: &BTreeMap<OutPoint, TxOut>, opts: &VerifyOpts) -> bool {
+    let prevouts: Option<Vec<TxOut>> = tx
+        .inputs
+        .iter()
+        .map(|input| utxos.get(&input.previous_output).cloned())
+        .collect();
+    prevouts.map_or(false, |prevouts| {
+        (0..tx.inputs.len()).all(|index| verify_input_with_opts(tx, index, &prevouts, opts).is_ok())
+    })
+}
+
+/// Expected counts for a vector file.
+///
+/// An exact match is required, so a change in either direction fails the
+/// test until the baseline is updated with it.
+#[derive(Debug, PartialEq, Eq)]
+struct Baseline {
+    pass: usize,
+    fail: usize,
+    wrong_error: usize,
+    skip: usize,
+}
+
+impl From<&Report> for Baseline {
+    fn from(report: &Report) -> Self {
+        Baseline {
+            pass: report.pass,
+            fail: report.fail,
+            wrong_error: report.wrong_error,
+            skip: report.skip,
+        }
+    }
+}
+
+/// Prints `report` and asserts it matches `baseline`.
+///
+/// The Core files are not checked in yet, so their baselines are `None`
+/// until they are; a vendored file without a recorded baseline fails, with
+/// the counts to record.
+fn check_baseline(file: &str, report: Option<Report>, baseline: Option<Baseline>) {
+    let report = match report {
+        Some(report) => report,
+        None => return println!("{file}: not vendored, skipped (see tests/data/README.md)"),
+    };
+    print!("{report}");
+    let actual = Baseline::from(&report);
+    match baseline {
+        Some(baseline) => assert_eq!(actual, baseline, "{file} moved off its baseline"),
+        None => panic!("{file} has no recorded baseline; record {actual:?}"),
+    }
+}
+
+/// Baseline for Core v27.1's `script_tests.json`, once vendored.
+const SCRIPT_TESTS_BASELINE: Option<Baseline> = None;
+
+/// Baseline for Core v27.1's `tx_valid.json`, once vendored.
+const TX_VALID_BASELINE: Option<Baseline> = None;
+
+/// Baseline for Core v27.1's `tx_invalid.json`, once vendored.
+const TX_INVALID_BASELINE: Option<Baseline> = None;
+
+#[test]
+fn core_script_tests() {
+    let file = "core/script_tests.json";
+    let report = run_script_tests(&data_path(file), "script_tests");
+    check_baseline(file, report, SCRIPT_TESTS_BASELINE);
+}
+
+#[test]
+fn core_tx_valid() {
+    let file = "core/tx_valid.json";
+    let report = run_tx_tests(&data_path(file), "tx_valid", true);
+    check_baseline(file, report, TX_VALID_BASELINE);
+}
+
+#[test]
+fn core_tx_invalid() {
+    let file = "core/tx_invalid.json";
+    let report = run_tx_tests(&data_path(file), "tx_invalid", false);
+    check_baseline(file, report, TX_INVALID_BASELINE);
+}
+
+/// The sample file uses Core's format but only cases this crate handles,
+/// so it keeps the harness itself honest even when Core's file is absent.
+/// Its one skip is a case expecting a MINIMALDATA failure.
+#[test]
+fn sample_script_tests_baseline() {
+    let file = "script_tests_sample.json";
+    let report = run_script_tests(&data_path(file), "sample");
+    assert!(report.is_some(), "sample vectors are checked in");
+    let baseline = Baseline {
+        pass: 38,
+        fail: 0,
+        wrong_error: 0,
+        skip: 1,
+    };
+    check_baseline(file, report, Some(baseline));
+}
+
+// ---------------------------------------------------------------------------
+// Harness unit tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn asm_numbers() {
+    assert_eq!(
+        parse_core_asm("0 -1 1 16").unwrap(),
+        vec![0x00, 0x4f, 0x51, 0x60]
+    );
+    assert_eq!(parse_core_asm("17").unwrap(), vec![0x01, 0x11]);
+    assert_eq!(parse_core_asm("-2").unwrap(), vec![0x01, 0x82]);
+    assert_eq!(parse_core_asm("128").unwrap(), vec![0x02, 0x80, 0x00]);
+}
+
+#[test]
+fn asm_raw_hex_is_spliced() {
+    assert_eq!(
+        parse_core_asm("0x4c 0x01 0x07").unwrap(),
+        vec![0x4c, 0x01, 0x07]
+    );
+}
+
+#[test]
+fn asm_quoted_string_is_pushed() {
+    assert_eq!(parse_core_asm("'ab'").unwrap(), vec![0x02, b'a', b'b']);
+    assert_eq!(parse_core_asm("''").unwrap(), vec![0x00]);
+}
+
+#[test]
+fn asm_opcode_names() {
+    assert_eq!(
+        parse_core_asm("DUP OP_HASH160 NOP2 CHECKLOCKTIMEVERIFY").unwrap(),
+        vec![0x76, 0xa9, 0xb1, 0xb1]
+    );
+    assert!(parse_core_asm("BOGUS").is_err());
+}
+
+#[test]
+fn flags_parse() {
+    assert_eq!(
+        parse_core_flags("P2SH,STRICTENC").unwrap(),
+        ["P2SH", "STRICTENC"]
+    );
+    assert!(parse_core_flags("").unwrap().is_empty());
+    assert!(parse_core_flags("P2SH,MADE_UP").is_err());
+}
+
+#[test]
+fn flags_map_to_opts() {
+    let names = parse_core_flags("P2SH,STRICTENC,LOW_S,CHECKSEQUENCEVERIFY,CLEANSTACK").unwrap();
+    let (opts, unhonoured) = verify_opts(&names);
+    assert_eq!(opts.flags, VerifyFlags::STRICTENC | VerifyFlags::LOW_S);
+    assert!(opts.p2sh && opts.checksequenceverify);
+    assert!(!opts.segwit && !opts.taproot && !opts.checklocktimeverify);
+    assert_eq!(unhonoured, ["CLEANSTACK"]);
+
+    assert_eq!(unhonoured_reason(true, &unhonoured), None);
+    assert_eq!(
+        unhonoured_reason(false, &unhonoured).as_deref(),
+        Some("flag CLEANSTACK not enforced")
+    );
+    assert_eq!(unhonoured_reason(false, &[]), None);
+}
+
+#[test]
+fn result_codes() {
+    use mini_bitcoin_script::error::ScriptError;
+    use mini_bitcoin_script::sighash::SighashError;
+
+    assert_eq!(result_code(&Ok(())), Some("OK"));
+    assert_eq!(
+        result_code(&Err(VerifyError::Script(ScriptError::ScriptFailed))),
+        Some("EVAL_FALSE")
+    );
+    assert_eq!(
+        result_code(&Err(VerifyError::Script(ScriptError::EqualVerifyFailed))),
+        Some("EQUALVERIFY")
+    );
+    assert_eq!(
+        result_code(&Err(VerifyError::Sighash(SighashError::InputIndex {
+            index: 1,
+            inputs: 1
+        }))),
+        Some("UNKNOWN_ERROR")
+    );
+    assert_eq!(result_code(&Err(VerifyError::UnsupportedTaproot)), None);
+}
+
+#[test]
+fn script_cases_spend_core_transactions() {
+    let credit = crediting_tx(vec![0x51], 1000);
+    assert_eq!(credit.inputs[0].script_sig, [0x00, 0x00]);
+    let spend = spending_tx(&credit, vec![], vec![vec![0x01]]);
+    assert_eq!(spend.inputs[0].previous_output.txid, credit.txid());
+    assert_eq!(spend.outputs[0].value, 1000);
+    assert_eq!((spend.version, spend.lock_time), (1, 0));
+    assert_eq!(spend.inputs[0].sequence, u32::MAX);
+
+    assert_eq!(
+        parse_witness(&[serde_json::json!("51"), serde_json::json!(0.00000123)]),
+        Some((vec![vec![0x51]], 123))
+    );
+    assert_eq!(
+        parse_witness(&[serde_json::json!("zz"), serde_json::json!(0)]),
+        None
+    );
+}
+
+#[test]
+fn tx_cases() {
+    use mini_bitcoin_script::hex::encode_hex;
+    use serde_json::json;
+
+    // Spends output 0 of a txid that displays as 00…01.
+    let mut txid = [0; 32];
+    txid[0] = 1;
+    let outpoint = OutPoint { txid, vout: 0 };
+    let display = format!("{}01", "00".repeat(31));
+    let mut tx = Transaction {
+        version: 1,
+        inputs: vec![TxIn::new(outpoint)],
+        outputs: vec![TxOut {
+            value: 0,
+            script_pubkey: vec![],
+        }],
+        lock_time: 0,
+    };
+    let hex = encode_hex(&tx.serialize());
+
+    let mut report = Report::new("tx");
+    run_tx_case(&json!([[[display, 0, "1"]], hex, ""]), true, &mut report);
+    run_tx_case(
+        &json!([[[display, 0, "0"]], hex, "P2SH"]),
+        false,
+        &mut report,
+    );
+    // Outputs above the placeholder amounts are not a failure.
+    tx.outputs[0].value = 1;
+    let hex = encode_hex(&tx.serialize());
+    run_tx_case(
+        &json!([[[display, 0, "1"]], hex, "NONE"]),
+        true,
+        &mut report,
+    );
+
+    tx.inputs.push(TxIn::new(outpoint));
+    let hex = encode_hex(&tx.serialize());
+    run_tx_case(
+        &json!([[[display, 0, "1"]], hex, "BADTX"]),
+        false,
+        &mut report,
+    );
+    run_tx_case(&json!(["a comment"]), true, &mut report);
+    assert_eq!((report.pass, report.total()), (4, 4), "{report}");
+}
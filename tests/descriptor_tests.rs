@@ -0,0 +1,293 @@
+use mini_bitcoin_script::builder;
+use mini_bitcoin_script::classify::OutputType;
+use mini_bitcoin_script::descriptor::{Descriptor, DescriptorError};
+use mini_bitcoin_script::hex::encode_hex;
+use mini_bitcoin_script::weight::estimate_satisfaction;
+
+const G1: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+const G2: &str = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+const G3: &str = "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+const UNCOMPRESSED: &str = "04a34b99f22c790c4e36b2b3c2c35a36db06226e41c692fc82b8b56ac1c540c5bd5b8dec5235a0fa8722476c7709c02559e3aa73aa03918ba2d492eea75abea235";
+
+fn parse(s: &str) -> Descriptor {
+    s.parse().unwrap_or_else(|e| panic!("{s}: {e}"))
+}
+
+fn script_hex(s: &str) -> String {
+    parse(s).script_pubkey().unwrap().to_hex()
+}
+
+#[test]
+fn single_key_scripts() {
+    assert_eq!(script_hex(&format!("pk({G1})")), format!("21{G1}ac"));
+    assert_eq!(
+        script_hex(&format!("pkh({G2})")),
+        "76a91406afd46bcdfd22ef94ac122aa11f241244a37ecc88ac"
+    );
+    assert_eq!(
+        script_hex(&format!("wpkh({G3})")),
+        "00147dd65592d0ab2fe0d0257d571abf032cd9db93dc"
+    );
+    assert_eq!(
+        script_hex(&format!("wsh(pkh({G2}))")),
+        "0020cdf0713be811ae97176dde2e457ff8e5454f2e1dc364b3f264a9b008dffed679"
+    );
+}
+
+#[test]
+fn checksums() {
+    let desc = format!("pk({G1})");
+    assert_eq!(parse(&desc).to_string(), format!("{desc}#gn28ywm7"));
+    parse(&format!("{desc}#gn28ywm7"));
+    assert_eq!(
+        format!("{desc}#gn28ywm8").parse::<Descriptor>(),
+        Err(DescriptorError::InvalidChecksum)
+    );
+    assert_eq!(
+        format!("{desc}#").parse::<Descriptor>(),
+        Err(DescriptorError::InvalidChecksum)
+    );
+}
+
+#[test]
+fn multisig() {
+    let sorted = parse(&format!("sortedmulti(1,{G3},{G1})"));
+    assert_eq!(
+        sorted.to_string(),
+        format!("sortedmulti(1,{G3},{G1})#hxwwr80x")
+    );
+    let expected = builder::multisig(
+        1,
+        &[
+            &mini_bitcoin_script::hex::decode_hex(G1).unwrap(),
+            &mini_bitcoin_script::hex::decode_hex(G3).unwrap(),
+        ],
+    )
+    .unwrap();
+    assert_eq!(sorted.script_pubkey().unwrap().as_bytes(), &expected[..]);
+    assert_eq!(
+        sorted.output_type(),
+        Ok(OutputType::Multisig {
+            required: 1,
+            keys: 2
+        })
+    );
+
+    let wsh = parse(&format!(
+        "wsh(multi(2,[d34db33f/48'/0'/0'/2']{G1},{G2}))#ysdke8s6"
+    ));
+    let witness_script = wsh.witness_script().unwrap().unwrap();
+    assert!(witness_script.is_multisig());
+    assert_eq!(wsh.redeem_script(), Ok(None));
+    assert!(wsh.script_pubkey().unwrap().is_p2wsh());
+    assert_eq!(
+        wsh.max_satisfaction_size(),
+        estimate_satisfaction(OutputType::P2wsh, None, Some(witness_script.as_bytes()))
+    );
+
+    let nested = parse(&format!("sh(wsh(multi(1,{G1})))"));
+    assert!(nested.script_pubkey().unwrap().is_p2sh());
+    assert!(nested.redeem_script().unwrap().unwrap().is_p2wsh());
+    assert!(nested.witness_script().unwrap().unwrap().is_multisig());
+}
+
+#[test]
+fn multisig_limits() {
+    for desc in [
+        format!("multi(0,{G1})"),
+        format!("multi(2,{G1})"),
+        format!("multi(1,{G1},{G2},{G3},{G1})"),
+        format!("sh(multi(1{}))", format!(",{UNCOMPRESSED}").repeat(8)),
+    ] {
+        assert_eq!(
+            desc.parse::<Descriptor>(),
+            Err(DescriptorError::InvalidMultisig),
+            "{desc}"
+        );
+    }
+    // Four keys are fine below the top level.
+    parse(&format!("wsh(multi(1,{G1},{G2},{G3},{G1}))"));
+}
+
+#[test]
+fn contexts() {
+    let cases = [
+        (format!("wsh(wpkh({G1}))"), 4),
+        (format!("sh(sh(pk({G1})))"), 3),
+        (format!("wsh(sh(pk({G1})))"), 4),
+        (format!("sh(tr({G1}))"), 3),
+        ("sh(raw(51))".to_string(), 3),
+        (format!("tr({G1},pkh({G2}))"), 70),
+    ];
+    for (desc, index) in cases {
+        assert_eq!(
+            desc.parse::<Descriptor>(),
+            Err(DescriptorError::InvalidContext { index }),
+            "{desc}"
+        );
+    }
+    // Uncompressed keys only outside segwit.
+    parse(&format!("sh(pk({UNCOMPRESSED}))"));
+    assert_eq!(
+        format!("wpkh({UNCOMPRESSED})").parse::<Descriptor>(),
+        Err(DescriptorError::InvalidContext { index: 5 })
+    );
+    assert_eq!(
+        format!("wsh(pk({UNCOMPRESSED}))").parse::<Descriptor>(),
+        Err(DescriptorError::InvalidContext { index: 7 })
+    );
+}
+
+#[test]
+fn syntax_errors() {
+    assert_eq!(
+        "foo(00)".parse::<Descriptor>(),
+        Err(DescriptorError::UnknownFunction { index: 0 })
+    );
+    assert_eq!(
+        format!("pk({G1}").parse::<Descriptor>(),
+        Err(DescriptorError::Syntax { index: 69 })
+    );
+    assert_eq!(
+        format!("pk({G1}))").parse::<Descriptor>(),
+        Err(DescriptorError::Syntax { index: 70 })
+    );
+    assert_eq!(
+        "pk(02abcd)".parse::<Descriptor>(),
+        Err(DescriptorError::InvalidKey { index: 3 })
+    );
+    assert_eq!(
+        format!("pk([d34db33f/x]{G1})").parse::<Descriptor>(),
+        Err(DescriptorError::InvalidKey { index: 3 })
+    );
+    assert!(matches!(
+        "raw(5)".parse::<Descriptor>(),
+        Err(DescriptorError::InvalidHex(_))
+    ));
+}
+
+#[test]
+fn addresses() {
+    let cases = [
+        (
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+            "76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac",
+        ),
+        (
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "0014751e76e8199196d454941c45d1b3a323f1433bd6",
+        ),
+        (
+            "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4",
+            "0014751e76e8199196d454941c45d1b3a323f1433bd6",
+        ),
+        (
+            "tb1p09uhj7te09uhj7te09uhj7te09uhj7te09uhj7te09uhj7te09us774qej",
+            "51207979797979797979797979797979797979797979797979797979797979797979",
+        ),
+    ];
+    for (address, script) in cases {
+        let desc = parse(&format!("addr({address})"));
+        assert_eq!(desc.script_pubkey().unwrap().to_hex(), script);
+        assert!(desc.to_string().starts_with(&format!("addr({address})#")));
+    }
+
+    for address in [
+        // Mixed case.
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3T4",
+        // Version 1 with a bech32 (not bech32m) checksum.
+        "bc1p09uhj7te09uhj7te09uhj7te09uhj7te09uhj7te09uhj7te09usu2nrxl",
+        // Bad base58 checksum.
+        "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb",
+    ] {
+        assert_eq!(
+            format!("addr({address})").parse::<Descriptor>(),
+            Err(DescriptorError::InvalidAddress),
+            "{address}"
+        );
+    }
+}
+
+#[test]
+fn raw_scripts() {
+    let desc = parse("raw(6a0102)#th8vffhh");
+    assert_eq!(desc.output_type(), Ok(OutputType::NullData));
+    assert_eq!(
+        encode_hex(desc.script_pubkey().unwrap().as_bytes()),
+        "6a0102"
+    );
+}
+
+#[cfg(not(feature = "secp256k1"))]
+#[test]
+fn taproot_needs_secp256k1() {
+    let desc = parse(&format!("tr({G1})"));
+    assert_eq!(desc.output_type(), Ok(OutputType::P2tr));
+    assert_eq!(desc.script_pubkey(), Err(DescriptorError::TaprootTweak));
+}
+
+#[cfg(feature = "secp256k1")]
+mod taproot {
+    use super::*;
+    use mini_bitcoin_script::hash::tagged_hash;
+    use mini_bitcoin_script::secp256k1::XOnlyPublicKey;
+    use mini_bitcoin_script::sign::{taproot_output_key, taproot_script_output_key};
+
+    fn x_only(hex: &str) -> XOnlyPublicKey {
+        let bytes = mini_bitcoin_script::hex::decode_hex(hex).unwrap();
+        XOnlyPublicKey::from_slice(&bytes[bytes.len() - 32..]).unwrap()
+    }
+
+    fn leaf_hash(key: &str) -> [u8; 32] {
+        let mut data = vec![0xc0, 34, 32];
+        data.extend(x_only(key).serialize());
+        data.push(0xac);
+        tagged_hash("TapLeaf", &data)
+    }
+
+    #[test]
+    fn key_path_only() {
+        let expected = builder::p2tr(&taproot_output_key(&x_only(G1)).serialize());
+        // Compressed and x-only internal keys give the same output.
+        for key in [G1, &G1[2..]] {
+            let desc = parse(&format!("tr({key})"));
+            assert_eq!(desc.script_pubkey().unwrap().as_bytes(), &expected[..]);
+        }
+    }
+
+    #[test]
+    fn script_tree() {
+        let (a, b) = (leaf_hash(G2), leaf_hash(G3));
+        let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+        let root = tagged_hash("TapBranch", &[lo, hi].concat());
+        let expected =
+            builder::p2tr(&taproot_script_output_key(&x_only(G1), Some(&root)).serialize());
+
+        let x2 = &G2[2..];
+        let x3 = &G3[2..];
+        for desc in [
+            format!("tr({G1},{{pk({x2}),pk({x3})}})"),
+            format!("tr({G1},{{pk({x3}),pk({x2})}})"),
+        ] {
+            let desc = parse(&desc);
+            assert_eq!(desc.script_pubkey().unwrap().as_bytes(), &expected[..]);
+            assert_eq!(parse(&desc.to_string()), desc);
+        }
+    }
+
+    #[test]
+    fn bip386_leaf_keys_are_x_only() {
+        const INTERNAL: &str = "a34b99f22c790c4e36b2b3c2c35a36db06226e41c692fc82b8b56ac1c540c5bd";
+        const LEAF: &str = "669b8afcec803a0d323e9a17f3ea8e68e8abe5a278020a929adbec52421adbd0";
+        let expected = "512017cf18db381d836d8923b1bdb246cfcd818da1a9f0e6e7907f187f0b2f937754";
+        // A compressed leaf key of either parity pushes only its x
+        // coordinate.
+        for leaf in [LEAF.to_string(), format!("02{LEAF}"), format!("03{LEAF}")] {
+            let desc = format!("tr({INTERNAL},pk({leaf}))");
+            assert_eq!(script_hex(&desc), expected, "{desc}");
+        }
+        let desc = format!("tr({G1},{{pk({G2}),pk({G3})}})");
+        let x_only = format!("tr({G1},{{pk({}),pk({})}})", &G2[2..], &G3[2..]);
+        assert_eq!(script_hex(&desc), script_hex(&x_only));
+    }
+}
@@ -176,7 +176,7 @@ fn op_equalverify_pass() {
 fn op_equalverify_fail() {
     // OP_1 OP_2 OP_EQUALVERIFY
     let err = run(&[0x51, 0x52, 0x88]).unwrap_err();
-    assert_eq!(err, ScriptError::VerifyFailed);
+    assert_eq!(err, ScriptError::EqualVerifyFailed);
 }
 
 #[test]
@@ -0,0 +1,152 @@
+#![cfg(feature = "test-utils")]
+
+use mini_bitcoin_script::builder::ScriptBuilder;
+use mini_bitcoin_script::engine::execute_on_items;
+use mini_bitcoin_script::error::ScriptError;
+use mini_bitcoin_script::script::validate_p2pkh_with_opts;
+use mini_bitcoin_script::secp256k1::{ecdsa, schnorr, Message, Secp256k1};
+use mini_bitcoin_script::sign;
+use mini_bitcoin_script::test_utils::{fixture, ScriptType, TestKey, FEE, FUNDING_VALUE};
+use mini_bitcoin_script::token::Token;
+use mini_bitcoin_script::tokenizer::parse_script;
+use mini_bitcoin_script::verify::verify_input;
+
+const ALL_TYPES: [ScriptType; 4] = [
+    ScriptType::P2pkh,
+    ScriptType::P2wpkh,
+    ScriptType::P2shMultisig,
+    ScriptType::P2trKeyPath,
+];
+
+#[test]
+fn keys_are_deterministic() {
+    assert_eq!(TestKey::from_seed(b"alice"), TestKey::from_seed(b"alice"));
+    assert_ne!(TestKey::from_seed(b"alice"), TestKey::from_seed(b"bob"));
+}
+
+#[test]
+fn fixtures_are_deterministic() {
+    for script_type in ALL_TYPES {
+        let a = fixture(script_type, b"seed");
+        let b = fixture(script_type, b"seed");
+        assert_eq!(a.spending_tx.serialize(), b.spending_tx.serialize());
+        let c = fixture(script_type, b"other seed");
+        assert_ne!(a.spending_tx.serialize(), c.spending_tx.serialize());
+    }
+}
+
+#[test]
+fn spending_tx_spends_funding_output() {
+    for script_type in ALL_TYPES {
+        let fx = fixture(script_type, b"seed");
+        let input = &fx.spending_tx.inputs[0];
+        assert_eq!(input.previous_output.txid, fx.funding_tx.txid());
+        assert_eq!(input.previous_output.vout, 0);
+        assert_eq!(fx.prevout().value, FUNDING_VALUE);
+        assert_eq!(fx.spending_tx.outputs[0].value, FUNDING_VALUE - FEE);
+    }
+}
+
+#[test]
+fn p2pkh_fixture_validates() {
+    let fx = fixture(ScriptType::P2pkh, b"seed");
+    let opts = fx.execute_opts();
+    let valid = validate_p2pkh_with_opts(fx.script_sig(), &fx.prevout().script_pubkey, &opts);
+    assert_eq!(valid, Ok(true));
+}
+
+/// The script code alone: the witness run against it is what
+/// `verify_input` evaluates for a P2WPKH spend.
+#[test]
+fn p2wpkh_fixture_validates_against_script_code() {
+    let fx = fixture(ScriptType::P2wpkh, b"seed");
+    assert!(fx.script_sig().is_empty());
+
+    let mut stack = fx.witness().to_vec();
+    let script_code = parse_script(&fx.script_code()).unwrap();
+    execute_on_items(&script_code, &mut stack, &fx.execute_opts()).unwrap();
+    assert_eq!(stack, vec![vec![0x01]]);
+}
+
+#[test]
+fn fixtures_verify_as_inputs() {
+    for script_type in ALL_TYPES {
+        let fx = fixture(script_type, b"seed");
+        let prevouts = [fx.prevout().clone()];
+        let flags = fx.execute_opts().flags;
+        assert_eq!(
+            verify_input(&fx.spending_tx, 0, &prevouts, flags),
+            Ok(()),
+            "{script_type:?}"
+        );
+
+        let mut tampered = fx.spending_tx.clone();
+        tampered.outputs[0].value += 1;
+        assert!(
+            verify_input(&tampered, 0, &prevouts, flags).is_err(),
+            "{script_type:?}"
+        );
+    }
+}
+
+#[test]
+fn p2sh_multisig_fixture_signatures_verify() {
+    let fx = fixture(ScriptType::P2shMultisig, b"seed");
+    let redeem_script = fx.redeem_script.clone().unwrap();
+    let tokens = parse_script(fx.script_sig()).unwrap();
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(tokens[3], Token::PushData(redeem_script));
+
+    let secp = Secp256k1::verification_only();
+    let message = Message::from_digest(fx.sighash());
+    for (token, key) in tokens[1..3].iter().zip(&fx.keys) {
+        let sig = match token {
+            Token::PushData(sig) => sig,
+            Token::Op(_) => panic!("expected a signature push"),
+        };
+        let signature = ecdsa::Signature::from_der(&sig[..sig.len() - 1]).unwrap();
+        secp.verify_ecdsa(&message, &signature, &key.public_key)
+            .unwrap();
+    }
+}
+
+#[test]
+fn p2tr_fixture_signature_verifies() {
+    let fx = fixture(ScriptType::P2trKeyPath, b"seed");
+    assert_eq!(fx.witness().len(), 1);
+    assert_eq!(fx.witness()[0].len(), 64);
+
+    let output_key = sign::taproot_output_key(&fx.keys[0].x_only());
+    let secp = Secp256k1::verification_only();
+    let signature = schnorr::Signature::from_slice(&fx.witness()[0]).unwrap();
+    secp.verify_schnorr(&signature, &Message::from_digest(fx.sighash()), &output_key)
+        .unwrap();
+}
+
+#[test]
+fn tampered_spend_fails() {
+    let mut fx = fixture(ScriptType::P2pkh, b"seed");
+    fx.spending_tx.outputs[0].value += 1;
+    let opts = fx.execute_opts();
+    let valid = validate_p2pkh_with_opts(fx.script_sig(), &fx.prevout().script_pubkey, &opts);
+    // Under NULLFAIL a failed check with a non-empty signature is an error.
+    assert_eq!(valid, Err(ScriptError::NullFail));
+
+    // Swapping in another key's signature fails too.
+    let other = fixture(ScriptType::P2pkh, b"other seed");
+    let pubkey = fx.keys[0].pubkey_bytes();
+    let forged = ScriptBuilder::new()
+        .push_slice(&parse_push(other.script_sig(), 0))
+        .push_slice(&pubkey)
+        .into_bytes();
+    let fx = fixture(ScriptType::P2pkh, b"seed");
+    let valid = validate_p2pkh_with_opts(&forged, &fx.prevout().script_pubkey, &fx.execute_opts());
+    assert_eq!(valid, Err(ScriptError::NullFail));
+}
+
+fn parse_push(script: &[u8], index: usize) -> Vec<u8> {
+    match &parse_script(script).unwrap()[index] {
+        Token::PushData(data) => data.clone(),
+        Token::Op(op) => panic!("expected a push, found {op}"),
+    }
+}
@@ -0,0 +1,96 @@
+use mini_bitcoin_script::hex::{
+    decode_hex, decode_hex_array, decode_hex_lenient, encode_hex, HexError, ReversedHex,
+};
+use mini_bitcoin_script::transaction::{OutPoint, Transaction, TxIn};
+
+#[test]
+fn error_positions() {
+    assert_eq!(
+        decode_hex("00ag"),
+        Err(HexError::InvalidChar {
+            index: 3,
+            byte: b'g'
+        })
+    );
+    // Positions are byte offsets, so a multi-byte character is reported
+    // by its first byte.
+    let err = decode_hex("00é0").unwrap_err();
+    assert_eq!(
+        err,
+        HexError::InvalidChar {
+            index: 2,
+            byte: 0xc3
+        }
+    );
+    assert_eq!(err.to_string(), "invalid hex byte 0xc3 at 2");
+    assert_eq!(
+        decode_hex("0z").unwrap_err().to_string(),
+        "invalid hex character 'z' at 1"
+    );
+}
+
+#[test]
+fn lenient_decoding() {
+    assert_eq!(decode_hex_lenient("0XABcd").unwrap(), [0xab, 0xcd]);
+    assert_eq!(decode_hex_lenient("ab\n cd\t").unwrap(), [0xab, 0xcd]);
+    assert_eq!(decode_hex_lenient("  ").unwrap(), []);
+    assert_eq!(
+        decode_hex_lenient("a b c"),
+        Err(HexError::OddLength { len: 3 })
+    );
+    // Positions count the skipped prefix and whitespace.
+    assert_eq!(
+        decode_hex_lenient(" 0x00 zz"),
+        Err(HexError::InvalidChar {
+            index: 6,
+            byte: b'z'
+        })
+    );
+    // Only one prefix, and only at the start.
+    assert!(decode_hex_lenient("00 0x11").is_err());
+}
+
+#[test]
+fn fixed_size_decoding() {
+    const KEY: [u8; 3] = match decode_hex_array("0a0B0c") {
+        Ok(bytes) => bytes,
+        Err(_) => panic!(),
+    };
+    assert_eq!(KEY, [0x0a, 0x0b, 0x0c]);
+    assert_eq!(
+        decode_hex_array::<2>("001122"),
+        Err(HexError::WrongLength {
+            expected: 2,
+            found: 3
+        })
+    );
+    assert_eq!(
+        decode_hex_array::<1>("0"),
+        Err(HexError::OddLength { len: 1 })
+    );
+    assert_eq!(decode_hex_array::<0>(""), Ok([]));
+}
+
+#[test]
+fn txid_display_is_reversed() {
+    let tx = Transaction {
+        version: 1,
+        inputs: vec![TxIn {
+            previous_output: OutPoint {
+                txid: [0; 32],
+                vout: 0xffff_ffff,
+            },
+            script_sig: vec![0x51],
+            sequence: 0xffff_ffff,
+            witness: vec![],
+        }],
+        outputs: vec![],
+        lock_time: 0,
+    };
+    let txid = tx.txid();
+    let shown = ReversedHex(&txid).to_string();
+    let mut reversed = txid;
+    reversed.reverse();
+    assert_eq!(shown, encode_hex(&reversed));
+    assert_eq!(decode_hex(&shown).unwrap(), reversed);
+}
@@ -0,0 +1,473 @@
+use mini_bitcoin_script::engine::{execute_with_opts, ExecuteOpts, SigVersion};
+use mini_bitcoin_script::hash;
+use mini_bitcoin_script::hex::encode_hex;
+use mini_bitcoin_script::miniscript::{Context, Miniscript, MiniscriptError, Satisfier, Terminal};
+use mini_bitcoin_script::script::ScriptBuf;
+use mini_bitcoin_script::token::Token;
+
+const G1: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+const G2: &str = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+const G3: &str = "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+const X1: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+const X2: &str = "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+
+/// Any non-empty signature passes OP_CHECKSIG in stub mode.
+const STUB_SIG: [u8; 9] = [0x30; 9];
+
+fn parse(s: &str, ctx: Context) -> Miniscript {
+    Miniscript::parse(s, ctx).unwrap_or_else(|e| panic!("{s}: {e}"))
+}
+
+fn key_bytes(hex: &str) -> Vec<u8> {
+    mini_bitcoin_script::hex::decode_hex(hex).unwrap()
+}
+
+/// Pushes the witness, then runs the script.
+fn run(ms: &Miniscript, witness: &[Vec<u8>], opts: &ExecuteOpts) -> bool {
+    let mut tokens: Vec<Token> = witness.iter().cloned().map(Token::PushData).collect();
+    tokens.extend(ms.to_tokens());
+    execute_with_opts(&tokens, opts).unwrap_or(false)
+}
+
+// ── Parsing and display ────────────────────────────────────────────────
+
+#[test]
+fn display_roundtrips() {
+    let expressions = [
+        format!("pk({G1})"),
+        format!("pkh({G1})"),
+        format!("and_v(v:pk({G1}),older(144))"),
+        format!("or_d(pk({G1}),and_v(v:pkh({G2}),after(500000)))"),
+        format!("andor(pk({G1}),older(10),pk({G2}))"),
+        format!("and_n(pk({G1}),sha256({}))", "11".repeat(32)),
+        format!(
+            "or_i(pk({G1}),and_v(v:pk({G2}),hash160({})))",
+            "22".repeat(20)
+        ),
+        format!("thresh(2,pk({G1}),s:pk({G2}),sln:older(12960))"),
+        format!("multi(2,{G1},{G2},{G3})"),
+        format!("or_b(pk({G1}),a:pk({G2}))"),
+        format!("and_b(pk({G1}),s:pk({G2}))"),
+        format!("t:or_c(pk({G1}),v:ripemd160({}))", "33".repeat(20)),
+        format!("c:and_v(v:older(1),pk_k({G1}))"),
+        format!("j:and_v(v:hash256({}),pk({G1}))", "44".repeat(32)),
+        format!("t:or_c(pk({G1}),and_v(v:pk({G2}),v:after(100)))"),
+    ];
+    for s in &expressions {
+        assert_eq!(parse(s, Context::Wsh).to_string(), *s);
+    }
+    // Sugar and plain forms parse to the same tree.
+    assert_eq!(
+        parse(&format!("c:pk_k({G1})"), Context::Wsh),
+        parse(&format!("pk({G1})"), Context::Wsh)
+    );
+    assert_eq!(
+        parse(&format!("andor(pk({G1}),1,0)"), Context::Wsh).to_string(),
+        format!("and_n(pk({G1}),1)")
+    );
+}
+
+#[test]
+fn types() {
+    let ty = |s: &str| parse(s, Context::Wsh).ty().to_string();
+    assert_eq!(ty(&format!("multi(1,{G1})")), "Bndu");
+    assert_eq!(ty(&format!("sha256({})", "00".repeat(32))), "Bondu");
+    assert_eq!(ty(&format!("andor(pk({G1}),older(10),pk({G2}))")), "Bd");
+    assert_eq!(ty(&format!("or_b(pk({G1}),a:pk({G2}))")), "Bdu");
+    assert_eq!(ty("after(100)"), "Bz");
+    // d: is only unit in tapscript, where OP_IF requires a minimal
+    // condition.
+    assert_eq!(ty("or_i(1,dv:older(1))"), "Bd");
+    let tap = parse("or_i(1,dv:older(1))", Context::Tap);
+    assert_eq!(tap.ty().to_string(), "Bdu");
+}
+
+#[test]
+fn parse_errors() {
+    let err = |s: &str, ctx| Miniscript::parse(s, ctx).unwrap_err();
+    assert_eq!(
+        err("pk(", Context::Wsh),
+        MiniscriptError::Syntax { index: 3 }
+    );
+    assert_eq!(
+        err(&format!("pk({G1}))"), Context::Wsh),
+        MiniscriptError::Syntax { index: 70 }
+    );
+    assert_eq!(
+        err("foo(1)", Context::Wsh),
+        MiniscriptError::UnknownFragment { index: 0 }
+    );
+    assert_eq!(
+        err(&format!("x:pk({G1})"), Context::Wsh),
+        MiniscriptError::UnknownFragment { index: 0 }
+    );
+    // An x-only key in P2WSH.
+    assert_eq!(
+        err(&format!("pk({X1})"), Context::Wsh),
+        MiniscriptError::InvalidArgument { index: 3 }
+    );
+    assert_eq!(
+        err("older(0)", Context::Wsh),
+        MiniscriptError::InvalidArgument { index: 6 }
+    );
+    assert_eq!(
+        err(&format!("multi(3,{G1},{G2})"), Context::Wsh),
+        MiniscriptError::InvalidArgument { index: 6 }
+    );
+    assert_eq!(
+        err(&format!("multi_a(1,{G1})"), Context::Wsh),
+        MiniscriptError::InvalidContext { index: 0 }
+    );
+    assert_eq!(
+        err(&format!("multi(1,{X1})"), Context::Tap),
+        MiniscriptError::InvalidContext { index: 0 }
+    );
+    // and_v needs a V on the left.
+    assert_eq!(
+        err(&format!("and_v(pk({G1}),pk({G2}))"), Context::Wsh),
+        MiniscriptError::TypeCheck { index: 0 }
+    );
+    assert_eq!(
+        err(&format!("or_d(pk({G1}),s:pk({G2}))"), Context::Wsh),
+        MiniscriptError::TypeCheck { index: 0 }
+    );
+    // Not type B at the top.
+    assert_eq!(
+        err(&format!("v:pk({G1})"), Context::Wsh),
+        MiniscriptError::TypeCheck { index: 0 }
+    );
+    // s: needs a one-arg expression; the error points at the wrapped
+    // subexpression.
+    assert_eq!(
+        err(&format!("or_b(pk({G1}),s:pkh({G2}))"), Context::Wsh),
+        MiniscriptError::TypeCheck { index: 76 }
+    );
+}
+
+// ── Compilation ────────────────────────────────────────────────────────
+
+#[test]
+fn compiles_to_script() {
+    let hex = |s: &str, ctx| parse(s, ctx).to_script().to_hex();
+    assert_eq!(hex(&format!("pk({G1})"), Context::Wsh), format!("21{G1}ac"));
+    assert_eq!(
+        hex(&format!("and_v(v:pk({G1}),older(144))"), Context::Wsh),
+        format!("21{G1}ad029000b2")
+    );
+    assert_eq!(
+        hex(&format!("or_d(pk({G1}),older(1))"), Context::Wsh),
+        format!("21{G1}ac736451b268")
+    );
+    assert_eq!(
+        hex(&format!("multi(2,{G1},{G2})"), Context::Wsh),
+        format!("5221{G1}21{G2}52ae")
+    );
+    let h = "ab".repeat(32);
+    assert_eq!(
+        hex(&format!("sha256({h})"), Context::Wsh),
+        format!("82012088a820{h}87")
+    );
+    assert_eq!(
+        hex(&format!("and_v(v:sha256({h}),1)"), Context::Wsh),
+        format!("82012088a820{h}8851")
+    );
+    assert_eq!(
+        hex(&format!("pkh({G1})"), Context::Wsh),
+        format!("76a914{}88ac", encode_hex(&hash::hash160(&key_bytes(G1))))
+    );
+    assert_eq!(
+        hex(&format!("multi_a(1,{X1},{X2})"), Context::Tap),
+        format!("20{X1}ac20{X2}ba519c")
+    );
+    assert_eq!(
+        hex(
+            &format!("thresh(2,pk({G1}),s:pk({G2}),sln:older(7))"),
+            Context::Wsh
+        ),
+        format!("21{G1}ac7c21{G2}ac937c63006757b29268935287")
+    );
+}
+
+// ── Lifting ────────────────────────────────────────────────────────────
+
+#[test]
+fn lifts_compiled_scripts() {
+    let expressions = [
+        format!("pk({G1})"),
+        format!("and_v(v:pk({G1}),older(144))"),
+        format!("and_v(v:pk({G1}),and_v(v:pk({G2}),after(1000)))"),
+        format!("or_d(pk({G1}),and_v(v:pk({G2}),older(4032)))"),
+        format!("andor(pk({G1}),older(10),pk({G2}))"),
+        format!("and_n(pk({G1}),sha256({}))", "11".repeat(32)),
+        format!(
+            "or_i(pk({G1}),and_v(v:pk({G2}),hash160({})))",
+            "22".repeat(20)
+        ),
+        format!("thresh(2,pk({G1}),s:pk({G2}),sln:older(12960))"),
+        format!("thresh(1,pk({G1}),a:pk({G2}))"),
+        format!("multi(2,{G1},{G2},{G3})"),
+        format!("or_b(pk({G1}),a:pk({G2}))"),
+        format!("and_b(pk({G1}),s:pk({G2}))"),
+        format!("t:or_c(pk({G1}),v:ripemd160({}))", "33".repeat(20)),
+        format!("t:or_c(pk({G1}),and_v(v:pk({G2}),v:after(100)))"),
+        format!("j:and_v(v:hash256({}),pk({G1}))", "44".repeat(32)),
+        format!("and_v(v:multi(1,{G1},{G2}),n:after(5))"),
+        format!("andor(pk({G1}),and_v(v:pk({G2}),older(10)),pk({G3}))"),
+    ];
+    for s in &expressions {
+        let ms = parse(s, Context::Wsh);
+        let lifted = Miniscript::from_script(&ms.to_script(), Context::Wsh)
+            .unwrap_or_else(|e| panic!("{s}: {e}"));
+        assert_eq!(lifted.to_string(), *s);
+    }
+
+    let tap = parse(
+        &format!("and_v(v:pk({X1}),multi_a(1,{X1},{X2}))"),
+        Context::Tap,
+    );
+    assert_eq!(
+        Miniscript::from_script(&tap.to_script(), Context::Tap).unwrap(),
+        tap
+    );
+}
+
+#[test]
+fn lifted_key_hashes_stay_hashes() {
+    let ms = parse(&format!("pkh({G1})"), Context::Wsh);
+    let lifted = Miniscript::from_script(&ms.to_script(), Context::Wsh).unwrap();
+    let key_hash = hash::hash160(&key_bytes(G1));
+    assert_eq!(
+        lifted.to_string(),
+        format!("c:expr_raw_pkh({})", encode_hex(&key_hash))
+    );
+    match lifted.node() {
+        Terminal::Check(inner) => assert_eq!(*inner.node(), Terminal::RawPkH(key_hash)),
+        other => panic!("unexpected {other:?}"),
+    }
+    assert_eq!(lifted.to_script(), ms.to_script());
+}
+
+#[test]
+fn rejects_non_miniscript() {
+    let lift = |asm: &str, ctx| {
+        let script: ScriptBuf = asm.parse().unwrap();
+        Miniscript::from_script(&script, ctx)
+    };
+    assert!(lift("OP_DUP OP_DROP", Context::Wsh).is_err());
+    assert!(lift("", Context::Wsh).is_err());
+    // A non-minimal number.
+    assert!(lift("0x03 0x900000 OP_CHECKSEQUENCEVERIFY", Context::Wsh).is_err());
+    // OP_EQUAL OP_VERIFY instead of OP_EQUALVERIFY.
+    let asm = format!(
+        "OP_SIZE 0x01 0x20 OP_EQUAL OP_VERIFY OP_SHA256 0x20 0x{} OP_EQUAL",
+        "00".repeat(32)
+    );
+    assert!(lift(&asm, Context::Wsh).is_err());
+    // Keys of the wrong context.
+    let asm = format!("0x20 0x{X1} OP_CHECKSIG");
+    assert_eq!(
+        lift(&asm, Context::Wsh),
+        Err(MiniscriptError::NotMiniscript)
+    );
+    assert!(lift(&asm, Context::Tap).is_ok());
+    // Not type B.
+    assert!(lift(&format!("0x21 0x{G1} OP_CHECKSIGVERIFY"), Context::Wsh).is_err());
+}
+
+// ── Satisfaction ───────────────────────────────────────────────────────
+
+fn stub_signatures(keys: &[&str]) -> Satisfier {
+    Satisfier {
+        signatures: keys
+            .iter()
+            .map(|key| (key_bytes(key), STUB_SIG.to_vec()))
+            .collect(),
+        ..Satisfier::default()
+    }
+}
+
+#[test]
+fn satisfies_in_stub_mode() {
+    let ms = parse(
+        &format!("or_d(pk({G1}),and_v(v:pk({G2}),older(144)))"),
+        Context::Wsh,
+    );
+    let opts = ExecuteOpts {
+        sequence: Some(144),
+        ..ExecuteOpts::default()
+    };
+
+    // The primary key alone.
+    let witness = ms.satisfy(&stub_signatures(&[G1])).unwrap();
+    assert_eq!(witness, [STUB_SIG.to_vec()]);
+    assert!(run(&ms, &witness, &opts));
+
+    // The recovery key once the timelock has passed.
+    let mut satisfier = stub_signatures(&[G2]);
+    assert_eq!(ms.satisfy(&satisfier), Err(MiniscriptError::Unsatisfiable));
+    satisfier.sequence = Some(144);
+    let witness = ms.satisfy(&satisfier).unwrap();
+    assert_eq!(witness, [STUB_SIG.to_vec(), vec![]]);
+    assert!(run(&ms, &witness, &opts));
+    assert!(!run(
+        &ms,
+        &witness,
+        &ExecuteOpts {
+            sequence: Some(143),
+            ..ExecuteOpts::default()
+        }
+    ));
+}
+
+#[test]
+fn satisfies_hashes_and_thresholds() {
+    let preimage = vec![0x5a; 32];
+    let h = encode_hex(&hash::sha256(&preimage));
+    let ms = parse(
+        &format!("thresh(2,pk({G1}),s:pk({G2}),a:sha256({h}))"),
+        Context::Wsh,
+    );
+
+    let mut satisfier = stub_signatures(&[G2]);
+    assert_eq!(ms.satisfy(&satisfier), Err(MiniscriptError::Unsatisfiable));
+    satisfier.preimages.push(preimage.clone());
+    let witness = ms.satisfy(&satisfier).unwrap();
+    // Bottom first: the hash, then G2's signature, then G1's empty one.
+    assert_eq!(witness, [preimage, STUB_SIG.to_vec(), vec![]]);
+    assert!(run(&ms, &witness, &ExecuteOpts::default()));
+
+    // The dissatisfied branch uses a wrong preimage, so it still fails.
+    let mut wrong = witness.clone();
+    wrong[0] = vec![0; 32];
+    assert!(!run(&ms, &wrong, &ExecuteOpts::default()));
+}
+
+#[test]
+fn satisfies_or_i_and_multi() {
+    let ms = parse(
+        &format!("or_i(and_v(v:after(600000),pk({G3})),multi(2,{G1},{G2},{G3}))"),
+        Context::Wsh,
+    );
+    let witness = ms.satisfy(&stub_signatures(&[G1, G3])).unwrap();
+    assert_eq!(
+        witness,
+        [vec![], STUB_SIG.to_vec(), STUB_SIG.to_vec(), vec![]]
+    );
+    assert!(run(&ms, &witness, &ExecuteOpts::default()));
+
+    // Once the locktime passes, G3 alone is smaller.
+    let satisfier = Satisfier {
+        lock_time: Some(600_000),
+        sequence: Some(0xffff_fffe),
+        ..stub_signatures(&[G1, G3])
+    };
+    let witness = ms.satisfy(&satisfier).unwrap();
+    assert_eq!(witness, [STUB_SIG.to_vec(), vec![1]]);
+    let opts = ExecuteOpts {
+        lock_time: Some(600_000),
+        sequence: Some(0xffff_fffe),
+        ..ExecuteOpts::default()
+    };
+    assert!(run(&ms, &witness, &opts));
+}
+
+#[test]
+fn satisfies_multi_a() {
+    let ms = parse(&format!("multi_a(1,{X1},{X2})"), Context::Tap);
+    let witness = ms.satisfy(&stub_signatures(&[X2])).unwrap();
+    // X1 is checked first, so its (empty) signature is on top.
+    assert_eq!(witness, [STUB_SIG.to_vec(), vec![]]);
+    let opts = ExecuteOpts {
+        sig_version: SigVersion::Tapscript,
+        ..ExecuteOpts::default()
+    };
+    assert!(run(&ms, &witness, &opts));
+    // OP_CHECKSIGADD is a bad opcode outside tapscript.
+    assert!(!run(&ms, &witness, &ExecuteOpts::default()));
+    assert_eq!(
+        ms.satisfy(&Satisfier::default()),
+        Err(MiniscriptError::Unsatisfiable)
+    );
+}
+
+#[cfg(feature = "test-utils")]
+mod signed {
+    use super::*;
+    use mini_bitcoin_script::builder;
+    use mini_bitcoin_script::flags::VerifyFlags;
+    use mini_bitcoin_script::sighash::segwit_v0_sighash;
+    use mini_bitcoin_script::sign::sign_ecdsa;
+    use mini_bitcoin_script::test_utils::TestKey;
+    use mini_bitcoin_script::transaction::{OutPoint, Transaction, TxIn, TxOut};
+
+    const AMOUNT: u64 = 50_000;
+
+    fn spending_tx(sequence: u32) -> Transaction {
+        let mut input = TxIn::new(OutPoint {
+            txid: [0x17; 32],
+            vout: 1,
+        });
+        input.sequence = sequence;
+        Transaction {
+            version: 2,
+            inputs: vec![input],
+            outputs: vec![TxOut {
+                value: 40_000,
+                script_pubkey: builder::p2wpkh(&[0x99; 20]),
+            }],
+            lock_time: 0,
+        }
+    }
+
+    #[test]
+    fn p2wsh_spend_verifies_in_engine() {
+        let primary = TestKey::from_seed(b"miniscript primary");
+        let recovery = TestKey::from_seed(b"miniscript recovery");
+        let policy = format!(
+            "or_d(pk({}),and_v(v:pk({}),older(144)))",
+            encode_hex(&primary.pubkey_bytes()),
+            encode_hex(&recovery.pubkey_bytes())
+        );
+        let ms = parse(&policy, Context::Wsh);
+        let witness_script = ms.to_script();
+
+        let tx = spending_tx(144);
+        let sighash = segwit_v0_sighash(&tx, 0, witness_script.as_bytes(), AMOUNT, 0x01).unwrap();
+        let opts = ExecuteOpts {
+            sighash: Some(sighash),
+            flags: VerifyFlags::DERSIG
+                | VerifyFlags::LOW_S
+                | VerifyFlags::STRICTENC
+                | VerifyFlags::NULLFAIL,
+            sequence: Some(144),
+            ..ExecuteOpts::default()
+        };
+
+        let mut satisfier = Satisfier {
+            sequence: Some(144),
+            ..Satisfier::default()
+        };
+        satisfier.signatures.insert(
+            recovery.pubkey_bytes().to_vec(),
+            sign_ecdsa(&recovery.secret_key, &sighash, 0x01),
+        );
+        let witness = ms.satisfy(&satisfier).unwrap();
+        assert!(run(&ms, &witness, &opts));
+
+        // A signature for another transaction fails.
+        let other = segwit_v0_sighash(
+            &spending_tx(145),
+            0,
+            witness_script.as_bytes(),
+            AMOUNT,
+            0x01,
+        )
+        .unwrap();
+        satisfier.signatures.insert(
+            recovery.pubkey_bytes().to_vec(),
+            sign_ecdsa(&recovery.secret_key, &other, 0x01),
+        );
+        let witness = ms.satisfy(&satisfier).unwrap();
+        assert!(!run(&ms, &witness, &opts));
+    }
+}
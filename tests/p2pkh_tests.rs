@@ -51,7 +51,7 @@ fn p2pkh_wrong_pubkey_hash() {
     let script_pubkey = build_script_pubkey(&wrong_hash);
 
     let err = validate_p2pkh(&script_sig, &script_pubkey).unwrap_err();
-    assert_eq!(err, ScriptError::VerifyFailed);
+    assert_eq!(err, ScriptError::EqualVerifyFailed);
 }
 
 #[test]
@@ -59,7 +59,7 @@ fn p2pkh_empty_scriptsig() {
     let pubkey_hash = [0x00; 20];
     let script_pubkey = build_script_pubkey(&pubkey_hash);
 
-    let err = validate_p2pkh(&[], &script_pubkey).unwrap_err();
+    let err = validate_p2pkh([], &script_pubkey).unwrap_err();
     assert_eq!(err, ScriptError::StackUnderflow);
 }
 
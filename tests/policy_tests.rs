@@ -0,0 +1,305 @@
+use mini_bitcoin_script::builder::{self, ScriptBuilder};
+use mini_bitcoin_script::hash;
+use mini_bitcoin_script::policy::{
+    check_inputs, check_transaction, dust_threshold, PolicyViolation,
+};
+use mini_bitcoin_script::transaction::{OutPoint, Transaction, TxIn, TxOut};
+
+fn tx(outputs: Vec<TxOut>) -> Transaction {
+    Transaction {
+        version: 2,
+        inputs: vec![TxIn::new(OutPoint {
+            txid: [0x42; 32],
+            vout: 0,
+        })],
+        outputs,
+        lock_time: 0,
+    }
+}
+
+fn output(value: u64, script_pubkey: Vec<u8>) -> TxOut {
+    TxOut {
+        value,
+        script_pubkey,
+    }
+}
+
+fn op_return(data_len: usize) -> Vec<u8> {
+    let mut script = vec![0x6a];
+    script.extend(
+        ScriptBuilder::new()
+            .push_slice(&vec![0xee; data_len])
+            .into_bytes(),
+    );
+    script
+}
+
+#[test]
+fn standard_transaction_passes() {
+    let tx = tx(vec![
+        output(10_000, builder::p2wpkh(&[1; 20])),
+        output(0, op_return(80)),
+    ]);
+    assert_eq!(check_transaction(&tx), vec![]);
+}
+
+#[test]
+fn version_and_script_sig() {
+    let mut tx = tx(vec![output(10_000, builder::p2tr(&[1; 32]))]);
+    tx.version = 4;
+    tx.inputs[0].script_sig = vec![0x51, 0x76];
+    let violations = check_transaction(&tx);
+    assert_eq!(
+        violations,
+        vec![
+            PolicyViolation::Version(4),
+            PolicyViolation::ScriptSigNotPushOnly { input: 0 },
+        ]
+    );
+    assert_eq!(violations[1].reason(), "scriptsig-not-pushonly");
+
+    tx.version = 1;
+    tx.inputs[0].script_sig = ScriptBuilder::new().push_slice(&[0; 1_648]).into_bytes();
+    assert_eq!(
+        check_transaction(&tx),
+        vec![PolicyViolation::ScriptSigSize {
+            input: 0,
+            size: 1_651
+        }]
+    );
+}
+
+#[test]
+fn output_rules() {
+    let key = [0x02; 33];
+    let bare_4 = builder::multisig(1, &[&key, &key, &key, &key]).unwrap();
+    let bare_3 = builder::multisig(1, &[&key, &key, &key]).unwrap();
+    let tx = tx(vec![
+        output(10_000, vec![0x51]),
+        output(10_000, bare_4),
+        output(10_000, bare_3),
+        output(0, op_return(81)),
+        output(0, op_return(0)),
+    ]);
+    let violations = check_transaction(&tx);
+    assert_eq!(
+        violations,
+        vec![
+            PolicyViolation::NonStandardOutput { output: 0 },
+            PolicyViolation::BareMultisig { output: 1, keys: 4 },
+            PolicyViolation::OpReturnSize {
+                output: 3,
+                size: 84
+            },
+            PolicyViolation::MultipleOpReturn,
+        ]
+    );
+    assert_eq!(violations[0].reason(), "scriptpubkey");
+    assert_eq!(violations[1].reason(), "scriptpubkey");
+}
+
+#[test]
+fn dust() {
+    assert_eq!(dust_threshold(&builder::p2sh(&[0; 20])), 540);
+    assert_eq!(dust_threshold(&builder::p2wsh(&[0; 32])), 330);
+    assert_eq!(dust_threshold(&op_return(4)), 0);
+
+    let tx = tx(vec![
+        output(545, builder::p2pkh(&[1; 20])),
+        output(546, builder::p2pkh(&[1; 20])),
+        output(293, builder::p2wpkh(&[1; 20])),
+    ]);
+    assert_eq!(
+        check_transaction(&tx),
+        vec![
+            PolicyViolation::Dust {
+                output: 0,
+                value: 545,
+                threshold: 546
+            },
+            PolicyViolation::Dust {
+                output: 2,
+                value: 293,
+                threshold: 294
+            },
+        ]
+    );
+}
+
+#[test]
+fn weight_limit() {
+    let tx = tx(vec![output(10_000, op_return(80)); 5_000]);
+    let violations = check_transaction(&tx);
+    assert!(matches!(violations[0], PolicyViolation::TxWeight(w) if w > 400_000));
+}
+
+#[test]
+fn spent_output_types() {
+    let tx = Transaction {
+        inputs: vec![TxIn::new(OutPoint::default()); 2],
+        ..tx(vec![])
+    };
+    let prevouts = [
+        output(1_000, vec![0x51]),
+        output(1_000, vec![0x52, 0x02, 0xaa, 0xbb]),
+    ];
+    assert_eq!(
+        check_inputs(&tx, &prevouts),
+        vec![
+            PolicyViolation::NonStandardInput { input: 0 },
+            PolicyViolation::NonStandardInput { input: 1 },
+        ]
+    );
+    assert_eq!(
+        check_inputs(&tx, &prevouts[..1]),
+        vec![PolicyViolation::PrevoutCount {
+            expected: 2,
+            actual: 1
+        }]
+    );
+}
+
+#[test]
+fn p2sh_sigop_limit() {
+    // OP_CHECKSIG sixteen times.
+    let redeem = vec![0xac; 16];
+    let mut tx = tx(vec![]);
+    tx.inputs[0].script_sig = ScriptBuilder::new().push_slice(&redeem).into_bytes();
+    let prevout = output(1_000, builder::p2sh(&hash::hash160(&redeem)));
+    assert_eq!(
+        check_inputs(&tx, &[prevout]),
+        vec![PolicyViolation::P2shSigops {
+            input: 0,
+            sigops: 16
+        }]
+    );
+}
+
+#[test]
+fn p2wsh_witness_limits() {
+    let witness_script = vec![0x51; 3_601];
+    let prevout = output(1_000, builder::p2wsh(&hash::sha256(&witness_script)));
+    let mut tx = tx(vec![]);
+    let mut witness = vec![vec![0; 81]];
+    witness.extend(vec![vec![]; 100]);
+    witness.push(witness_script);
+    tx.inputs[0].witness = witness;
+
+    let violations = check_inputs(&tx, std::slice::from_ref(&prevout));
+    assert_eq!(
+        violations,
+        vec![
+            PolicyViolation::WitnessScriptSize {
+                input: 0,
+                size: 3_601
+            },
+            PolicyViolation::WitnessStackItems {
+                input: 0,
+                count: 101
+            },
+            PolicyViolation::WitnessItemSize {
+                input: 0,
+                item: 0,
+                size: 81
+            },
+        ]
+    );
+    assert!(violations
+        .iter()
+        .all(|v| v.reason() == "bad-witness-nonstandard"));
+
+    // The same witness nested in P2SH is checked the same way.
+    let program = prevout.script_pubkey;
+    tx.inputs[0].script_sig = ScriptBuilder::new().push_slice(&program).into_bytes();
+    let nested = output(1_000, builder::p2sh(&hash::hash160(&program)));
+    assert_eq!(check_inputs(&tx, &[nested]).len(), 3);
+}
+
+#[test]
+fn taproot_witness() {
+    let prevout = output(1_000, builder::p2tr(&[1; 32]));
+    let mut tx = tx(vec![]);
+
+    // Key path spend with an annex.
+    tx.inputs[0].witness = vec![vec![0; 64], vec![0x50, 0x00]];
+    assert_eq!(
+        check_inputs(&tx, std::slice::from_ref(&prevout)),
+        vec![PolicyViolation::Annex { input: 0 }]
+    );
+
+    // Tapscript spend with an oversized stack item.
+    tx.inputs[0].witness = vec![vec![0; 81], vec![0x51], vec![0xc0; 33]];
+    assert_eq!(
+        check_inputs(&tx, std::slice::from_ref(&prevout)),
+        vec![PolicyViolation::WitnessItemSize {
+            input: 0,
+            item: 0,
+            size: 81
+        }]
+    );
+
+    // Plain key path spend.
+    tx.inputs[0].witness = vec![vec![0; 64]];
+    assert_eq!(check_inputs(&tx, &[prevout]), vec![]);
+}
+
+#[test]
+fn pay_to_anchor() {
+    let anchor = output(240, vec![0x51, 0x02, 0x4e, 0x73]);
+    assert_eq!(dust_threshold(&anchor.script_pubkey), 240);
+    assert_eq!(check_transaction(&tx(vec![anchor.clone()])), vec![]);
+
+    let mut tx = tx(vec![]);
+    assert_eq!(check_inputs(&tx, std::slice::from_ref(&anchor)), vec![]);
+
+    tx.inputs[0].witness = vec![vec![]];
+    let violations = check_inputs(&tx, &[anchor]);
+    assert_eq!(
+        violations,
+        vec![PolicyViolation::AnchorWitness { input: 0 }]
+    );
+    assert_eq!(violations[0].reason(), "bad-witness-nonstandard");
+
+    // Other version 1 programs stay non-standard to spend.
+    tx.inputs[0].witness.clear();
+    assert_eq!(
+        check_inputs(&tx, &[output(240, vec![0x51, 0x02, 0x4e, 0x74])]),
+        vec![PolicyViolation::NonStandardInput { input: 0 }]
+    );
+}
+
+#[test]
+fn witness_on_legacy_output() {
+    let mut tx = tx(vec![]);
+    tx.inputs[0].witness = vec![vec![1]];
+    assert_eq!(
+        check_inputs(&tx, &[output(1_000, builder::p2pkh(&[1; 20]))]),
+        vec![PolicyViolation::UnexpectedWitness { input: 0 }]
+    );
+}
+
+#[cfg(feature = "test-utils")]
+#[test]
+fn fixtures_are_standard() {
+    use mini_bitcoin_script::test_utils::{fixture, ScriptType};
+
+    for script_type in [
+        ScriptType::P2pkh,
+        ScriptType::P2wpkh,
+        ScriptType::P2shMultisig,
+        ScriptType::P2trKeyPath,
+    ] {
+        let fx = fixture(script_type, b"policy");
+        assert_eq!(
+            check_transaction(&fx.spending_tx),
+            vec![],
+            "{script_type:?}"
+        );
+        let prevouts = [fx.prevout().clone()];
+        assert_eq!(
+            check_inputs(&fx.spending_tx, &prevouts),
+            vec![],
+            "{script_type:?}"
+        );
+    }
+}
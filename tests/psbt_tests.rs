@@ -0,0 +1,227 @@
+use mini_bitcoin_script::builder;
+use mini_bitcoin_script::psbt::{Psbt, PsbtError, PsbtInput};
+use mini_bitcoin_script::transaction::{OutPoint, Transaction, TxIn, TxOut};
+
+// From the BIP174 test vectors: one P2PKH input, with its non-witness
+// UTXO, and two outputs.
+const BIP174_P2PKH: &str = "cHNidP8BAHUCAAAAAaGiV2D5BmAFBNrWI4X3oTVmCWkmK7+BKkFzWM9zVAfzAQAAAAD+////AtPf9QUAAAAAGXapFNDFmQPFusKGh2DpD9UhpGZap2UgiKwA4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAAAQD9pQEBAAAAAAECiaPHHqtNIOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89p95PUzSe7LmF/////4b4qkOnHf8USIk6UwpyN+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkCxDVZQ6vLNL1TU/////8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cluiHv1irHU6m80GfWx6ajnQWHAkcwRAIgJxK+IuAnDzlPVoMR3HyppolwuAJf3TskAinwf4pfOiQCIAGLONfc0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/jnF6efkE/IQUCSDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0CIGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAAAAAA";
+
+fn unsigned_tx() -> Transaction {
+    Transaction {
+        version: 2,
+        inputs: vec![TxIn::new(OutPoint {
+            txid: [0x42; 32],
+            vout: 0,
+        })],
+        outputs: vec![TxOut {
+            value: 9_000,
+            script_pubkey: builder::p2wpkh(&[0x33; 20]),
+        }],
+        lock_time: 0,
+    }
+}
+
+#[test]
+fn bip174_vector_roundtrips() {
+    let psbt: Psbt = BIP174_P2PKH.parse().unwrap();
+    assert_eq!(psbt.version, 0);
+    assert_eq!(psbt.inputs.len(), 1);
+    assert_eq!(psbt.outputs.len(), 2);
+    assert_eq!(psbt.inputs[0].sequence, 0xffff_fffe);
+    assert!(psbt.inputs[0].non_witness_utxo.is_some());
+    assert_eq!(psbt.to_string(), BIP174_P2PKH);
+
+    // The same PSBT as version 2 keeps the transaction.
+    let v2 = Psbt {
+        version: 2,
+        ..psbt.clone()
+    };
+    let reparsed: Psbt = v2.to_string().parse().unwrap();
+    assert_eq!(reparsed.unsigned_tx(), psbt.unsigned_tx());
+}
+
+#[test]
+fn rejects_malformed_input() {
+    assert_eq!("cHNidP8".parse::<Psbt>(), Err(PsbtError::Base64));
+    assert_eq!(
+        Psbt::deserialize(b"psbx\xff\x00"),
+        Err(PsbtError::InvalidMagic)
+    );
+    let mut bytes = Psbt::from_unsigned_tx(&unsigned_tx()).unwrap().serialize();
+    bytes.pop();
+    assert!(matches!(
+        Psbt::deserialize(&bytes),
+        Err(PsbtError::Decode(_))
+    ));
+
+    let mut signed = unsigned_tx();
+    signed.inputs[0].script_sig = vec![0x51];
+    assert_eq!(
+        Psbt::from_unsigned_tx(&signed),
+        Err(PsbtError::UnsignedTxNotEmpty)
+    );
+}
+
+#[test]
+fn finalizer_needs_utxo_and_signatures() {
+    let mut psbt = Psbt::from_unsigned_tx(&unsigned_tx()).unwrap();
+    assert_eq!(psbt.finalize(), Err(PsbtError::MissingUtxo { index: 0 }));
+    assert_eq!(
+        psbt.extract_tx(Default::default()),
+        Err(PsbtError::NotFinalized { index: 0 })
+    );
+
+    psbt.inputs[0].witness_utxo = Some(TxOut {
+        value: 10_000,
+        script_pubkey: builder::p2wpkh(&[0x44; 20]),
+    });
+    assert_eq!(psbt.finalize(), Err(PsbtError::CannotFinalize { index: 0 }));
+
+    // A non-witness UTXO must be the transaction the input spends.
+    let mut input = PsbtInput::new(psbt.inputs[0].previous_output);
+    input.non_witness_utxo = Some(unsigned_tx());
+    psbt.inputs[0] = input;
+    assert_eq!(
+        psbt.spent_output(0),
+        Err(PsbtError::UtxoMismatch { index: 0 })
+    );
+    assert_eq!(
+        psbt.spent_output(1),
+        Err(PsbtError::InputIndex { index: 1 })
+    );
+}
+
+#[cfg(feature = "test-utils")]
+mod signed {
+    use super::*;
+    use mini_bitcoin_script::flags::VerifyFlags;
+    use mini_bitcoin_script::sighash::legacy_sighash;
+    use mini_bitcoin_script::sign::sign_ecdsa;
+    use mini_bitcoin_script::test_utils::{fixture, Fixture, ScriptType};
+    use mini_bitcoin_script::token::Token;
+    use mini_bitcoin_script::tokenizer::parse_script;
+    use mini_bitcoin_script::verify::VerifyError;
+
+    const FLAGS: VerifyFlags = VerifyFlags::DERSIG;
+
+    /// The fixture's spend as a PSBT holding only what a signer would
+    /// produce: the UTXO, scripts and partial signatures.
+    fn unfinalized(fx: &Fixture) -> Psbt {
+        let mut tx = fx.spending_tx.clone();
+        tx.inputs[0].script_sig.clear();
+        tx.inputs[0].witness.clear();
+        let mut psbt = Psbt::from_unsigned_tx(&tx).unwrap();
+        let input = &mut psbt.inputs[0];
+
+        let pushes: Vec<Vec<u8>> = parse_script(fx.script_sig())
+            .unwrap()
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::PushData(data) if !data.is_empty() => Some(data),
+                _ => None,
+            })
+            .collect();
+        match fx.script_type {
+            ScriptType::P2pkh => {
+                input.non_witness_utxo = Some(fx.funding_tx.clone());
+                input
+                    .partial_sigs
+                    .insert(pushes[1].clone(), pushes[0].clone());
+            }
+            ScriptType::P2wpkh => {
+                input.witness_utxo = Some(fx.prevout().clone());
+                let witness = fx.witness();
+                input
+                    .partial_sigs
+                    .insert(witness[1].clone(), witness[0].clone());
+            }
+            ScriptType::P2shMultisig => {
+                input.non_witness_utxo = Some(fx.funding_tx.clone());
+                input.redeem_script = fx.redeem_script.clone();
+                for (key, sig) in fx.keys.iter().zip(&pushes[..2]) {
+                    input
+                        .partial_sigs
+                        .insert(key.pubkey_bytes().to_vec(), sig.clone());
+                }
+            }
+            ScriptType::P2trKeyPath => {
+                input.witness_utxo = Some(fx.prevout().clone());
+                input.tap_key_sig = Some(fx.witness()[0].clone());
+            }
+        }
+        psbt
+    }
+
+    #[test]
+    fn finalize_and_extract_standard_types() {
+        for script_type in [
+            ScriptType::P2pkh,
+            ScriptType::P2wpkh,
+            ScriptType::P2shMultisig,
+            ScriptType::P2trKeyPath,
+        ] {
+            let fx = fixture(script_type, b"psbt");
+            let mut psbt = unfinalized(&fx);
+            // Signers pass the PSBT around as text.
+            psbt = psbt.to_string().parse().unwrap();
+            psbt.finalize().unwrap();
+
+            let input = &psbt.inputs[0];
+            assert!(input.partial_sigs.is_empty() && input.redeem_script.is_none());
+            assert_eq!(
+                psbt.extract_tx(FLAGS).as_ref(),
+                Ok(&fx.spending_tx),
+                "{script_type:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn version_2_extracts_the_same_transaction() {
+        let fx = fixture(ScriptType::P2wpkh, b"psbt v2");
+        let mut psbt = Psbt {
+            version: 2,
+            ..unfinalized(&fx)
+        };
+        psbt = Psbt::deserialize(&psbt.serialize()).unwrap();
+        psbt.finalize().unwrap();
+        assert_eq!(psbt.extract_tx(FLAGS), Ok(fx.spending_tx));
+    }
+
+    #[test]
+    fn extractor_rejects_invalid_signatures() {
+        let fx = fixture(ScriptType::P2wpkh, b"psbt tampered");
+        let mut psbt = unfinalized(&fx);
+        psbt.finalize().unwrap();
+        psbt.outputs[0].amount -= 1;
+        assert_eq!(
+            psbt.extract_tx(FLAGS),
+            Err(PsbtError::Verify {
+                index: 0,
+                error: VerifyError::Script(mini_bitcoin_script::error::ScriptError::ScriptFailed),
+            })
+        );
+
+        // A multisig input needs every required signature.
+        let fx = fixture(ScriptType::P2shMultisig, b"psbt tampered");
+        let mut psbt = unfinalized(&fx);
+        let first = psbt.inputs[0].partial_sigs.keys().next().cloned().unwrap();
+        psbt.inputs[0].partial_sigs.remove(&first);
+        assert_eq!(psbt.finalize(), Err(PsbtError::CannotFinalize { index: 0 }));
+    }
+
+    #[test]
+    fn extracts_mixed_sighash_types() {
+        let fx = fixture(ScriptType::P2shMultisig, b"psbt mixed");
+        let mut psbt = unfinalized(&fx);
+        let redeem_script = fx.redeem_script.clone().unwrap();
+        let sighash = legacy_sighash(&fx.spending_tx, 0, &redeem_script, 0x81).unwrap();
+        let key = &fx.keys[1];
+        psbt.inputs[0].partial_sigs.insert(
+            key.pubkey_bytes().to_vec(),
+            sign_ecdsa(&key.secret_key, &sighash, 0x81),
+        );
+        psbt.finalize().unwrap();
+        assert!(psbt.extract_tx(FLAGS).is_ok());
+    }
+}
@@ -0,0 +1,115 @@
+use mini_bitcoin_script::builder::{self, ScriptBuilder};
+use mini_bitcoin_script::classify::OutputType;
+use mini_bitcoin_script::error::ScriptError;
+use mini_bitcoin_script::hash;
+use mini_bitcoin_script::opcode::Opcode;
+use mini_bitcoin_script::script::{validate_p2pkh, Instruction, Script, ScriptBuf};
+
+#[test]
+fn template_checks() {
+    let p2pkh = ScriptBuf::from_bytes(builder::p2pkh(&[1; 20]));
+    assert!(p2pkh.is_p2pkh());
+    assert!(!p2pkh.is_p2sh());
+    assert_eq!(p2pkh.output_type(), OutputType::P2pkh);
+
+    assert!(Script::from_bytes(&builder::p2sh(&[1; 20])).is_p2sh());
+    assert!(Script::from_bytes(&builder::p2wpkh(&[1; 20])).is_p2wpkh());
+    assert!(Script::from_bytes(&builder::p2wsh(&[1; 32])).is_p2wsh());
+
+    let p2tr = builder::p2tr(&[7; 32]);
+    let p2tr = Script::from_bytes(&p2tr);
+    assert!(p2tr.is_p2tr());
+    assert!(p2tr.is_witness_program());
+    assert_eq!(p2tr.witness_program(), Some((1, &[7; 32][..])));
+
+    let multisig = builder::multisig(1, &[&[0x02; 33], &[0x03; 33]]).unwrap();
+    let multisig = Script::from_bytes(&multisig);
+    assert!(multisig.is_multisig());
+    assert_eq!(multisig.legacy_sigop_count(), 20);
+    assert_eq!(multisig.accurate_sigop_count(), 2);
+
+    assert!(Script::from_bytes(&[0x6a, 0x01, 0xff]).is_op_return());
+    assert!(Script::from_bytes(&[0x00, 0x51, 0x01, 0xff]).is_push_only());
+    assert!(!p2pkh.is_push_only());
+}
+
+#[test]
+fn instructions() {
+    let script = ScriptBuilder::new()
+        .push_opcode(Opcode::OpDup)
+        .push_slice(&[0xaa; 80])
+        .push_int(0)
+        .into_script();
+    let instructions: Vec<_> = script.instructions().map(Result::unwrap).collect();
+    assert_eq!(
+        instructions,
+        vec![
+            Instruction::Op(0x76),
+            Instruction::PushBytes(&[0xaa; 80]),
+            Instruction::Op(0x00),
+        ]
+    );
+    assert_eq!(instructions[0].opcode(), Some(Opcode::OpDup));
+    assert_eq!(instructions[1].opcode(), None);
+    assert_eq!(script.tokens().unwrap().len(), 3);
+}
+
+#[test]
+fn asm_round_trip() {
+    let script = ScriptBuf::from_bytes(builder::p2pkh(&[0xab; 20]));
+    let asm = script.to_string();
+    assert_eq!(
+        asm,
+        "OP_DUP OP_HASH160 <abababababababababababababababababababab> OP_EQUALVERIFY OP_CHECKSIG"
+    );
+    assert_eq!(asm.parse::<ScriptBuf>().unwrap(), script);
+
+    // The OP_ prefix is optional and `0x` words are spliced in raw.
+    let parsed: ScriptBuf = "2 DUP 0xae".parse().unwrap();
+    assert_eq!(parsed.as_bytes(), &[0x52, 0x76, 0xae]);
+}
+
+#[test]
+fn hex_round_trip() {
+    let script: ScriptBuf = "76a914".parse().unwrap();
+    assert_eq!(script.as_bytes(), &[0x76, 0xa9, 0x14]);
+    assert_eq!(script.to_hex(), "76a914");
+    assert_eq!(ScriptBuf::from_hex("76a9").unwrap().len(), 2);
+    assert_eq!(ScriptBuf::from_hex("7"), Err(ScriptError::InvalidHex));
+    assert!("OP_NOPE".parse::<ScriptBuf>().is_err());
+}
+
+#[test]
+fn hex_digit_words_fall_back_to_asm() {
+    let parse = |s: &str| s.parse::<ScriptBuf>().map(|script| script.into_bytes());
+    assert_eq!(parse("ADD"), Ok(vec![0x93]));
+    assert_eq!(parse("0"), Ok(vec![0x00]));
+    assert_eq!(parse("1"), Ok(vec![0x51]));
+    assert_eq!(parse("1 1 ADD"), Ok(vec![0x51, 0x51, 0x93]));
+    // Even-length hex digits decode as hex first.
+    assert_eq!(parse("10"), Ok(vec![0x10]));
+    assert_eq!(parse("abc"), Err(ScriptError::InvalidHex));
+}
+
+#[test]
+fn script_hashes_match_outputs() {
+    let redeem = ScriptBuf::from_bytes(builder::multisig(1, &[&[0x02; 33]]).unwrap());
+    assert_eq!(redeem.script_hash(), hash::hash160(redeem.as_bytes()));
+    assert!(Script::from_bytes(&builder::p2sh(&redeem.script_hash())).is_p2sh());
+    assert_eq!(redeem.wscript_hash(), hash::sha256(redeem.as_bytes()));
+}
+
+#[test]
+fn validate_accepts_script_types() {
+    let pubkey = b"fake-public-key-data";
+    let script_pubkey = ScriptBuf::from_bytes(builder::p2pkh(&hash::hash160(pubkey)));
+    let script_sig = ScriptBuilder::new()
+        .push_slice(b"fake-signature")
+        .push_slice(pubkey)
+        .into_script();
+    assert_eq!(validate_p2pkh(&script_sig, &script_pubkey), Ok(true));
+    assert_eq!(
+        validate_p2pkh(script_sig.as_script(), script_pubkey.as_bytes()),
+        Ok(true)
+    );
+}
@@ -0,0 +1,191 @@
+#![cfg(feature = "serde")]
+
+use mini_bitcoin_script::analysis::analyze;
+use mini_bitcoin_script::block::{BlockValidation, TxFailure};
+use mini_bitcoin_script::error::ScriptError;
+use mini_bitcoin_script::opcode::Opcode;
+use mini_bitcoin_script::sighash::SighashError;
+use mini_bitcoin_script::token::Token;
+use mini_bitcoin_script::tokenizer::parse_script_hex;
+use mini_bitcoin_script::transaction::OutPoint;
+use mini_bitcoin_script::verify::{TxVerification, TxVerifyError, VerifyError};
+
+fn roundtrip<T>(value: &T) -> T
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let json = serde_json::to_string(value).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+// ---------------------------------------------------------------------------
+// Opcode
+// ---------------------------------------------------------------------------
+
+#[test]
+fn opcode_serializes_as_name() {
+    let json = serde_json::to_string(&Opcode::OpCheckSig).unwrap();
+    assert_eq!(json, r#""OP_CHECKSIG""#);
+}
+
+#[test]
+fn opcode_roundtrip_all() {
+    for byte in 0..=u8::MAX {
+        if let Some(opcode) = Opcode::from_byte(byte) {
+            assert_eq!(roundtrip(&opcode), opcode);
+        }
+    }
+}
+
+#[test]
+fn opcode_unknown_name_rejected() {
+    assert!(serde_json::from_str::<Opcode>(r#""OP_ROT""#).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// Token
+// ---------------------------------------------------------------------------
+
+#[test]
+fn token_push_serializes_as_hex() {
+    let json = serde_json::to_string(&Token::PushData(vec![0x89, 0xab])).unwrap();
+    assert_eq!(json, r#""89ab""#);
+}
+
+#[test]
+fn token_op_serializes_as_name() {
+    let json = serde_json::to_string(&Token::Op(Opcode::OpDup)).unwrap();
+    assert_eq!(json, r#""OP_DUP""#);
+}
+
+#[test]
+fn token_script_roundtrip() {
+    let hex = "76a914".to_string() + &"ab".repeat(20) + "88ac";
+    let tokens = parse_script_hex(&hex).unwrap();
+    let json = serde_json::to_string(&tokens).unwrap();
+    let recovered: Vec<Token> = serde_json::from_str(&json).unwrap();
+    assert_eq!(recovered, tokens);
+}
+
+#[test]
+fn token_empty_push_roundtrip() {
+    let token = Token::PushData(vec![]);
+    assert_eq!(serde_json::to_string(&token).unwrap(), r#""""#);
+    assert_eq!(roundtrip(&token), token);
+}
+
+#[test]
+fn token_invalid_hex_rejected() {
+    assert!(serde_json::from_str::<Token>(r#""abc""#).is_err());
+    assert!(serde_json::from_str::<Token>(r#""OP_BOGUS""#).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// ScriptError
+// ---------------------------------------------------------------------------
+
+#[test]
+fn error_unit_variant_tagged() {
+    let json = serde_json::to_string(&ScriptError::StackUnderflow).unwrap();
+    assert_eq!(json, r#"{"error":"StackUnderflow"}"#);
+}
+
+#[test]
+fn error_payload_variant_tagged() {
+    let json = serde_json::to_string(&ScriptError::UnsupportedOpcode(0xb0)).unwrap();
+    assert_eq!(json, r#"{"error":"UnsupportedOpcode","payload":176}"#);
+}
+
+#[test]
+fn error_roundtrip() {
+    let errors = [
+        ScriptError::StackUnderflow,
+        ScriptError::UnexpectedEndOfScript,
+        ScriptError::InvalidPushData,
+        ScriptError::UnsupportedOpcode(0xff),
+        ScriptError::VerifyFailed,
+        ScriptError::ScriptFailed,
+        ScriptError::OpReturnEncountered,
+        ScriptError::UnbalancedConditional,
+        ScriptError::InvalidHex,
+        ScriptError::InvalidAsm,
+        ScriptError::EqualVerifyFailed,
+        ScriptError::DisabledOpcode(0x7e),
+        ScriptError::SigHighS,
+        ScriptError::WitnessMalleatedP2sh,
+    ];
+    for err in &errors {
+        assert_eq!(&roundtrip(err), err);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Reports
+// ---------------------------------------------------------------------------
+
+#[test]
+fn analysis_serializes() {
+    let tokens = parse_script_hex("6a51").unwrap();
+    let json = serde_json::to_value(analyze(&tokens)).unwrap();
+    assert_eq!(json["findings"][0]["kind"], "always_fails");
+    assert_eq!(json["findings"][1]["kind"], "unreachable");
+    assert_eq!(json["findings"][1]["index"], 1);
+    assert_eq!(json["truncated"], false);
+}
+
+#[test]
+fn tx_verification_serializes() {
+    let report = TxVerification {
+        fee: 500,
+        inputs: vec![
+            Ok(()),
+            Err(VerifyError::Script(ScriptError::ScriptFailed)),
+            Err(VerifyError::Sighash(SighashError::InputIndex {
+                index: 2,
+                inputs: 1,
+            })),
+        ],
+    };
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "fee": 500,
+            "inputs": [
+                {"Ok": null},
+                {"Err": {"script": {"error": "ScriptFailed"}}},
+                {"Err": {"sighash": {"input_index": {"index": 2, "inputs": 1}}}},
+            ],
+        })
+    );
+}
+
+#[test]
+fn block_validation_serializes_txids_as_hex() {
+    let mut txid = [0u8; 32];
+    txid[0] = 0x01;
+    let report = BlockValidation {
+        fees: 0,
+        failures: vec![(
+            1,
+            txid,
+            TxFailure::Tx(TxVerifyError::MissingUtxo {
+                index: 0,
+                outpoint: OutPoint { txid, vout: 3 },
+            }),
+        )],
+    };
+    let shown = format!("{}01", "00".repeat(31));
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "fees": 0,
+            "failures": [[
+                1,
+                shown,
+                {"tx": {"missing_utxo": {"index": 0, "outpoint": {"txid": shown, "vout": 3}}}},
+            ]],
+        })
+    );
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "test-utils")]
+
+use std::sync::Arc;
+use std::thread;
+
+use mini_bitcoin_script::engine::{execute_on_items, ExecuteOpts};
+use mini_bitcoin_script::flags::VerifyFlags;
+use mini_bitcoin_script::script::validate_p2pkh_with_opts;
+use mini_bitcoin_script::sigcache::SignatureCache;
+use mini_bitcoin_script::test_utils::{fixture, Fixture, ScriptType};
+use mini_bitcoin_script::token::Token;
+use mini_bitcoin_script::tokenizer::parse_script;
+
+fn cached_opts(fx: &Fixture, cache: &Arc<SignatureCache>) -> ExecuteOpts {
+    ExecuteOpts {
+        sig_cache: Some(Arc::clone(cache)),
+        ..fx.execute_opts()
+    }
+}
+
+fn validate(fx: &Fixture, opts: &ExecuteOpts) -> bool {
+    validate_p2pkh_with_opts(fx.script_sig(), &fx.prevout().script_pubkey, opts).unwrap()
+}
+
+#[test]
+fn second_validation_hits_cache() {
+    let fx = fixture(ScriptType::P2pkh, b"sigcache");
+    let cache = Arc::new(SignatureCache::default());
+    let opts = cached_opts(&fx, &cache);
+
+    assert!(validate(&fx, &opts));
+    assert_eq!((cache.hits(), cache.misses(), cache.len()), (0, 1, 1));
+    assert!(validate(&fx, &opts));
+    assert_eq!((cache.hits(), cache.misses()), (1, 1));
+}
+
+#[test]
+fn different_sighash_is_not_a_hit() {
+    let fx = fixture(ScriptType::P2pkh, b"sigcache");
+    let cache = Arc::new(SignatureCache::default());
+    assert!(validate(&fx, &cached_opts(&fx, &cache)));
+
+    let mut tampered = cached_opts(&fx, &cache);
+    tampered.sighash = Some([0x55; 32]);
+    tampered.flags = VerifyFlags::NONE;
+    assert!(!validate(&fx, &tampered));
+    assert!(!validate(&fx, &tampered));
+    assert_eq!((cache.hits(), cache.misses(), cache.len()), (0, 3, 1));
+}
+
+#[test]
+fn multisig_signatures_are_cached_individually() {
+    let fx = fixture(ScriptType::P2shMultisig, b"sigcache");
+    let cache = Arc::new(SignatureCache::new(8));
+    let opts = cached_opts(&fx, &cache);
+    let redeem_script = parse_script(fx.redeem_script.as_ref().unwrap()).unwrap();
+    // OP_0 <sig> <sig>, without the redeem script push.
+    let items: Vec<Vec<u8>> = parse_script(fx.script_sig()).unwrap()[..3]
+        .iter()
+        .map(|token| match token {
+            Token::PushData(data) => data.clone(),
+            Token::Op(_) => Vec::new(),
+        })
+        .collect();
+
+    for _ in 0..2 {
+        let mut stack = items.clone();
+        execute_on_items(&redeem_script, &mut stack, &opts).unwrap();
+        assert_eq!(stack, [vec![0x01]]);
+    }
+    assert_eq!((cache.hits(), cache.misses(), cache.len()), (2, 2, 2));
+}
+
+#[test]
+fn shared_between_threads() {
+    let fx = Arc::new(fixture(ScriptType::P2pkh, b"sigcache threads"));
+    let cache = Arc::new(SignatureCache::default());
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let fx = Arc::clone(&fx);
+            let opts = cached_opts(&fx, &cache);
+            thread::spawn(move || (0..10).all(|_| validate(&fx, &opts)))
+        })
+        .collect();
+    for handle in handles {
+        assert!(handle.join().unwrap());
+    }
+    assert_eq!(cache.hits() + cache.misses(), 40);
+    assert_eq!(cache.len(), 1);
+}
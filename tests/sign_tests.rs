@@ -0,0 +1,272 @@
+#![cfg(feature = "secp256k1")]
+
+use hex_literal::hex;
+use mini_bitcoin_script::builder;
+use mini_bitcoin_script::engine::ExecuteOpts;
+use mini_bitcoin_script::flags::VerifyFlags;
+use mini_bitcoin_script::hash;
+use mini_bitcoin_script::opcode::Opcode;
+use mini_bitcoin_script::script::validate_p2pkh_with_opts;
+use mini_bitcoin_script::secp256k1::{
+    ecdsa, schnorr, Message, PublicKey, Secp256k1, SecretKey, XOnlyPublicKey,
+};
+use mini_bitcoin_script::sighash;
+use mini_bitcoin_script::sign::{self, SignError};
+use mini_bitcoin_script::token::Token;
+use mini_bitcoin_script::tokenizer::parse_script;
+use mini_bitcoin_script::transaction::{OutPoint, Transaction, TxIn, TxOut};
+
+fn key(byte: u8) -> SecretKey {
+    SecretKey::from_slice(&[byte; 32]).unwrap()
+}
+
+fn pubkey(secret_key: &SecretKey) -> [u8; 33] {
+    PublicKey::from_secret_key(&Secp256k1::new(), secret_key).serialize()
+}
+
+/// A one-input, one-output transaction spending `txid:0`.
+fn spending_tx() -> Transaction {
+    Transaction {
+        version: 2,
+        inputs: vec![TxIn::new(OutPoint {
+            txid: [0x42; 32],
+            vout: 0,
+        })],
+        outputs: vec![TxOut {
+            value: 90_000,
+            script_pubkey: builder::p2wpkh(&[0x99; 20]),
+        }],
+        lock_time: 0,
+    }
+}
+
+fn all_flags() -> VerifyFlags {
+    VerifyFlags::DERSIG | VerifyFlags::LOW_S | VerifyFlags::STRICTENC | VerifyFlags::NULLFAIL
+}
+
+// ── P2PKH ──────────────────────────────────────────────────────────────
+
+#[test]
+fn p2pkh_spend_verifies_in_engine() {
+    let secret_key = key(0x01);
+    let prevout = TxOut {
+        value: 100_000,
+        script_pubkey: builder::p2pkh(&hash::hash160(&pubkey(&secret_key))),
+    };
+    let mut tx = spending_tx();
+    sign::sign_p2pkh(&mut tx, 0, &secret_key, &prevout, 0x01).unwrap();
+
+    let sighash = sighash::legacy_sighash(&tx, 0, &prevout.script_pubkey, 0x01).unwrap();
+    let opts = ExecuteOpts {
+        sighash: Some(sighash),
+        flags: all_flags(),
+        ..ExecuteOpts::default()
+    };
+    let script_sig = &tx.inputs[0].script_sig;
+    assert!(validate_p2pkh_with_opts(script_sig, &prevout.script_pubkey, &opts).unwrap());
+
+    // The signature commits to the outputs.
+    tx.outputs[0].value -= 1;
+    let tampered = sighash::legacy_sighash(&tx, 0, &prevout.script_pubkey, 0x01).unwrap();
+    let opts = ExecuteOpts {
+        sighash: Some(tampered),
+        flags: VerifyFlags::NONE,
+        ..ExecuteOpts::default()
+    };
+    assert!(!validate_p2pkh_with_opts(script_sig, &prevout.script_pubkey, &opts).unwrap());
+}
+
+#[test]
+fn p2pkh_anyonecanpay_verifies_in_engine() {
+    let secret_key = key(0x02);
+    let prevout = TxOut {
+        value: 100_000,
+        script_pubkey: builder::p2pkh(&hash::hash160(&pubkey(&secret_key))),
+    };
+    let mut tx = spending_tx();
+    sign::sign_p2pkh(&mut tx, 0, &secret_key, &prevout, 0x81).unwrap();
+
+    let sighash = sighash::legacy_sighash(&tx, 0, &prevout.script_pubkey, 0x81).unwrap();
+    let opts = ExecuteOpts {
+        sighash: Some(sighash),
+        flags: all_flags(),
+        ..ExecuteOpts::default()
+    };
+    let script_sig = &tx.inputs[0].script_sig;
+    assert!(validate_p2pkh_with_opts(script_sig, &prevout.script_pubkey, &opts).unwrap());
+}
+
+#[test]
+fn p2pkh_rejects_wrong_prevout() {
+    let prevout = TxOut {
+        value: 100_000,
+        script_pubkey: builder::p2pkh(&[0; 20]),
+    };
+    let mut tx = spending_tx();
+    let err = sign::sign_p2pkh(&mut tx, 0, &key(0x01), &prevout, 0x01).unwrap_err();
+    assert_eq!(err, SignError::PrevoutMismatch);
+    assert!(tx.inputs[0].script_sig.is_empty());
+}
+
+// ── P2WPKH ─────────────────────────────────────────────────────────────
+
+/// BIP143's native P2WPKH example: signing input 1 with its private key
+/// reproduces the published witness byte for byte (RFC 6979 nonces).
+#[test]
+fn p2wpkh_matches_bip143_example() {
+    let mut tx = Transaction {
+        version: 1,
+        inputs: vec![
+            TxIn {
+                sequence: 0xffff_ffee,
+                ..TxIn::new(OutPoint {
+                    txid: hex!("fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f"),
+                    vout: 0,
+                })
+            },
+            TxIn::new(OutPoint {
+                txid: hex!("ef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a"),
+                vout: 1,
+            }),
+        ],
+        outputs: vec![
+            TxOut {
+                value: 112_340_000,
+                script_pubkey: hex!("76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac").to_vec(),
+            },
+            TxOut {
+                value: 223_450_000,
+                script_pubkey: hex!("76a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac").to_vec(),
+            },
+        ],
+        lock_time: 17,
+    };
+    let secret_key = SecretKey::from_slice(&hex!(
+        "619c335025c7f4012e556c2a58b2506e30b8511b53ade95ea316fd8c3286feb9"
+    ))
+    .unwrap();
+    let prevout = TxOut {
+        value: 600_000_000,
+        script_pubkey: hex!("00141d0f172a0ecb48aee1be1f2687d2963ae33f71a1").to_vec(),
+    };
+
+    sign::sign_p2wpkh(&mut tx, 1, &secret_key, &prevout, 0x01).unwrap();
+
+    assert!(tx.inputs[1].script_sig.is_empty());
+    assert_eq!(
+        tx.inputs[1].witness,
+        vec![
+            hex!(
+                "304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a"
+                "0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee01"
+            )
+            .to_vec(),
+            hex!("025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357").to_vec(),
+        ]
+    );
+}
+
+// ── P2SH multisig ──────────────────────────────────────────────────────
+
+#[test]
+fn p2sh_multisig_signs_in_script_order() {
+    let keys = [key(0x11), key(0x12), key(0x13)];
+    let pubkeys: Vec<[u8; 33]> = keys.iter().map(pubkey).collect();
+    let redeem_script = builder::multisig(2, &[&pubkeys[0], &pubkeys[1], &pubkeys[2]]).unwrap();
+    let prevout = TxOut {
+        value: 100_000,
+        script_pubkey: builder::p2sh(&hash::hash160(&redeem_script)),
+    };
+
+    // Keys supplied out of order; the script wants key 0 before key 2.
+    let mut tx = spending_tx();
+    let signers = [keys[2], keys[0]];
+    sign::sign_p2sh_multisig(&mut tx, 0, &signers, &redeem_script, &prevout, 0x01).unwrap();
+
+    let tokens = parse_script(&tx.inputs[0].script_sig).unwrap();
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(tokens[0], Token::Op(Opcode::Op0));
+    assert_eq!(tokens[3], Token::PushData(redeem_script.clone()));
+
+    let sighash = sighash::legacy_sighash(&tx, 0, &redeem_script, 0x01).unwrap();
+    let secp = Secp256k1::verification_only();
+    let message = Message::from_digest(sighash);
+    for (token, signer) in tokens[1..3].iter().zip([&pubkeys[0], &pubkeys[2]]) {
+        let sig = match token {
+            Token::PushData(sig) => sig,
+            Token::Op(_) => panic!("expected a signature push"),
+        };
+        assert_eq!(*sig.last().unwrap(), 0x01);
+        let signature = ecdsa::Signature::from_der(&sig[..sig.len() - 1]).unwrap();
+        let public_key = PublicKey::from_slice(signer).unwrap();
+        secp.verify_ecdsa(&message, &signature, &public_key)
+            .unwrap();
+    }
+}
+
+#[test]
+fn p2sh_multisig_needs_enough_keys() {
+    let keys = [key(0x21), key(0x22)];
+    let pubkeys: Vec<[u8; 33]> = keys.iter().map(pubkey).collect();
+    let redeem_script = builder::multisig(2, &[&pubkeys[0], &pubkeys[1]]).unwrap();
+    let prevout = TxOut {
+        value: 100_000,
+        script_pubkey: builder::p2sh(&hash::hash160(&redeem_script)),
+    };
+    let mut tx = spending_tx();
+    let signers = [keys[0], key(0x23)];
+    let err =
+        sign::sign_p2sh_multisig(&mut tx, 0, &signers, &redeem_script, &prevout, 0x01).unwrap_err();
+    assert_eq!(
+        err,
+        SignError::NotEnoughKeys {
+            required: 2,
+            provided: 1
+        }
+    );
+}
+
+// ── P2TR key path ──────────────────────────────────────────────────────
+
+#[test]
+fn p2tr_key_path_signature_verifies() {
+    let secret_key = key(0x31);
+    let secp = Secp256k1::new();
+    let (internal_key, _) = PublicKey::from_secret_key(&secp, &secret_key).x_only_public_key();
+    let output_key = sign::taproot_output_key(&internal_key);
+    let prevouts = vec![TxOut {
+        value: 100_000,
+        script_pubkey: builder::p2tr(&output_key.serialize()),
+    }];
+
+    for hash_type in [0x00, 0x01, 0x83] {
+        let mut tx = spending_tx();
+        sign::sign_p2tr_key_path(&mut tx, 0, &secret_key, &prevouts, hash_type).unwrap();
+        let witness = &tx.inputs[0].witness;
+        assert_eq!(witness.len(), 1);
+
+        let sig = &witness[0];
+        let expected_len = if hash_type == 0x00 { 64 } else { 65 };
+        assert_eq!(sig.len(), expected_len);
+
+        let sighash = sighash::taproot_key_spend_sighash(&tx, 0, &prevouts, hash_type).unwrap();
+        let signature = schnorr::Signature::from_slice(&sig[..64]).unwrap();
+        secp.verify_schnorr(&signature, &Message::from_digest(sighash), &output_key)
+            .unwrap();
+    }
+}
+
+#[test]
+fn p2tr_rejects_untweaked_key() {
+    let secret_key = key(0x32);
+    let secp = Secp256k1::new();
+    let (internal_key, _): (XOnlyPublicKey, _) =
+        PublicKey::from_secret_key(&secp, &secret_key).x_only_public_key();
+    let prevouts = vec![TxOut {
+        value: 100_000,
+        script_pubkey: builder::p2tr(&internal_key.serialize()),
+    }];
+    let mut tx = spending_tx();
+    let err = sign::sign_p2tr_key_path(&mut tx, 0, &secret_key, &prevouts, 0x00).unwrap_err();
+    assert_eq!(err, SignError::PrevoutMismatch);
+}
@@ -0,0 +1,83 @@
+use mini_bitcoin_script::builder::{self, ScriptBuilder};
+use mini_bitcoin_script::hash;
+use mini_bitcoin_script::sigops::{
+    accurate_sigop_count, input_sigop_cost, legacy_sigop_count, p2sh_sigop_count,
+    witness_sigop_count,
+};
+
+fn multisig_2_of_3() -> Vec<u8> {
+    builder::multisig(2, &[&[0x02; 33], &[0x03; 33], &[0x02; 33]]).unwrap()
+}
+
+/// A P2SH scriptSig: OP_0, two dummy signatures, then the redeem script.
+fn p2sh_script_sig(redeem_script: &[u8]) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_int(0)
+        .push_slice(&[0x30; 71])
+        .push_slice(&[0x30; 71])
+        .push_slice(redeem_script)
+        .into_bytes()
+}
+
+#[test]
+fn standard_output_counts() {
+    assert_eq!(legacy_sigop_count(&builder::p2pkh(&[0; 20])), 1);
+    assert_eq!(legacy_sigop_count(&builder::p2sh(&[0; 20])), 0);
+    assert_eq!(legacy_sigop_count(&builder::p2wpkh(&[0; 20])), 0);
+
+    let multisig = multisig_2_of_3();
+    assert_eq!(legacy_sigop_count(&multisig), 20);
+    // The accurate count uses n, not m.
+    assert_eq!(accurate_sigop_count(&multisig), 3);
+}
+
+#[test]
+fn p2sh_counts_redeem_script() {
+    let redeem = multisig_2_of_3();
+    let script_pubkey = builder::p2sh(&hash::hash160(&redeem));
+    let script_sig = p2sh_script_sig(&redeem);
+
+    assert_eq!(p2sh_sigop_count(&script_pubkey, &script_sig), 3);
+    // Pushes in the scriptSig are data, not sigops.
+    assert_eq!(legacy_sigop_count(&script_sig), 0);
+    // Only P2SH outputs have a redeem script.
+    assert_eq!(p2sh_sigop_count(&builder::p2pkh(&[0; 20]), &script_sig), 0);
+    // A scriptSig with a non-push is not counted.
+    let mut not_push_only = script_sig.clone();
+    not_push_only.insert(0, 0x61);
+    assert_eq!(p2sh_sigop_count(&script_pubkey, &not_push_only), 0);
+
+    assert_eq!(input_sigop_cost(&script_sig, &script_pubkey, &[]), 12);
+}
+
+#[test]
+fn witness_counts() {
+    let p2wpkh = builder::p2wpkh(&[0; 20]);
+    assert_eq!(witness_sigop_count(&[], &p2wpkh, &[]), 1);
+
+    let witness_script = multisig_2_of_3();
+    let p2wsh = builder::p2wsh(&hash::sha256(&witness_script));
+    let witness = vec![vec![], vec![0x30; 71], vec![0x30; 71], witness_script];
+    assert_eq!(witness_sigop_count(&[], &p2wsh, &witness), 3);
+    assert_eq!(witness_sigop_count(&[], &p2wsh, &[]), 0);
+    // Witness sigops are not scaled.
+    assert_eq!(input_sigop_cost(&[], &p2wsh, &witness), 3);
+
+    // Nested in P2SH: the scriptSig pushes the witness program.
+    let script_sig = ScriptBuilder::new().push_slice(&p2wsh).into_bytes();
+    let script_pubkey = builder::p2sh(&hash::hash160(&p2wsh));
+    assert_eq!(
+        witness_sigop_count(&script_sig, &script_pubkey, &witness),
+        3
+    );
+    // The program has no sigops of its own as a redeem script.
+    assert_eq!(input_sigop_cost(&script_sig, &script_pubkey, &witness), 3);
+
+    // Taproot and legacy outputs have no witness sigops.
+    let p2tr = builder::p2tr(&[1; 32]);
+    assert_eq!(witness_sigop_count(&[], &p2tr, &[vec![0; 64]]), 0);
+    assert_eq!(
+        witness_sigop_count(&[], &builder::p2pkh(&[0; 20]), &witness),
+        0
+    );
+}
@@ -0,0 +1,281 @@
+use hex_literal::hex;
+use mini_bitcoin_script::flags::VerifyFlags;
+use mini_bitcoin_script::snapshot::{Field, SnapshotError, UtxoSnapshot};
+use mini_bitcoin_script::transaction::{DecodeError, OutPoint, Transaction, TxOut};
+use mini_bitcoin_script::verify::{verify_tx, UtxoProvider};
+
+const MAINNET_CSV: &str = include_str!("data/mainnet_utxos.csv");
+const MAINNET_BIN: &[u8] = include_bytes!("data/mainnet.utxo");
+
+/// Block 170: the first transaction sending bitcoin between people,
+/// spending the block 9 coinbase.
+const TX_F4184F: [u8; 275] = hex!(
+    "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000"
+);
+
+fn mainnet() -> UtxoSnapshot {
+    UtxoSnapshot::deserialize(MAINNET_BIN).unwrap()
+}
+
+fn entry(txid: u8, vout: u32) -> (OutPoint, TxOut) {
+    let outpoint = OutPoint {
+        txid: [txid; 32],
+        vout,
+    };
+    let output = TxOut {
+        value: u64::from(vout) * 1000,
+        script_pubkey: vec![0x51; vout as usize],
+    };
+    (outpoint, output)
+}
+
+#[test]
+fn mainnet_files_agree() {
+    let snapshot = mainnet();
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(UtxoSnapshot::from_csv(MAINNET_CSV), Ok(snapshot.clone()));
+    assert_eq!(snapshot.serialize(), MAINNET_BIN);
+}
+
+#[test]
+fn replays_mainnet_spend() {
+    let tx = Transaction::deserialize(&TX_F4184F).unwrap();
+    let snapshot = mainnet();
+    let result = verify_tx(&tx, &snapshot, VerifyFlags::NONE).unwrap();
+    assert!(result.is_valid(), "{:?}", result.first_error());
+    assert_eq!(result.fee, 0);
+
+    let spent = snapshot.utxo(&tx.inputs[0].previous_output).unwrap();
+    assert_eq!(spent.value, 50 * 100_000_000);
+}
+
+#[cfg(feature = "secp256k1")]
+#[test]
+fn mainnet_signature_is_checked() {
+    let mut tx = Transaction::deserialize(&TX_F4184F).unwrap();
+    tx.outputs[0].value -= 1;
+    let result = verify_tx(&tx, &mainnet(), VerifyFlags::NONE).unwrap();
+    assert!(!result.is_valid());
+}
+
+#[test]
+fn binary_roundtrip_and_order() {
+    let snapshot: UtxoSnapshot = [entry(2, 0), entry(1, 7), entry(1, 3)]
+        .into_iter()
+        .collect();
+    let bytes = snapshot.serialize();
+    let decoded = UtxoSnapshot::deserialize(&bytes).unwrap();
+    assert_eq!(decoded, snapshot);
+    let order: Vec<_> = decoded.iter().map(|(outpoint, _)| outpoint.vout).collect();
+    assert_eq!(order, [3, 7, 0]);
+
+    assert_eq!(
+        UtxoSnapshot::deserialize(&UtxoSnapshot::new().serialize()),
+        Ok(UtxoSnapshot::new())
+    );
+}
+
+#[test]
+fn rejects_malformed_binary() {
+    let mut bytes = mainnet().serialize();
+
+    let mut bad = bytes.clone();
+    bad[0] = b'x';
+    assert_eq!(
+        UtxoSnapshot::deserialize(&bad),
+        Err(SnapshotError::BadMagic)
+    );
+
+    let mut bad = bytes.clone();
+    bad[4] = 2;
+    assert_eq!(
+        UtxoSnapshot::deserialize(&bad),
+        Err(SnapshotError::UnsupportedVersion(2))
+    );
+
+    assert_eq!(
+        UtxoSnapshot::deserialize(&bytes[..bytes.len() - 1]),
+        Err(SnapshotError::Decode(DecodeError::UnexpectedEnd))
+    );
+
+    // A count far larger than the data is rejected before allocating.
+    let mut bad = bytes.clone();
+    bad[8] = 0xfc;
+    assert_eq!(
+        UtxoSnapshot::deserialize(&bad),
+        Err(SnapshotError::Decode(DecodeError::UnexpectedEnd))
+    );
+
+    // The same entry twice is out of order.
+    let entry = bytes[9..].to_vec();
+    bytes[8] = 2;
+    bytes.extend_from_slice(&entry);
+    assert_eq!(
+        UtxoSnapshot::deserialize(&bytes),
+        Err(SnapshotError::Unsorted { index: 1 })
+    );
+
+    bytes[8] = 1;
+    assert_eq!(
+        UtxoSnapshot::deserialize(&bytes),
+        Err(SnapshotError::Decode(DecodeError::TrailingData))
+    );
+}
+
+#[test]
+fn csv_roundtrip() {
+    let snapshot: UtxoSnapshot = [entry(0xab, 0), entry(0xcd, 2)].into_iter().collect();
+    let csv = snapshot.to_csv();
+    assert!(csv.starts_with("txid,vout,amount,script_pubkey\n"));
+    assert_eq!(UtxoSnapshot::from_csv(&csv), Ok(snapshot));
+
+    let padded = format!(
+        "\n  {} , 1 ,  600 , 0014{} \n",
+        "11".repeat(32),
+        "22".repeat(20)
+    );
+    let snapshot = UtxoSnapshot::from_csv(&padded).unwrap();
+    let output = snapshot
+        .get(&OutPoint {
+            txid: [0x11; 32],
+            vout: 1,
+        })
+        .unwrap();
+    assert_eq!(output.value, 600);
+    assert_eq!(output.script_pubkey.len(), 22);
+}
+
+#[test]
+fn rejects_invalid_csv() {
+    let txid = "11".repeat(32);
+    let cases = [
+        (
+            format!("{txid},0,1"),
+            SnapshotError::FieldCount { line: 1, found: 3 },
+        ),
+        (
+            format!("# comment\n{},0,1,51", "11".repeat(31)),
+            SnapshotError::InvalidField {
+                line: 2,
+                field: Field::Txid,
+            },
+        ),
+        (
+            format!("{txid},-1,1,51"),
+            SnapshotError::InvalidField {
+                line: 1,
+                field: Field::Vout,
+            },
+        ),
+        (
+            format!("{txid},0,1.5,51"),
+            SnapshotError::InvalidField {
+                line: 1,
+                field: Field::Amount,
+            },
+        ),
+        (
+            format!("{txid},0,1,5"),
+            SnapshotError::InvalidField {
+                line: 1,
+                field: Field::ScriptPubKey,
+            },
+        ),
+        (
+            format!("{txid},0,1,51\n{txid},0,2,52"),
+            SnapshotError::DuplicateOutpoint {
+                line: 2,
+                outpoint: OutPoint {
+                    txid: [0x11; 32],
+                    vout: 0,
+                },
+            },
+        ),
+    ];
+    for (csv, expected) in cases {
+        assert_eq!(UtxoSnapshot::from_csv(&csv), Err(expected), "{csv}");
+    }
+    assert_eq!(
+        SnapshotError::InvalidField {
+            line: 3,
+            field: Field::ScriptPubKey
+        }
+        .to_string(),
+        "line 3: invalid script_pubkey"
+    );
+}
+
+#[cfg(feature = "serde")]
+mod json {
+    use super::*;
+
+    const MAINNET_JSON: &str = include_str!("data/mainnet_utxos.json");
+
+    #[test]
+    fn imports_scantxoutset_records() {
+        let snapshot: UtxoSnapshot = serde_json::from_str(MAINNET_JSON).unwrap();
+        assert_eq!(snapshot, mainnet());
+    }
+
+    fn amount(json: &str) -> Result<u64, serde_json::Error> {
+        let record = format!(
+            r#"[{{"txid": "{}", "vout": 0, "value": {json}, "scriptPubKey": ""}}]"#,
+            "00".repeat(32)
+        );
+        let snapshot: UtxoSnapshot = serde_json::from_str(&record)?;
+        let value = snapshot.iter().next().unwrap().1.value;
+        Ok(value)
+    }
+
+    #[test]
+    fn reads_bitcoin_amounts_exactly() {
+        assert_eq!(amount("5000000000").unwrap(), 5_000_000_000);
+        assert_eq!(amount("50.00000000").unwrap(), 5_000_000_000);
+        assert_eq!(amount("0.00000001").unwrap(), 1);
+        assert_eq!(amount("0.3").unwrap(), 30_000_000);
+        assert_eq!(amount("0.29").unwrap(), 29_000_000);
+        assert_eq!(amount("1e-8").unwrap(), 1);
+        assert_eq!(amount("20999999.99999999").unwrap(), 2_099_999_999_999_999);
+        assert_eq!(amount("20999999.9769").unwrap(), 2_099_999_997_690_000);
+
+        assert!(amount("0.000000001").is_err());
+        assert!(amount("-0.1").is_err());
+        assert!(amount("-1").is_err());
+        assert!(amount("1e300").is_err());
+    }
+
+    #[test]
+    fn roundtrip() {
+        let snapshot: UtxoSnapshot = [entry(1, 0), entry(2, 3)].into_iter().collect();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.starts_with(&format!(
+            r#"[{{"txid":"{}","vout":0,"amount":0,"script_pubkey":""}}"#,
+            "01".repeat(32)
+        )));
+        assert_eq!(
+            serde_json::from_str::<UtxoSnapshot>(&json).unwrap(),
+            snapshot
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_records() {
+        let json = r#"[{"txid": "00", "vout": 0, "amount": 1, "script_pubkey": ""}]"#;
+        let err = serde_json::from_str::<UtxoSnapshot>(json).unwrap_err();
+        assert!(
+            err.to_string().starts_with("record 0: invalid txid"),
+            "{err}"
+        );
+
+        let record = format!(
+            r#"{{"txid": "{}", "vout": 0, "amount": 1, "script_pubkey": ""}}"#,
+            "00".repeat(32)
+        );
+        let json = format!("[{record}, {record}]");
+        let err = serde_json::from_str::<UtxoSnapshot>(&json).unwrap_err();
+        assert!(
+            err.to_string().starts_with("record 1: duplicate outpoint"),
+            "{err}"
+        );
+    }
+}
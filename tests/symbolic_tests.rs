@@ -0,0 +1,200 @@
+use mini_bitcoin_script::builder;
+use mini_bitcoin_script::error::ScriptError;
+use mini_bitcoin_script::symbolic::{symbolic_execute, Constraint, Expr, HashFn};
+use mini_bitcoin_script::token::Token;
+use mini_bitcoin_script::tokenizer::{parse_asm, parse_script};
+
+fn asm(text: &str) -> Vec<Token> {
+    parse_asm(text).unwrap()
+}
+
+fn texts(constraints: &[Constraint]) -> Vec<String> {
+    constraints.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn p2pkh_conditions() {
+    let tokens = parse_script(&builder::p2pkh(&[0xab; 20])).unwrap();
+    let conditions = symbolic_execute(&tokens).unwrap();
+    assert_eq!(conditions.paths.len(), 1);
+
+    let path = &conditions.paths[0];
+    assert_eq!(path.inputs, 2);
+    assert!(path.is_satisfiable());
+    assert_eq!(
+        path.constraints,
+        vec![
+            Constraint::IsTrue(Expr::Equal(
+                Box::new(Expr::Hash(HashFn::Hash160, Box::new(Expr::Var(1)))),
+                Box::new(Expr::Const(vec![0xab; 20])),
+            )),
+            Constraint::IsTrue(Expr::CheckSig(
+                Box::new(Expr::Var(0)),
+                Box::new(Expr::Var(1)),
+            )),
+        ]
+    );
+}
+
+/// A hash time-lock-style contract without the timelock: either the
+/// recipient reveals a preimage, or the sender signs.
+#[test]
+fn hashlock_or_refund() {
+    let tokens = asm(
+        "OP_IF OP_SHA256 <1111111111111111111111111111111111111111111111111111111111111111> \
+         OP_EQUALVERIFY <02aa> OP_ELSE <03bb> OP_ENDIF OP_CHECKSIG",
+    );
+    let conditions = symbolic_execute(&tokens).unwrap();
+    assert_eq!(conditions.paths.len(), 2);
+    assert!(!conditions.truncated);
+
+    let claim = &conditions.paths[0];
+    assert_eq!(claim.branches, vec![(0, true)]);
+    assert_eq!(claim.inputs, 3);
+    assert_eq!(
+        texts(&claim.constraints),
+        vec![
+            "x2".to_string(),
+            "sha256(x1) == <1111111111111111111111111111111111111111111111111111111111111111>"
+                .to_string(),
+            "checksig(x0, <02aa>)".to_string(),
+        ]
+    );
+
+    let refund = &conditions.paths[1];
+    assert_eq!(refund.branches, vec![(0, false)]);
+    assert_eq!(refund.inputs, 2);
+    assert_eq!(
+        texts(&refund.constraints),
+        vec!["!x1".to_string(), "checksig(x0, <03bb>)".to_string()]
+    );
+}
+
+#[test]
+fn notif_negates_condition() {
+    let conditions = symbolic_execute(&asm("OP_NOTIF OP_1 OP_ELSE OP_1 OP_ENDIF")).unwrap();
+    assert_eq!(texts(&conditions.paths[0].constraints), vec!["!x0"]);
+    assert_eq!(texts(&conditions.paths[1].constraints), vec!["x0"]);
+}
+
+#[test]
+fn equality_negation_renders_as_not_equal() {
+    let conditions = symbolic_execute(&asm("<aa> OP_EQUAL OP_NOTIF OP_1 OP_ENDIF OP_1")).unwrap();
+    assert_eq!(texts(&conditions.paths[0].constraints), vec!["x0 != <aa>"]);
+}
+
+#[test]
+fn numeric_comparisons() {
+    let conditions = symbolic_execute(&asm("OP_1ADD OP_5 OP_LESSTHAN")).unwrap();
+    assert_eq!(
+        texts(&conditions.paths[0].constraints),
+        vec!["x0 + <01> < <05>"]
+    );
+
+    let conditions = symbolic_execute(&asm("OP_2 OP_5 OP_WITHIN")).unwrap();
+    assert_eq!(
+        texts(&conditions.paths[0].constraints),
+        vec!["booland(x0 >= <02>, x0 < <05>)"]
+    );
+
+    // A false comparison renders as its opposite.
+    let conditions = symbolic_execute(&asm(
+        "OP_SUB OP_ABS OP_3 OP_GREATERTHAN OP_NOTIF OP_1 OP_ENDIF OP_1",
+    ))
+    .unwrap();
+    assert_eq!(
+        texts(&conditions.paths[0].constraints),
+        vec!["abs(x0 - x1) <= <03>"]
+    );
+
+    // Known operands fold.
+    let conditions = symbolic_execute(&asm("OP_3 OP_1NEGATE OP_MIN OP_NEGATE OP_1SUB")).unwrap();
+    assert_eq!(conditions.paths[0].inputs, 0);
+    assert!(!conditions.paths[0].is_satisfiable());
+}
+
+#[test]
+fn unsatisfiable_paths() {
+    let conditions = symbolic_execute(&asm("OP_IF OP_RETURN OP_ENDIF OP_1")).unwrap();
+    assert_eq!(conditions.paths.len(), 2);
+    assert_eq!(conditions.paths[0].fails_at, Some(1));
+    assert_eq!(conditions.satisfiable().count(), 1);
+
+    let conditions = symbolic_execute(&asm("OP_1 OP_2 OP_EQUALVERIFY OP_1")).unwrap();
+    assert_eq!(conditions.paths[0].fails_at, Some(2));
+}
+
+#[test]
+fn false_final_top_is_unsatisfiable() {
+    for (script, len) in [("OP_0", 1), ("OP_1 OP_0", 2), ("OP_DROP OP_0", 2)] {
+        let conditions = symbolic_execute(&asm(script)).unwrap();
+        assert_eq!(conditions.paths[0].fails_at, Some(len), "{script}");
+        assert_eq!(conditions.satisfiable().count(), 0, "{script}");
+    }
+
+    // Branches that end false are dropped, the others kept.
+    let conditions = symbolic_execute(&asm("OP_IF OP_1 OP_ELSE OP_0 OP_ENDIF")).unwrap();
+    assert_eq!(conditions.paths[0].fails_at, None);
+    assert_eq!(conditions.paths[1].fails_at, Some(5));
+}
+
+#[test]
+fn empty_final_stack_reads_a_witness_item() {
+    let conditions = symbolic_execute(&[]).unwrap();
+    let path = &conditions.paths[0];
+    assert_eq!(path.inputs, 1);
+    assert_eq!(texts(&path.constraints), vec!["x0"]);
+}
+
+#[test]
+fn unbalanced_is_an_error() {
+    assert_eq!(
+        symbolic_execute(&asm("OP_IF OP_1")),
+        Err(ScriptError::UnbalancedConditional)
+    );
+    assert_eq!(
+        symbolic_execute(&asm("OP_1 OP_ENDIF")),
+        Err(ScriptError::UnbalancedConditional)
+    );
+    assert_eq!(
+        symbolic_execute(&asm("OP_IF OP_CAT OP_ENDIF OP_1")),
+        Err(ScriptError::DisabledOpcode(0x7e))
+    );
+}
+
+#[test]
+fn text_report() {
+    let conditions =
+        symbolic_execute(&asm("OP_IF OP_CHECKSIG OP_ELSE OP_RETURN OP_ENDIF")).unwrap();
+    assert_eq!(
+        conditions.to_string(),
+        "path 0 [0:if]: 3 inputs\n  x2\n  checksig(x0, x1)\n\
+         path 1 [0:else]: 1 inputs\n  unsatisfiable: fails at token 3\n"
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_export() {
+    let tokens = parse_script(&builder::p2pkh(&[0xab; 20])).unwrap();
+    let conditions = symbolic_execute(&tokens).unwrap();
+    let json = serde_json::to_value(&conditions).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "paths": [{
+                "branches": [],
+                "inputs": 2,
+                "constraints": [
+                    {"is_true": {"equal": [
+                        {"hash": ["hash160", {"var": 1}]},
+                        {"const": "abababababababababababababababababababab"}
+                    ]}},
+                    {"is_true": {"check_sig": [{"var": 0}, {"var": 1}]}}
+                ],
+                "fails_at": null
+            }],
+            "truncated": false
+        })
+    );
+}
@@ -0,0 +1,239 @@
+use std::collections::{BTreeMap, HashMap};
+
+use mini_bitcoin_script::builder;
+use mini_bitcoin_script::flags::VerifyFlags;
+use mini_bitcoin_script::transaction::{OutPoint, Transaction, TxIn, TxOut};
+use mini_bitcoin_script::verify::{verify_tx, TxVerifyError, MAX_MONEY};
+
+fn outpoint(n: u8) -> OutPoint {
+    OutPoint {
+        txid: [n; 32],
+        vout: u32::from(n),
+    }
+}
+
+/// Outputs anyone can spend with an empty scriptSig.
+fn utxos(values: &[u64]) -> BTreeMap<OutPoint, TxOut> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(n, &value)| {
+            let prevout = TxOut {
+                value,
+                script_pubkey: vec![0x51],
+            };
+            (outpoint(n as u8), prevout)
+        })
+        .collect()
+}
+
+fn spend(inputs: &[u8], values: &[u64]) -> Transaction {
+    Transaction {
+        version: 2,
+        inputs: inputs.iter().map(|&n| TxIn::new(outpoint(n))).collect(),
+        outputs: values
+            .iter()
+            .map(|&value| TxOut {
+                value,
+                script_pubkey: builder::p2wpkh(&[0x11; 20]),
+            })
+            .collect(),
+        lock_time: 0,
+    }
+}
+
+#[test]
+fn verifies_each_input_and_computes_fee() {
+    let utxos = utxos(&[5_000, 7_000]);
+    let result = verify_tx(&spend(&[0, 1], &[11_000]), &utxos, VerifyFlags::NONE).unwrap();
+    assert!(result.is_valid());
+    assert_eq!(result.fee, 1_000);
+    assert_eq!(result.inputs, [Ok(()), Ok(())]);
+
+    // A zero fee is allowed; HashMap works as a provider too.
+    let utxos: HashMap<_, _> = utxos.into_iter().collect();
+    let result = verify_tx(&spend(&[0, 1], &[12_000]), &utxos, VerifyFlags::NONE).unwrap();
+    assert_eq!(result.fee, 0);
+}
+
+#[test]
+fn reports_failing_inputs_individually() {
+    let mut utxos = utxos(&[5_000, 5_000]);
+    utxos.get_mut(&outpoint(1)).unwrap().script_pubkey = vec![0x00];
+    let result = verify_tx(&spend(&[0, 1], &[9_000]), &utxos, VerifyFlags::NONE).unwrap();
+    assert!(!result.is_valid());
+    assert!(result.inputs[0].is_ok());
+    let (index, _) = result.first_error().unwrap();
+    assert_eq!(index, 1);
+}
+
+#[test]
+fn rejects_unbalanced_transactions() {
+    let utxos = utxos(&[5_000, 5_000]);
+    assert_eq!(
+        verify_tx(&spend(&[0, 1], &[6_000, 4_001]), &utxos, VerifyFlags::NONE),
+        Err(TxVerifyError::NegativeFee {
+            inputs: 10_000,
+            outputs: 10_001
+        })
+    );
+    assert_eq!(
+        verify_tx(&spend(&[0], &[MAX_MONEY + 1]), &utxos, VerifyFlags::NONE),
+        Err(TxVerifyError::ValueOutOfRange)
+    );
+    assert_eq!(
+        verify_tx(&spend(&[0], &[MAX_MONEY, 1]), &utxos, VerifyFlags::NONE),
+        Err(TxVerifyError::ValueOutOfRange)
+    );
+}
+
+#[test]
+fn rejects_bad_input_sets() {
+    let utxos = utxos(&[5_000]);
+    assert_eq!(
+        verify_tx(&spend(&[], &[1]), &utxos, VerifyFlags::NONE),
+        Err(TxVerifyError::NoInputs)
+    );
+    assert_eq!(
+        verify_tx(&spend(&[0], &[]), &utxos, VerifyFlags::NONE),
+        Err(TxVerifyError::NoOutputs)
+    );
+    assert_eq!(
+        verify_tx(&spend(&[0, 0], &[1]), &utxos, VerifyFlags::NONE),
+        Err(TxVerifyError::DuplicateInput { index: 1 })
+    );
+    assert_eq!(
+        verify_tx(&spend(&[0, 3], &[1]), &utxos, VerifyFlags::NONE),
+        Err(TxVerifyError::MissingUtxo {
+            index: 1,
+            outpoint: outpoint(3)
+        })
+    );
+}
+
+#[cfg(feature = "test-utils")]
+mod signed {
+    use super::*;
+    use mini_bitcoin_script::builder::ScriptBuilder;
+    use mini_bitcoin_script::error::ScriptError;
+    use mini_bitcoin_script::opcode::Opcode;
+    use mini_bitcoin_script::sighash::legacy_sighash;
+    use mini_bitcoin_script::sign::sign_ecdsa;
+    use mini_bitcoin_script::test_utils::{fixture, Fixture, ScriptType, FEE};
+    use mini_bitcoin_script::token::Token;
+    use mini_bitcoin_script::tokenizer::parse_script;
+    use mini_bitcoin_script::verify::{verify_input, VerifyError};
+
+    fn flags() -> VerifyFlags {
+        VerifyFlags::DERSIG | VerifyFlags::LOW_S | VerifyFlags::STRICTENC | VerifyFlags::NULLFAIL
+    }
+
+    fn provider(fx: &Fixture) -> HashMap<OutPoint, TxOut> {
+        let outpoint = fx.spending_tx.inputs[0].previous_output;
+        HashMap::from([(outpoint, fx.prevout().clone())])
+    }
+
+    #[test]
+    fn fixtures_verify() {
+        for script_type in [
+            ScriptType::P2pkh,
+            ScriptType::P2wpkh,
+            ScriptType::P2shMultisig,
+            ScriptType::P2trKeyPath,
+        ] {
+            let fx = fixture(script_type, b"verify_tx");
+            let result = verify_tx(&fx.spending_tx, &provider(&fx), flags()).unwrap();
+            assert!(result.is_valid(), "{script_type:?}: {:?}", result.inputs);
+            assert_eq!(result.fee, FEE);
+        }
+    }
+
+    #[test]
+    fn signatures_commit_to_outputs() {
+        let fx = fixture(ScriptType::P2wpkh, b"verify_tx");
+        let utxos = provider(&fx);
+        let mut tx = fx.spending_tx.clone();
+        tx.outputs[0].value -= 1;
+        let result = verify_tx(&tx, &utxos, flags()).unwrap();
+        assert_eq!(result.fee, FEE + 1);
+        // NULLFAIL turns the failed check into an error.
+        assert_eq!(
+            result.inputs,
+            [Err(VerifyError::Script(ScriptError::NullFail))]
+        );
+    }
+
+    #[test]
+    fn lax_der_is_accepted_without_dersig() {
+        let fx = fixture(ScriptType::P2pkh, b"lax der");
+        let prevouts = [fx.prevout().clone()];
+        let pushes = pushes(fx.script_sig());
+        // Pad R with a zero byte: valid BER, but not strict DER.
+        let sig = &pushes[0];
+        let r_len = usize::from(sig[3]);
+        let mut padded = vec![0x30, sig[1] + 1, 0x02, sig[3] + 1, 0x00];
+        padded.extend_from_slice(&sig[4..]);
+        assert_eq!(padded.len(), sig.len() + 1);
+        assert_eq!(padded[5 + r_len], 0x02);
+
+        let mut tx = fx.spending_tx.clone();
+        tx.inputs[0].script_sig = ScriptBuilder::new()
+            .push_slice(&padded)
+            .push_slice(&pushes[1])
+            .into_bytes();
+        assert_eq!(verify_input(&tx, 0, &prevouts, VerifyFlags::NONE), Ok(()));
+        assert_eq!(
+            verify_input(&tx, 0, &prevouts, VerifyFlags::DERSIG),
+            Err(VerifyError::Script(ScriptError::SigDer))
+        );
+    }
+
+    fn pushes(script: &[u8]) -> Vec<Vec<u8>> {
+        parse_script(script)
+            .unwrap()
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::PushData(data) => Some(data),
+                Token::Op(_) => None,
+            })
+            .collect()
+    }
+
+    /// The multisig fixture's spend with its second signature replaced.
+    fn with_second_signature(fx: &Fixture, sig: &[u8]) -> Transaction {
+        let pushes = pushes(fx.script_sig());
+        let mut tx = fx.spending_tx.clone();
+        tx.inputs[0].script_sig = ScriptBuilder::new()
+            .push_opcode(Opcode::Op0)
+            .push_slice(&pushes[0])
+            .push_slice(sig)
+            .push_slice(&pushes[2])
+            .into_bytes();
+        tx
+    }
+
+    #[test]
+    fn each_signature_uses_its_own_sighash_type() {
+        let fx = fixture(ScriptType::P2shMultisig, b"verify_tx");
+        let prevouts = [fx.prevout().clone()];
+        let redeem_script = fx.redeem_script.clone().unwrap();
+        let second = &fx.keys[1];
+
+        // Relabelled from SIGHASH_ALL to SIGHASH_NONE, the signature no
+        // longer matches its digest.
+        let sighash = legacy_sighash(&fx.spending_tx, 0, &redeem_script, 0x01).unwrap();
+        let mut sig = sign_ecdsa(&second.secret_key, &sighash, 0x01);
+        *sig.last_mut().unwrap() = 0x02;
+        let tx = with_second_signature(&fx, &sig);
+        assert_eq!(
+            verify_input(&tx, 0, &prevouts, flags()),
+            Err(VerifyError::Script(ScriptError::NullFail))
+        );
+
+        // SIGHASH_ALL and SIGHASH_ALL|ANYONECANPAY signatures mix.
+        let sighash = legacy_sighash(&fx.spending_tx, 0, &redeem_script, 0x81).unwrap();
+        let sig = sign_ecdsa(&second.secret_key, &sighash, 0x81);
+        let tx = with_second_signature(&fx, &sig);
+        assert_eq!(verify_input(&tx, 0, &prevouts, flags()), Ok(()));
+    }
+}
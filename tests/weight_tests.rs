@@ -0,0 +1,123 @@
+use mini_bitcoin_script::builder;
+use mini_bitcoin_script::classify::{classify, OutputType};
+use mini_bitcoin_script::hash;
+use mini_bitcoin_script::weight::{estimate_satisfaction, EstimateError, InputSize};
+
+fn multisig_2_of_3() -> Vec<u8> {
+    builder::multisig(2, &[&[0x02; 33], &[0x03; 33], &[0x02; 33]]).unwrap()
+}
+
+#[test]
+fn p2pkh_sizes() {
+    let size = estimate_satisfaction(OutputType::P2pkh, None, None).unwrap();
+    assert_eq!(
+        size.max,
+        InputSize {
+            script_sig: 108,
+            witness: 0
+        }
+    );
+    assert_eq!(size.expected.script_sig, 107);
+    // The familiar 148-vbyte P2PKH input.
+    assert_eq!(size.expected.vsize(), 148);
+}
+
+#[test]
+fn segwit_sizes() {
+    let p2wpkh = estimate_satisfaction(OutputType::P2wpkh, None, None).unwrap();
+    assert_eq!(p2wpkh.expected.witness, 108);
+    assert_eq!(p2wpkh.expected.vsize(), 68);
+
+    let p2tr = estimate_satisfaction(OutputType::P2tr, None, None).unwrap();
+    assert_eq!(p2tr.expected.witness, 66);
+    assert_eq!(p2tr.max.witness, 67);
+    assert_eq!(p2tr.expected.weight(), 230);
+}
+
+#[test]
+fn nested_p2wpkh() {
+    let redeem = builder::p2wpkh(&[0x11; 20]);
+    let size = estimate_satisfaction(OutputType::P2sh, Some(&redeem), None).unwrap();
+    assert_eq!(size.expected.script_sig, 23);
+    assert_eq!(size.expected.witness, 108);
+    assert_eq!(size.expected.vsize(), 91);
+}
+
+#[test]
+fn multisig_sizes() {
+    let script = multisig_2_of_3();
+    let bare = estimate_satisfaction(classify(&script), None, None).unwrap();
+    assert_eq!(bare.max.script_sig, 1 + 2 * 74);
+
+    let p2sh = estimate_satisfaction(OutputType::P2sh, Some(&script), None).unwrap();
+    // OP_0, two signatures, then the 105-byte redeem script via PUSHDATA1.
+    assert_eq!(p2sh.max.script_sig, 1 + 2 * 74 + 2 + 105);
+    assert_eq!(p2sh.expected.script_sig, 1 + 2 * 73 + 2 + 105);
+
+    let p2wsh = estimate_satisfaction(OutputType::P2wsh, None, Some(&script)).unwrap();
+    assert_eq!(p2wsh.expected.witness, 1 + 1 + 2 * 73 + 1 + 105);
+    assert_eq!(p2wsh.expected.vsize(), 105);
+
+    let nested = builder::p2wsh(&hash::sha256(&script));
+    let p2sh_p2wsh = estimate_satisfaction(OutputType::P2sh, Some(&nested), Some(&script)).unwrap();
+    assert_eq!(p2sh_p2wsh.expected.script_sig, 35);
+    assert_eq!(p2sh_p2wsh.expected.witness, p2wsh.expected.witness);
+}
+
+#[test]
+fn estimation_errors() {
+    assert_eq!(
+        estimate_satisfaction(OutputType::P2sh, None, None),
+        Err(EstimateError::MissingRedeemScript)
+    );
+    assert_eq!(
+        estimate_satisfaction(OutputType::P2wsh, None, None),
+        Err(EstimateError::MissingWitnessScript)
+    );
+    assert_eq!(
+        estimate_satisfaction(OutputType::NullData, None, None),
+        Err(EstimateError::Unsupported(OutputType::NullData))
+    );
+    // A redeem script with no known satisfaction.
+    assert_eq!(
+        estimate_satisfaction(OutputType::P2sh, Some(&[0x51]), None),
+        Err(EstimateError::Unsupported(OutputType::NonStandard))
+    );
+}
+
+#[cfg(feature = "test-utils")]
+#[test]
+fn fixtures_fit_within_max() {
+    use mini_bitcoin_script::test_utils::{fixture, ScriptType};
+
+    for script_type in [
+        ScriptType::P2pkh,
+        ScriptType::P2wpkh,
+        ScriptType::P2shMultisig,
+        ScriptType::P2trKeyPath,
+    ] {
+        let fx = fixture(script_type, b"weight");
+        let output_type = classify(&fx.prevout().script_pubkey);
+        let size = estimate_satisfaction(output_type, fx.redeem_script.as_deref(), None).unwrap();
+
+        let input = &fx.spending_tx.inputs[0];
+        let witness = if input.witness.is_empty() {
+            0
+        } else {
+            1 + input
+                .witness
+                .iter()
+                .map(|item| 1 + item.len())
+                .sum::<usize>()
+        };
+        assert!(
+            input.script_sig.len() <= size.max.script_sig,
+            "{script_type:?}"
+        );
+        assert!(
+            input.script_sig.len() + 2 >= size.expected.script_sig,
+            "{script_type:?}"
+        );
+        assert!(witness <= size.max.witness, "{script_type:?}");
+    }
+}